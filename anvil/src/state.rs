@@ -247,6 +247,7 @@ delegate_shm!(@<BackendData: Backend + 'static> AnvilState<BackendData>);
 impl<BackendData: Backend> SeatHandler for AnvilState<BackendData> {
     type KeyboardFocus = FocusTarget;
     type PointerFocus = FocusTarget;
+    type TouchFocus = WlSurface;
 
     fn seat_state(&mut self) -> &mut SeatState<AnvilState<BackendData>> {
         &mut self.seat_state
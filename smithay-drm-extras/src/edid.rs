@@ -12,6 +12,9 @@
 //!
 //! println!("Monitor name: {}", info.model);
 //! println!("Manufacturer name: {}", info.manufacturer);
+//! if let Some(hdr) = info.hdr_capabilities {
+//!     println!("Supports HDR10: {}", hdr.supports_hdr10());
+//! }
 //! ```
 
 use drm::control::{connector, Device as ControlDevice, PropertyValueSet};
@@ -26,23 +29,67 @@ pub struct EdidInfo {
     pub model: String,
     /// Name of manufacturer of this monitor
     pub manufacturer: String,
+    /// HDR capabilities of this monitor,
+    /// `None` if the EDID contains no HDR static metadata data block
+    pub hdr_capabilities: Option<HdrCapabilities>,
+}
+
+/// HDR capabilities of a monitor, acquired from the CTA-861 extension of its EDID
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HdrCapabilities {
+    /// Supports traditional gamma with SDR luminance range
+    pub traditional_sdr: bool,
+    /// Supports traditional gamma with HDR luminance range
+    pub traditional_hdr: bool,
+    /// Supports SMPTE ST 2084 (PQ)
+    pub smpte_st2084: bool,
+    /// Supports Hybrid Log-Gamma
+    pub hlg: bool,
+    /// Supports static metadata type 1
+    pub static_metadata_type1: bool,
+    /// Supports BT.2020 RGB colorimetry
+    pub bt2020_rgb: bool,
+    /// Supports BT.2020 YCbCr colorimetry
+    pub bt2020_ycc: bool,
+    /// Supports DCI-P3 colorimetry
+    pub dci_p3: bool,
+    /// Desired content max luminance in cd/m²
+    pub max_luminance: Option<f32>,
+    /// Desired content max frame-average luminance in cd/m²
+    pub max_frame_average_luminance: Option<f32>,
+    /// Desired content min luminance in cd/m²
+    pub min_luminance: Option<f32>,
+}
+
+impl HdrCapabilities {
+    /// Returns whether the monitor can display HDR10 content (PQ with BT.2020 RGB)
+    pub fn supports_hdr10(&self) -> bool {
+        self.smpte_st2084 && self.static_metadata_type1 && self.bt2020_rgb
+    }
 }
 
 impl EdidInfo {
     /// Get EDID info from supplied connector
     pub fn for_connector(device: &impl ControlDevice, connector: connector::Handle) -> Option<EdidInfo> {
-        device
+        let data = device
             .get_properties(connector)
             .ok()
-            .and_then(|props| get_edid(device, &props))
-            .map(|edid| EdidInfo {
-                model: get_monitor_name(&edid),
-                manufacturer: get_manufacturer_name(&edid),
-            })
+            .and_then(|props| get_edid_blob(device, &props))?;
+
+        let hdr_capabilities = get_hdr_capabilities(&data);
+
+        let mut reader = std::io::Cursor::new(data);
+        let edid = edid_rs::parse(&mut reader).ok()?;
+
+        Some(EdidInfo {
+            model: get_monitor_name(&edid),
+            manufacturer: get_manufacturer_name(&edid),
+            hdr_capabilities,
+        })
     }
 }
 
-fn get_edid(device: &impl ControlDevice, props: &PropertyValueSet) -> Option<edid_rs::EDID> {
+fn get_edid_blob(device: &impl ControlDevice, props: &PropertyValueSet) -> Option<Vec<u8>> {
     let (info, value) = props
         .into_iter()
         .filter_map(|(handle, value)| {
@@ -53,11 +100,73 @@ fn get_edid(device: &impl ControlDevice, props: &PropertyValueSet) -> Option<edi
         .find(|(info, _)| info.name().to_str() == Ok("EDID"))?;
 
     let blob = info.value_type().convert_value(*value).as_blob()?;
-    let data = device.get_property_blob(blob).ok()?;
+    device.get_property_blob(blob).ok()
+}
+
+// edid-rs does not parse extension blocks, so we look for the
+// hdr static metadata and colorimetry data blocks in the CTA-861 extensions ourselves
+fn get_hdr_capabilities(data: &[u8]) -> Option<HdrCapabilities> {
+    const BLOCK_SIZE: usize = 128;
+    const CTA_EXTENSION_TAG: u8 = 0x02;
+    const EXTENDED_TAG: u8 = 0x07;
+    const COLORIMETRY_TAG: u8 = 0x05;
+    const HDR_STATIC_METADATA_TAG: u8 = 0x06;
+
+    let mut caps = HdrCapabilities::default();
+    let mut has_hdr_block = false;
+
+    for ext in data.chunks_exact(BLOCK_SIZE).skip(1) {
+        if ext[0] != CTA_EXTENSION_TAG {
+            continue;
+        }
+
+        // data blocks are located between the header and the detailed timing descriptors
+        let dtd_offset = (ext[2] as usize).clamp(4, BLOCK_SIZE);
+        let mut offset = 4;
+        while offset < dtd_offset {
+            let tag = ext[offset] >> 5;
+            let len = (ext[offset] & 0x1f) as usize;
+            let Some(block) = ext.get(offset + 1..offset + 1 + len) else {
+                break;
+            };
+            offset += 1 + len;
 
-    let mut reader = std::io::Cursor::new(data);
+            if tag != EXTENDED_TAG || block.is_empty() {
+                continue;
+            }
+            let payload = &block[1..];
+            match block[0] {
+                COLORIMETRY_TAG if !payload.is_empty() => {
+                    caps.bt2020_ycc = payload[0] & (1 << 6) != 0;
+                    caps.bt2020_rgb = payload[0] & (1 << 7) != 0;
+                    caps.dci_p3 = payload.get(1).map(|b| b & (1 << 7) != 0).unwrap_or(false);
+                }
+                HDR_STATIC_METADATA_TAG if payload.len() >= 2 => {
+                    has_hdr_block = true;
+                    caps.traditional_sdr = payload[0] & (1 << 0) != 0;
+                    caps.traditional_hdr = payload[0] & (1 << 1) != 0;
+                    caps.smpte_st2084 = payload[0] & (1 << 2) != 0;
+                    caps.hlg = payload[0] & (1 << 3) != 0;
+                    caps.static_metadata_type1 = payload[1] & (1 << 0) != 0;
+
+                    // luminance values are optional and encoded as code values,
+                    // see CTA-861-G section 7.5.13
+                    let luminance = |cv: u8| 50.0 * 2f32.powf(cv as f32 / 32.0);
+                    caps.max_luminance = payload.get(2).filter(|cv| **cv != 0).map(|cv| luminance(*cv));
+                    caps.max_frame_average_luminance =
+                        payload.get(3).filter(|cv| **cv != 0).map(|cv| luminance(*cv));
+                    caps.min_luminance = caps.max_luminance.and_then(|max| {
+                        payload
+                            .get(4)
+                            .map(|cv| max * (*cv as f32 / 255.0).powi(2) / 100.0)
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
 
-    edid_rs::parse(&mut reader).ok()
+    has_hdr_block.then_some(caps)
 }
 
 fn get_manufacturer_name(edid: &edid_rs::EDID) -> String {
@@ -87,3 +196,115 @@ fn get_monitor_name(edid: &edid_rs::EDID) -> String {
         })
         .unwrap_or_else(|| edid.product.product_code.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{get_hdr_capabilities, HdrCapabilities};
+
+    // 4K monitor with a CTA-861 extension carrying colorimetry (BT.2020) and
+    // hdr static metadata (SDR, PQ, HLG, type 1, luminance 617/351/0.18 cd/m²) data blocks
+    const HDR_EDID: [u8; 256] = [
+        0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x1e, 0x6d, 0x7f, 0x5b, 0x01, 0x01, 0x01, 0x01, 0x01,
+        0x1e, 0x01, 0x04, 0xb5, 0x3c, 0x22, 0x78, 0x9f, 0xf8, 0x30, 0xad, 0x50, 0x37, 0xad, 0x25, 0x0c, 0x48,
+        0x4f, 0x21, 0x08, 0x00, 0xd1, 0xc0, 0x71, 0x40, 0x81, 0x80, 0x95, 0x00, 0xa9, 0xc0, 0x81, 0xc0, 0x01,
+        0x01, 0x01, 0x01, 0x4d, 0xd0, 0x00, 0xa0, 0xf0, 0x70, 0x3e, 0x80, 0x30, 0x20, 0x35, 0x00, 0x58, 0x54,
+        0x21, 0x00, 0x00, 0x1a, 0x00, 0x00, 0x00, 0xfd, 0x00, 0x28, 0x3c, 0x87, 0x87, 0x38, 0x01, 0x0a, 0x20,
+        0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0xfc, 0x00, 0x4c, 0x47, 0x20, 0x48, 0x44, 0x52, 0x20,
+        0x34, 0x4b, 0x0a, 0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0xff, 0x00, 0x31, 0x32, 0x33, 0x4e, 0x54, 0x41,
+        0x42, 0x31, 0x32, 0x33, 0x34, 0x35, 0x0a, 0x01, 0xbc, 0x02, 0x03, 0x1c, 0x70, 0x44, 0x90, 0x04, 0x03,
+        0x01, 0x23, 0x09, 0x07, 0x07, 0x83, 0x01, 0x00, 0x00, 0xe3, 0x05, 0xc0, 0x00, 0xe6, 0x06, 0x0d, 0x01,
+        0x74, 0x5a, 0x2c, 0x02, 0x3a, 0x80, 0x18, 0x71, 0x38, 0x2d, 0x40, 0x58, 0x2c, 0x45, 0x00, 0x58, 0x54,
+        0x21, 0x00, 0x00, 0x1e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x9b,
+    ];
+
+    // 1080p monitor with a CTA-861 extension without hdr related data blocks
+    const SDR_EDID: [u8; 256] = [
+        0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x1e, 0x6d, 0x7f, 0x5b, 0x01, 0x01, 0x01, 0x01, 0x01,
+        0x1e, 0x01, 0x04, 0xb5, 0x3c, 0x22, 0x78, 0x9f, 0xf8, 0x30, 0xad, 0x50, 0x37, 0xad, 0x25, 0x0c, 0x48,
+        0x4f, 0x21, 0x08, 0x00, 0xd1, 0xc0, 0x71, 0x40, 0x81, 0x80, 0x95, 0x00, 0xa9, 0xc0, 0x81, 0xc0, 0x01,
+        0x01, 0x01, 0x01, 0x4d, 0xd0, 0x00, 0xa0, 0xf0, 0x70, 0x3e, 0x80, 0x30, 0x20, 0x35, 0x00, 0x58, 0x54,
+        0x21, 0x00, 0x00, 0x1a, 0x00, 0x00, 0x00, 0xfd, 0x00, 0x28, 0x3c, 0x87, 0x87, 0x38, 0x01, 0x0a, 0x20,
+        0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0xfc, 0x00, 0x44, 0x45, 0x4c, 0x4c, 0x20, 0x50, 0x32,
+        0x34, 0x31, 0x39, 0x48, 0x0a, 0x20, 0x00, 0x00, 0x00, 0xff, 0x00, 0x31, 0x32, 0x33, 0x4e, 0x54, 0x41,
+        0x42, 0x31, 0x32, 0x33, 0x34, 0x35, 0x0a, 0x01, 0x83, 0x02, 0x03, 0x14, 0x70, 0x44, 0x90, 0x04, 0x03,
+        0x01, 0x23, 0x09, 0x07, 0x07, 0x83, 0x01, 0x00, 0x00, 0xe2, 0x00, 0xd5, 0x02, 0x3a, 0x80, 0x18, 0x71,
+        0x38, 0x2d, 0x40, 0x58, 0x2c, 0x45, 0x00, 0x58, 0x54, 0x21, 0x00, 0x00, 0x1e, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x88,
+    ];
+
+    fn assert_close(value: Option<f32>, expected: f32) {
+        let value = value.expect("missing luminance");
+        assert!((value - expected).abs() < 0.01, "{value} != {expected}");
+    }
+
+    #[test]
+    fn hdr_static_metadata() {
+        let caps = get_hdr_capabilities(&HDR_EDID).expect("no hdr capabilities");
+        assert!(caps.traditional_sdr);
+        assert!(!caps.traditional_hdr);
+        assert!(caps.smpte_st2084);
+        assert!(caps.hlg);
+        assert!(caps.static_metadata_type1);
+        assert!(caps.bt2020_rgb);
+        assert!(caps.bt2020_ycc);
+        assert!(!caps.dci_p3);
+        assert!(caps.supports_hdr10());
+        assert_close(caps.max_luminance, 616.88);
+        assert_close(caps.max_frame_average_luminance, 351.25);
+        assert_close(caps.min_luminance, 0.18);
+    }
+
+    #[test]
+    fn no_hdr_static_metadata() {
+        assert_eq!(get_hdr_capabilities(&SDR_EDID), None);
+        // base block only
+        assert_eq!(get_hdr_capabilities(&HDR_EDID[..128]), None);
+    }
+
+    #[test]
+    fn truncated_or_malformed() {
+        // incomplete extension blocks are ignored
+        assert_eq!(get_hdr_capabilities(&HDR_EDID[..200]), None);
+        assert_eq!(get_hdr_capabilities(&[]), None);
+
+        // the data blocks claim to extend past the end of the extension
+        let mut edid = HDR_EDID;
+        edid[128 + 2] = 0xff;
+        for offset in [4, 36, 68, 100] {
+            edid[128 + offset] = 0xff;
+        }
+        assert_eq!(get_hdr_capabilities(&edid), None);
+
+        // hdr static metadata block without its mandatory payload
+        let mut edid = HDR_EDID;
+        edid[128 + 21] = 0xe2;
+        assert_eq!(get_hdr_capabilities(&edid), None);
+    }
+
+    #[test]
+    fn hdr_without_luminance() {
+        // strip the optional luminance values of the hdr static metadata block
+        let mut edid = HDR_EDID;
+        edid[128 + 21] = 0xe3;
+        let caps = get_hdr_capabilities(&edid).expect("no hdr capabilities");
+        assert_eq!(
+            caps,
+            HdrCapabilities {
+                max_luminance: None,
+                max_frame_average_luminance: None,
+                min_luminance: None,
+                ..get_hdr_capabilities(&HDR_EDID).unwrap()
+            }
+        );
+    }
+}
@@ -18,7 +18,7 @@ use crate::utils::{Buffer as BufferCoords, Size};
 #[cfg(feature = "wayland_frontend")]
 use crate::wayland::compositor::{Blocker, BlockerState};
 use std::hash::{Hash, Hasher};
-use std::os::unix::io::{AsFd, BorrowedFd, OwnedFd};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Weak};
 use std::{error, fmt};
@@ -251,6 +251,85 @@ impl Dmabuf {
         let blocker = DmabufBlocker(source.signal.clone());
         Ok((blocker, source))
     }
+
+    /// Map the plane at the given index into memory.
+    ///
+    /// The returned mapping starts at the beginning of the underlying file descriptor,
+    /// the plane data is located at the plane's offset (see [`Dmabuf::offsets`]).
+    /// Chroma planes of vertically subsampled formats (e.g. `NV12`) only cover their reduced height.
+    ///
+    /// Accessing the mapping should be framed by calls to [`Dmabuf::sync_plane`] to ensure coherency
+    /// with other users of the buffer. Mapping will usually only yield useful results for
+    /// buffers with a linear memory layout.
+    pub fn map_plane(
+        &self,
+        idx: usize,
+        mode: DmabufMappingMode,
+    ) -> Result<DmabufMapping, DmabufMappingError> {
+        let plane = self
+            .0
+            .planes
+            .get(idx)
+            .ok_or(DmabufMappingError::InvalidPlane(idx))?;
+
+        // chroma planes of subsampled formats have fewer rows than the buffer
+        let subsampling = if idx > 0 {
+            vertical_subsampling(self.0.format)
+        } else {
+            1
+        };
+        let height = (self.0.size.h as usize + subsampling - 1) / subsampling;
+        let len = plane.offset as usize + plane.stride as usize * height;
+        let mut prot = rustix::mm::ProtFlags::empty();
+        if mode.contains(DmabufMappingMode::READ) {
+            prot |= rustix::mm::ProtFlags::READ;
+        }
+        if mode.contains(DmabufMappingMode::WRITE) {
+            prot |= rustix::mm::ProtFlags::WRITE;
+        }
+
+        let ptr = unsafe {
+            rustix::mm::mmap(
+                std::ptr::null_mut(),
+                len,
+                prot,
+                rustix::mm::MapFlags::SHARED,
+                &plane.fd,
+                0,
+            )
+        }
+        .map_err(|err| DmabufMappingError::Mmap(err.into()))?;
+
+        Ok(DmabufMapping { ptr, len })
+    }
+
+    /// Synchronize access to the plane at the given index.
+    ///
+    /// Every cpu access to a mapping (see [`Dmabuf::map_plane`]) should be started
+    /// by a call with [`DmabufSyncFlags::START`] and ended by a call with [`DmabufSyncFlags::END`].
+    pub fn sync_plane(&self, idx: usize, flags: DmabufSyncFlags) -> std::io::Result<()> {
+        // struct dma_buf_sync { __u64 flags; }
+        // _IOW('b', 0, struct dma_buf_sync)
+        const DMA_BUF_IOCTL_SYNC: libc::c_ulong = 0x4008_6200;
+
+        let plane = self
+            .0
+            .planes
+            .get(idx)
+            .ok_or_else(|| std::io::Error::from_raw_os_error(libc::EINVAL))?;
+        let sync = flags.bits();
+        loop {
+            let res =
+                unsafe { libc::ioctl(plane.fd.as_raw_fd(), DMA_BUF_IOCTL_SYNC as _, &sync as *const u64) };
+            if res == 0 {
+                return Ok(());
+            }
+            let err = std::io::Error::last_os_error();
+            if !matches!(err.raw_os_error(), Some(libc::EINTR) | Some(libc::EAGAIN)) {
+                return Err(err);
+            }
+        }
+    }
 }
 
 impl WeakDmabuf {
@@ -267,6 +346,84 @@ impl WeakDmabuf {
     }
 }
 
+bitflags::bitflags! {
+    /// Access modes of a [`DmabufMapping`]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct DmabufMappingMode: u32 {
+        /// The mapping is readable
+        const READ = 1;
+        /// The mapping is writable
+        const WRITE = 2;
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags for [`Dmabuf::sync_plane`]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct DmabufSyncFlags: u64 {
+        /// Start of a cpu access
+        const START = 0;
+        /// The cpu access reads the buffer
+        const READ = 1;
+        /// The cpu access writes the buffer
+        const WRITE = 2;
+        /// End of a cpu access
+        const END = 4;
+    }
+}
+
+/// Error returned by [`Dmabuf::map_plane`]
+#[derive(Debug, thiserror::Error)]
+pub enum DmabufMappingError {
+    /// The dmabuf has no plane with the given index
+    #[error("The dmabuf has no plane with index {0}")]
+    InvalidPlane(usize),
+    /// Mapping the plane failed
+    #[error("Failed to map the dmabuf plane: {0}")]
+    Mmap(#[source] std::io::Error),
+}
+
+// vertical subsampling factor of the chroma planes of multi-planar yuv formats
+fn vertical_subsampling(code: Fourcc) -> usize {
+    match code {
+        Fourcc::Nv12 | Fourcc::Nv21 | Fourcc::Nv15 | Fourcc::P010 | Fourcc::P012 | Fourcc::P016 => 2,
+        Fourcc::Yuv420 | Fourcc::Yvu420 => 2,
+        Fourcc::Yuv410 | Fourcc::Yvu410 => 4,
+        _ => 1,
+    }
+}
+
+/// A memory mapping of a dmabuf plane
+///
+/// The mapping is removed, once this is dropped.
+#[derive(Debug)]
+pub struct DmabufMapping {
+    ptr: *mut std::ffi::c_void,
+    len: usize,
+}
+
+// SAFETY: The mapping is just a region of memory, which is not bound to any particular thread
+unsafe impl Send for DmabufMapping {}
+unsafe impl Sync for DmabufMapping {}
+
+impl DmabufMapping {
+    /// Pointer to the start of the mapping
+    pub fn ptr(&self) -> *mut std::ffi::c_void {
+        self.ptr
+    }
+
+    /// Length of the mapping in bytes
+    pub fn length(&self) -> usize {
+        self.len
+    }
+}
+
+impl Drop for DmabufMapping {
+    fn drop(&mut self) {
+        let _ = unsafe { rustix::mm::munmap(self.ptr, self.len) };
+    }
+}
+
 /// Buffer that can be exported as Dmabufs
 pub trait AsDmabuf {
     /// Error type returned, if exporting fails
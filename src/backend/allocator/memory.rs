@@ -0,0 +1,186 @@
+//! Module for CPU-accessible memory buffers
+//!
+//! A [`MemoryBuffer`] is a plain chunk of system memory with a known size, stride and pixel format.
+//! Like [`Dmabuf`](super::dmabuf::Dmabuf)s, `MemoryBuffer`s act alike to smart pointers and can be freely
+//! cloned and passed around, all clones referencing the same underlying memory.
+//!
+//! They are mostly useful as rendering targets for renderers working without any graphics hardware,
+//! like the [`SoftwareRenderer`](crate::backend::renderer::software::SoftwareRenderer), or to read back
+//! rendering results on headless setups.
+
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use super::{format::get_bpp, Allocator, Buffer, Format, Fourcc, Modifier};
+use crate::utils::{Buffer as BufferCoords, Size};
+
+/// A buffer backed by system memory
+#[derive(Clone)]
+pub struct MemoryBuffer {
+    mem: Arc<RwLock<Vec<u8>>>,
+    format: Fourcc,
+    size: Size<i32, BufferCoords>,
+    stride: i32,
+}
+
+impl fmt::Debug for MemoryBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryBuffer")
+            .field("format", &self.format)
+            .field("size", &self.size)
+            .field("stride", &self.stride)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for MemoryBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.mem, &other.mem)
+    }
+}
+impl Eq for MemoryBuffer {}
+
+impl MemoryBuffer {
+    /// Create a new zero-initialized buffer with the given format and size
+    ///
+    /// Fails if the bits per pixel of `format` are unknown or
+    /// the size is negative or too large.
+    pub fn new(
+        format: Fourcc,
+        size: impl Into<Size<i32, BufferCoords>>,
+    ) -> Result<MemoryBuffer, MemoryAllocatorError> {
+        let size = size.into();
+        let bytes_per_pixel = get_bpp(format).ok_or(MemoryAllocatorError::UnsupportedFormat(format))? / 8;
+        let invalid_size = || MemoryAllocatorError::InvalidSize(size);
+        if size.w < 0 || size.h < 0 {
+            return Err(invalid_size());
+        }
+        let stride = (size.w as usize)
+            .checked_mul(bytes_per_pixel)
+            .ok_or_else(invalid_size)?;
+        let len = stride.checked_mul(size.h as usize).ok_or_else(invalid_size)?;
+        let stride = i32::try_from(stride).map_err(|_| invalid_size())?;
+        Ok(MemoryBuffer {
+            mem: Arc::new(RwLock::new(vec![0; len])),
+            format,
+            size,
+            stride,
+        })
+    }
+
+    /// Stride of the buffer in bytes
+    pub fn stride(&self) -> i32 {
+        self.stride
+    }
+
+    /// Pixel format of the buffer
+    pub fn fourcc(&self) -> Fourcc {
+        self.format
+    }
+
+    /// Read access to the contents of the buffer
+    ///
+    /// Blocks while the buffer is currently written to.
+    pub fn data(&self) -> MemoryBufferReadGuard<'_> {
+        MemoryBufferReadGuard(self.mem.read().unwrap())
+    }
+
+    /// Write access to the contents of the buffer
+    ///
+    /// Blocks while the buffer is currently accessed.
+    pub fn data_mut(&self) -> MemoryBufferWriteGuard<'_> {
+        MemoryBufferWriteGuard(self.mem.write().unwrap())
+    }
+}
+
+impl Buffer for MemoryBuffer {
+    fn size(&self) -> Size<i32, BufferCoords> {
+        self.size
+    }
+
+    fn format(&self) -> Format {
+        Format {
+            code: self.format,
+            modifier: Modifier::Linear,
+        }
+    }
+}
+
+/// Read access to the contents of a [`MemoryBuffer`]
+#[derive(Debug)]
+pub struct MemoryBufferReadGuard<'a>(RwLockReadGuard<'a, Vec<u8>>);
+
+impl<'a> Deref for MemoryBufferReadGuard<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Write access to the contents of a [`MemoryBuffer`]
+#[derive(Debug)]
+pub struct MemoryBufferWriteGuard<'a>(RwLockWriteGuard<'a, Vec<u8>>);
+
+impl<'a> Deref for MemoryBufferWriteGuard<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'a> DerefMut for MemoryBufferWriteGuard<'a> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+/// Error returned by [`MemoryBuffer::new`] and the [`MemoryAllocator`]
+#[derive(Debug, thiserror::Error)]
+pub enum MemoryAllocatorError {
+    /// The requested format has an unknown amount of bits per pixel
+    #[error("Unsupported format: {0:?}")]
+    UnsupportedFormat(Fourcc),
+    /// The requested size is negative or the buffer would be too large
+    #[error("Invalid buffer size: {0:?}")]
+    InvalidSize(Size<i32, BufferCoords>),
+    /// None of the requested modifiers describe a linear memory layout
+    #[error("Memory buffers are always linear")]
+    UnsupportedModifiers,
+}
+
+/// Allocator for [`MemoryBuffer`]s
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoryAllocator;
+
+impl Allocator for MemoryAllocator {
+    type Buffer = MemoryBuffer;
+    type Error = MemoryAllocatorError;
+
+    fn create_buffer(
+        &mut self,
+        width: u32,
+        height: u32,
+        fourcc: Fourcc,
+        modifiers: &[Modifier],
+    ) -> Result<MemoryBuffer, MemoryAllocatorError> {
+        // memory buffers are always linear
+        if !modifiers.is_empty()
+            && modifiers
+                .iter()
+                .all(|&x| x != Modifier::Invalid && x != Modifier::Linear)
+        {
+            return Err(MemoryAllocatorError::UnsupportedModifiers);
+        }
+
+        let size = (
+            i32::try_from(width).unwrap_or(i32::MAX),
+            i32::try_from(height).unwrap_or(i32::MAX),
+        );
+        MemoryBuffer::new(fourcc, size)
+    }
+}
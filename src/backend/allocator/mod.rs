@@ -6,11 +6,13 @@
 //! Allocators provided:
 //! - Dumb Buffers through [`crate::backend::drm::DrmDevice`]
 //! - Gbm Buffers through [`::gbm::Device`]
+//! - Memory Buffers through [`memory::MemoryAllocator`]
 //!
 //! Buffer types supported:
 //! - [DumbBuffers](dumb::DumbBuffer)
 //! - [GbmBuffers](::gbm::BufferObject)
 //! - [DmaBufs](dmabuf::Dmabuf)
+//! - [MemoryBuffers](memory::MemoryBuffer)
 //!
 //! Helpers:
 //! - [`Swapchain`] to help with buffer management for framebuffers
@@ -21,6 +23,7 @@ pub mod dumb;
 pub mod format;
 #[cfg(feature = "backend_gbm")]
 pub mod gbm;
+pub mod memory;
 #[cfg(feature = "backend_vulkan")]
 pub mod vulkan;
 
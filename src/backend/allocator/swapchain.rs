@@ -234,6 +234,19 @@ where
         self.slots = Default::default();
     }
 
+    /// Change the format of newly returned buffers.
+    ///
+    /// Already obtained buffers are unaffected and will be cleaned up on drop.
+    pub fn set_format(&mut self, fourcc: Fourcc, modifiers: Vec<Modifier>) {
+        if self.fourcc == fourcc && self.modifiers == modifiers {
+            return;
+        }
+
+        self.fourcc = fourcc;
+        self.modifiers = modifiers;
+        self.slots = Default::default();
+    }
+
     /// Remove all internally cached buffers.
     pub fn reset_buffers(&mut self) {
         for slot in &mut self.slots {
@@ -258,4 +271,9 @@ where
     pub fn format(&self) -> Fourcc {
         self.fourcc
     }
+
+    /// Get set modifiers
+    pub fn modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
 }
@@ -166,7 +166,9 @@ use crate::{
     utils::{Buffer as BufferCoords, DevPath, Physical, Point, Rectangle, Scale, Size, Transform},
 };
 
-use super::{DrmDeviceFd, DrmSurface, Framebuffer, PlaneClaim, PlaneInfo, Planes};
+use super::{
+    Colorspace, DrmDeviceFd, DrmSurface, Framebuffer, HdrOutputMetadata, PlaneClaim, PlaneInfo, Planes,
+};
 
 mod elements;
 pub mod gbm;
@@ -710,6 +712,17 @@ impl<B: Framebuffer> FrameState<B> {
         surface.page_flip(self.build_planes(supports_fencing), event)
     }
 
+    #[profiling::function]
+    fn page_flip_async(
+        &mut self,
+        surface: &DrmSurface,
+        supports_fencing: bool,
+        event: bool,
+    ) -> Result<(), crate::backend::drm::error::Error> {
+        debug_assert!(!self.planes.iter().any(|(_, state)| state.needs_test));
+        surface.page_flip_async(self.build_planes(supports_fencing), event)
+    }
+
     #[profiling::function]
     fn build_planes(&mut self, supports_fencing: bool) -> impl IntoIterator<Item = super::PlaneState<'_>> {
         for (_, state) in self.planes.iter_mut().filter(|(_, state)| !state.skip) {
@@ -1404,7 +1417,7 @@ where
 
     current_frame: Frame<A, F>,
     pending_frame: Option<(Frame<A, F>, U)>,
-    queued_frame: Option<(Frame<A, F>, U)>,
+    queued_frame: Option<(Frame<A, F>, U, bool)>,
     next_frame: Option<Frame<A, F>>,
 
     swapchain: Swapchain<A>,
@@ -1571,9 +1584,40 @@ where
         planes: &Planes,
         allocator: A,
         framebuffer_exporter: &F,
-        mut renderer_formats: HashSet<DrmFormat>,
+        renderer_formats: HashSet<DrmFormat>,
         code: DrmFourcc,
     ) -> Result<(Swapchain<A>, Frame<A, F>, bool), (A, FrameErrorType<A, F>)> {
+        let modifiers = match Self::select_modifiers(planes, renderer_formats, code) {
+            Ok(modifiers) => modifiers,
+            Err(err) => return Err((allocator, err)),
+        };
+        let mode = drm.pending_mode();
+
+        let mut swapchain: Swapchain<A> = Swapchain::new(
+            allocator,
+            mode.size().0 as u32,
+            mode.size().1 as u32,
+            code,
+            modifiers,
+        );
+
+        match Self::test_swapchain(
+            &drm,
+            supports_fencing,
+            planes,
+            &mut swapchain,
+            framebuffer_exporter,
+        ) {
+            Ok((current_frame_state, use_opaque)) => Ok((swapchain, current_frame_state, use_opaque)),
+            Err(err) => Err((swapchain.allocator, err)),
+        }
+    }
+
+    fn select_modifiers(
+        planes: &Planes,
+        mut renderer_formats: HashSet<DrmFormat>,
+        code: DrmFourcc,
+    ) -> Result<Vec<DrmModifier>, FrameErrorType<A, F>> {
         // select a format
         let mut plane_formats = planes.primary.formats.clone();
 
@@ -1582,7 +1626,7 @@ where
             .iter()
             .any(|fmt| fmt.code == code || fmt.code == opaque_code)
         {
-            return Err((allocator, FrameError::NoSupportedPlaneFormat));
+            return Err(FrameError::NoSupportedPlaneFormat);
         }
         plane_formats.retain(|fmt| fmt.code == code || fmt.code == opaque_code);
         renderer_formats.retain(|fmt| fmt.code == code);
@@ -1606,9 +1650,9 @@ where
         );
 
         if plane_formats.is_empty() {
-            return Err((allocator, FrameError::NoSupportedPlaneFormat));
+            return Err(FrameError::NoSupportedPlaneFormat);
         } else if renderer_formats.is_empty() {
-            return Err((allocator, FrameError::NoSupportedRendererFormat));
+            return Err(FrameError::NoSupportedRendererFormat);
         }
 
         let formats = {
@@ -1641,39 +1685,39 @@ where
         };
         debug!("Testing Formats: {:?}", formats);
 
-        let modifiers = formats.iter().map(|x| x.modifier).collect::<Vec<_>>();
-        let mode = drm.pending_mode();
-
-        let mut swapchain: Swapchain<A> = Swapchain::new(
-            allocator,
-            mode.size().0 as u32,
-            mode.size().1 as u32,
-            code,
-            modifiers,
-        );
+        Ok(formats.iter().map(|x| x.modifier).collect::<Vec<_>>())
+    }
 
+    fn test_swapchain(
+        drm: &DrmSurface,
+        supports_fencing: bool,
+        planes: &Planes,
+        swapchain: &mut Swapchain<A>,
+        framebuffer_exporter: &F,
+    ) -> Result<(Frame<A, F>, bool), FrameErrorType<A, F>> {
         // Test format
         let buffer = match swapchain.acquire() {
             Ok(buffer) => buffer.unwrap(),
-            Err(err) => return Err((swapchain.allocator, FrameError::Allocator(err))),
+            Err(err) => return Err(FrameError::Allocator(err)),
         };
 
         let dmabuf = match buffer.export() {
             Ok(dmabuf) => dmabuf,
             Err(err) => {
-                return Err((swapchain.allocator, FrameError::AsDmabufError(err)));
+                return Err(FrameError::AsDmabufError(err));
             }
         };
 
-        let use_opaque = !plane_formats.iter().any(|f| f.code == code);
+        let code = swapchain.format();
+        let use_opaque = !planes.primary.formats.iter().any(|f| f.code == code);
         let fb_buffer = match framebuffer_exporter.add_framebuffer(
             drm.device_fd(),
             ExportBuffer::Allocator(&buffer),
             use_opaque,
         ) {
             Ok(Some(fb_buffer)) => fb_buffer,
-            Ok(None) => return Err((swapchain.allocator, FrameError::NoFramebuffer)),
-            Err(err) => return Err((swapchain.allocator, FrameError::FramebufferExport(err))),
+            Ok(None) => return Err(FrameError::NoFramebuffer),
+            Err(err) => return Err(FrameError::FramebufferExport(err)),
         };
         buffer
             .userdata()
@@ -1693,7 +1737,7 @@ where
             Some(claim) => claim,
             None => {
                 warn!("failed to claim primary plane",);
-                return Err((swapchain.allocator, FrameError::PrimaryPlaneClaimFailed));
+                return Err(FrameError::PrimaryPlaneClaimFailed);
             }
         };
 
@@ -1719,11 +1763,11 @@ where
             }),
         };
 
-        match current_frame_state.test_state(&drm, supports_fencing, planes.primary.handle, plane_state, true)
+        match current_frame_state.test_state(drm, supports_fencing, planes.primary.handle, plane_state, true)
         {
             Ok(_) => {
                 debug!("Chosen format: {:?}", dmabuf.format());
-                Ok((swapchain, current_frame_state, use_opaque))
+                Ok((current_frame_state, use_opaque))
             }
             Err(err) => {
                 warn!(
@@ -1731,7 +1775,7 @@ where
                     dmabuf.format(),
                     err
                 );
-                Err((swapchain.allocator, err.into()))
+                Err(err.into())
             }
         }
     }
@@ -2393,6 +2437,24 @@ where
     /// `user_data` can be used to attach some data to a specific buffer and later retrieved with [`DrmCompositor::frame_submitted`]
     #[profiling::function]
     pub fn queue_frame(&mut self, user_data: U) -> FrameResult<(), A, F> {
+        self.queue_frame_internal(user_data, false)
+    }
+
+    /// Queues the current frame for scan-out, allowing it to tear.
+    ///
+    /// This works exactly like [`DrmCompositor::queue_frame`], but if the primary plane of the
+    /// frame is directly scanned out (e.g. for a fullscreen surface), the frame will be presented
+    /// with an async page flip instead of waiting for the next vblank.
+    /// Clients can request this kind of presentation through the `wp_tearing_control_v1` protocol.
+    ///
+    /// Frames that required composition on the primary plane or a modeset are always presented
+    /// synchronized to vblank. The same is true, if the driver rejects the async page flip.
+    #[profiling::function]
+    pub fn queue_frame_async(&mut self, user_data: U) -> FrameResult<(), A, F> {
+        self.queue_frame_internal(user_data, true)
+    }
+
+    fn queue_frame_internal(&mut self, user_data: U, allow_tearing: bool) -> FrameResult<(), A, F> {
         if !self.surface.is_active() {
             return Err(FrameErrorType::<A, F>::DrmError(DrmError::DeviceInactive));
         }
@@ -2416,7 +2478,7 @@ where
             }
         }
 
-        self.queued_frame = Some((next_frame, user_data));
+        self.queued_frame = Some((next_frame, user_data, allow_tearing));
         if self.pending_frame.is_none() {
             self.submit()?;
         }
@@ -2425,10 +2487,25 @@ where
 
     #[profiling::function]
     fn submit(&mut self) -> FrameResult<(), A, F> {
-        let (mut state, user_data) = self.queued_frame.take().unwrap();
+        let (mut state, user_data, allow_tearing) = self.queued_frame.take().unwrap();
+
+        // async page flips are only used for directly scanned out primary planes
+        let allow_tearing = allow_tearing
+            && state
+                .plane_buffer(self.planes.primary.handle)
+                .map(|config| !matches!(&config.buffer, ScanoutBuffer::Swapchain(_)))
+                .unwrap_or(false);
 
         let flip = if self.surface.commit_pending() {
             state.commit(&self.surface, self.supports_fencing, true)
+        } else if allow_tearing {
+            match state.page_flip_async(&self.surface, self.supports_fencing, true) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    debug!(?err, "async page flip failed, falling back to vsync");
+                    state.page_flip(&self.surface, self.supports_fencing, true)
+                }
+            }
         } else {
             state.page_flip(&self.surface, self.supports_fencing, true)
         };
@@ -2554,6 +2631,98 @@ where
         Ok(())
     }
 
+    /// Returns the number of entries per channel of the gamma ramp
+    /// of the underlying [`crtc`](drm::control::crtc).
+    ///
+    /// See [`DrmSurface::gamma_size`] for details.
+    pub fn gamma_size(&self) -> FrameResult<u32, A, F> {
+        self.surface.gamma_size().map_err(FrameError::DrmError)
+    }
+
+    /// Sets a new gamma ramp for the underlying [`crtc`](drm::control::crtc).
+    ///
+    /// See [`DrmSurface::set_gamma`] for details.
+    pub fn set_gamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> FrameResult<(), A, F> {
+        self.surface
+            .set_gamma(red, green, blue)
+            .map_err(FrameError::DrmError)
+    }
+
+    /// Resets the gamma ramp of the underlying [`crtc`](drm::control::crtc) to a linear one.
+    ///
+    /// See [`DrmSurface::reset_gamma`] for details.
+    pub fn reset_gamma(&self) -> FrameResult<(), A, F> {
+        self.surface.reset_gamma().map_err(FrameError::DrmError)
+    }
+
+    /// Tries to set a new [`Colorspace`] to be used after the next commit.
+    ///
+    /// See [`DrmSurface::use_colorspace`] for details.
+    pub fn use_colorspace(&self, colorspace: Colorspace) -> FrameResult<(), A, F> {
+        self.surface
+            .use_colorspace(colorspace)
+            .map_err(FrameError::DrmError)
+    }
+
+    /// Tries to set new [`HdrOutputMetadata`] to be used after the next commit.
+    ///
+    /// See [`DrmSurface::use_hdr_output_metadata`] for details.
+    pub fn use_hdr_output_metadata(&self, metadata: Option<HdrOutputMetadata>) -> FrameResult<(), A, F> {
+        self.surface
+            .use_hdr_output_metadata(metadata)
+            .map_err(FrameError::DrmError)
+    }
+
+    /// Tries to switch the format of the primary plane swapchain.
+    ///
+    /// `color_formats` are tested in order until a working configuration is found, just like in
+    /// [`DrmCompositor::new`], using the `renderer_formats` as reported by the used renderer.
+    ///
+    /// The formats are tested against the pending state of the underlying [`DrmSurface`],
+    /// so this should be called after changing e.g. the hdr metadata, which might require
+    /// a high bit-depth format like [`DrmFourcc::Xrgb2101010`].
+    ///
+    /// On failure the previous format is kept.
+    #[instrument(level = "debug", parent = &self.span, skip_all)]
+    pub fn set_format(
+        &mut self,
+        color_formats: &[DrmFourcc],
+        renderer_formats: HashSet<DrmFormat>,
+    ) -> FrameResult<(), A, F> {
+        let previous_format = self.swapchain.format();
+        let previous_modifiers = self.swapchain.modifiers().to_vec();
+
+        let mut error = None;
+        for format in color_formats {
+            debug!("Testing color format: {}", format);
+            let res = Self::select_modifiers(&self.planes, renderer_formats.clone(), *format).and_then(
+                |modifiers| {
+                    self.swapchain.set_format(*format, modifiers);
+                    Self::test_swapchain(
+                        &self.surface,
+                        self.supports_fencing,
+                        &self.planes,
+                        &mut self.swapchain,
+                        &self.framebuffer_exporter,
+                    )
+                },
+            );
+            match res {
+                Ok((_, is_opaque)) => {
+                    self.primary_is_opaque = is_opaque;
+                    return Ok(());
+                }
+                Err(err) => {
+                    warn!("Preferred format {} not available: {:?}", format, err);
+                    error = Some(err);
+                }
+            }
+        }
+
+        self.swapchain.set_format(previous_format, previous_modifiers);
+        Err(error.unwrap_or(FrameError::NoSupportedPlaneFormat))
+    }
+
     /// Set the [`DebugFlags`] to use
     ///
     /// Note: This will reset the primary plane swapchain if
@@ -2737,6 +2906,16 @@ where
         R: Renderer,
         E: RenderElement<R>,
     {
+        // Nothing would be blended below a translucent primary plane
+        if element.alpha() != 1.0 {
+            trace!(
+                "failed to assign element {:?} to primary {:?}, element is translucent",
+                element.id(),
+                self.planes.primary.handle
+            );
+            return Ok(Err(None));
+        }
+
         let element_config = self.element_config(
             renderer,
             element,
@@ -3644,12 +3823,22 @@ where
             .flatten()
         };
 
+        // Clients using explicit sync may commit before the buffer is ready,
+        // let the kernel wait for the acquire point in that case
+        let sync = match &element_config.buffer.buffer {
+            ScanoutBuffer::Wayland(buffer) if self.supports_fencing => buffer
+                .acquire_point()
+                .filter(|acquire_point| !acquire_point.is_signaled().unwrap_or(true))
+                .map(|acquire_point| (SyncPoint::from(acquire_point.clone()), None)),
+            _ => None,
+        };
+
         let config = PlaneConfig {
             properties: element_config.properties,
             buffer: element_config.buffer.clone(),
             damage_clips,
             plane_claim,
-            sync: None,
+            sync,
         };
 
         let is_compatible = previous_state
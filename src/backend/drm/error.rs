@@ -65,6 +65,17 @@ pub enum Error {
         /// Property name
         name: &'static str,
     },
+    /// The provided gamma ramp does not match the gamma size of the crtc
+    #[error("Gamma ramp does not match the gamma size ({expected}) of crtc `{crtc:?}`")]
+    InvalidGammaSize {
+        /// CRTC
+        crtc: crtc::Handle,
+        /// Expected number of entries per channel
+        expected: usize,
+    },
+    /// The requested colorspace or hdr metadata is not supported by the connectors of the crtc
+    #[error("The requested colorimetry is not supported by the connectors of crtc `{0:?}`")]
+    UnsupportedColorimetry(crtc::Handle),
     /// Atomic Test failed for new properties
     #[error("Atomic Test failed for new properties on crtc ({0:?})")]
     TestFailed(crtc::Handle),
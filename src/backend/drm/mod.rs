@@ -92,7 +92,9 @@ pub use error::Error as DrmError;
 pub use node::{CreateDrmNodeError, DrmNode, NodeType};
 #[cfg(feature = "backend_gbm")]
 pub use surface::gbm::{Error as GbmBufferedSurfaceError, GbmBufferedSurface};
-pub use surface::{DrmSurface, PlaneConfig, PlaneDamageClips, PlaneState};
+pub use surface::{
+    Colorspace, DrmSurface, HdrEotf, HdrOutputMetadata, PlaneConfig, PlaneDamageClips, PlaneState,
+};
 
 use drm::{
     control::{crtc, framebuffer, plane, Device as ControlDevice, PlaneType},
@@ -5,7 +5,7 @@ use drm::control::{
 };
 
 use std::collections::HashSet;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsFd, AsRawFd};
 use std::sync::Mutex;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
@@ -28,13 +28,16 @@ use crate::{
 
 use tracing::{debug, info, info_span, instrument, trace, warn};
 
-use super::{PlaneConfig, PlaneState};
+use super::{Colorspace, HdrOutputMetadata, PlaneConfig, PlaneState};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct State {
     pub mode: Mode,
     pub blob: property::Value<'static>,
     pub connectors: HashSet<connector::Handle>,
+    // raw value of the `Colorspace` enum property
+    pub colorspace: u64,
+    pub hdr_output_metadata: property::Value<'static>,
 }
 
 impl State {
@@ -74,6 +77,9 @@ impl State {
         //
         // If they don't match, `commit_pending` will return true and they will be changed on the next `commit`.
         let mut current_connectors = HashSet::new();
+        // the colorimetry is read from any connector using our crtc, as we always set it for all of them
+        let mut current_colorspace = 0;
+        let mut current_hdr_output_metadata = property::Value::Blob(0);
         // make sure the mapping is up to date
         map_props(fd, res_handles.connectors(), &mut prop_mapping.0)?;
         for conn in res_handles.connectors() {
@@ -101,12 +107,25 @@ impl State {
                         break;
                     }
                 }
+
+                if current_connectors.contains(conn) {
+                    let conn_props = prop_mapping.0.get(conn).expect("Unknown handle");
+                    for (&id, &val) in ids.iter().zip(vals.iter()) {
+                        if conn_props.get("Colorspace") == Some(&id) {
+                            current_colorspace = val;
+                        } else if conn_props.get("HDR_OUTPUT_METADATA") == Some(&id) {
+                            current_hdr_output_metadata = property::Value::Blob(val);
+                        }
+                    }
+                }
             }
         }
         Ok(State {
             mode: current_mode,
             blob: current_blob,
             connectors: current_connectors,
+            colorspace: current_colorspace,
+            hdr_output_metadata: current_hdr_output_metadata,
         })
     }
 }
@@ -121,6 +140,12 @@ pub struct AtomicDrmSurface {
     prop_mapping: RwLock<Mapping>,
     state: RwLock<State>,
     pending: RwLock<State>,
+    // gamma lut blob created by us, that is currently applied
+    gamma_lut: Mutex<Option<property::Value<'static>>>,
+    // gamma lut to be set with the next commit or page flip, a null blob resets the lut
+    pending_gamma_lut: Mutex<Option<property::Value<'static>>>,
+    // hdr metadata blobs created by us, the blob of the current state might be owned by a previous drm master
+    hdr_metadata_blobs: Mutex<HashSet<u64>>,
     pub(super) span: tracing::Span,
 }
 
@@ -152,6 +177,8 @@ impl AtomicDrmSurface {
             mode,
             blob,
             connectors: connectors.iter().copied().collect(),
+            colorspace: 0,
+            hdr_output_metadata: property::Value::Blob(0),
         };
 
         drop(_guard);
@@ -164,6 +191,9 @@ impl AtomicDrmSurface {
             prop_mapping: RwLock::new(prop_mapping),
             state: RwLock::new(state),
             pending: RwLock::new(pending),
+            gamma_lut: Mutex::new(None),
+            pending_gamma_lut: Mutex::new(None),
+            hdr_metadata_blobs: Mutex::new(HashSet::new()),
             span,
         };
 
@@ -275,6 +305,8 @@ impl AtomicDrmSurface {
         // check if the connector can handle the current mode
         if info.modes().contains(&pending.mode) {
             let test_buffer = self.create_test_buffer(pending.mode.size(), self.plane)?;
+            let mut state = pending.clone();
+            state.connectors.insert(conn);
 
             // check if config is supported
             let req = self.build_request(
@@ -295,7 +327,7 @@ impl AtomicDrmSurface {
                         fence: None,
                     }),
                 }],
-                Some(pending.blob),
+                Some(&state),
             )?;
             self.fd
                 .atomic_commit(
@@ -328,6 +360,8 @@ impl AtomicDrmSurface {
 
         // check if new config is supported (should be)
         let test_buffer = self.create_test_buffer(pending.mode.size(), self.plane)?;
+        let mut state = pending.clone();
+        state.connectors.remove(&conn);
 
         let req = self.build_request(
             &mut [].iter(),
@@ -347,7 +381,7 @@ impl AtomicDrmSurface {
                     fence: None,
                 }),
             }],
-            Some(pending.blob),
+            Some(&state),
         )?;
         self.fd
             .atomic_commit(
@@ -382,6 +416,8 @@ impl AtomicDrmSurface {
         let mut removed = current.connectors.difference(&conns);
 
         let test_buffer = self.create_test_buffer(pending.mode.size(), self.plane)?;
+        let mut state = pending.clone();
+        state.connectors = conns.clone();
 
         let req = self.build_request(
             &mut added,
@@ -401,7 +437,7 @@ impl AtomicDrmSurface {
                     fence: None,
                 }),
             }],
-            Some(pending.blob),
+            Some(&state),
         )?;
 
         self.fd
@@ -435,6 +471,9 @@ impl AtomicDrmSurface {
             })?;
 
         let test_buffer = self.create_test_buffer(mode.size(), self.plane)?;
+        let mut state = pending.clone();
+        state.mode = mode;
+        state.blob = new_blob;
 
         let req = self.build_request(
             &mut pending.connectors.iter(),
@@ -454,7 +493,7 @@ impl AtomicDrmSurface {
                     fence: None,
                 }),
             }],
-            Some(new_blob),
+            Some(&state),
         )?;
         if let Err(err) = self
             .fd
@@ -475,6 +514,161 @@ impl AtomicDrmSurface {
         Ok(())
     }
 
+    #[instrument(level = "debug", parent = &self.span, skip(self))]
+    pub fn use_colorspace(&self, colorspace: Colorspace) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        let mut pending = self.pending.write().unwrap();
+
+        let value = if colorspace == Colorspace::Default {
+            0
+        } else {
+            self.colorspace_value(&pending.connectors, colorspace)?
+        };
+
+        // check if new config is supported
+        let mut state = pending.clone();
+        state.colorspace = value;
+        self.test_pending_state(&state)?;
+
+        pending.colorspace = value;
+
+        Ok(())
+    }
+
+    // the raw values of the `Colorspace` property are the same for all connectors,
+    // but the supported set of values differs.
+    fn colorspace_value(
+        &self,
+        connectors: &HashSet<connector::Handle>,
+        colorspace: Colorspace,
+    ) -> Result<u64, Error> {
+        let prop_mapping = self.prop_mapping.read().unwrap();
+
+        let mut value = None;
+        for conn in connectors {
+            let prop = conn_prop_handle(&prop_mapping, *conn, "Colorspace")
+                .map_err(|_| Error::UnsupportedColorimetry(self.crtc))?;
+            let info = self.fd.get_property(prop).map_err(|source| Error::Access {
+                errmsg: "Error loading property info",
+                dev: self.fd.dev_path(),
+                source,
+            })?;
+            let property::ValueType::Enum(values) = info.value_type() else {
+                return Err(Error::UnsupportedColorimetry(self.crtc));
+            };
+            let (_, enums) = values.values();
+            let raw = enums
+                .iter()
+                .find(|val| val.name().to_bytes() == colorspace.name().as_bytes())
+                .ok_or(Error::UnsupportedColorimetry(self.crtc))?
+                .value();
+            value = Some(raw);
+        }
+
+        value.ok_or(Error::UnsupportedColorimetry(self.crtc))
+    }
+
+    #[instrument(level = "debug", parent = &self.span, skip(self))]
+    pub fn use_hdr_output_metadata(&self, metadata: Option<HdrOutputMetadata>) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        let current = self.state.read().unwrap();
+        let mut pending = self.pending.write().unwrap();
+
+        let blob = match metadata {
+            Some(metadata) => {
+                let supported = {
+                    let prop_mapping = self.prop_mapping.read().unwrap();
+                    pending
+                        .connectors
+                        .iter()
+                        .all(|conn| conn_prop_handle(&prop_mapping, *conn, "HDR_OUTPUT_METADATA").is_ok())
+                };
+                if !supported {
+                    return Err(Error::UnsupportedColorimetry(self.crtc));
+                }
+
+                let blob = self
+                    .fd
+                    .create_property_blob(&metadata.to_raw())
+                    .map_err(|source| Error::Access {
+                        errmsg: "Failed to create Property Blob for hdr metadata",
+                        dev: self.fd.dev_path(),
+                        source,
+                    })?;
+                if let property::Value::Blob(id) = blob {
+                    self.hdr_metadata_blobs.lock().unwrap().insert(id);
+                }
+                blob
+            }
+            None => property::Value::Blob(0),
+        };
+
+        // check if new config is supported
+        let mut state = pending.clone();
+        state.hdr_output_metadata = blob;
+        if let Err(err) = self.test_pending_state(&state) {
+            self.destroy_hdr_metadata_blob(blob);
+            return Err(err);
+        }
+
+        let old = std::mem::replace(&mut pending.hdr_output_metadata, blob);
+        // an uncommitted blob is not referenced anywhere else
+        if old != current.hdr_output_metadata {
+            self.destroy_hdr_metadata_blob(old);
+        }
+
+        Ok(())
+    }
+
+    // destroys a hdr metadata blob, if it was created by us
+    fn destroy_hdr_metadata_blob(&self, blob: property::Value<'static>) {
+        if let property::Value::Blob(id) = blob {
+            if self.hdr_metadata_blobs.lock().unwrap().remove(&id) {
+                if let Err(err) = self.fd.destroy_property_blob(id) {
+                    warn!("Failed to destroy hdr metadata property blob: {}", err);
+                }
+            }
+        }
+    }
+
+    // test a modified pending state with a test buffer on the primary plane
+    fn test_pending_state(&self, state: &State) -> Result<(), Error> {
+        let test_buffer = self.create_test_buffer(state.mode.size(), self.plane)?;
+
+        let req = self.build_request(
+            &mut state.connectors.iter(),
+            &mut [].iter(),
+            [&PlaneState {
+                handle: self.plane,
+                config: Some(PlaneConfig {
+                    src: Rectangle::from_loc_and_size(Point::default(), state.mode.size()).to_f64(),
+                    dst: Rectangle::from_loc_and_size(
+                        Point::default(),
+                        (state.mode.size().0 as i32, state.mode.size().1 as i32),
+                    ),
+                    transform: Transform::Normal,
+                    alpha: 1.0,
+                    damage_clips: None,
+                    fb: test_buffer.fb,
+                    fence: None,
+                }),
+            }],
+            Some(state),
+        )?;
+        self.fd
+            .atomic_commit(
+                AtomicCommitFlags::ALLOW_MODESET | AtomicCommitFlags::TEST_ONLY,
+                req,
+            )
+            .map_err(|_| Error::TestFailed(self.crtc))
+    }
+
     pub fn commit_pending(&self) -> bool {
         *self.pending.read().unwrap() != *self.state.read().unwrap()
     }
@@ -500,7 +694,8 @@ impl AtomicDrmSurface {
         let mut removed = current_conns.difference(&pending_conns);
         let mut added = pending_conns.difference(&current_conns);
 
-        let req = self.build_request(&mut added, &mut removed, &*planes, Some(pending.blob))?;
+        let mut req = self.build_request(&mut added, &mut removed, &*planes, Some(&*pending))?;
+        self.add_pending_gamma_lut(&mut req)?;
 
         let flags = if allow_modeset {
             AtomicCommitFlags::ALLOW_MODESET | AtomicCommitFlags::TEST_ONLY
@@ -561,8 +756,9 @@ impl AtomicDrmSurface {
         trace!("Testing screen config");
 
         // test the new config and return the request if it would be accepted by the driver.
-        let req = {
-            let req = self.build_request(&mut added, &mut removed, &*planes, Some(pending.blob))?;
+        let (req, gamma_lut) = {
+            let mut req = self.build_request(&mut added, &mut removed, &*planes, Some(&*pending))?;
+            let gamma_lut = self.add_pending_gamma_lut(&mut req)?;
 
             if let Err(err) = self
                 .fd
@@ -583,7 +779,7 @@ impl AtomicDrmSurface {
                 }
 
                 // new config
-                req
+                (req, gamma_lut)
             }
         };
 
@@ -614,7 +810,11 @@ impl AtomicDrmSurface {
             });
 
         if result.is_ok() {
+            if current.hdr_output_metadata != pending.hdr_output_metadata {
+                self.destroy_hdr_metadata_blob(current.hdr_output_metadata);
+            }
             *current = pending.clone();
+            self.apply_pending_gamma_lut(gamma_lut);
             for plane in planes.iter() {
                 if plane.config.is_some() {
                     used_planes.insert(plane.handle);
@@ -633,6 +833,7 @@ impl AtomicDrmSurface {
         &self,
         planes: impl IntoIterator<Item = PlaneState<'a>>,
         event: bool,
+        async_flip: bool,
     ) -> Result<(), Error> {
         if !self.active.load(Ordering::SeqCst) {
             return Err(Error::DeviceInactive);
@@ -642,29 +843,33 @@ impl AtomicDrmSurface {
         let planes = planes.into_iter().collect::<Vec<_>>();
 
         // page flips work just like commits with fewer parameters..
-        let req = self.build_request(&mut [].iter(), &mut [].iter(), &*planes, None)?;
+        let mut req = self.build_request(&mut [].iter(), &mut [].iter(), &*planes, None)?;
+        // gamma changes can be applied without a modeset, but not with an async flip
+        let gamma_lut = if async_flip {
+            None
+        } else {
+            self.add_pending_gamma_lut(&mut req)?
+        };
 
         // .. and without `AtomicCommitFlags::AllowModeset`.
         // If we would set anything here, that would require a modeset, this would fail,
         // indicating a problem in our assumptions.
-        trace!(?planes, "Queueing page flip: {:?}", req);
-        let res = self
-            .fd
-            .atomic_commit(
-                if event {
-                    AtomicCommitFlags::PAGE_FLIP_EVENT | AtomicCommitFlags::NONBLOCK
-                } else {
-                    AtomicCommitFlags::NONBLOCK
-                },
-                req,
-            )
-            .map_err(|source| Error::Access {
-                errmsg: "Page flip commit failed",
-                dev: self.fd.dev_path(),
-                source,
-            });
+        trace!(?planes, async_flip, "Queueing page flip: {:?}", req);
+        let mut flags = AtomicCommitFlags::NONBLOCK;
+        if event {
+            flags |= AtomicCommitFlags::PAGE_FLIP_EVENT;
+        }
+        if async_flip {
+            flags |= AtomicCommitFlags::PAGE_FLIP_ASYNC;
+        }
+        let res = self.fd.atomic_commit(flags, req).map_err(|source| Error::Access {
+            errmsg: "Page flip commit failed",
+            dev: self.fd.dev_path(),
+            source,
+        });
 
         if res.is_ok() {
+            self.apply_pending_gamma_lut(gamma_lut);
             for plane in planes.iter() {
                 if plane.config.is_some() {
                     used_planes.insert(plane.handle);
@@ -677,7 +882,7 @@ impl AtomicDrmSurface {
         res
     }
 
-    // If a mode is set a matching blob needs to be set (the inverse is not true)
+    // If a state is given, its mode and the colorimetry of its connectors are set as well
     #[allow(clippy::too_many_arguments)]
     #[profiling::function]
     pub fn build_request<'a>(
@@ -685,7 +890,7 @@ impl AtomicDrmSurface {
         new_connectors: &mut dyn Iterator<Item = &connector::Handle>,
         removed_connectors: &mut dyn Iterator<Item = &connector::Handle>,
         planes: impl IntoIterator<Item = &'a PlaneState<'a>>,
-        blob: Option<property::Value<'static>>,
+        state: Option<&State>,
     ) -> Result<AtomicModeReq, Error> {
         let prop_mapping = self.prop_mapping.read().unwrap();
 
@@ -717,12 +922,29 @@ impl AtomicDrmSurface {
         }
 
         // we need to set the new mode, if there is one
-        if let Some(blob) = blob {
+        if let Some(state) = state {
             req.add_property(
                 self.crtc,
                 crtc_prop_handle(&prop_mapping, self.crtc, "MODE_ID")?,
-                blob,
+                state.blob,
             );
+
+            // the colorimetry is set on the connectors, but applied together with the mode.
+            // Setting unchanged values does not trigger a modeset.
+            for conn in state.connectors.iter() {
+                match conn_prop_handle(&prop_mapping, *conn, "Colorspace") {
+                    Ok(prop) => {
+                        req.add_property(*conn, prop, property::Value::UnsignedRange(state.colorspace))
+                    }
+                    Err(err) if state.colorspace != 0 => return Err(err),
+                    Err(_) => {}
+                }
+                match conn_prop_handle(&prop_mapping, *conn, "HDR_OUTPUT_METADATA") {
+                    Ok(prop) => req.add_property(*conn, prop, state.hdr_output_metadata),
+                    Err(err) if state.hdr_output_metadata != property::Value::Blob(0) => return Err(err),
+                    Err(_) => {}
+                }
+            }
         }
 
         // we also need to set this crtc active
@@ -1025,6 +1247,156 @@ impl AtomicDrmSurface {
         result
     }
 
+    pub fn gamma_size(&self) -> Result<u32, Error> {
+        let Ok(prop) = crtc_prop_handle(&self.prop_mapping.read().unwrap(), self.crtc, "GAMMA_LUT_SIZE")
+        else {
+            // no color management support
+            return Ok(0);
+        };
+        let props = self
+            .fd
+            .get_properties(self.crtc)
+            .map_err(|source| Error::Access {
+                errmsg: "Error reading crtc properties",
+                dev: self.fd.dev_path(),
+                source,
+            })?;
+        let (ids, vals) = props.as_props_and_values();
+        ids.iter()
+            .zip(vals.iter())
+            .find(|(id, _)| **id == prop)
+            .map(|(_, val)| *val as u32)
+            .ok_or(Error::UnknownProperty {
+                handle: self.crtc.into(),
+                name: "GAMMA_LUT_SIZE",
+            })
+    }
+
+    #[instrument(level = "debug", parent = &self.span, skip(self, red, green, blue))]
+    pub fn set_gamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        let size = self.gamma_size()? as usize;
+        if red.len() != size || green.len() != size || blue.len() != size {
+            return Err(Error::InvalidGammaSize {
+                crtc: self.crtc,
+                expected: size,
+            });
+        }
+
+        let prop = crtc_prop_handle(&self.prop_mapping.read().unwrap(), self.crtc, "GAMMA_LUT")?;
+
+        // the kernel expects an array of `struct drm_color_lut`
+        let mut lut = red
+            .iter()
+            .zip(green.iter())
+            .zip(blue.iter())
+            .map(|((r, g), b)| drm_ffi::drm_color_lut {
+                red: *r,
+                green: *g,
+                blue: *b,
+                reserved: 0,
+            })
+            .collect::<Vec<_>>();
+        // SAFETY: `drm_color_lut` is a `#[repr(C)]` struct of plain integers without padding,
+        // so the vector's initialized elements can be viewed as bytes. `lut` outlives `data`
+        // and is not accessed otherwise while the byte slice is alive.
+        let data = unsafe {
+            std::slice::from_raw_parts_mut(
+                lut.as_mut_ptr() as *mut u8,
+                std::mem::size_of::<drm_ffi::drm_color_lut>() * lut.len(),
+            )
+        };
+        let blob = drm_ffi::mode::create_property_blob(self.fd.as_fd().as_raw_fd(), data)
+            .map(|blob| property::Value::Blob(blob.blob_id as u64))
+            .map_err(|source| Error::Access {
+                errmsg: "Failed to create property blob for gamma lut",
+                dev: self.fd.dev_path(),
+                source,
+            })?;
+
+        // check if the lut is accepted, without waiting for a pending page flip
+        let mut req = AtomicModeReq::new();
+        req.add_property(self.crtc, prop, blob);
+        if let Err(err) = self
+            .fd
+            .atomic_commit(AtomicCommitFlags::TEST_ONLY, req)
+            .map_err(|_| Error::TestFailed(self.crtc))
+        {
+            if let property::Value::Blob(id) = blob {
+                let _ = self.fd.destroy_property_blob(id);
+            }
+            return Err(err);
+        }
+
+        self.queue_gamma_lut(blob);
+        Ok(())
+    }
+
+    #[instrument(level = "debug", parent = &self.span, skip(self))]
+    pub fn reset_gamma(&self) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        // a null blob restores the default (linear) lut
+        self.queue_gamma_lut(property::Value::Blob(0));
+        Ok(())
+    }
+
+    // replaces the pending gamma lut, destroying a previously queued blob
+    fn queue_gamma_lut(&self, blob: property::Value<'static>) {
+        let current = self.gamma_lut.lock().unwrap();
+        let old = self.pending_gamma_lut.lock().unwrap().replace(blob);
+        if let Some(property::Value::Blob(old)) = old {
+            if old != 0 && *current != Some(property::Value::Blob(old)) {
+                if let Err(err) = self.fd.destroy_property_blob(old) {
+                    warn!("Failed to destroy old gamma lut property blob: {}", err);
+                }
+            }
+        }
+    }
+
+    // adds the pending gamma lut to a request and returns it
+    fn add_pending_gamma_lut(
+        &self,
+        req: &mut AtomicModeReq,
+    ) -> Result<Option<property::Value<'static>>, Error> {
+        let Some(blob) = *self.pending_gamma_lut.lock().unwrap() else {
+            return Ok(None);
+        };
+        req.add_property(
+            self.crtc,
+            crtc_prop_handle(&self.prop_mapping.read().unwrap(), self.crtc, "GAMMA_LUT")?,
+            blob,
+        );
+        Ok(Some(blob))
+    }
+
+    // marks a gamma lut as applied after a successful commit
+    fn apply_pending_gamma_lut(&self, blob: Option<property::Value<'static>>) {
+        let Some(blob) = blob else {
+            return;
+        };
+
+        let mut current = self.gamma_lut.lock().unwrap();
+        let mut pending = self.pending_gamma_lut.lock().unwrap();
+        // the lut might have been replaced in the meantime
+        if *pending == Some(blob) {
+            *pending = None;
+        }
+        let new = (blob != property::Value::Blob(0)).then_some(blob);
+        if let Some(property::Value::Blob(old)) = std::mem::replace(&mut *current, new) {
+            if Some(property::Value::Blob(old)) != new && *pending != Some(property::Value::Blob(old)) {
+                if let Err(err) = self.fd.destroy_property_blob(old) {
+                    warn!("Failed to destroy old gamma lut property blob: {}", err);
+                }
+            }
+        }
+    }
+
     pub(crate) fn reset_state<B: DevPath + ControlDevice + 'static>(
         &self,
         fd: Option<&B>,
@@ -1034,6 +1406,12 @@ impl AtomicDrmSurface {
         } else {
             State::current_state(&*self.fd, self.crtc, &mut self.prop_mapping.write().unwrap())?
         };
+
+        // whoever had the device in the meantime might have changed the gamma lut,
+        // so restore ours with the next commit, unless another one is queued already.
+        if let Some(blob) = *self.gamma_lut.lock().unwrap() {
+            self.pending_gamma_lut.lock().unwrap().get_or_insert(blob);
+        }
         Ok(())
     }
 
@@ -1063,6 +1441,19 @@ impl Drop for TestBuffer {
 
 impl Drop for AtomicDrmSurface {
     fn drop(&mut self) {
+        let gamma_lut = self.gamma_lut.get_mut().unwrap().take();
+        if let Some(property::Value::Blob(blob)) = gamma_lut {
+            let _ = self.fd.destroy_property_blob(blob);
+        }
+        if let Some(property::Value::Blob(blob)) = self.pending_gamma_lut.get_mut().unwrap().take() {
+            if blob != 0 && gamma_lut != Some(property::Value::Blob(blob)) {
+                let _ = self.fd.destroy_property_blob(blob);
+            }
+        }
+        for blob in self.hdr_metadata_blobs.get_mut().unwrap().drain() {
+            let _ = self.fd.destroy_property_blob(blob);
+        }
+
         if !self.active.load(Ordering::SeqCst) {
             // the device is gone or we are on another tty
             // old state has been restored, we shouldn't touch it.
@@ -1093,6 +1484,14 @@ impl Drop for AtomicDrmSurface {
                 .get("CRTC_ID")
                 .expect("Unknown property CRTC_ID");
             req.add_property(*conn, *prop, property::Value::CRTC(None));
+
+            // don't leave the sinks in hdr mode
+            if let Ok(prop) = conn_prop_handle(&prop_mapping, *conn, "Colorspace") {
+                req.add_property(*conn, prop, property::Value::UnsignedRange(0));
+            }
+            if let Ok(prop) = conn_prop_handle(&prop_mapping, *conn, "HDR_OUTPUT_METADATA") {
+                req.add_property(*conn, prop, property::Value::Blob(0));
+            }
         }
         let active_prop = prop_mapping
             .1
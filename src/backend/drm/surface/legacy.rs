@@ -3,7 +3,7 @@ use drm::control::{connector, crtc, encoder, framebuffer, Device as ControlDevic
 use std::collections::HashSet;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Arc, RwLock,
+    Arc, Mutex, RwLock,
 };
 
 use crate::{
@@ -13,7 +13,7 @@ use crate::{
     utils::DevPath,
 };
 
-use tracing::{debug, info, info_span, instrument, trace};
+use tracing::{debug, info, info_span, instrument, trace, warn};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct State {
@@ -77,6 +77,7 @@ pub struct LegacyDrmSurface {
     crtc: crtc::Handle,
     state: RwLock<State>,
     pending: RwLock<State>,
+    gamma: Mutex<Option<[Vec<u16>; 3]>>,
     pub(super) span: tracing::Span,
 }
 
@@ -105,6 +106,7 @@ impl LegacyDrmSurface {
             crtc,
             state: RwLock::new(state),
             pending: RwLock::new(pending),
+            gamma: Mutex::new(None),
             span,
         };
 
@@ -307,28 +309,32 @@ impl LegacyDrmSurface {
 
     #[instrument(level = "trace", parent = &self.span, skip(self))]
     #[profiling::function]
-    pub fn page_flip(&self, framebuffer: framebuffer::Handle, event: bool) -> Result<(), Error> {
-        trace!("Queueing Page flip");
+    pub fn page_flip(
+        &self,
+        framebuffer: framebuffer::Handle,
+        event: bool,
+        async_flip: bool,
+    ) -> Result<(), Error> {
+        trace!(async_flip, "Queueing Page flip");
 
         if !self.active.load(Ordering::SeqCst) {
             return Err(Error::DeviceInactive);
         }
 
-        ControlDevice::page_flip(
-            &*self.fd,
-            self.crtc,
-            framebuffer,
-            if event {
-                PageFlipFlags::EVENT
-            } else {
-                PageFlipFlags::empty()
-            },
-            None,
-        )
-        .map_err(|source| Error::Access {
-            errmsg: "Failed to page flip",
-            dev: self.fd.dev_path(),
-            source,
+        let mut flags = PageFlipFlags::empty();
+        if event {
+            flags |= PageFlipFlags::EVENT;
+        }
+        if async_flip {
+            flags |= PageFlipFlags::ASYNC;
+        }
+
+        ControlDevice::page_flip(&*self.fd, self.crtc, framebuffer, flags, None).map_err(|source| {
+            Error::Access {
+                errmsg: "Failed to page flip",
+                dev: self.fd.dev_path(),
+                source,
+            }
         })
     }
 
@@ -413,6 +419,70 @@ impl LegacyDrmSurface {
         }
     }
 
+    pub fn gamma_size(&self) -> Result<u32, Error> {
+        self.fd
+            .get_crtc(self.crtc)
+            .map(|info| info.gamma_length())
+            .map_err(|source| Error::Access {
+                errmsg: "Error loading crtc info",
+                dev: self.fd.dev_path(),
+                source,
+            })
+    }
+
+    #[instrument(level = "debug", parent = &self.span, skip(self, red, green, blue))]
+    pub fn set_gamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        let size = self.gamma_size()? as usize;
+        if red.len() != size || green.len() != size || blue.len() != size {
+            return Err(Error::InvalidGammaSize {
+                crtc: self.crtc,
+                expected: size,
+            });
+        }
+
+        let mut gamma = self.gamma.lock().unwrap();
+        self.commit_gamma(red, green, blue)?;
+        *gamma = Some([red.to_vec(), green.to_vec(), blue.to_vec()]);
+        Ok(())
+    }
+
+    #[instrument(level = "debug", parent = &self.span, skip(self))]
+    pub fn reset_gamma(&self) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        // the legacy api has no notion of a default ramp, so we set a linear one.
+        let size = self.gamma_size()? as usize;
+        if size == 0 {
+            // no gamma ramp to reset, the ioctl would fail anyway
+            *self.gamma.lock().unwrap() = None;
+            return Ok(());
+        }
+        let ramp = (0..size)
+            .map(|i| (i * u16::MAX as usize / size.saturating_sub(1).max(1)) as u16)
+            .collect::<Vec<_>>();
+
+        let mut gamma = self.gamma.lock().unwrap();
+        self.commit_gamma(&ramp, &ramp, &ramp)?;
+        *gamma = None;
+        Ok(())
+    }
+
+    fn commit_gamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> Result<(), Error> {
+        self.fd
+            .set_gamma(self.crtc, red, green, blue)
+            .map_err(|source| Error::Access {
+                errmsg: "Failed to set gamma",
+                dev: self.fd.dev_path(),
+                source,
+            })
+    }
+
     pub(crate) fn reset_state<B: DevPath + ControlDevice + 'static>(
         &self,
         fd: Option<&B>,
@@ -422,6 +492,16 @@ impl LegacyDrmSurface {
         } else {
             State::current_state(&*self.fd, self.crtc)?
         };
+
+        // whoever had the device in the meantime might have changed the gamma ramp,
+        // so restore ours, if we have set one.
+        if self.active.load(Ordering::SeqCst) {
+            if let Some([red, green, blue]) = &*self.gamma.lock().unwrap() {
+                if let Err(err) = self.commit_gamma(red, green, blue) {
+                    warn!("Failed to restore gamma ramp: {}", err);
+                }
+            }
+        }
         Ok(())
     }
 
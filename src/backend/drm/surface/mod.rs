@@ -154,6 +154,140 @@ pub struct PlaneConfig<'a> {
     pub fence: Option<BorrowedFd<'a>>,
 }
 
+/// Colorimetry signaled to the sinks of a [`DrmSurface`] via the `Colorspace` connector property
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Colorspace {
+    /// Default colorimetry of the sink, usually sRGB / BT.709
+    #[default]
+    Default,
+    /// ITU-R BT.709 YCbCr
+    Bt709Ycc,
+    /// ITU-R BT.2020 RGB, as used by HDR10
+    Bt2020Rgb,
+    /// ITU-R BT.2020 YCbCr
+    Bt2020Ycc,
+    /// ITU-R BT.2020 constant luminance YCbCr
+    Bt2020Cycc,
+    /// DCI-P3 RGB with a D65 white point
+    DciP3RgbD65,
+    /// DCI-P3 RGB with the theater white point
+    DciP3RgbTheater,
+    /// opRGB (formerly Adobe RGB)
+    OpRgb,
+}
+
+impl Colorspace {
+    /// Name of the matching enum value of the `Colorspace` property
+    pub fn name(&self) -> &'static str {
+        match self {
+            Colorspace::Default => "Default",
+            Colorspace::Bt709Ycc => "BT709_YCC",
+            Colorspace::Bt2020Rgb => "BT2020_RGB",
+            Colorspace::Bt2020Ycc => "BT2020_YCC",
+            Colorspace::Bt2020Cycc => "BT2020_CYCC",
+            Colorspace::DciP3RgbD65 => "DCI-P3_RGB_D65",
+            Colorspace::DciP3RgbTheater => "DCI-P3_RGB_Theater",
+            Colorspace::OpRgb => "opRGB",
+        }
+    }
+}
+
+/// Electro-optical transfer function signaled via [`HdrOutputMetadata`] (see CTA-861-G)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HdrEotf {
+    /// Traditional gamma with SDR luminance range
+    TraditionalSdr,
+    /// Traditional gamma with HDR luminance range
+    TraditionalHdr,
+    /// SMPTE ST 2084 (PQ), as used by HDR10
+    SmpteSt2084,
+    /// Hybrid Log-Gamma (ITU-R BT.2100)
+    Hlg,
+}
+
+/// Static HDR metadata sent to the sinks of a [`DrmSurface`] via the `HDR_OUTPUT_METADATA`
+/// connector property, which results in a HDR infoframe (static metadata type 1).
+///
+/// Chromaticities are given as CIE 1931 xy coordinates, luminances in cd/m².
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HdrOutputMetadata {
+    /// Transfer function of the content
+    pub eotf: HdrEotf,
+    /// Red, green and blue primaries of the mastering display
+    pub display_primaries: [(f32, f32); 3],
+    /// White point of the mastering display
+    pub white_point: (f32, f32),
+    /// Maximum luminance of the mastering display
+    pub max_display_mastering_luminance: f32,
+    /// Minimum luminance of the mastering display
+    pub min_display_mastering_luminance: f32,
+    /// Maximum content light level, `0` if unknown
+    pub max_cll: f32,
+    /// Maximum frame-average light level, `0` if unknown
+    pub max_fall: f32,
+}
+
+impl HdrOutputMetadata {
+    /// Metadata for HDR10 content mastered on a BT.2020 display
+    /// with the given luminance range and unknown content light levels
+    pub fn hdr10(min_luminance: f32, max_luminance: f32) -> HdrOutputMetadata {
+        HdrOutputMetadata {
+            eotf: HdrEotf::SmpteSt2084,
+            display_primaries: [(0.708, 0.292), (0.170, 0.797), (0.131, 0.046)],
+            white_point: (0.3127, 0.3290),
+            max_display_mastering_luminance: max_luminance,
+            min_display_mastering_luminance: min_luminance,
+            max_cll: 0.0,
+            max_fall: 0.0,
+        }
+    }
+
+    pub(super) fn to_raw(self) -> drm_ffi::hdr_output_metadata {
+        // chromaticities are encoded in units of 0.00002
+        let chromaticity = |(x, y): (f32, f32)| {
+            (
+                (x * 50000.0).round().clamp(0.0, 50000.0) as u16,
+                (y * 50000.0).round().clamp(0.0, 50000.0) as u16,
+            )
+        };
+        let luminance = |lum: f32, scale: f32| (lum * scale).round().clamp(0.0, u16::MAX as f32) as u16;
+
+        let mut infoframe = drm_ffi::hdr_metadata_infoframe {
+            eotf: match self.eotf {
+                HdrEotf::TraditionalSdr => 0,
+                HdrEotf::TraditionalHdr => 1,
+                HdrEotf::SmpteSt2084 => 2,
+                HdrEotf::Hlg => 3,
+            },
+            // static metadata type 1
+            metadata_type: 0,
+            // max mastering luminance, max cll and max fall are in units of 1 cd/m²,
+            // min mastering luminance in units of 0.0001 cd/m²
+            max_display_mastering_luminance: luminance(self.max_display_mastering_luminance, 1.0),
+            min_display_mastering_luminance: luminance(self.min_display_mastering_luminance, 10000.0),
+            max_cll: luminance(self.max_cll, 1.0),
+            max_fall: luminance(self.max_fall, 1.0),
+            ..Default::default()
+        };
+        for (primary, raw) in self
+            .display_primaries
+            .into_iter()
+            .zip(infoframe.display_primaries.iter_mut())
+        {
+            (raw.x, raw.y) = chromaticity(primary);
+        }
+        (infoframe.white_point.x, infoframe.white_point.y) = chromaticity(self.white_point);
+
+        let mut metadata = drm_ffi::hdr_output_metadata {
+            // HDMI_STATIC_METADATA_TYPE1
+            metadata_type: 0,
+            ..Default::default()
+        };
+        metadata.__bindgen_anon_1.hdmi_metadata_type1 = infoframe;
+        metadata
+    }
+}
+
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum DrmSurfaceInternal {
@@ -272,6 +406,43 @@ impl DrmSurface {
         }
     }
 
+    /// Returns the number of entries per channel of the gamma ramp
+    /// of the underlying [`crtc`](drm::control::crtc).
+    ///
+    /// A size of `0` means the crtc does not support setting a gamma ramp.
+    pub fn gamma_size(&self) -> Result<u32, Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.gamma_size(),
+            DrmSurfaceInternal::Legacy(surf) => surf.gamma_size(),
+        }
+    }
+
+    /// Sets a new gamma ramp for the underlying [`crtc`](drm::control::crtc).
+    ///
+    /// Each channel needs to have exactly [`DrmSurface::gamma_size`] entries.
+    /// On atomic drm the ramp is applied together with the next commit or page flip,
+    /// while the legacy api applies it immediately.
+    ///
+    /// The ramp is remembered and restored by [`DrmSurface::reset_state`],
+    /// so it survives session switches.
+    pub fn set_gamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.set_gamma(red, green, blue),
+            DrmSurfaceInternal::Legacy(surf) => surf.set_gamma(red, green, blue),
+        }
+    }
+
+    /// Resets the gamma ramp of the underlying [`crtc`](drm::control::crtc) to a linear one,
+    /// discarding any ramp previously set via [`DrmSurface::set_gamma`].
+    ///
+    /// Like [`DrmSurface::set_gamma`] this is applied with the next commit or page flip on atomic drm.
+    pub fn reset_gamma(&self) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.reset_gamma(),
+            DrmSurfaceInternal::Legacy(surf) => surf.reset_gamma(),
+        }
+    }
+
     /// Tries to set a new [`Mode`](drm::control::Mode)
     /// to be used after the next commit.
     ///
@@ -285,6 +456,43 @@ impl DrmSurface {
         }
     }
 
+    /// Tries to set a new [`Colorspace`] to be signaled to the sinks
+    /// of the pending [`connector`](drm::control::connector)s after the next commit.
+    ///
+    /// The colorspace is applied atomically together with the pending [`Mode`](drm::control::Mode),
+    /// changing it will thus result in a modeset.
+    ///
+    /// Fails if any of the pending connectors does not support the colorspace.
+    /// The legacy api only supports [`Colorspace::Default`].
+    pub fn use_colorspace(&self, colorspace: Colorspace) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.use_colorspace(colorspace),
+            DrmSurfaceInternal::Legacy(_) if colorspace == Colorspace::Default => Ok(()),
+            DrmSurfaceInternal::Legacy(_) => Err(Error::UnsupportedColorimetry(self.crtc)),
+        }
+    }
+
+    /// Tries to set new [`HdrOutputMetadata`] to be sent to the sinks
+    /// of the pending [`connector`](drm::control::connector)s after the next commit.
+    /// `None` stops sending any hdr metadata.
+    ///
+    /// The metadata is applied atomically together with the pending [`Mode`](drm::control::Mode),
+    /// changing it will thus result in a modeset.
+    ///
+    /// To enable HDR10 combine [`HdrOutputMetadata::hdr10`] with [`Colorspace::Bt2020Rgb`]
+    /// (see [`DrmSurface::use_colorspace`]) and a high bit-depth format for the primary plane,
+    /// e.g. [`DrmFourcc::Xrgb2101010`](drm_fourcc::DrmFourcc::Xrgb2101010).
+    ///
+    /// Fails if any of the pending connectors does not support hdr metadata.
+    /// The legacy api does not support hdr metadata.
+    pub fn use_hdr_output_metadata(&self, metadata: Option<HdrOutputMetadata>) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.use_hdr_output_metadata(metadata),
+            DrmSurfaceInternal::Legacy(_) if metadata.is_none() => Ok(()),
+            DrmSurfaceInternal::Legacy(_) => Err(Error::UnsupportedColorimetry(self.crtc)),
+        }
+    }
+
     /// Disables the given plane.
     ///
     /// Errors if the plane is not supported by this crtc or if the underlying
@@ -302,6 +510,8 @@ impl DrmSurface {
     /// - [`add_connector`](DrmSurface::add_connector)
     /// - [`remove_connector`](DrmSurface::remove_connector)
     /// - [`use_mode`](DrmSurface::use_mode)
+    /// - [`use_colorspace`](DrmSurface::use_colorspace)
+    /// - [`use_hdr_output_metadata`](DrmSurface::use_hdr_output_metadata)
     pub fn commit_pending(&self) -> bool {
         match &*self.internal {
             DrmSurfaceInternal::Atomic(surf) => surf.commit_pending(),
@@ -375,10 +585,35 @@ impl DrmSurface {
         event: bool,
     ) -> Result<(), Error> {
         match &*self.internal {
-            DrmSurfaceInternal::Atomic(surf) => surf.page_flip(planes, event),
+            DrmSurfaceInternal::Atomic(surf) => surf.page_flip(planes, event, false),
+            DrmSurfaceInternal::Legacy(surf) => {
+                let fb = ensure_legacy_planes(self, planes)?;
+                surf.page_flip(fb, event, false)
+            }
+        }
+    }
+
+    /// Page-flip the underlying [`crtc`](drm::control::crtc)
+    /// to a new given set of [`framebuffer`]s without waiting for the next vblank.
+    ///
+    /// This uses `DRM_MODE_PAGE_FLIP_ASYNC` and thus may result in visible tearing.
+    /// Drivers commonly only support async page flips, if nothing but the framebuffer
+    /// of the primary plane changes. If the driver rejects the flip, an error is returned
+    /// and the caller is expected to fall back to [`DrmSurface::page_flip`].
+    ///
+    /// This operation is not blocking and will produce a `vblank` event once swapping is done.
+    /// Make sure to have the device registered in your event loop to not miss the event.
+    #[profiling::function]
+    pub fn page_flip_async<'a>(
+        &self,
+        planes: impl IntoIterator<Item = PlaneState<'a>>,
+        event: bool,
+    ) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.page_flip(planes, event, true),
             DrmSurfaceInternal::Legacy(surf) => {
                 let fb = ensure_legacy_planes(self, planes)?;
-                surf.page_flip(fb, event)
+                surf.page_flip(fb, event, true)
             }
         }
     }
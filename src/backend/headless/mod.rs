@@ -0,0 +1,615 @@
+//! Implementation of a headless backend without any display hardware.
+//!
+//! This backend makes it possible to run a compositor without a parent display server
+//! and without access to a KMS device, e.g. for integration tests, remote-desktop servers
+//! or screenshot bots.
+//!
+//! The backend is initialized using [`HeadlessBackend::new`]. The returned [`HeadlessBackend`]
+//! has to be inserted into an [`EventLoop`](calloop::EventLoop) to drive frame timing.
+//!
+//! Virtual outputs are created using [`HeadlessBackend::create_output`], which returns a
+//! [`HeadlessOutput`]. A headless output owns a [`Swapchain`] of offscreen buffers, which can
+//! be obtained using [`HeadlessOutput::buffer`] and rendered into by any renderer able to bind them.
+//! Once rendering is done the frame is handed back to the output by calling [`HeadlessOutput::submit`].
+//!
+//! Each output emulates a display refreshing at the rate of its current [`Mode`].
+//! Once a submitted frame hits the next (virtual) vblank, the backend emits a
+//! [`HeadlessEvent::VBlank`]. The compositor should then call [`HeadlessOutput::frame_presented`]
+//! and is free to render the next frame. The metadata of the event can be used to report
+//! presentation feedback to clients, see [`HeadlessFrameMetadata::presentation_flags`] and
+//! `HeadlessFrameMetadata::report_presentation` (requires the `desktop` feature).
+//!
+//! ## Example usage
+//!
+//! ```rust,no_run
+//! # use std::error::Error;
+//! use smithay::backend::allocator::{memory::MemoryAllocator, Fourcc};
+//! use smithay::backend::headless::{HeadlessBackend, HeadlessEvent, HeadlessOutput};
+//! use smithay::output::Mode;
+//!
+//! # struct CompositorState { output: HeadlessOutput };
+//! fn init_headless_backend(
+//!     handle: calloop::LoopHandle<CompositorState>,
+//! ) -> Result<HeadlessOutput, Box<dyn Error>> {
+//!     let backend = HeadlessBackend::new()?;
+//!
+//!     // Create a virtual 1080p output refreshing at 60Hz
+//!     let mode = Mode {
+//!         size: (1920, 1080).into(),
+//!         refresh: 60_000,
+//!     };
+//!     let output = backend.create_output("HEADLESS-1", mode, MemoryAllocator, Fourcc::Argb8888);
+//!
+//!     // Insert the backend into the event loop to receive vblank events.
+//!     handle.insert_source(backend, |event, _, state| match event {
+//!         HeadlessEvent::VBlank { output, metadata } => {
+//!             if state.output.output() == &output {
+//!                 state.output.frame_presented();
+//!                 // Send frame callbacks and presentation feedback, e.g. using
+//!                 // `metadata.report_presentation(..)`
+//!             }
+//!         }
+//!     })?;
+//!
+//!     Ok(output)
+//! }
+//! ```
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+use calloop::{
+    ping::{make_ping, Ping, PingError, PingSource},
+    timer::{TimeoutAction, Timer},
+    EventSource, Poll, PostAction, Readiness, Token, TokenFactory,
+};
+use tracing::{debug_span, info, instrument, trace};
+#[cfg(feature = "wayland_frontend")]
+use wayland_protocols::wp::presentation_time::server::wp_presentation_feedback;
+
+use crate::{
+    backend::allocator::{memory::MemoryAllocator, Allocator, Fourcc, Slot, Swapchain},
+    output::{Mode, Output, PhysicalProperties, Subpixel},
+    utils::{Clock, Monotonic, Time},
+};
+
+/// Refresh interval used for modes not specifying a refresh rate
+const DEFAULT_REFRESH: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Errors thrown by the headless backend
+#[derive(Debug, thiserror::Error)]
+pub enum HeadlessError {
+    /// Creating the event source failed
+    #[error("Failed to create the event source: {0}")]
+    Io(#[from] std::io::Error),
+    /// Processing events failed
+    #[error(transparent)]
+    Ping(#[from] PingError),
+    /// Allocating a new buffer failed
+    #[error("Allocating a new buffer failed: {0}")]
+    Allocation(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// No free slots
+    #[error("No free slots in the swapchain")]
+    NoFreeSlots,
+    /// No buffer was acquired before submitting
+    #[error("No buffer was acquired for the submitted frame")]
+    NoBuffer,
+    /// A frame is still waiting for the next vblank
+    #[error("A frame is already pending presentation")]
+    FramePending,
+}
+
+/// An event emitted by the headless backend.
+#[derive(Debug)]
+pub enum HeadlessEvent {
+    /// A previously submitted frame of an output has been presented.
+    ///
+    /// You should call [`HeadlessOutput::frame_presented`] on the matching output.
+    VBlank {
+        /// The output the frame was presented on
+        output: Output,
+        /// Timing information about the presentation
+        metadata: HeadlessFrameMetadata,
+    },
+}
+
+/// Timing information of a presented frame
+#[derive(Debug, Clone, Copy)]
+pub struct HeadlessFrameMetadata {
+    /// Time of the (virtual) vblank the frame was presented at
+    pub time: Time<Monotonic>,
+    /// Refresh interval of the output at the time of presentation
+    pub refresh: Duration,
+    /// Vblank sequence counter of the output
+    pub sequence: u64,
+}
+
+#[cfg(feature = "wayland_frontend")]
+impl HeadlessFrameMetadata {
+    /// Returns the flags to report with presentation feedback for this frame
+    ///
+    /// Frames are presented at a (virtual) vblank, but the timestamps are not provided by hardware.
+    pub fn presentation_flags(&self) -> wp_presentation_feedback::Kind {
+        wp_presentation_feedback::Kind::Vsync
+    }
+
+    /// Marks the presentation feedbacks collected for this frame as presented.
+    #[cfg(feature = "desktop")]
+    pub fn report_presentation(&self, feedback: &mut crate::desktop::utils::OutputPresentationFeedback) {
+        feedback.presented(self.time, self.refresh, self.sequence, self.presentation_flags());
+    }
+}
+
+#[derive(Debug)]
+struct OutputTiming {
+    output: Output,
+    refresh: Duration,
+    epoch: Instant,
+    sequence_base: u64,
+    pending: Option<(Instant, u64)>,
+    presented: Option<HeadlessFrameMetadata>,
+}
+
+impl OutputTiming {
+    fn sequence_at(&self, time: Instant) -> u64 {
+        let elapsed = time.saturating_duration_since(self.epoch).as_nanos();
+        self.sequence_base + (elapsed / self.refresh.as_nanos()) as u64
+    }
+
+    fn next_vblank(&self, time: Instant) -> (Instant, u64) {
+        let sequence = self.sequence_at(time) + 1;
+        let ticks = (sequence - self.sequence_base) as u32;
+        (self.epoch + self.refresh * ticks, sequence)
+    }
+
+    fn set_refresh(&mut self, refresh: Duration) {
+        let now = Instant::now();
+        self.sequence_base = self.sequence_at(now);
+        self.epoch = now;
+        self.refresh = refresh;
+    }
+}
+
+fn refresh_interval(mode: Mode) -> Duration {
+    if mode.refresh > 0 {
+        Duration::from_nanos(1_000_000_000_000 / mode.refresh as u64)
+    } else {
+        DEFAULT_REFRESH
+    }
+}
+
+/// Headless backend driving the frame timing of virtual outputs
+///
+/// This type has to be inserted into an event loop to emit [`HeadlessEvent`]s.
+pub struct HeadlessBackend {
+    outputs: Arc<Mutex<Vec<Weak<Mutex<OutputTiming>>>>>,
+    ping: Ping,
+    ping_source: PingSource,
+    timer: Option<(Timer, Instant)>,
+    stale_timer: Option<Timer>,
+    span: tracing::Span,
+}
+
+impl fmt::Debug for HeadlessBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeadlessBackend")
+            .field("outputs", &self.outputs)
+            .field("timer", &self.timer.as_ref().map(|(_, deadline)| deadline))
+            .finish_non_exhaustive()
+    }
+}
+
+impl HeadlessBackend {
+    /// Initializes a new headless backend
+    pub fn new() -> Result<HeadlessBackend, HeadlessError> {
+        let span = debug_span!("backend_headless");
+        let (ping, ping_source) = make_ping()?;
+        info!(parent: &span, "Initializing headless backend");
+
+        Ok(HeadlessBackend {
+            outputs: Arc::new(Mutex::new(Vec::new())),
+            ping,
+            ping_source,
+            timer: None,
+            stale_timer: None,
+            span,
+        })
+    }
+
+    /// Creates a new virtual output using the given mode.
+    ///
+    /// The mode is set as the current and preferred mode of the created [`Output`].
+    /// Buffers for rendering are allocated using the provided allocator in the given format.
+    ///
+    /// The [`Output`] is not advertised to clients, use [`Output::create_global`] for that.
+    #[instrument(level = "debug", parent = &self.span, skip(self, name, allocator))]
+    pub fn create_output<A: Allocator, U>(
+        &self,
+        name: impl Into<String>,
+        mode: Mode,
+        allocator: A,
+        format: Fourcc,
+    ) -> HeadlessOutput<A, U> {
+        let output = Output::new(
+            name.into(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: "Smithay".into(),
+                model: "Headless".into(),
+            },
+        );
+        output.add_mode(mode);
+        output.set_preferred(mode);
+        output.change_current_state(Some(mode), None, None, None);
+
+        let timing = Arc::new(Mutex::new(OutputTiming {
+            output: output.clone(),
+            refresh: refresh_interval(mode),
+            epoch: Instant::now(),
+            sequence_base: 0,
+            pending: None,
+            presented: None,
+        }));
+        let mut outputs = self.outputs.lock().unwrap();
+        outputs.retain(|timing| timing.strong_count() > 0);
+        outputs.push(Arc::downgrade(&timing));
+
+        let swapchain = Swapchain::new(
+            allocator,
+            mode.size.w as u32,
+            mode.size.h as u32,
+            format,
+            Vec::new(),
+        );
+
+        let span = debug_span!(parent: &self.span, "headless_output", name = %output.name());
+        HeadlessOutput {
+            output,
+            swapchain,
+            buffer: None,
+            queued: None,
+            front: None,
+            unclaimed: VecDeque::new(),
+            timing,
+            ping: self.ping.clone(),
+            span,
+        }
+    }
+}
+
+impl EventSource for HeadlessBackend {
+    type Event = HeadlessEvent;
+    type Metadata = ();
+    type Ret = ();
+    type Error = HeadlessError;
+
+    #[profiling::function]
+    fn process_events<F>(
+        &mut self,
+        readiness: Readiness,
+        token: Token,
+        mut callback: F,
+    ) -> Result<PostAction, HeadlessError>
+    where
+        F: FnMut(Self::Event, &mut Self::Metadata) -> Self::Ret,
+    {
+        let _guard = self.span.enter();
+
+        self.ping_source.process_events(readiness, token, |_, _| {})?;
+        if let Some((timer, _)) = self.timer.as_mut() {
+            let mut fired = false;
+            timer.process_events(readiness, token, |_, _| {
+                fired = true;
+                TimeoutAction::Drop
+            })?;
+            if fired {
+                self.timer = None;
+            }
+        }
+
+        let now = Instant::now();
+        let clock_now: Duration = Clock::<Monotonic>::new().now().into();
+        let mut events = Vec::new();
+        let mut next_deadline: Option<Instant> = None;
+        self.outputs.lock().unwrap().retain(|timing| {
+            let Some(timing) = timing.upgrade() else {
+                return false;
+            };
+            let mut timing = timing.lock().unwrap();
+            match timing.pending {
+                Some((deadline, sequence)) if deadline <= now => {
+                    let metadata = HeadlessFrameMetadata {
+                        time: clock_now
+                            .saturating_sub(now.saturating_duration_since(deadline))
+                            .into(),
+                        refresh: timing.refresh,
+                        sequence,
+                    };
+                    trace!(output = timing.output.name(), sequence, "vblank");
+                    timing.pending = None;
+                    timing.presented = Some(metadata);
+                    events.push(HeadlessEvent::VBlank {
+                        output: timing.output.clone(),
+                        metadata,
+                    });
+                }
+                Some((deadline, _)) => {
+                    next_deadline = Some(next_deadline.map_or(deadline, |next| next.min(deadline)));
+                }
+                None => {}
+            }
+            true
+        });
+
+        for event in events {
+            callback(event, &mut ());
+        }
+
+        // re-arm the timer, if the earliest pending vblank changed
+        if self.timer.as_ref().map(|(_, deadline)| *deadline) != next_deadline {
+            self.stale_timer = self.timer.take().map(|(timer, _)| timer);
+            self.timer = next_deadline.map(|deadline| (Timer::from_deadline(deadline), deadline));
+            return Ok(PostAction::Reregister);
+        }
+
+        Ok(PostAction::Continue)
+    }
+
+    fn register(&mut self, poll: &mut Poll, token_factory: &mut TokenFactory) -> calloop::Result<()> {
+        self.ping_source.register(poll, token_factory)?;
+        if let Some((timer, _)) = self.timer.as_mut() {
+            timer.register(poll, token_factory)?;
+        }
+        Ok(())
+    }
+
+    fn reregister(&mut self, poll: &mut Poll, token_factory: &mut TokenFactory) -> calloop::Result<()> {
+        if let Some(mut timer) = self.stale_timer.take() {
+            timer.unregister(poll)?;
+        }
+        self.ping_source.reregister(poll, token_factory)?;
+        if let Some((timer, _)) = self.timer.as_mut() {
+            timer.reregister(poll, token_factory)?;
+        }
+        Ok(())
+    }
+
+    fn unregister(&mut self, poll: &mut Poll) -> calloop::Result<()> {
+        if let Some(mut timer) = self.stale_timer.take() {
+            timer.unregister(poll)?;
+        }
+        self.ping_source.unregister(poll)?;
+        if let Some((timer, _)) = self.timer.as_mut() {
+            timer.unregister(poll)?;
+        }
+        Ok(())
+    }
+}
+
+/// A virtual output of the [`HeadlessBackend`]
+///
+/// The user data `U` can be used to attach data to submitted frames, that is returned
+/// once the frame has been presented, e.g. an
+/// [`OutputPresentationFeedback`](crate::desktop::utils::OutputPresentationFeedback).
+pub struct HeadlessOutput<A: Allocator = MemoryAllocator, U = ()> {
+    output: Output,
+    swapchain: Swapchain<A>,
+    buffer: Option<Slot<A::Buffer>>,
+    queued: Option<(Slot<A::Buffer>, U)>,
+    front: Option<Slot<A::Buffer>>,
+    unclaimed: VecDeque<U>,
+    timing: Arc<Mutex<OutputTiming>>,
+    ping: Ping,
+    span: tracing::Span,
+}
+
+impl<A: Allocator, U> fmt::Debug for HeadlessOutput<A, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeadlessOutput")
+            .field("output", &self.output)
+            .field("timing", &self.timing)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A: Allocator, U> HeadlessOutput<A, U> {
+    /// Returns the [`Output`] represented by this headless output
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    /// Returns the format of the buffers used by this output
+    pub fn format(&self) -> Fourcc {
+        self.swapchain.format()
+    }
+
+    /// Returns the refresh interval of the current mode of this output
+    pub fn refresh_interval(&self) -> Duration {
+        self.timing.lock().unwrap().refresh
+    }
+
+    /// Switches the output to a new mode.
+    ///
+    /// The mode is added to the modes of the [`Output`] if necessary. Buffers of the
+    /// previous mode are released once they are not in use anymore.
+    #[instrument(level = "debug", parent = &self.span, skip(self))]
+    pub fn use_mode(&mut self, mode: Mode) {
+        if !self.output.modes().contains(&mode) {
+            self.output.add_mode(mode);
+        }
+        self.output.change_current_state(Some(mode), None, None, None);
+        self.swapchain.resize(mode.size.w as u32, mode.size.h as u32);
+        self.buffer = None;
+        self.timing.lock().unwrap().set_refresh(refresh_interval(mode));
+    }
+
+    /// Returns the buffer that is currently displayed on this output, if any.
+    ///
+    /// This is the buffer of the last frame that hit a vblank, which makes it
+    /// suitable for inspecting the output's contents e.g. for screenshots.
+    pub fn presented_buffer(&self) -> Option<&A::Buffer> {
+        self.front.as_deref()
+    }
+
+    /// Returns timing information about the last presented frame, if any.
+    pub fn last_presentation(&self) -> Option<HeadlessFrameMetadata> {
+        self.timing.lock().unwrap().presented
+    }
+
+    /// Returns if a submitted frame is still waiting for the next vblank
+    pub fn is_frame_pending(&self) -> bool {
+        self.timing.lock().unwrap().pending.is_some()
+    }
+
+    /// Submits the current buffer, to be presented at the next vblank.
+    ///
+    /// The buffer has to be obtained using [`HeadlessOutput::buffer`] first. The provided
+    /// `user_data` is returned by [`HeadlessOutput::frame_presented`] once the frame has been
+    /// presented.
+    #[instrument(level = "trace", parent = &self.span, skip(self, user_data))]
+    #[profiling::function]
+    pub fn submit(&mut self, user_data: U) -> Result<(), HeadlessError> {
+        let mut timing = self.timing.lock().unwrap();
+        if timing.pending.is_some() {
+            return Err(HeadlessError::FramePending);
+        }
+        let slot = self.buffer.take().ok_or(HeadlessError::NoBuffer)?;
+
+        // the previous frame was presented, but never retrieved
+        if let Some((slot, user_data)) = self.queued.take() {
+            self.front = Some(slot);
+            self.unclaimed.push_back(user_data);
+        }
+
+        self.swapchain.submitted(&slot);
+        let (deadline, sequence) = timing.next_vblank(Instant::now());
+        trace!(sequence, "frame queued");
+        timing.pending = Some((deadline, sequence));
+        self.queued = Some((slot, user_data));
+        self.ping.ping();
+
+        Ok(())
+    }
+
+    /// Marks the last submitted frame as presented.
+    ///
+    /// This should be called once a [`HeadlessEvent::VBlank`] was received for this output
+    /// and returns the user data passed to [`HeadlessOutput::submit`].
+    ///
+    /// If frames were presented without calling this function in between, their user data
+    /// is returned by subsequent calls in the order the frames were submitted.
+    /// Returns `None` if no frame has been presented since the last call.
+    pub fn frame_presented(&mut self) -> Option<U> {
+        if self.timing.lock().unwrap().pending.is_none() {
+            if let Some((slot, user_data)) = self.queued.take() {
+                self.front = Some(slot);
+                self.unclaimed.push_back(user_data);
+            }
+        }
+        self.unclaimed.pop_front()
+    }
+
+    /// Resets the internal buffers.
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    pub fn reset_buffers(&mut self) {
+        self.swapchain.reset_buffers();
+        self.buffer = None;
+    }
+
+    /// Resets the buffer ages, forcing a full redraw for the next frame.
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    pub fn reset_buffer_ages(&mut self) {
+        self.swapchain.reset_buffer_ages();
+    }
+}
+
+impl<A, U> HeadlessOutput<A, U>
+where
+    A: Allocator,
+    A::Buffer: Clone,
+    A::Error: Send + Sync + 'static,
+{
+    /// Returns the next buffer to render into and its age.
+    ///
+    /// You may bind this buffer to a renderer to render.
+    /// This function will return the same buffer until [`submit`](Self::submit) is called
+    /// or [`reset_buffers`](Self::reset_buffers) is used to reset the buffers.
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    #[profiling::function]
+    pub fn buffer(&mut self) -> Result<(A::Buffer, u8), HeadlessError> {
+        if self.buffer.is_none() {
+            self.buffer = Some(
+                self.swapchain
+                    .acquire()
+                    .map_err(|err| HeadlessError::Allocation(Box::new(err)))?
+                    .ok_or(HeadlessError::NoFreeSlots)?,
+            );
+        }
+
+        let slot = self.buffer.as_ref().unwrap();
+        let age = slot.age();
+        Ok(((**slot).clone(), age))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{HeadlessBackend, HeadlessEvent, HeadlessOutput};
+    use crate::{
+        backend::allocator::{memory::MemoryAllocator, Buffer, Fourcc},
+        output::Mode,
+    };
+
+    #[test]
+    fn vblank_after_submit() {
+        let mut event_loop = calloop::EventLoop::<Vec<u64>>::try_new().unwrap();
+        let backend = HeadlessBackend::new().unwrap();
+        let mode = Mode {
+            size: (64, 32).into(),
+            refresh: 500_000,
+        };
+        let mut output: HeadlessOutput<MemoryAllocator, u32> =
+            backend.create_output("HEADLESS-1", mode, MemoryAllocator, Fourcc::Argb8888);
+        event_loop
+            .handle()
+            .insert_source(backend, |event, _, sequences| match event {
+                HeadlessEvent::VBlank { metadata, .. } => sequences.push(metadata.sequence),
+            })
+            .unwrap();
+
+        let (buffer, age) = output.buffer().unwrap();
+        assert_eq!(age, 0);
+        assert_eq!(buffer.width(), 64);
+        output.submit(1).unwrap();
+        assert!(output.is_frame_pending());
+        assert!(output.submit(2).is_err());
+
+        let mut sequences = Vec::new();
+        while sequences.is_empty() {
+            event_loop
+                .dispatch(Duration::from_millis(100), &mut sequences)
+                .unwrap();
+        }
+        assert_eq!(sequences.len(), 1);
+        assert_eq!(output.frame_presented(), Some(1));
+        assert!(output.presented_buffer().is_some());
+
+        let (_, age) = output.buffer().unwrap();
+        assert_eq!(age, 0);
+        output.submit(2).unwrap();
+        while sequences.len() < 2 {
+            event_loop
+                .dispatch(Duration::from_millis(100), &mut sequences)
+                .unwrap();
+        }
+        assert!(sequences[1] > sequences[0]);
+        assert_eq!(output.frame_presented(), Some(2));
+    }
+}
@@ -5,8 +5,10 @@ use std::path::PathBuf;
 mod tablet;
 
 pub use tablet::{
-    ProximityState, TabletToolAxisEvent, TabletToolButtonEvent, TabletToolCapabilities, TabletToolDescriptor,
-    TabletToolEvent, TabletToolProximityEvent, TabletToolTipEvent, TabletToolTipState, TabletToolType,
+    ProximityState, TabletPadAxisSource, TabletPadButtonEvent, TabletPadDescriptor, TabletPadEvent,
+    TabletPadGroupDescriptor, TabletPadRingEvent, TabletPadStripEvent, TabletToolAxisEvent,
+    TabletToolButtonEvent, TabletToolCapabilities, TabletToolDescriptor, TabletToolEvent,
+    TabletToolProximityEvent, TabletToolTipEvent, TabletToolTipState, TabletToolType,
 };
 
 use crate::utils::{Logical, Point, Raw, Size};
@@ -531,6 +533,49 @@ pub trait TouchFrameEvent<B: InputBackend>: Event<B> {}
 
 impl<B: InputBackend> TouchFrameEvent<B> for UnusedEvent {}
 
+/// Types of switches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Switch {
+    /// The laptop lid was closed when the switch is on, or was opened when it is off
+    Lid,
+    /// Whether the device is in tablet mode
+    ///
+    /// When the switch is off, the device is in laptop mode. When it is on, the device
+    /// behaves like a tablet, where the primary interaction is usually a touch screen and
+    /// the keyboard or touchpad may not be accessible.
+    TabletMode,
+}
+
+/// State of a switch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SwitchState {
+    /// Switch is off
+    Off,
+    /// Switch is on
+    On,
+}
+
+/// Trait for switch toggle events
+pub trait SwitchToggleEvent<B: InputBackend>: Event<B> {
+    /// Switch that triggered this event
+    ///
+    /// `None` if the switch type is not known
+    fn switch(&self) -> Option<Switch>;
+
+    /// State the switch was toggled to
+    fn state(&self) -> SwitchState;
+}
+
+impl<B: InputBackend> SwitchToggleEvent<B> for UnusedEvent {
+    fn switch(&self) -> Option<Switch> {
+        match *self {}
+    }
+
+    fn state(&self) -> SwitchState {
+        match *self {}
+    }
+}
+
 /// Trait that describes objects providing a source of input events. All input backends
 /// need to implement this and provide the same base guarantees about the precision of
 /// given events.
@@ -581,6 +626,14 @@ pub trait InputBackend: Sized {
     type TabletToolTipEvent: TabletToolTipEvent<Self>;
     /// Type representing button events on tablet tool devices
     type TabletToolButtonEvent: TabletToolButtonEvent<Self>;
+    /// Type representing button events on tablet pad devices
+    type TabletPadButtonEvent: TabletPadButtonEvent<Self>;
+    /// Type representing ring events on tablet pad devices
+    type TabletPadRingEvent: TabletPadRingEvent<Self>;
+    /// Type representing strip events on tablet pad devices
+    type TabletPadStripEvent: TabletPadStripEvent<Self>;
+    /// Type representing toggle events of switch devices
+    type SwitchToggleEvent: SwitchToggleEvent<Self>;
 
     /// Special events that are custom to this backend
     type SpecialEvent;
@@ -717,6 +770,30 @@ pub enum InputEvent<B: InputBackend> {
         event: B::TabletToolButtonEvent,
     },
 
+    /// A tablet pad button was pressed or released
+    TabletPadButton {
+        /// The tablet pad button event
+        event: B::TabletPadButtonEvent,
+    },
+
+    /// A tablet pad ring changed its position
+    TabletPadRing {
+        /// The tablet pad ring event
+        event: B::TabletPadRingEvent,
+    },
+
+    /// A tablet pad strip changed its position
+    TabletPadStrip {
+        /// The tablet pad strip event
+        event: B::TabletPadStripEvent,
+    },
+
+    /// A switch was toggled
+    SwitchToggle {
+        /// The switch toggle event
+        event: B::SwitchToggleEvent,
+    },
+
     /// Special event specific of this backend
     Special(B::SpecialEvent),
 }
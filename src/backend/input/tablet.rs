@@ -1,6 +1,7 @@
 use super::{ButtonState, Event, InputBackend, UnusedEvent};
 use crate::utils::{Logical, Point, Raw, Size};
 use bitflags::bitflags;
+use std::path::PathBuf;
 
 /// Description of physical tablet tool
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
@@ -369,3 +370,157 @@ impl<B: InputBackend> TabletToolButtonEvent<B> for UnusedEvent {
         match *self {}
     }
 }
+
+/// Description of a physical tablet pad
+///
+/// A pad is the set of buttons, rings and strips usually physically present
+/// on the tablet device itself.
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct TabletPadDescriptor {
+    /// Pad device name
+    pub name: String,
+    /// Pad device USB (product,vendor) id
+    pub usb_id: Option<(u32, u32)>,
+    /// Path to the device
+    pub syspath: Option<PathBuf>,
+    /// Number of buttons on this pad
+    pub buttons: u32,
+    /// Mode groups of this pad
+    ///
+    /// Every pad has at least one group.
+    pub groups: Vec<TabletPadGroupDescriptor>,
+}
+
+/// Description of a mode group of a tablet pad
+///
+/// A group is a distinct (sub)set of buttons, rings and strips present on the pad,
+/// that share a common mode.
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct TabletPadGroupDescriptor {
+    /// Indices of the buttons in this group
+    pub buttons: Vec<u32>,
+    /// Indices of the rings in this group
+    pub rings: Vec<u32>,
+    /// Indices of the strips in this group
+    pub strips: Vec<u32>,
+    /// Number of modes this group can switch between
+    pub modes: u32,
+}
+
+/// Describes how a ring or strip event was physically generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TabletPadAxisSource {
+    /// The source is not known
+    Unknown,
+    /// A finger on the ring or strip
+    Finger,
+}
+
+/// Tablet pad event
+pub trait TabletPadEvent<B: InputBackend>: Event<B> {
+    /// Returns the mode the button, ring or strip that triggered this event is in.
+    ///
+    /// Mode indices start at 0, a device that does not support modes always returns 0.
+    /// If the button is a mode toggle button and the event caused a new mode to be
+    /// toggled, the mode returned is the new mode.
+    fn mode(&self) -> u32;
+
+    /// Returns the index of the mode group the button, ring or strip that triggered
+    /// this event belongs to.
+    fn mode_group(&self) -> u32;
+}
+
+impl<B: InputBackend> TabletPadEvent<B> for UnusedEvent {
+    fn mode(&self) -> u32 {
+        match *self {}
+    }
+
+    fn mode_group(&self) -> u32 {
+        match *self {}
+    }
+}
+
+/// Signals that a button on a device with the `DeviceCapability::TabletPad` capability
+/// was pressed or released.
+///
+/// This event is not to be confused with the button events emitted by tools
+/// on a tablet, see [`TabletToolButtonEvent`].
+pub trait TabletPadButtonEvent<B: InputBackend>: TabletPadEvent<B> {
+    /// Return the index of the button that triggered this event, starting at 0.
+    fn button(&self) -> u32;
+
+    /// Return the button state of the event.
+    fn button_state(&self) -> ButtonState;
+}
+
+impl<B: InputBackend> TabletPadButtonEvent<B> for UnusedEvent {
+    fn button(&self) -> u32 {
+        match *self {}
+    }
+
+    fn button_state(&self) -> ButtonState {
+        match *self {}
+    }
+}
+
+/// Signals a status change on a ring of a device with the `DeviceCapability::TabletPad`
+/// capability.
+pub trait TabletPadRingEvent<B: InputBackend>: TabletPadEvent<B> {
+    /// Returns the number of the ring that has changed state, with 0 being the first ring.
+    fn number(&self) -> u32;
+
+    /// Returns the current position of the ring, in degrees clockwise from the
+    /// northern-most point of the ring in the tablet's current logical orientation.
+    ///
+    /// If the source is [`TabletPadAxisSource::Finger`], a terminating event with a
+    /// position of -1 is sent when the finger is lifted from the ring.
+    fn position(&self) -> f64;
+
+    /// Returns the source of the interaction with the ring.
+    fn source(&self) -> TabletPadAxisSource;
+}
+
+impl<B: InputBackend> TabletPadRingEvent<B> for UnusedEvent {
+    fn number(&self) -> u32 {
+        match *self {}
+    }
+
+    fn position(&self) -> f64 {
+        match *self {}
+    }
+
+    fn source(&self) -> TabletPadAxisSource {
+        match *self {}
+    }
+}
+
+/// Signals a status change on a strip of a device with the `DeviceCapability::TabletPad`
+/// capability.
+pub trait TabletPadStripEvent<B: InputBackend>: TabletPadEvent<B> {
+    /// Returns the number of the strip that has changed state, with 0 being the first strip.
+    fn number(&self) -> u32;
+
+    /// Returns the current position of the strip, normalized to the range [0, 1],
+    /// with 0 being the top/left-most point in the tablet's current logical orientation.
+    ///
+    /// If the source is [`TabletPadAxisSource::Finger`], a terminating event with a
+    /// position of -1 is sent when the finger is lifted from the strip.
+    fn position(&self) -> f64;
+
+    /// Returns the source of the interaction with the strip.
+    fn source(&self) -> TabletPadAxisSource;
+}
+
+impl<B: InputBackend> TabletPadStripEvent<B> for UnusedEvent {
+    fn number(&self) -> u32 {
+        match *self {}
+    }
+
+    fn position(&self) -> f64 {
+        match *self {}
+    }
+
+    fn source(&self) -> TabletPadAxisSource {
+        match *self {}
+    }
+}
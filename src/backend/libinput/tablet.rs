@@ -1,10 +1,11 @@
 use crate::backend::input::{
-    self as backend, TabletToolCapabilities, TabletToolDescriptor, TabletToolTipState, TabletToolType,
+    self as backend, Device, TabletPadAxisSource, TabletPadDescriptor, TabletPadGroupDescriptor,
+    TabletToolCapabilities, TabletToolDescriptor, TabletToolTipState, TabletToolType,
 };
 
 use input as libinput;
 use input::event;
-use input::event::{tablet_tool, EventTrait};
+use input::event::{tablet_pad, tablet_tool, EventTrait};
 
 use super::LibinputInputBackend;
 
@@ -187,3 +188,123 @@ impl backend::TabletToolButtonEvent<LibinputInputBackend> for tablet_tool::Table
         tablet_tool::TabletToolButtonEvent::button_state(self).into()
     }
 }
+
+impl From<&libinput::Device> for TabletPadDescriptor {
+    fn from(device: &libinput::Device) -> Self {
+        let buttons = device.tablet_pad_number_of_buttons().max(0) as u32;
+        let rings = device.tablet_pad_number_of_rings().max(0) as u32;
+        let strips = device.tablet_pad_number_of_strips().max(0) as u32;
+
+        let groups = (0..device.tablet_pad_number_of_mode_groups().max(0) as u32)
+            .filter_map(|index| device.tablet_pad_mode_group(index))
+            .map(|group| TabletPadGroupDescriptor {
+                buttons: (0..buttons).filter(|b| group.has_button(*b)).collect(),
+                rings: (0..rings).filter(|r| group.has_ring(*r)).collect(),
+                strips: (0..strips).filter(|s| group.has_strip(*s)).collect(),
+                modes: group.number_of_modes(),
+            })
+            .collect();
+
+        TabletPadDescriptor {
+            name: Device::name(device),
+            usb_id: Device::usb_id(device),
+            syspath: Device::syspath(device),
+            buttons,
+            groups,
+        }
+    }
+}
+
+/// Marker for tablet pad events
+pub trait IsTabletPadEvent: tablet_pad::TabletPadEventTrait + EventTrait {}
+
+impl IsTabletPadEvent for tablet_pad::TabletPadButtonEvent {}
+impl IsTabletPadEvent for tablet_pad::TabletPadRingEvent {}
+impl IsTabletPadEvent for tablet_pad::TabletPadStripEvent {}
+
+impl<E> backend::TabletPadEvent<LibinputInputBackend> for E
+where
+    E: IsTabletPadEvent + backend::Event<LibinputInputBackend>,
+{
+    fn mode(&self) -> u32 {
+        tablet_pad::TabletPadEventTrait::mode(self)
+    }
+
+    fn mode_group(&self) -> u32 {
+        tablet_pad::TabletPadEventTrait::mode_group(self).index()
+    }
+}
+
+impl backend::Event<LibinputInputBackend> for tablet_pad::TabletPadButtonEvent {
+    fn time(&self) -> u64 {
+        tablet_pad::TabletPadEventTrait::time_usec(self)
+    }
+
+    fn device(&self) -> libinput::Device {
+        event::EventTrait::device(self)
+    }
+}
+
+impl backend::TabletPadButtonEvent<LibinputInputBackend> for tablet_pad::TabletPadButtonEvent {
+    fn button(&self) -> u32 {
+        tablet_pad::TabletPadButtonEvent::button_number(self)
+    }
+
+    fn button_state(&self) -> backend::ButtonState {
+        tablet_pad::TabletPadButtonEvent::button_state(self).into()
+    }
+}
+
+impl backend::Event<LibinputInputBackend> for tablet_pad::TabletPadRingEvent {
+    fn time(&self) -> u64 {
+        tablet_pad::TabletPadEventTrait::time_usec(self)
+    }
+
+    fn device(&self) -> libinput::Device {
+        event::EventTrait::device(self)
+    }
+}
+
+impl backend::TabletPadRingEvent<LibinputInputBackend> for tablet_pad::TabletPadRingEvent {
+    fn number(&self) -> u32 {
+        tablet_pad::TabletPadRingEvent::number(self)
+    }
+
+    fn position(&self) -> f64 {
+        tablet_pad::TabletPadRingEvent::position(self)
+    }
+
+    fn source(&self) -> TabletPadAxisSource {
+        match tablet_pad::TabletPadRingEvent::source(self) {
+            tablet_pad::RingAxisSource::Finger => TabletPadAxisSource::Finger,
+            tablet_pad::RingAxisSource::Unknown => TabletPadAxisSource::Unknown,
+        }
+    }
+}
+
+impl backend::Event<LibinputInputBackend> for tablet_pad::TabletPadStripEvent {
+    fn time(&self) -> u64 {
+        tablet_pad::TabletPadEventTrait::time_usec(self)
+    }
+
+    fn device(&self) -> libinput::Device {
+        event::EventTrait::device(self)
+    }
+}
+
+impl backend::TabletPadStripEvent<LibinputInputBackend> for tablet_pad::TabletPadStripEvent {
+    fn number(&self) -> u32 {
+        tablet_pad::TabletPadStripEvent::number(self)
+    }
+
+    fn position(&self) -> f64 {
+        tablet_pad::TabletPadStripEvent::position(self)
+    }
+
+    fn source(&self) -> TabletPadAxisSource {
+        match tablet_pad::TabletPadStripEvent::source(self) {
+            tablet_pad::StripAxisSource::Finger => TabletPadAxisSource::Finger,
+            tablet_pad::StripAxisSource::Unknown => TabletPadAxisSource::Unknown,
+        }
+    }
+}
@@ -78,6 +78,13 @@
 //! development and debugging. That backend is both a renderer and an input provider, and is
 //! accessible in the [`winit`] module, gated by the `backend_winit` cargo feature.
 //!
+//! ## Headless backend
+//!
+//! Smithay also provides a backend without any display hardware, which drives virtual outputs
+//! and renders into offscreen buffers. It is useful for integration tests, remote-desktop servers
+//! or screenshot tools. The backend is accessible in the [`headless`] module, gated by the
+//! `backend_headless` cargo feature.
+//!
 
 pub mod allocator;
 pub mod input;
@@ -87,6 +94,8 @@ pub mod renderer;
 pub mod drm;
 #[cfg(feature = "backend_egl")]
 pub mod egl;
+#[cfg(feature = "backend_headless")]
+pub mod headless;
 #[cfg(feature = "backend_libinput")]
 pub mod libinput;
 #[cfg(feature = "backend_session")]
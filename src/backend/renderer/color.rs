@@ -0,0 +1,351 @@
+//! Color descriptions for color managed rendering
+//!
+//! A [`ColorDescription`] describes how the color values of a buffer or output are encoded
+//! through their [`Primaries`], [`TransferFunction`] and [`Luminance`] range.
+//!
+//! Renderers supporting color transformations (like the `GlesRenderer` with
+//! `Capability::ColorTransformations`) decode their inputs into a linear blending space
+//! and encode the result for the output, allowing to mix e.g. sRGB and BT.2100/PQ content.
+
+use cgmath::{Matrix3, SquareMatrix, Vector3};
+
+/// Chromaticity coordinates of the primaries and white point of a color space in CIE 1931 xy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Primaries {
+    /// Red primary
+    pub red: (f32, f32),
+    /// Green primary
+    pub green: (f32, f32),
+    /// Blue primary
+    pub blue: (f32, f32),
+    /// White point
+    pub white: (f32, f32),
+}
+
+const D65: (f32, f32) = (0.3127, 0.3290);
+const ILLUMINANT_C: (f32, f32) = (0.310, 0.316);
+
+impl Primaries {
+    /// Primaries of sRGB and BT.709
+    pub const SRGB: Primaries = Primaries {
+        red: (0.64, 0.33),
+        green: (0.30, 0.60),
+        blue: (0.15, 0.06),
+        white: D65,
+    };
+    /// Primaries of BT.470 System M (PAL-M)
+    pub const PAL_M: Primaries = Primaries {
+        red: (0.67, 0.33),
+        green: (0.21, 0.71),
+        blue: (0.14, 0.08),
+        white: ILLUMINANT_C,
+    };
+    /// Primaries of BT.601 625 lines (PAL)
+    pub const PAL: Primaries = Primaries {
+        red: (0.64, 0.33),
+        green: (0.29, 0.60),
+        blue: (0.15, 0.06),
+        white: D65,
+    };
+    /// Primaries of BT.601 525 lines (NTSC)
+    pub const NTSC: Primaries = Primaries {
+        red: (0.630, 0.340),
+        green: (0.310, 0.595),
+        blue: (0.155, 0.070),
+        white: D65,
+    };
+    /// Primaries of generic film (color filters using Illuminant C)
+    pub const GENERIC_FILM: Primaries = Primaries {
+        red: (0.681, 0.319),
+        green: (0.243, 0.692),
+        blue: (0.145, 0.049),
+        white: ILLUMINANT_C,
+    };
+    /// Primaries of BT.2020 and BT.2100
+    pub const BT2020: Primaries = Primaries {
+        red: (0.708, 0.292),
+        green: (0.170, 0.797),
+        blue: (0.131, 0.046),
+        white: D65,
+    };
+    /// Primaries of CIE 1931 XYZ with equal energy white point
+    pub const CIE1931_XYZ: Primaries = Primaries {
+        red: (1.0, 0.0),
+        green: (0.0, 1.0),
+        blue: (0.0, 0.0),
+        white: (1.0 / 3.0, 1.0 / 3.0),
+    };
+    /// Primaries of DCI-P3
+    pub const DCI_P3: Primaries = Primaries {
+        red: (0.680, 0.320),
+        green: (0.265, 0.690),
+        blue: (0.150, 0.060),
+        white: (0.314, 0.351),
+    };
+    /// Primaries of Display P3
+    pub const DISPLAY_P3: Primaries = Primaries {
+        red: (0.680, 0.320),
+        green: (0.265, 0.690),
+        blue: (0.150, 0.060),
+        white: D65,
+    };
+    /// Primaries of Adobe RGB (1998)
+    pub const ADOBE_RGB: Primaries = Primaries {
+        red: (0.64, 0.33),
+        green: (0.21, 0.71),
+        blue: (0.15, 0.06),
+        white: D65,
+    };
+
+    /// Matrix converting linear RGB values with these primaries into CIE XYZ
+    ///
+    /// Returns `None` for degenerate primaries.
+    pub fn to_xyz(&self) -> Option<Matrix3<f32>> {
+        let primaries = Matrix3::from_cols(
+            xy_to_xyz(self.red)?,
+            xy_to_xyz(self.green)?,
+            xy_to_xyz(self.blue)?,
+        );
+        let scale = primaries.invert()? * xy_to_xyz(self.white)?;
+
+        Some(Matrix3::from_cols(
+            primaries.x * scale.x,
+            primaries.y * scale.y,
+            primaries.z * scale.z,
+        ))
+    }
+
+    /// Matrix converting linear RGB values with these primaries into linear RGB values with the `target` primaries
+    ///
+    /// Differing white points are adapted using the Bradford transform.
+    /// Returns `None` for degenerate primaries.
+    pub fn conversion_matrix(&self, target: &Primaries) -> Option<Matrix3<f32>> {
+        if self == target {
+            return Some(Matrix3::identity());
+        }
+
+        let to_xyz = self.to_xyz()?;
+        let from_xyz = target.to_xyz()?.invert()?;
+
+        let adaptation = if self.white != target.white {
+            #[rustfmt::skip]
+            let bradford = Matrix3::new(
+                0.8951, -0.7502, 0.0389,
+                0.2664, 1.7135, -0.0685,
+                -0.1614, 0.0367, 1.0296,
+            );
+            let src = bradford * xy_to_xyz(self.white)?;
+            let dst = bradford * xy_to_xyz(target.white)?;
+            let scale = Matrix3::from_diagonal(Vector3::new(dst.x / src.x, dst.y / src.y, dst.z / src.z));
+            bradford.invert()? * scale * bradford
+        } else {
+            Matrix3::identity()
+        };
+
+        Some(from_xyz * adaptation * to_xyz)
+    }
+}
+
+fn xy_to_xyz((x, y): (f32, f32)) -> Option<Vector3<f32>> {
+    if y == 0.0 {
+        // primaries without luminance (e.g. of XYZ), only the direction matters
+        return (0.0..=1.0).contains(&x).then(|| Vector3::new(x, 0.0, 1.0 - x));
+    }
+    Some(Vector3::new(x / y, 1.0, (1.0 - x - y) / y))
+}
+
+/// Transfer function describing the encoding of color values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransferFunction {
+    /// Piece-wise sRGB transfer function (IEC 61966-2-1)
+    Srgb,
+    /// Pure power law with exponent 2.2
+    Gamma22,
+    /// Pure power law with exponent 2.8
+    Gamma28,
+    /// BT.1886 with a black level of zero, making it a pure power law with exponent 2.4
+    Bt1886,
+    /// Linear encoding
+    Linear,
+    /// SMPTE ST 2084 perceptual quantizer, encoding absolute luminance up to 10000 cd/m²
+    Pq,
+    /// Pure power law with the given exponent
+    Power(f32),
+}
+
+const PQ_M1: f32 = 2610.0 / 16384.0;
+const PQ_M2: f32 = 2523.0 / 4096.0 * 128.0;
+const PQ_C1: f32 = 3424.0 / 4096.0;
+const PQ_C2: f32 = 2413.0 / 4096.0 * 32.0;
+const PQ_C3: f32 = 2392.0 / 4096.0 * 32.0;
+
+impl TransferFunction {
+    /// Maximum luminance in cd/m² encodable by [`TransferFunction::Pq`]
+    pub const PQ_MAX_LUMINANCE: f32 = 10000.0;
+
+    /// Returns the exponent, if the transfer function is a pure power law
+    pub fn exponent(&self) -> Option<f32> {
+        match self {
+            TransferFunction::Gamma22 => Some(2.2),
+            TransferFunction::Gamma28 => Some(2.8),
+            TransferFunction::Bt1886 => Some(2.4),
+            TransferFunction::Linear => Some(1.0),
+            TransferFunction::Power(exp) => Some(*exp),
+            TransferFunction::Srgb | TransferFunction::Pq => None,
+        }
+    }
+
+    /// Decodes an encoded value into a linear value in the range `0.0..=1.0`
+    ///
+    /// For [`TransferFunction::Pq`] `1.0` corresponds to [`TransferFunction::PQ_MAX_LUMINANCE`].
+    pub fn eotf(&self, value: f32) -> f32 {
+        let value = value.clamp(0.0, 1.0);
+        match self {
+            TransferFunction::Srgb => {
+                if value <= 0.04045 {
+                    value / 12.92
+                } else {
+                    ((value + 0.055) / 1.055).powf(2.4)
+                }
+            }
+            TransferFunction::Pq => {
+                let p = value.powf(1.0 / PQ_M2);
+                ((p - PQ_C1).max(0.0) / (PQ_C2 - PQ_C3 * p)).powf(1.0 / PQ_M1)
+            }
+            tf => value.powf(tf.exponent().unwrap()),
+        }
+    }
+
+    /// Encodes a linear value in the range `0.0..=1.0`, inverse of [`TransferFunction::eotf`]
+    pub fn inverse_eotf(&self, value: f32) -> f32 {
+        let value = value.clamp(0.0, 1.0);
+        match self {
+            TransferFunction::Srgb => {
+                if value <= 0.0031308 {
+                    value * 12.92
+                } else {
+                    1.055 * value.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            TransferFunction::Pq => {
+                let p = value.powf(PQ_M1);
+                ((PQ_C1 + PQ_C2 * p) / (1.0 + PQ_C3 * p)).powf(PQ_M2)
+            }
+            tf => value.powf(1.0 / tf.exponent().unwrap()),
+        }
+    }
+}
+
+/// Luminance range of a color description in cd/m²
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Luminance {
+    /// Minimum luminance
+    pub min: f32,
+    /// Maximum luminance
+    pub max: f32,
+    /// Luminance of the reference white
+    pub reference: f32,
+}
+
+impl Luminance {
+    /// Default luminance range of sRGB displays
+    pub const SRGB: Luminance = Luminance {
+        min: 0.2,
+        max: 80.0,
+        reference: 80.0,
+    };
+    /// Default luminance range of [`TransferFunction::Pq`] as defined by BT.2100
+    pub const PQ: Luminance = Luminance {
+        min: 0.005,
+        max: TransferFunction::PQ_MAX_LUMINANCE,
+        reference: 203.0,
+    };
+
+    /// Returns the default luminance range for a given transfer function
+    pub fn default_for(tf: TransferFunction) -> Luminance {
+        match tf {
+            TransferFunction::Pq => Luminance::PQ,
+            _ => Luminance::SRGB,
+        }
+    }
+}
+
+/// Description of the encoding of color values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorDescription {
+    /// Primaries and white point
+    pub primaries: Primaries,
+    /// Transfer function
+    pub transfer_function: TransferFunction,
+    /// Luminance range
+    pub luminance: Luminance,
+}
+
+impl Default for ColorDescription {
+    fn default() -> Self {
+        ColorDescription::SRGB
+    }
+}
+
+impl ColorDescription {
+    /// sRGB color description, the assumed encoding of content without a color description
+    pub const SRGB: ColorDescription = ColorDescription {
+        primaries: Primaries::SRGB,
+        transfer_function: TransferFunction::Srgb,
+        luminance: Luminance::SRGB,
+    };
+    /// BT.2100 color description using the perceptual quantizer
+    pub const BT2100_PQ: ColorDescription = ColorDescription {
+        primaries: Primaries::BT2020,
+        transfer_function: TransferFunction::Pq,
+        luminance: Luminance::PQ,
+    };
+
+    /// Create a new color description with the default luminance range of the transfer function
+    pub fn new(primaries: Primaries, transfer_function: TransferFunction) -> ColorDescription {
+        ColorDescription {
+            primaries,
+            transfer_function,
+            luminance: Luminance::default_for(transfer_function),
+        }
+    }
+
+    /// Factor converting the output of [`TransferFunction::eotf`] into linear values relative
+    /// to the reference white of this description
+    pub fn luminance_scale(&self) -> f32 {
+        let max = match self.transfer_function {
+            TransferFunction::Pq => TransferFunction::PQ_MAX_LUMINANCE,
+            _ => self.luminance.max,
+        };
+        max / self.luminance.reference
+    }
+
+    /// Converts an encoded color with premultiplied alpha into the linear blending space of `target`
+    ///
+    /// The blending space uses the primaries of `target` and maps the reference white to `1.0`.
+    pub fn to_blending_space(&self, target: &ColorDescription, color: [f32; 4]) -> [f32; 4] {
+        let alpha = color[3];
+        if alpha <= 0.0 {
+            return [0.0; 4];
+        }
+
+        let scale = self.luminance_scale();
+        let linear = Vector3::new(
+            self.transfer_function.eotf(color[0] / alpha) * scale,
+            self.transfer_function.eotf(color[1] / alpha) * scale,
+            self.transfer_function.eotf(color[2] / alpha) * scale,
+        );
+        let converted = self
+            .primaries
+            .conversion_matrix(&target.primaries)
+            .unwrap_or_else(Matrix3::identity)
+            * linear;
+
+        [
+            converted.x * alpha,
+            converted.y * alpha,
+            converted.z * alpha,
+            alpha,
+        ]
+    }
+}
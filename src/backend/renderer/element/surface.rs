@@ -224,7 +224,10 @@ use wayland_server::protocol::wl_surface;
 use crate::{
     backend::renderer::{utils::RendererSurfaceStateUserData, Frame, ImportAll, Renderer, Texture},
     utils::{Buffer, Physical, Point, Rectangle, Scale, Size, Transform},
-    wayland::compositor::{self, SurfaceData, TraversalAction},
+    wayland::{
+        alpha_modifier::surface_alpha_multiplier,
+        compositor::{self, SurfaceData, TraversalAction},
+    },
 };
 
 use super::{CommitCounter, Element, Id, Kind, RenderElement, UnderlyingStorage};
@@ -335,6 +338,7 @@ impl<R: Renderer + ImportAll> WaylandSurfaceRenderElement<R> {
     {
         let id = Id::from_wayland_resource(surface);
         crate::backend::renderer::utils::import_surface(renderer, states)?;
+        let alpha = alpha * surface_alpha_multiplier(states);
 
         Ok(Self {
             id,
@@ -523,7 +527,11 @@ where
             if let Some(data) = data {
                 let data = data.borrow();
 
-                if let Some(texture) = data.texture::<R>(frame.id()) {
+                if let Some(color) = data.single_pixel_color() {
+                    // the color is premultiplied, so the element alpha applies to all channels
+                    let color = color.map(|c| c * self.alpha);
+                    frame.draw_solid(dst, damage, color)?;
+                } else if let Some(texture) = data.texture::<R>(frame.id()) {
                     frame.render_texture_from_to(
                         texture,
                         src,
@@ -83,6 +83,9 @@ pub enum GlesError {
         /// Uniform type that was declared when compiling
         declared: UniformType,
     },
+    /// Color transformations are not supported by this renderer
+    #[error("Color transformations are not supported by this renderer")]
+    ColorTransformationsUnsupported,
 }
 
 impl From<GlesError> for SwapBuffersError {
@@ -110,6 +113,7 @@ impl From<GlesError> for SwapBuffersError {
             | x @ GlesError::CreateShaderObject
             | x @ GlesError::UniformTypeMismatch { .. }
             | x @ GlesError::UnknownUniform(_)
+            | x @ GlesError::ColorTransformationsUnsupported
             | x @ GlesError::EGLBufferAccessError(_) => SwapBuffersError::TemporaryFailure(Box::new(x)),
         }
     }
@@ -134,6 +138,7 @@ impl From<GlesError> for SwapBuffersError {
             | x @ GlesError::CreateShaderObject
             | x @ GlesError::UniformTypeMismatch { .. }
             | x @ GlesError::UnknownUniform(_)
+            | x @ GlesError::ColorTransformationsUnsupported
             | x @ GlesError::BindBufferEGLError(_) => SwapBuffersError::TemporaryFailure(Box::new(x)),
         }
     }
@@ -38,8 +38,10 @@ pub use uniform::*;
 use self::version::GlVersion;
 
 use super::{
-    sync::SyncPoint, Bind, Blit, DebugFlags, ExportMem, Frame, ImportDma, ImportMem, Offscreen, Renderer,
-    Texture, TextureFilter, TextureMapping, Unbind,
+    color::{ColorDescription, TransferFunction},
+    sync::SyncPoint,
+    Bind, Blit, DebugFlags, ExportMem, Frame, ImportDma, ImportMem, Offscreen, Renderer, Texture,
+    TextureFilter, TextureMapping, Unbind,
 };
 use crate::backend::egl::{
     ffi::egl::{self as ffi_egl, types::EGLImage},
@@ -398,8 +400,9 @@ pub struct GlesRenderer {
     tex_program: GlesTexProgram,
     solid_program: GlesSolidProgram,
     // color-transformation shaders
-    // TODO new tex/solid? shaders
+    tex_color_program: Option<GlesTexProgram>,
     output_program: Option<GlesColorOutputProgram>,
+    output_color: Option<ColorDescription>,
 
     // caches
     buffers: Vec<GlesBuffer>,
@@ -430,6 +433,7 @@ pub struct GlesFrame<'frame> {
     transform: Transform,
     size: Size<i32, Physical>,
     tex_program_override: Option<(GlesTexProgram, Vec<Uniform<'static>>)>,
+    input_color: ColorDescription,
     finished: AtomicBool,
     span: EnteredSpan,
 }
@@ -441,6 +445,7 @@ impl<'frame> fmt::Debug for GlesFrame<'frame> {
             .field("current_projection", &self.current_projection)
             .field("transform", &self.transform)
             .field("tex_program_override", &self.tex_program_override)
+            .field("input_color", &self.input_color)
             .field("size", &self.size)
             .field("finished", &self.finished)
             .finish_non_exhaustive()
@@ -456,6 +461,7 @@ impl fmt::Debug for GlesRenderer {
             .field("capabilities", &self.capabilities)
             .field("tex_program", &self.tex_program)
             .field("solid_program", &self.solid_program)
+            .field("output_color", &self.output_color)
             .field("dmabuf_cache", &self.dmabuf_cache)
             .field("egl", &self.egl)
             .field("gl_version", &self.gl_version)
@@ -697,6 +703,10 @@ impl GlesRenderer {
         let (tx, rx) = channel();
         let tex_program = texture_program(&gl, shaders::FRAGMENT_SHADER, &[], tx.clone())?;
         let solid_program = solid_program(&gl)?;
+        let tex_color_program = capabilities
+            .contains(&Capability::ColorTransformations)
+            .then(|| color_texture_program(&gl, tx.clone()))
+            .transpose()?;
         let output_program = capabilities
             .contains(&Capability::ColorTransformations)
             .then(|| color_output_program(&gl, tx.clone()))
@@ -744,7 +754,9 @@ impl GlesRenderer {
 
             tex_program,
             solid_program,
+            tex_color_program,
             output_program,
+            output_color: None,
             vbos,
             min_filter: TextureFilter::Linear,
             max_filter: TextureFilter::Linear,
@@ -829,6 +841,32 @@ impl GlesRenderer {
     pub fn capabilities(&self) -> &[Capability] {
         &self.capabilities
     }
+
+    /// Sets the color description of the targets rendered to.
+    ///
+    /// If set, frames blend in linear light using the primaries of the output and encode the result
+    /// using its transfer function and luminance range. Textures are decoded according to
+    /// [`GlesFrame::set_input_color_description`] and colors passed to [`Frame::clear`] or
+    /// [`Frame::draw_solid`] are treated as sRGB. If `None` (the default), no color transformations
+    /// are applied at all.
+    ///
+    /// Requires [`Capability::ColorTransformations`]. Custom texture and pixel shaders
+    /// need to handle the color transformations themselves.
+    pub fn set_output_color_description(
+        &mut self,
+        description: Option<ColorDescription>,
+    ) -> Result<(), GlesError> {
+        if description.is_some() && !self.capabilities.contains(&Capability::ColorTransformations) {
+            return Err(GlesError::ColorTransformationsUnsupported);
+        }
+        self.output_color = description;
+        Ok(())
+    }
+
+    /// Returns the color description of the targets rendered to, see [`GlesRenderer::set_output_color_description`].
+    pub fn output_color_description(&self) -> Option<&ColorDescription> {
+        self.output_color.as_ref()
+    }
 }
 
 #[cfg(feature = "wayland_frontend")]
@@ -2332,6 +2370,7 @@ impl Renderer for GlesRenderer {
             transform,
             size: output_size,
             tex_program_override: None,
+            input_color: ColorDescription::SRGB,
             finished: AtomicBool::new(false),
             span,
         })
@@ -2572,6 +2611,25 @@ impl<'frame> GlesFrame<'frame> {
                     self.renderer.gl.UseProgram(program.program);
                     self.renderer.gl.Uniform1i(program.uniform_tex, 0);
 
+                    // without an output description the shadow buffer is copied as is
+                    let (tf, tf_power, luminance_scale) = match self.renderer.output_color.as_ref() {
+                        Some(output) => {
+                            let (tf, tf_power) =
+                                shaders::transfer_function_uniforms(output.transfer_function);
+                            (tf, tf_power, output.luminance_scale().recip())
+                        }
+                        None => {
+                            let (tf, tf_power) =
+                                shaders::transfer_function_uniforms(TransferFunction::Linear);
+                            (tf, tf_power, 1.0)
+                        }
+                    };
+                    self.renderer.gl.Uniform1i(program.uniform_tf, tf);
+                    self.renderer.gl.Uniform1f(program.uniform_tf_power, tf_power);
+                    self.renderer
+                        .gl
+                        .Uniform1f(program.uniform_luminance_scale, luminance_scale);
+
                     self.renderer
                         .gl
                         .EnableVertexAttribArray(program.attrib_vert as u32);
@@ -2632,6 +2690,26 @@ impl<'frame> GlesFrame<'frame> {
         self.tex_program_override = None;
     }
 
+    /// Sets the color description of textures rendered afterwards.
+    ///
+    /// Only has an effect, if an output color description was set via
+    /// [`GlesRenderer::set_output_color_description`] and the default texture shader is used.
+    /// Passing `None` resets the description to sRGB.
+    pub fn set_input_color_description(&mut self, description: Option<ColorDescription>) {
+        self.input_color = description.unwrap_or_default();
+    }
+
+    // Returns the output color description, if color transformations are active
+    fn color_transformation(&self) -> Option<&ColorDescription> {
+        self.renderer.output_color.as_ref().filter(|_| {
+            self.renderer
+                .target
+                .as_ref()
+                .map(|target| target.has_shadow())
+                .unwrap_or(false)
+        })
+    }
+
     /// Draw a solid color to the current target at the specified destination with the specified color.
     #[instrument(skip(self), parent = &self.span)]
     #[profiling::function]
@@ -2648,6 +2726,11 @@ impl<'frame> GlesFrame<'frame> {
         let mut mat = Matrix3::<f32>::identity();
         mat = self.current_projection * mat;
 
+        let color = match self.color_transformation() {
+            Some(output) => ColorDescription::SRGB.to_blending_space(output, color),
+            None => color,
+        };
+
         let instances = damage
             .iter()
             .flat_map(|rect| {
@@ -2909,10 +2992,41 @@ impl<'frame> GlesFrame<'frame> {
         } else {
             ffi::TEXTURE_2D
         };
-        let (tex_program, additional_uniforms) = program
+        let color_uniforms;
+        let (tex_program, additional_uniforms) = match program
             .map(|p| (p, additional_uniforms))
             .or_else(|| self.tex_program_override.as_ref().map(|(p, a)| (p, &**a)))
-            .unwrap_or((&self.renderer.tex_program, &[]));
+        {
+            Some(program) => program,
+            None => match (
+                self.color_transformation(),
+                self.renderer.tex_color_program.as_ref(),
+            ) {
+                (Some(output), Some(color_program)) => {
+                    let (tf, tf_power) =
+                        shaders::transfer_function_uniforms(self.input_color.transfer_function);
+                    let color_matrix = self
+                        .input_color
+                        .primaries
+                        .conversion_matrix(&output.primaries)
+                        .unwrap_or_else(Matrix3::identity);
+                    color_uniforms = [
+                        Uniform::new(shaders::TRANSFER_FUNCTION, tf),
+                        Uniform::new(shaders::TRANSFER_FUNCTION_POWER, tf_power),
+                        Uniform::new(
+                            shaders::COLOR_MATRIX,
+                            UniformValue::Matrix3x3 {
+                                matrices: vec![*AsRef::<[f32; 9]>::as_ref(&color_matrix)],
+                                transpose: false,
+                            },
+                        ),
+                        Uniform::new(shaders::LUMINANCE_SCALE, self.input_color.luminance_scale()),
+                    ];
+                    (color_program, &color_uniforms[..])
+                }
+                _ => (&self.renderer.tex_program, &[][..]),
+            },
+        };
         let program_variant = tex_program.variant_for_format(
             if !tex.0.is_external { tex.0.format } else { None },
             tex.0.has_alpha,
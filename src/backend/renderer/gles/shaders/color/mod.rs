@@ -1,14 +1,34 @@
 /// OpenGL Shaders with support for color transformations
-use crate::backend::renderer::gles::*;
+use crate::backend::renderer::{color::TransferFunction, gles::*};
 
 pub(in super::super) const OUTPUT_VERTEX_SHADER: &str = include_str!("./output.vert");
 pub(in super::super) const OUTPUT_FRAGMENT_SHADER: &str = include_str!("./output.frag");
 
+pub(in super::super) const TEXTURE_FRAGMENT_SHADER: &str = include_str!("./texture.frag");
+
+// uniforms of the color transforming texture shader
+pub(in super::super) const TRANSFER_FUNCTION: &str = "tf";
+pub(in super::super) const TRANSFER_FUNCTION_POWER: &str = "tf_power";
+pub(in super::super) const COLOR_MATRIX: &str = "color_matrix";
+pub(in super::super) const LUMINANCE_SCALE: &str = "luminance_scale";
+
+/// Returns the values of the `tf` and `tf_power` uniforms for a transfer function
+pub(in super::super) fn transfer_function_uniforms(tf: TransferFunction) -> (i32, f32) {
+    match tf {
+        TransferFunction::Srgb => (0, 1.0),
+        TransferFunction::Pq => (2, 1.0),
+        tf => (1, tf.exponent().unwrap_or(1.0)),
+    }
+}
+
 #[derive(Debug)]
 pub(in super::super) struct GlesColorOutputProgram {
     pub(in super::super) program: ffi::types::GLuint,
     pub(in super::super) attrib_vert: ffi::types::GLint,
     pub(in super::super) uniform_tex: ffi::types::GLint,
+    pub(in super::super) uniform_tf: ffi::types::GLint,
+    pub(in super::super) uniform_tf_power: ffi::types::GLint,
+    pub(in super::super) uniform_luminance_scale: ffi::types::GLint,
     pub(super) destruction_callback_sender: Sender<CleanupResource>,
 }
 
@@ -1,12 +1,42 @@
 #version 300 es
 
-// TODO color transformation
-
 precision highp float;
 
 uniform sampler2D tex;
+// transfer function of the output
+uniform int tf;
+uniform float tf_power;
+// scales linear values relative to the reference white into the range of the transfer function
+uniform float luminance_scale;
 out vec4 color;
 
+const int TF_SRGB = 0;
+const int TF_PQ = 2;
+
+const float PQ_M1 = 0.1593017578125;
+const float PQ_M2 = 78.84375;
+const float PQ_C1 = 0.8359375;
+const float PQ_C2 = 18.8515625;
+const float PQ_C3 = 18.6875;
+
+vec3 inverse_eotf(vec3 color) {
+    if (tf == TF_SRGB) {
+        return mix(color * 12.92, 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055, step(vec3(0.0031308), color));
+    } else if (tf == TF_PQ) {
+        vec3 p = pow(color, vec3(PQ_M1));
+        return pow((PQ_C1 + PQ_C2 * p) / (1.0 + PQ_C3 * p), vec3(PQ_M2));
+    } else {
+        return pow(color, vec3(1.0 / tf_power));
+    }
+}
+
 void main() {
     color = texelFetch(tex, ivec2(gl_FragCoord.xy), 0);
-}
\ No newline at end of file
+
+    // encoding has to happen without premultiplied alpha
+    if (color.a > 0.0) {
+        color.rgb /= color.a;
+    }
+    color.rgb = inverse_eotf(clamp(color.rgb * luminance_scale, 0.0, 1.0));
+    color.rgb *= color.a;
+}
@@ -0,0 +1,75 @@
+#version 100
+
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+varying vec2 v_coords;
+
+// transfer function of the texture
+uniform int tf;
+uniform float tf_power;
+// converts from the texture primaries into the blending space
+uniform mat3 color_matrix;
+// scales the decoded values relative to the reference white
+uniform float luminance_scale;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+const int TF_SRGB = 0;
+const int TF_PQ = 2;
+
+const float PQ_M1 = 0.1593017578125;
+const float PQ_M2 = 78.84375;
+const float PQ_C1 = 0.8359375;
+const float PQ_C2 = 18.8515625;
+const float PQ_C3 = 18.6875;
+
+vec3 eotf(vec3 color) {
+    if (tf == TF_SRGB) {
+        return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(vec3(0.04045), color));
+    } else if (tf == TF_PQ) {
+        vec3 p = pow(color, vec3(1.0 / PQ_M2));
+        return pow(max(p - PQ_C1, 0.0) / (PQ_C2 - PQ_C3 * p), vec3(1.0 / PQ_M1));
+    } else {
+        return pow(color, vec3(tf_power));
+    }
+}
+
+void main() {
+    vec4 color = texture2D(tex, v_coords);
+
+#if defined(NO_ALPHA)
+    color = vec4(color.rgb, 1.0);
+#endif
+
+    // decoding has to happen without premultiplied alpha
+    if (color.a > 0.0) {
+        color.rgb /= color.a;
+    }
+    color.rgb = color_matrix * (eotf(clamp(color.rgb, 0.0, 1.0)) * luminance_scale);
+    color = vec4(color.rgb * color.a, color.a) * alpha;
+
+#if defined(DEBUG_FLAGS)
+    if (tint == 1.0)
+        color = vec4(0.0, 0.3, 0.0, 0.2) + color * 0.8;
+#endif
+
+    gl_FragColor = color;
+}
@@ -237,10 +237,34 @@ pub(super) unsafe fn color_output_program(
 
     let vert = CStr::from_bytes_with_nul(b"vert\0").expect("NULL terminated");
     let tex = CStr::from_bytes_with_nul(b"tex\0").expect("NULL terminated");
+    let tf = CStr::from_bytes_with_nul(b"tf\0").expect("NULL terminated");
+    let tf_power = CStr::from_bytes_with_nul(b"tf_power\0").expect("NULL terminated");
+    let luminance_scale = CStr::from_bytes_with_nul(b"luminance_scale\0").expect("NULL terminated");
     Ok(GlesColorOutputProgram {
         program,
         attrib_vert: gl.GetAttribLocation(program, vert.as_ptr() as *const ffi::types::GLchar),
         uniform_tex: gl.GetUniformLocation(program, tex.as_ptr() as *const ffi::types::GLchar),
+        uniform_tf: gl.GetUniformLocation(program, tf.as_ptr() as *const ffi::types::GLchar),
+        uniform_tf_power: gl.GetUniformLocation(program, tf_power.as_ptr() as *const ffi::types::GLchar),
+        uniform_luminance_scale: gl
+            .GetUniformLocation(program, luminance_scale.as_ptr() as *const ffi::types::GLchar),
         destruction_callback_sender,
     })
 }
+
+pub(super) unsafe fn color_texture_program(
+    gl: &ffi::Gles2,
+    destruction_callback_sender: Sender<CleanupResource>,
+) -> Result<GlesTexProgram, GlesError> {
+    texture_program(
+        gl,
+        shaders::TEXTURE_FRAGMENT_SHADER,
+        &[
+            UniformName::new(shaders::TRANSFER_FUNCTION, UniformType::_1i),
+            UniformName::new(shaders::TRANSFER_FUNCTION_POWER, UniformType::_1f),
+            UniformName::new(shaders::COLOR_MATRIX, UniformType::Matrix3x3),
+            UniformName::new(shaders::LUMINANCE_SCALE, UniformType::_1f),
+        ],
+        destruction_callback_sender,
+    )
+}
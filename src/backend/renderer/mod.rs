@@ -6,6 +6,7 @@
 //! Supported rendering apis:
 //!
 //! - Raw OpenGL ES 2
+//! - CPU-only software rendering
 
 use std::collections::HashSet;
 use std::error::Error;
@@ -38,6 +39,9 @@ use crate::backend::egl::{
 #[cfg(feature = "renderer_multi")]
 pub mod multigpu;
 
+#[cfg(feature = "renderer_software")]
+pub mod software;
+
 pub mod utils;
 
 pub mod element;
@@ -46,6 +50,8 @@ pub mod damage;
 
 pub mod sync;
 
+pub mod color;
+
 #[cfg(feature = "renderer_test")]
 pub mod test;
 
@@ -532,7 +538,8 @@ pub trait ImportAll: Renderer {
     /// The `damage` argument provides a list of rectangle locating parts of the buffer that need to be updated. When provided
     /// with an empty list `&[]`, the renderer is allowed to not update the texture at all.
     ///
-    /// Returns `None`, if the buffer type cannot be determined.
+    /// Returns `None`, if the buffer type cannot be determined or the buffer does not require a texture,
+    /// like it is the case for [`BufferType::SinglePixel`] buffers. Those can be drawn using [`Frame::draw_solid`].
     fn import_buffer(
         &mut self,
         buffer: &wl_buffer::WlBuffer,
@@ -559,6 +566,8 @@ impl<R: Renderer + ImportMemWl + ImportEgl + ImportDmaWl> ImportAll for R {
             Some(BufferType::Shm) => Some(self.import_shm_buffer(buffer, surface, damage)),
             Some(BufferType::Egl) => Some(self.import_egl_buffer(buffer, surface, damage)),
             Some(BufferType::Dma) => Some(self.import_dma_buffer(buffer, surface, damage)),
+            // single pixel buffers are drawn as a solid color and never need a texture
+            Some(BufferType::SinglePixel) => None,
             _ => None,
         }
     }
@@ -578,6 +587,8 @@ impl<R: Renderer + ImportMemWl + ImportDmaWl> ImportAll for R {
         match buffer_type(buffer) {
             Some(BufferType::Shm) => Some(self.import_shm_buffer(buffer, surface, damage)),
             Some(BufferType::Dma) => Some(self.import_dma_buffer(buffer, surface, damage)),
+            // single pixel buffers are drawn as a solid color and never need a texture
+            Some(BufferType::SinglePixel) => None,
             _ => None,
         }
     }
@@ -698,6 +709,8 @@ pub enum BufferType {
     Egl,
     /// Buffer is managed by the [`crate::wayland::dmabuf`] global
     Dma,
+    /// Buffer is managed by the [`crate::wayland::single_pixel_buffer`] global
+    SinglePixel,
 }
 
 /// Returns the *type* of a wl_buffer
@@ -712,6 +725,10 @@ pub fn buffer_type(buffer: &wl_buffer::WlBuffer) -> Option<BufferType> {
         return Some(BufferType::Dma);
     }
 
+    if crate::wayland::single_pixel_buffer::get_single_pixel_buffer(buffer).is_ok() {
+        return Some(BufferType::SinglePixel);
+    }
+
     if !matches!(
         crate::wayland::shm::with_buffer_contents(buffer, |_, _, _| ()),
         Err(BufferAccessError::NotManaged)
@@ -751,6 +768,10 @@ pub fn buffer_has_alpha(buffer: &wl_buffer::WlBuffer) -> Option<bool> {
         return Some(crate::backend::allocator::format::has_alpha(dmabuf.0.format));
     }
 
+    if let Ok(spb) = crate::wayland::single_pixel_buffer::get_single_pixel_buffer(buffer) {
+        return Some(spb.has_alpha());
+    }
+
     if let Ok(has_alpha) = crate::wayland::shm::with_buffer_contents(buffer, |_, _, data| {
         shm_format_to_fourcc(data.format).map_or(false, has_alpha)
     }) {
@@ -787,6 +808,10 @@ pub fn buffer_dimensions(buffer: &wl_buffer::WlBuffer) -> Option<Size<i32, Buffe
         return Some((buf.width() as i32, buf.height() as i32).into());
     }
 
+    if crate::wayland::single_pixel_buffer::get_single_pixel_buffer(buffer).is_ok() {
+        return Some((1, 1).into());
+    }
+
     match shm::with_buffer_contents(buffer, |_, _, data| (data.width, data.height).into()) {
         Ok(data) => Some(data),
 
@@ -616,6 +616,10 @@ impl<A: GraphicsApi> GpuManager<A> {
                 // we just need to upload in import_shm_buffer
                 Ok(())
             }
+            Some(BufferType::SinglePixel) => {
+                // drawn as a solid color, nothing to import
+                Ok(())
+            }
             None => {
                 // welp, nothing we can do
                 Ok(())
@@ -0,0 +1,54 @@
+use crate::backend::{
+    allocator::{dmabuf::DmabufMappingError, Fourcc},
+    SwapBuffersError,
+};
+
+#[cfg(feature = "wayland_frontend")]
+use wayland_server::protocol::wl_shm;
+
+/// Error returned during rendering using the software renderer
+#[derive(thiserror::Error, Debug)]
+pub enum SoftwareError {
+    /// No target is currently bound
+    #[error("No rendering target is currently bound")]
+    NoTargetBound,
+    /// The given buffer has an unsupported pixel format
+    #[error("Unsupported pixel format: {0:?}")]
+    UnsupportedPixelFormat(Fourcc),
+    /// The given buffer has an unsupported pixel layout
+    #[error("Unsupported pixel layout")]
+    UnsupportedPixelLayout,
+    /// The given wl buffer has an unsupported pixel format
+    #[error("Unsupported wl_shm format: {0:?}")]
+    #[cfg(feature = "wayland_frontend")]
+    UnsupportedWlPixelFormat(wl_shm::Format),
+    /// The given buffer was not accessible
+    #[error("Error accessing the buffer ({0:?})")]
+    #[cfg(feature = "wayland_frontend")]
+    BufferAccessError(crate::wayland::shm::BufferAccessError),
+    /// The given buffer type is not supported by the software renderer
+    #[error("Unsupported buffer type")]
+    #[cfg(feature = "wayland_frontend")]
+    UnsupportedBufferType,
+    /// There was an error mapping the buffer
+    #[error("Error mapping the buffer")]
+    MappingError(#[from] DmabufMappingError),
+    /// There was an error synchronizing access to the buffer
+    #[error("Error synchronizing access to the buffer")]
+    SyncError(#[source] std::io::Error),
+    /// The provided buffer's size did not match the requested one.
+    #[error("Error reading buffer, size is too small for the given dimensions")]
+    UnexpectedSize,
+    /// The requested region is out of bounds of the buffer
+    #[error("The requested region is out of bounds")]
+    OutOfBounds,
+    /// The texture is currently bound as the rendering target and can't be used as a source at the same time
+    #[error("The texture can't be sampled while being the current rendering target")]
+    TextureBound,
+}
+
+impl From<SoftwareError> for SwapBuffersError {
+    fn from(err: SoftwareError) -> SwapBuffersError {
+        SwapBuffersError::TemporaryFailure(Box::new(err))
+    }
+}
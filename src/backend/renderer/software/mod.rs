@@ -0,0 +1,983 @@
+//! Implementation of the rendering traits using a CPU-only software rasterizer
+//!
+//! The [`SoftwareRenderer`] does not require any graphics hardware and renders directly
+//! into system memory. It is a lot slower than hardware accelerated renderers, but produces
+//! real pixels on any machine, which makes it useful for headless setups and automated testing.
+//!
+//! Supported rendering targets are [`MemoryBuffer`]s, [`SoftwareTexture`]s and linear
+//! single-plane [`Dmabuf`]s, which get mapped into memory while bound.
+//!
+//! ```
+//! use smithay::backend::{
+//!     allocator::{memory::MemoryBuffer, Fourcc},
+//!     renderer::{software::SoftwareRenderer, Bind, Frame, Offscreen, Renderer},
+//! };
+//! use smithay::utils::{Rectangle, Transform};
+//!
+//! let mut renderer = SoftwareRenderer::new();
+//! let buffer: MemoryBuffer = renderer.create_buffer(Fourcc::Argb8888, (64, 64).into()).unwrap();
+//! renderer.bind(buffer.clone()).unwrap();
+//!
+//! let mut frame = renderer.render((64, 64).into(), Transform::Normal).unwrap();
+//! frame
+//!     .clear([1.0, 0.0, 0.0, 1.0], &[Rectangle::from_loc_and_size((0, 0), (64, 64))])
+//!     .unwrap();
+//! let _ = frame.finish().unwrap();
+//!
+//! // Argb8888 is stored as little-endian BGRA
+//! assert_eq!(&buffer.data()[..4], &[0, 0, 255, 255]);
+//! ```
+
+use std::{collections::HashMap, fmt, slice};
+
+use tracing::{info_span, instrument, span, span::EnteredSpan, Level};
+
+#[cfg(feature = "wayland_frontend")]
+use std::{cell::RefCell, rc::Rc};
+
+mod error;
+mod pixel;
+mod texture;
+
+pub use error::*;
+pub use texture::*;
+
+use self::pixel::{ImageMut, ImageRef, Layout, SUPPORTED_FORMATS};
+use super::{
+    sync::SyncPoint, Bind, Blit, DebugFlags, ExportMem, Frame, ImportDma, ImportMem, Offscreen, Renderer,
+    Texture, TextureFilter, Unbind,
+};
+use crate::backend::allocator::{
+    dmabuf::{Dmabuf, DmabufMapping, DmabufMappingMode, DmabufSyncFlags, WeakDmabuf},
+    memory::MemoryBuffer,
+    Buffer, Format, Fourcc, Modifier,
+};
+use crate::utils::{Buffer as BufferCoord, Physical, Point, Rectangle, Size, Transform};
+
+#[cfg(all(feature = "wayland_frontend", feature = "use_system_lib"))]
+use super::ImportEgl;
+#[cfg(feature = "wayland_frontend")]
+use super::{ImportDmaWl, ImportMemWl};
+#[cfg(all(feature = "wayland_frontend", feature = "use_system_lib"))]
+use crate::backend::egl::display::EGLBufferReader;
+#[cfg(feature = "wayland_frontend")]
+use crate::wayland::shm::shm_format_to_fourcc;
+#[cfg(feature = "wayland_frontend")]
+use wayland_server::protocol::wl_buffer;
+
+crate::utils::ids::id_gen!(next_renderer_id, RENDERER_ID, RENDERER_IDS);
+struct RendererId(usize);
+impl Drop for RendererId {
+    fn drop(&mut self) {
+        RENDERER_IDS.lock().unwrap().remove(&self.0);
+    }
+}
+
+#[derive(Debug)]
+enum SoftwareTarget {
+    Memory(MemoryBuffer),
+    Texture(SoftwareTexture),
+    Dmabuf {
+        dmabuf: Dmabuf,
+        mapping: DmabufMapping,
+        layout: Layout,
+    },
+}
+
+impl SoftwareTarget {
+    fn size(&self) -> Size<i32, BufferCoord> {
+        match self {
+            SoftwareTarget::Memory(buffer) => buffer.size(),
+            SoftwareTarget::Texture(texture) => texture.size(),
+            SoftwareTarget::Dmabuf { dmabuf, .. } => dmabuf.size(),
+        }
+    }
+}
+
+/// A renderer utilizing only the CPU
+pub struct SoftwareRenderer {
+    // state
+    target: Option<SoftwareTarget>,
+    downscale_filter: TextureFilter,
+    upscale_filter: TextureFilter,
+    debug_flags: DebugFlags,
+
+    // caches
+    dmabuf_cache: HashMap<WeakDmabuf, SoftwareTexture>,
+
+    id: RendererId,
+    span: tracing::Span,
+}
+
+impl fmt::Debug for SoftwareRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SoftwareRenderer")
+            .field("id", &self.id.0)
+            .field("target", &self.target)
+            .field("downscale_filter", &self.downscale_filter)
+            .field("upscale_filter", &self.upscale_filter)
+            .field("debug_flags", &self.debug_flags)
+            .field("dmabuf_cache", &self.dmabuf_cache)
+            .finish()
+    }
+}
+
+impl Default for SoftwareRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SoftwareRenderer {
+    /// Creates a new software renderer
+    pub fn new() -> SoftwareRenderer {
+        let span = info_span!("renderer_software");
+        SoftwareRenderer {
+            target: None,
+            downscale_filter: TextureFilter::Linear,
+            upscale_filter: TextureFilter::Linear,
+            debug_flags: DebugFlags::empty(),
+            dmabuf_cache: HashMap::new(),
+            id: RendererId(next_renderer_id()),
+            span,
+        }
+    }
+
+    fn cleanup(&mut self) {
+        self.dmabuf_cache.retain(|entry, _| !entry.is_gone());
+    }
+
+    fn is_bound(&self, texture: &SoftwareTexture) -> bool {
+        matches!(&self.target, Some(SoftwareTarget::Texture(target)) if target == texture)
+    }
+
+    /// Run the given closure with write access to the pixels of the current target
+    fn with_target<T>(&mut self, func: impl FnOnce(&mut ImageMut<'_>) -> T) -> Result<T, SoftwareError> {
+        match self.target.as_mut().ok_or(SoftwareError::NoTargetBound)? {
+            SoftwareTarget::Memory(buffer) => {
+                let layout = Layout::for_format(buffer.fourcc())
+                    .ok_or(SoftwareError::UnsupportedPixelFormat(buffer.fourcc()))?;
+                let size = buffer.size();
+                let stride = buffer.stride() as usize;
+                let mut data = buffer.data_mut();
+                Ok(func(&mut ImageMut {
+                    data: &mut data,
+                    stride,
+                    layout,
+                    width: size.w,
+                    height: size.h,
+                }))
+            }
+            SoftwareTarget::Texture(texture) => {
+                let stride = texture.stride();
+                let mut data = texture.0.data.borrow_mut();
+                Ok(func(&mut ImageMut {
+                    data: &mut data,
+                    stride,
+                    layout: texture.0.layout,
+                    width: texture.0.size.w,
+                    height: texture.0.size.h,
+                }))
+            }
+            SoftwareTarget::Dmabuf {
+                dmabuf,
+                mapping,
+                layout,
+            } => {
+                let offset = dmabuf.offsets().next().unwrap() as usize;
+                let stride = dmabuf.strides().next().unwrap() as usize;
+                let size = dmabuf.size();
+                let flags = DmabufSyncFlags::READ | DmabufSyncFlags::WRITE;
+
+                dmabuf
+                    .sync_plane(0, DmabufSyncFlags::START | flags)
+                    .map_err(SoftwareError::SyncError)?;
+                // SAFETY: The mapping is valid as long as the target is bound and covers the whole plane
+                let data = unsafe { slice::from_raw_parts_mut(mapping.ptr() as *mut u8, mapping.length()) };
+                let res = func(&mut ImageMut {
+                    data: &mut data[offset..],
+                    stride,
+                    layout: *layout,
+                    width: size.w,
+                    height: size.h,
+                });
+                dmabuf
+                    .sync_plane(0, DmabufSyncFlags::END | flags)
+                    .map_err(SoftwareError::SyncError)?;
+                Ok(res)
+            }
+        }
+    }
+
+    fn read_target(
+        &mut self,
+        region: Rectangle<i32, BufferCoord>,
+        format: Fourcc,
+    ) -> Result<SoftwareMapping, SoftwareError> {
+        let dst_layout = Layout::for_format(format).ok_or(SoftwareError::UnsupportedPixelFormat(format))?;
+        let target_size = self.target.as_ref().ok_or(SoftwareError::NoTargetBound)?.size();
+        if region.loc.x < 0
+            || region.loc.y < 0
+            || region.size.w < 0
+            || region.size.h < 0
+            || !Rectangle::from_loc_and_size((0, 0), target_size).contains_rect(region)
+        {
+            return Err(SoftwareError::OutOfBounds);
+        }
+
+        let dst_stride = region.size.w as usize * Layout::BPP;
+        let mut data = vec![0; dst_stride * region.size.h as usize];
+        self.with_target(|image| {
+            let offset = region.loc.y as usize * image.stride + region.loc.x as usize * Layout::BPP;
+            pixel::convert(
+                &image.data[offset..],
+                image.stride,
+                image.layout,
+                &mut data,
+                dst_stride,
+                dst_layout,
+                region.size.w as usize,
+                region.size.h as usize,
+            );
+        })?;
+
+        Ok(SoftwareMapping {
+            data,
+            format,
+            size: region.size,
+        })
+    }
+
+    fn upload(
+        texture: &SoftwareTexture,
+        data: &[u8],
+        stride: usize,
+        layout: Layout,
+        regions: &[Rectangle<i32, BufferCoord>],
+    ) -> Result<(), SoftwareError> {
+        let size = texture.size();
+        let dst_stride = texture.stride();
+        let mut dst = texture.0.data.borrow_mut();
+        for region in regions {
+            let Some(region) = region.intersection(Rectangle::from_loc_and_size((0, 0), size)) else {
+                continue;
+            };
+            let src_offset = region.loc.y as usize * stride + region.loc.x as usize * Layout::BPP;
+            let dst_offset = region.loc.y as usize * dst_stride + region.loc.x as usize * Layout::BPP;
+            if data.len()
+                < src_offset + (region.size.h as usize - 1) * stride + region.size.w as usize * Layout::BPP
+            {
+                return Err(SoftwareError::UnexpectedSize);
+            }
+            pixel::convert(
+                &data[src_offset..],
+                stride,
+                layout,
+                &mut dst[dst_offset..],
+                dst_stride,
+                texture.0.layout,
+                region.size.w as usize,
+                region.size.h as usize,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Handle to the currently rendered frame during [`SoftwareRenderer::render`](Renderer::render).
+pub struct SoftwareFrame<'frame> {
+    renderer: &'frame mut SoftwareRenderer,
+    transform: Transform,
+    size: Size<i32, Physical>,
+    span: EnteredSpan,
+}
+
+impl<'frame> fmt::Debug for SoftwareFrame<'frame> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SoftwareFrame")
+            .field("renderer", &self.renderer)
+            .field("transform", &self.transform)
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    type Error = SoftwareError;
+    type TextureId = SoftwareTexture;
+    type Frame<'frame> = SoftwareFrame<'frame>;
+
+    fn id(&self) -> usize {
+        self.id.0
+    }
+
+    fn downscale_filter(&mut self, filter: TextureFilter) -> Result<(), Self::Error> {
+        self.downscale_filter = filter;
+        Ok(())
+    }
+
+    fn upscale_filter(&mut self, filter: TextureFilter) -> Result<(), Self::Error> {
+        self.upscale_filter = filter;
+        Ok(())
+    }
+
+    fn set_debug_flags(&mut self, flags: DebugFlags) {
+        self.debug_flags = flags;
+    }
+
+    fn debug_flags(&self) -> DebugFlags {
+        self.debug_flags
+    }
+
+    #[profiling::function]
+    fn render(
+        &mut self,
+        output_size: Size<i32, Physical>,
+        transform: Transform,
+    ) -> Result<SoftwareFrame<'_>, Self::Error> {
+        if self.target.is_none() {
+            return Err(SoftwareError::NoTargetBound);
+        }
+        self.cleanup();
+
+        // Handle the width/height swap when the output is rotated by 90°/270°.
+        let size = transform.transform_size(output_size);
+        let span = span!(parent: &self.span, Level::DEBUG, "renderer_software_frame", size = ?size, transform = ?transform).entered();
+
+        Ok(SoftwareFrame {
+            renderer: self,
+            transform,
+            size,
+            span,
+        })
+    }
+}
+
+impl<'frame> SoftwareFrame<'frame> {
+    /// Maps a rectangle of the frame onto the current target
+    fn to_target(&self, rect: Rectangle<i32, Physical>) -> Rectangle<i32, Physical> {
+        self.transform.transform_rect_in(rect, &self.size)
+    }
+
+    /// Clips damage relative to `dst` to the bounds of `dst` and the frame
+    fn clip_damage(
+        &self,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+    ) -> Vec<Rectangle<i32, Physical>> {
+        let frame = Rectangle::from_loc_and_size((0, 0), self.size);
+        damage
+            .iter()
+            .filter_map(|rect| {
+                let rect = Rectangle::from_loc_and_size(rect.loc + dst.loc, rect.size);
+                rect.intersection(dst).and_then(|rect| rect.intersection(frame))
+            })
+            .collect()
+    }
+
+    fn fill(
+        &mut self,
+        rects: &[Rectangle<i32, Physical>],
+        color: [f32; 4],
+        blend: bool,
+    ) -> Result<(), SoftwareError> {
+        let rects = rects.iter().map(|rect| self.to_target(*rect)).collect::<Vec<_>>();
+        self.renderer.with_target(|image| {
+            let bounds = Rectangle::<i32, Physical>::from_loc_and_size((0, 0), (image.width, image.height));
+            for rect in rects.iter().filter_map(|rect| rect.intersection(bounds)) {
+                if !blend || color[3] >= 1.0 {
+                    image.fill((rect.loc.x, rect.loc.y, rect.size.w, rect.size.h), color);
+                } else {
+                    for y in rect.loc.y..rect.loc.y + rect.size.h {
+                        for x in rect.loc.x..rect.loc.x + rect.size.w {
+                            image.blend(x, y, color);
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl<'frame> Frame for SoftwareFrame<'frame> {
+    type Error = SoftwareError;
+    type TextureId = SoftwareTexture;
+
+    fn id(&self) -> usize {
+        self.renderer.id()
+    }
+
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    #[profiling::function]
+    fn clear(&mut self, color: [f32; 4], at: &[Rectangle<i32, Physical>]) -> Result<(), SoftwareError> {
+        let rects = self.clip_damage(Rectangle::from_loc_and_size((0, 0), self.size), at);
+        self.fill(&rects, color, false)
+    }
+
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    #[profiling::function]
+    fn draw_solid(
+        &mut self,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        color: [f32; 4],
+    ) -> Result<(), SoftwareError> {
+        let rects = self.clip_damage(dst, damage);
+        self.fill(&rects, color, true)
+    }
+
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    #[profiling::function]
+    fn render_texture_from_to(
+        &mut self,
+        texture: &SoftwareTexture,
+        src: Rectangle<f64, BufferCoord>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        src_transform: Transform,
+        alpha: f32,
+    ) -> Result<(), SoftwareError> {
+        if src.size.is_empty() || dst.size.is_empty() || texture.size().is_empty() {
+            return Ok(());
+        }
+        if self.renderer.is_bound(texture) {
+            return Err(SoftwareError::TextureBound);
+        }
+
+        let rects = self.clip_damage(dst, damage);
+        if rects.is_empty() {
+            return Ok(());
+        }
+
+        // Maps a position relative to `dst` onto the texture, `src_transform` describes
+        // the transformation from the destination orientation into buffer space.
+        let logical_src_size = src_transform.transform_size(src.size);
+        let map = |x: f64, y: f64| -> Point<f64, BufferCoord> {
+            let point = Point::<f64, BufferCoord>::from((
+                x / dst.size.w as f64 * logical_src_size.w,
+                y / dst.size.h as f64 * logical_src_size.h,
+            ));
+            src_transform.transform_point_in(point, &logical_src_size) + src.loc
+        };
+        let origin = map(0.0, 0.0);
+        let step_x = map(1.0, 0.0) - origin;
+        let step_y = map(0.0, 1.0) - origin;
+
+        let upscale = (dst.size.w as f64 * dst.size.h as f64) > (src.size.w * src.size.h);
+        let filter = if upscale {
+            self.renderer.upscale_filter
+        } else {
+            self.renderer.downscale_filter
+        };
+        let tint = self.renderer.debug_flags.contains(DebugFlags::TINT);
+
+        // Maps frame pixels onto target pixels
+        let target_origin = self.to_target(Rectangle::from_loc_and_size((0, 0), (1, 1))).loc;
+        let target_x = self.to_target(Rectangle::from_loc_and_size((1, 0), (1, 1))).loc - target_origin;
+        let target_y = self.to_target(Rectangle::from_loc_and_size((0, 1), (1, 1))).loc - target_origin;
+
+        let data = texture.0.data.borrow();
+        let source = ImageRef {
+            data: &data,
+            stride: texture.stride(),
+            layout: texture.0.layout,
+            width: texture.0.size.w,
+            height: texture.0.size.h,
+            y_inverted: texture.0.y_inverted,
+        };
+
+        self.renderer.with_target(|image| {
+            for rect in rects {
+                for y in rect.loc.y..rect.loc.y + rect.size.h {
+                    for x in rect.loc.x..rect.loc.x + rect.size.w {
+                        let tx = target_origin.x + target_x.x * x + target_y.x * y;
+                        let ty = target_origin.y + target_x.y * x + target_y.y * y;
+                        if tx < 0 || ty < 0 || tx >= image.width || ty >= image.height {
+                            continue;
+                        }
+
+                        let rel_x = (x - dst.loc.x) as f64 + 0.5;
+                        let rel_y = (y - dst.loc.y) as f64 + 0.5;
+                        let sx = origin.x + step_x.x * rel_x + step_y.x * rel_y;
+                        let sy = origin.y + step_x.y * rel_x + step_y.y * rel_y;
+
+                        let mut color = match filter {
+                            TextureFilter::Nearest => source.sample_nearest(sx, sy),
+                            TextureFilter::Linear => source.sample_linear(sx, sy),
+                        };
+                        for channel in color.iter_mut() {
+                            *channel *= alpha;
+                        }
+                        if tint {
+                            color = [
+                                color[0] * 0.8,
+                                0.3 + color[1] * 0.8,
+                                color[2] * 0.8,
+                                0.2 + color[3] * 0.8,
+                            ];
+                        }
+                        image.blend(tx, ty, color);
+                    }
+                }
+            }
+        })
+    }
+
+    fn transformation(&self) -> Transform {
+        self.transform
+    }
+
+    fn finish(self) -> Result<SyncPoint, Self::Error> {
+        // All operations are executed immediately
+        Ok(SyncPoint::signaled())
+    }
+}
+
+impl ImportMem for SoftwareRenderer {
+    #[instrument(level = "trace", parent = &self.span, skip(self, data))]
+    #[profiling::function]
+    fn import_memory(
+        &mut self,
+        data: &[u8],
+        format: Fourcc,
+        size: Size<i32, BufferCoord>,
+        flipped: bool,
+    ) -> Result<SoftwareTexture, SoftwareError> {
+        let layout = Layout::for_format(format).ok_or(SoftwareError::UnsupportedPixelFormat(format))?;
+        let stride = size.w as usize * Layout::BPP;
+        if data.len() < stride * size.h as usize {
+            return Err(SoftwareError::UnexpectedSize);
+        }
+
+        let texture = SoftwareTexture::new(format, layout, size, flipped);
+        Self::upload(
+            &texture,
+            data,
+            stride,
+            layout,
+            &[Rectangle::from_loc_and_size((0, 0), size)],
+        )?;
+        Ok(texture)
+    }
+
+    #[instrument(level = "trace", parent = &self.span, skip(self, data))]
+    #[profiling::function]
+    fn update_memory(
+        &mut self,
+        texture: &SoftwareTexture,
+        data: &[u8],
+        region: Rectangle<i32, BufferCoord>,
+    ) -> Result<(), SoftwareError> {
+        if !Rectangle::from_loc_and_size((0, 0), texture.size()).contains_rect(region) {
+            return Err(SoftwareError::OutOfBounds);
+        }
+
+        Self::upload(texture, data, texture.stride(), texture.0.layout, &[region])
+    }
+
+    fn mem_formats(&self) -> Box<dyn Iterator<Item = Fourcc>> {
+        Box::new(SUPPORTED_FORMATS.iter().copied())
+    }
+}
+
+#[cfg(feature = "wayland_frontend")]
+impl ImportMemWl for SoftwareRenderer {
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    #[profiling::function]
+    fn import_shm_buffer(
+        &mut self,
+        buffer: &wl_buffer::WlBuffer,
+        surface: Option<&crate::wayland::compositor::SurfaceData>,
+        damage: &[Rectangle<i32, BufferCoord>],
+    ) -> Result<SoftwareTexture, SoftwareError> {
+        use crate::wayland::shm::with_buffer_contents;
+
+        // why not store a `SoftwareTexture`? because the user might do so.
+        // this is guaranteed a non-public internal type, so we are good.
+        type CacheMap = HashMap<usize, Rc<SoftwareTextureInternal>>;
+
+        with_buffer_contents(buffer, |ptr, len, data| {
+            let offset = data.offset as usize;
+            let size = Size::<i32, BufferCoord>::from((data.width, data.height));
+            let stride = data.stride as usize;
+            let format = shm_format_to_fourcc(data.format)
+                .ok_or(SoftwareError::UnsupportedWlPixelFormat(data.format))?;
+            let layout =
+                Layout::for_format(format).ok_or(SoftwareError::UnsupportedWlPixelFormat(data.format))?;
+
+            if offset > len {
+                return Err(SoftwareError::UnexpectedSize);
+            }
+            // SAFETY: The shm handler ensures the pool is at least `len` bytes large
+            let data = unsafe { slice::from_raw_parts(ptr.add(offset), len - offset) };
+
+            let id = self.id();
+            let cached = surface.and_then(|surface| {
+                surface
+                    .data_map
+                    .insert_if_missing(|| Rc::new(RefCell::new(CacheMap::new())));
+                surface
+                    .data_map
+                    .get::<Rc<RefCell<CacheMap>>>()
+                    .unwrap()
+                    .borrow()
+                    .get(&id)
+                    .cloned()
+                    .filter(|texture| texture.size == size && texture.format == format)
+            });
+
+            let (texture, upload_full) = match cached {
+                Some(texture) => (SoftwareTexture(texture), false),
+                None => {
+                    let texture = SoftwareTexture::new(format, layout, size, false);
+                    if let Some(surface) = surface {
+                        surface
+                            .data_map
+                            .get::<Rc<RefCell<CacheMap>>>()
+                            .unwrap()
+                            .borrow_mut()
+                            .insert(id, texture.0.clone());
+                    }
+                    (texture, true)
+                }
+            };
+
+            if upload_full || damage.is_empty() {
+                tracing::trace!("Uploading shm texture");
+                Self::upload(
+                    &texture,
+                    data,
+                    stride,
+                    layout,
+                    &[Rectangle::from_loc_and_size((0, 0), size)],
+                )?;
+            } else {
+                tracing::trace!("Uploading partial shm texture");
+                Self::upload(&texture, data, stride, layout, damage)?;
+            }
+
+            Ok(texture)
+        })
+        .map_err(SoftwareError::BufferAccessError)?
+    }
+}
+
+impl ImportDma for SoftwareRenderer {
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    #[profiling::function]
+    fn import_dmabuf(
+        &mut self,
+        buffer: &Dmabuf,
+        damage: Option<&[Rectangle<i32, BufferCoord>]>,
+    ) -> Result<SoftwareTexture, SoftwareError> {
+        let format = buffer.format();
+        if !self.has_dmabuf_format(format) || buffer.num_planes() != 1 {
+            return Err(SoftwareError::UnsupportedPixelLayout);
+        }
+        let layout =
+            Layout::for_format(format.code).ok_or(SoftwareError::UnsupportedPixelFormat(format.code))?;
+        let size = buffer.size();
+
+        let (texture, regions) = match self
+            .dmabuf_cache
+            .get(&buffer.weak())
+            .filter(|texture| texture.size() == size)
+        {
+            Some(texture) => match damage {
+                Some([]) => return Ok(texture.clone()),
+                Some(damage) => (texture.clone(), damage.to_vec()),
+                None => (texture.clone(), vec![Rectangle::from_loc_and_size((0, 0), size)]),
+            },
+            None => {
+                let texture = SoftwareTexture::new(format.code, layout, size, buffer.y_inverted());
+                self.dmabuf_cache.insert(buffer.weak(), texture.clone());
+                (texture, vec![Rectangle::from_loc_and_size((0, 0), size)])
+            }
+        };
+
+        let mapping = buffer.map_plane(0, DmabufMappingMode::READ)?;
+        let offset = buffer.offsets().next().unwrap() as usize;
+        let stride = buffer.strides().next().unwrap() as usize;
+
+        buffer
+            .sync_plane(0, DmabufSyncFlags::START | DmabufSyncFlags::READ)
+            .map_err(SoftwareError::SyncError)?;
+        // SAFETY: The mapping covers the whole plane and stays alive until the end of this function
+        let data = unsafe { slice::from_raw_parts(mapping.ptr() as *const u8, mapping.length()) };
+        let res = Self::upload(&texture, &data[offset..], stride, layout, &regions);
+        buffer
+            .sync_plane(0, DmabufSyncFlags::END | DmabufSyncFlags::READ)
+            .map_err(SoftwareError::SyncError)?;
+        res?;
+
+        Ok(texture)
+    }
+
+    fn dmabuf_formats(&self) -> Box<dyn Iterator<Item = Format>> {
+        Box::new(SUPPORTED_FORMATS.iter().flat_map(|code| {
+            [Modifier::Linear, Modifier::Invalid]
+                .into_iter()
+                .map(|modifier| Format {
+                    code: *code,
+                    modifier,
+                })
+        }))
+    }
+}
+
+#[cfg(feature = "wayland_frontend")]
+impl ImportDmaWl for SoftwareRenderer {}
+
+#[cfg(all(feature = "wayland_frontend", feature = "use_system_lib"))]
+impl ImportEgl for SoftwareRenderer {
+    fn bind_wl_display(
+        &mut self,
+        _display: &wayland_server::DisplayHandle,
+    ) -> Result<(), crate::backend::egl::Error> {
+        Err(crate::backend::egl::Error::EglExtensionNotSupported(&[
+            "EGL_WL_bind_wayland_display",
+        ]))
+    }
+
+    fn unbind_wl_display(&mut self) {}
+
+    fn egl_reader(&self) -> Option<&EGLBufferReader> {
+        None
+    }
+
+    fn import_egl_buffer(
+        &mut self,
+        _buffer: &wl_buffer::WlBuffer,
+        _surface: Option<&crate::wayland::compositor::SurfaceData>,
+        _damage: &[Rectangle<i32, BufferCoord>],
+    ) -> Result<SoftwareTexture, SoftwareError> {
+        Err(SoftwareError::UnsupportedBufferType)
+    }
+}
+
+impl ExportMem for SoftwareRenderer {
+    type TextureMapping = SoftwareMapping;
+
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    #[profiling::function]
+    fn copy_framebuffer(
+        &mut self,
+        region: Rectangle<i32, BufferCoord>,
+        format: Fourcc,
+    ) -> Result<SoftwareMapping, SoftwareError> {
+        self.read_target(region, format)
+    }
+
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    #[profiling::function]
+    fn copy_texture(
+        &mut self,
+        texture: &SoftwareTexture,
+        region: Rectangle<i32, BufferCoord>,
+        format: Fourcc,
+    ) -> Result<SoftwareMapping, SoftwareError> {
+        let old_target = self.target.replace(SoftwareTarget::Texture(texture.clone()));
+        let res = self.read_target(region, format);
+        self.target = old_target;
+        res
+    }
+
+    fn map_texture<'a>(&mut self, texture_mapping: &'a SoftwareMapping) -> Result<&'a [u8], SoftwareError> {
+        Ok(&texture_mapping.data)
+    }
+}
+
+impl Bind<MemoryBuffer> for SoftwareRenderer {
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    fn bind(&mut self, buffer: MemoryBuffer) -> Result<(), SoftwareError> {
+        Layout::for_format(buffer.fourcc()).ok_or(SoftwareError::UnsupportedPixelFormat(buffer.fourcc()))?;
+        self.target = Some(SoftwareTarget::Memory(buffer));
+        Ok(())
+    }
+
+    fn supported_formats(&self) -> Option<std::collections::HashSet<Format>> {
+        Some(
+            SUPPORTED_FORMATS
+                .iter()
+                .map(|code| Format {
+                    code: *code,
+                    modifier: Modifier::Linear,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Offscreen<MemoryBuffer> for SoftwareRenderer {
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    fn create_buffer(
+        &mut self,
+        format: Fourcc,
+        size: Size<i32, BufferCoord>,
+    ) -> Result<MemoryBuffer, SoftwareError> {
+        Layout::for_format(format).ok_or(SoftwareError::UnsupportedPixelFormat(format))?;
+        MemoryBuffer::new(format, size).map_err(|_| SoftwareError::UnexpectedSize)
+    }
+}
+
+impl Bind<SoftwareTexture> for SoftwareRenderer {
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    fn bind(&mut self, texture: SoftwareTexture) -> Result<(), SoftwareError> {
+        self.target = Some(SoftwareTarget::Texture(texture));
+        Ok(())
+    }
+
+    fn supported_formats(&self) -> Option<std::collections::HashSet<Format>> {
+        Some(
+            SUPPORTED_FORMATS
+                .iter()
+                .map(|code| Format {
+                    code: *code,
+                    modifier: Modifier::Linear,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Offscreen<SoftwareTexture> for SoftwareRenderer {
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    fn create_buffer(
+        &mut self,
+        format: Fourcc,
+        size: Size<i32, BufferCoord>,
+    ) -> Result<SoftwareTexture, SoftwareError> {
+        let layout = Layout::for_format(format).ok_or(SoftwareError::UnsupportedPixelFormat(format))?;
+        if size.w < 0 || size.h < 0 {
+            return Err(SoftwareError::UnexpectedSize);
+        }
+        Ok(SoftwareTexture::new(format, layout, size, false))
+    }
+}
+
+impl Bind<Dmabuf> for SoftwareRenderer {
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    fn bind(&mut self, dmabuf: Dmabuf) -> Result<(), SoftwareError> {
+        let format = dmabuf.format();
+        if !self.has_dmabuf_format(format) || dmabuf.num_planes() != 1 {
+            return Err(SoftwareError::UnsupportedPixelLayout);
+        }
+        let layout =
+            Layout::for_format(format.code).ok_or(SoftwareError::UnsupportedPixelFormat(format.code))?;
+
+        // unbind first, so we don't keep two mappings around
+        self.target = None;
+        let mapping = dmabuf.map_plane(0, DmabufMappingMode::READ | DmabufMappingMode::WRITE)?;
+
+        self.target = Some(SoftwareTarget::Dmabuf {
+            dmabuf,
+            mapping,
+            layout,
+        });
+        Ok(())
+    }
+
+    fn supported_formats(&self) -> Option<std::collections::HashSet<Format>> {
+        Some(self.dmabuf_formats().collect())
+    }
+}
+
+impl Unbind for SoftwareRenderer {
+    #[instrument(level = "trace", parent = &self.span, skip(self))]
+    fn unbind(&mut self) -> Result<(), SoftwareError> {
+        self.target = None;
+        Ok(())
+    }
+}
+
+impl<Target> Blit<Target> for SoftwareRenderer
+where
+    Self: Bind<Target>,
+{
+    #[instrument(level = "trace", parent = &self.span, skip(self, to))]
+    #[profiling::function]
+    fn blit_to(
+        &mut self,
+        to: Target,
+        src: Rectangle<i32, Physical>,
+        dst: Rectangle<i32, Physical>,
+        filter: TextureFilter,
+    ) -> Result<(), SoftwareError> {
+        let src_mapping = self.read_target(
+            Rectangle::from_loc_and_size((src.loc.x, src.loc.y), (src.size.w, src.size.h)),
+            Fourcc::Abgr8888,
+        )?;
+
+        let old_target = self.target.take();
+        let res = self
+            .bind(to)
+            .and_then(|_| self.blit_mapping(&src_mapping, dst, filter));
+        self.target = old_target;
+        res
+    }
+
+    #[instrument(level = "trace", parent = &self.span, skip(self, from))]
+    #[profiling::function]
+    fn blit_from(
+        &mut self,
+        from: Target,
+        src: Rectangle<i32, Physical>,
+        dst: Rectangle<i32, Physical>,
+        filter: TextureFilter,
+    ) -> Result<(), SoftwareError> {
+        let old_target = self.target.take();
+        let src_mapping = self.bind(from).and_then(|_| {
+            self.read_target(
+                Rectangle::from_loc_and_size((src.loc.x, src.loc.y), (src.size.w, src.size.h)),
+                Fourcc::Abgr8888,
+            )
+        });
+        self.target = old_target;
+        self.blit_mapping(&src_mapping?, dst, filter)
+    }
+}
+
+impl SoftwareRenderer {
+    /// Copies the contents of a mapping into `dst` of the current target
+    fn blit_mapping(
+        &mut self,
+        mapping: &SoftwareMapping,
+        dst: Rectangle<i32, Physical>,
+        filter: TextureFilter,
+    ) -> Result<(), SoftwareError> {
+        if dst.size.is_empty() || mapping.size.is_empty() {
+            return Ok(());
+        }
+
+        let source = ImageRef {
+            data: &mapping.data,
+            stride: mapping.size.w as usize * Layout::BPP,
+            layout: Layout::for_format(mapping.format).expect("Mappings are always of a supported format"),
+            width: mapping.size.w,
+            height: mapping.size.h,
+            y_inverted: false,
+        };
+        let scale_x = mapping.size.w as f64 / dst.size.w as f64;
+        let scale_y = mapping.size.h as f64 / dst.size.h as f64;
+
+        self.with_target(|image| {
+            let bounds = Rectangle::<i32, Physical>::from_loc_and_size((0, 0), (image.width, image.height));
+            if let Some(rect) = dst.intersection(bounds) {
+                for y in rect.loc.y..rect.loc.y + rect.size.h {
+                    for x in rect.loc.x..rect.loc.x + rect.size.w {
+                        let sx = ((x - dst.loc.x) as f64 + 0.5) * scale_x;
+                        let sy = ((y - dst.loc.y) as f64 + 0.5) * scale_y;
+                        let color = match filter {
+                            TextureFilter::Nearest => source.sample_nearest(sx, sy),
+                            TextureFilter::Linear => source.sample_linear(sx, sy),
+                        };
+                        image.set(x, y, color);
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,235 @@
+//! Pixel access and blending helpers of the software renderer
+//!
+//! All colors handled here are premultiplied `[r, g, b, a]` values in the range of `0.0..=1.0`.
+
+use crate::backend::allocator::Fourcc;
+
+/// Formats the software renderer is able to read and write
+pub(super) const SUPPORTED_FORMATS: &[Fourcc] = &[
+    Fourcc::Argb8888,
+    Fourcc::Xrgb8888,
+    Fourcc::Abgr8888,
+    Fourcc::Xbgr8888,
+    Fourcc::Rgba8888,
+    Fourcc::Rgbx8888,
+    Fourcc::Bgra8888,
+    Fourcc::Bgrx8888,
+];
+
+/// Byte offsets of the color channels inside a little-endian 32 bit pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Layout {
+    r: usize,
+    g: usize,
+    b: usize,
+    a: Option<usize>,
+    x: Option<usize>,
+}
+
+impl Layout {
+    pub(super) fn for_format(format: Fourcc) -> Option<Layout> {
+        let (r, g, b, a, x) = match format {
+            Fourcc::Argb8888 => (2, 1, 0, Some(3), None),
+            Fourcc::Xrgb8888 => (2, 1, 0, None, Some(3)),
+            Fourcc::Abgr8888 => (0, 1, 2, Some(3), None),
+            Fourcc::Xbgr8888 => (0, 1, 2, None, Some(3)),
+            Fourcc::Rgba8888 => (3, 2, 1, Some(0), None),
+            Fourcc::Rgbx8888 => (3, 2, 1, None, Some(0)),
+            Fourcc::Bgra8888 => (1, 2, 3, Some(0), None),
+            Fourcc::Bgrx8888 => (1, 2, 3, None, Some(0)),
+            _ => return None,
+        };
+        Some(Layout { r, g, b, a, x })
+    }
+
+    /// Bytes per pixel of all supported formats
+    pub(super) const BPP: usize = 4;
+
+    #[inline]
+    pub(super) fn read(&self, px: &[u8]) -> [f32; 4] {
+        [
+            px[self.r] as f32 / 255.0,
+            px[self.g] as f32 / 255.0,
+            px[self.b] as f32 / 255.0,
+            self.a.map(|a| px[a] as f32 / 255.0).unwrap_or(1.0),
+        ]
+    }
+
+    #[inline]
+    pub(super) fn encode(&self, color: [f32; 4]) -> [u8; 4] {
+        let mut px = [0u8; 4];
+        px[self.r] = to_u8(color[0]);
+        px[self.g] = to_u8(color[1]);
+        px[self.b] = to_u8(color[2]);
+        if let Some(a) = self.a {
+            px[a] = to_u8(color[3]);
+        }
+        if let Some(x) = self.x {
+            px[x] = u8::MAX;
+        }
+        px
+    }
+
+    #[inline]
+    pub(super) fn write(&self, px: &mut [u8], color: [f32; 4]) {
+        px[..Self::BPP].copy_from_slice(&self.encode(color));
+    }
+}
+
+#[inline]
+fn to_u8(val: f32) -> u8 {
+    (val.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Blends a premultiplied `src` color over `dst`
+#[inline]
+pub(super) fn blend(src: [f32; 4], dst: [f32; 4]) -> [f32; 4] {
+    let inv = 1.0 - src[3];
+    [
+        src[0] + dst[0] * inv,
+        src[1] + dst[1] * inv,
+        src[2] + dst[2] * inv,
+        src[3] + dst[3] * inv,
+    ]
+}
+
+/// Read-only view into pixel memory
+#[derive(Debug)]
+pub(super) struct ImageRef<'a> {
+    pub data: &'a [u8],
+    pub stride: usize,
+    pub layout: Layout,
+    pub width: i32,
+    pub height: i32,
+    pub y_inverted: bool,
+}
+
+impl<'a> ImageRef<'a> {
+    /// Returns the pixel at the given position, clamped to the edges of the image
+    #[inline]
+    pub(super) fn pixel(&self, x: i32, y: i32) -> [f32; 4] {
+        let x = x.clamp(0, self.width - 1) as usize;
+        let mut y = y.clamp(0, self.height - 1);
+        if self.y_inverted {
+            y = self.height - 1 - y;
+        }
+        let offset = y as usize * self.stride + x * Layout::BPP;
+        self.layout.read(&self.data[offset..offset + Layout::BPP])
+    }
+
+    /// Samples the image at the given (continuous) position using the nearest pixel
+    #[inline]
+    pub(super) fn sample_nearest(&self, x: f64, y: f64) -> [f32; 4] {
+        self.pixel(x.floor() as i32, y.floor() as i32)
+    }
+
+    /// Samples the image at the given (continuous) position using bilinear interpolation
+    #[inline]
+    pub(super) fn sample_linear(&self, x: f64, y: f64) -> [f32; 4] {
+        let x = x - 0.5;
+        let y = y - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = (x - x0) as f32;
+        let fy = (y - y0) as f32;
+        let (x0, y0) = (x0 as i32, y0 as i32);
+
+        let tl = self.pixel(x0, y0);
+        let tr = self.pixel(x0 + 1, y0);
+        let bl = self.pixel(x0, y0 + 1);
+        let br = self.pixel(x0 + 1, y0 + 1);
+
+        let mut res = [0.0; 4];
+        for i in 0..4 {
+            let top = tl[i] + (tr[i] - tl[i]) * fx;
+            let bottom = bl[i] + (br[i] - bl[i]) * fx;
+            res[i] = top + (bottom - top) * fy;
+        }
+        res
+    }
+}
+
+/// Writable view into pixel memory
+#[derive(Debug)]
+pub(super) struct ImageMut<'a> {
+    pub data: &'a mut [u8],
+    pub stride: usize,
+    pub layout: Layout,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl<'a> ImageMut<'a> {
+    #[inline]
+    fn offset(&self, x: i32, y: i32) -> usize {
+        y as usize * self.stride + x as usize * Layout::BPP
+    }
+
+    /// Returns the pixel at the given position
+    #[inline]
+    pub(super) fn pixel(&self, x: i32, y: i32) -> [f32; 4] {
+        let offset = self.offset(x, y);
+        self.layout.read(&self.data[offset..offset + Layout::BPP])
+    }
+
+    /// Overwrites the pixel at the given position
+    #[inline]
+    pub(super) fn set(&mut self, x: i32, y: i32, color: [f32; 4]) {
+        let offset = self.offset(x, y);
+        self.layout
+            .write(&mut self.data[offset..offset + Layout::BPP], color);
+    }
+
+    /// Blends the given color over the pixel at the given position
+    #[inline]
+    pub(super) fn blend(&mut self, x: i32, y: i32, color: [f32; 4]) {
+        if color[3] >= 1.0 {
+            self.set(x, y, color);
+        } else if color[3] > 0.0 || color[..3].iter().any(|c| *c > 0.0) {
+            let dst = self.pixel(x, y);
+            self.set(x, y, blend(color, dst));
+        }
+    }
+
+    /// Overwrites a rectangle of pixels given as `(x, y, w, h)`
+    ///
+    /// The rectangle has to be inside the bounds of the image.
+    pub(super) fn fill(&mut self, (x, y, w, h): (i32, i32, i32, i32), color: [f32; 4]) {
+        let px = self.layout.encode(color);
+        for row in y..y + h {
+            let start = self.offset(x, row);
+            let end = start + w as usize * Layout::BPP;
+            for chunk in self.data[start..end].chunks_exact_mut(Layout::BPP) {
+                chunk.copy_from_slice(&px);
+            }
+        }
+    }
+}
+
+/// Converts tightly or loosely packed pixel rows from one supported format into another.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn convert(
+    src: &[u8],
+    src_stride: usize,
+    src_layout: Layout,
+    dst: &mut [u8],
+    dst_stride: usize,
+    dst_layout: Layout,
+    width: usize,
+    height: usize,
+) {
+    for row in 0..height {
+        let src_row = &src[row * src_stride..row * src_stride + width * Layout::BPP];
+        let dst_row = &mut dst[row * dst_stride..row * dst_stride + width * Layout::BPP];
+        if src_layout == dst_layout {
+            dst_row.copy_from_slice(src_row);
+        } else {
+            for (s, d) in src_row
+                .chunks_exact(Layout::BPP)
+                .zip(dst_row.chunks_exact_mut(Layout::BPP))
+            {
+                dst_layout.write(d, src_layout.read(s));
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{
+    backend::{
+        allocator::{memory::MemoryBuffer, Fourcc},
+        renderer::{
+            damage::OutputDamageTracker,
+            element::solid::SolidColorRenderElement,
+            element::{Id, Kind},
+            utils::CommitCounter,
+            Bind, ExportMem, Frame, ImportMem, Offscreen, Renderer, TextureFilter,
+        },
+    },
+    utils::{Buffer, Physical, Point, Rectangle, Size, Transform},
+};
+
+use super::SoftwareRenderer;
+
+fn pixel(buffer: &MemoryBuffer, x: usize, y: usize) -> [u8; 4] {
+    let offset = y * buffer.stride() as usize + x * 4;
+    buffer.data()[offset..offset + 4].try_into().unwrap()
+}
+
+fn full(size: impl Into<Size<i32, Physical>>) -> Rectangle<i32, Physical> {
+    Rectangle::from_loc_and_size((0, 0), size.into())
+}
+
+#[test]
+fn clear_and_blend_solid() {
+    let mut renderer = SoftwareRenderer::new();
+    let buffer: MemoryBuffer = renderer.create_buffer(Fourcc::Abgr8888, (4, 4).into()).unwrap();
+    renderer.bind(buffer.clone()).unwrap();
+
+    let mut frame = renderer.render((4, 4).into(), Transform::Normal).unwrap();
+    frame.clear([0.0, 0.0, 1.0, 1.0], &[full((4, 4))]).unwrap();
+    frame
+        .draw_solid(
+            Rectangle::from_loc_and_size((2, 2), (2, 2)),
+            &[full((2, 2))],
+            [0.5, 0.0, 0.0, 0.5],
+        )
+        .unwrap();
+    let _ = frame.finish().unwrap();
+
+    assert_eq!(pixel(&buffer, 0, 0), [0, 0, 255, 255]);
+    assert_eq!(pixel(&buffer, 3, 3), [128, 0, 128, 255]);
+}
+
+#[test]
+fn damage_is_respected() {
+    let mut renderer = SoftwareRenderer::new();
+    let buffer: MemoryBuffer = renderer.create_buffer(Fourcc::Abgr8888, (4, 4).into()).unwrap();
+    renderer.bind(buffer.clone()).unwrap();
+
+    let mut frame = renderer.render((4, 4).into(), Transform::Normal).unwrap();
+    frame
+        .draw_solid(
+            full((4, 4)),
+            &[Rectangle::from_loc_and_size((1, 1), (1, 1))],
+            [1.0, 1.0, 1.0, 1.0],
+        )
+        .unwrap();
+    let _ = frame.finish().unwrap();
+
+    assert_eq!(pixel(&buffer, 1, 1), [255, 255, 255, 255]);
+    assert_eq!(pixel(&buffer, 0, 0), [0, 0, 0, 0]);
+    assert_eq!(pixel(&buffer, 2, 2), [0, 0, 0, 0]);
+}
+
+#[test]
+fn output_transform() {
+    let mut renderer = SoftwareRenderer::new();
+    let buffer: MemoryBuffer = renderer.create_buffer(Fourcc::Abgr8888, (4, 2).into()).unwrap();
+    renderer.bind(buffer.clone()).unwrap();
+
+    // the frame is 2x4 after applying the transform, mark its top-left pixel
+    let mut frame = renderer.render((4, 2).into(), Transform::_90).unwrap();
+    frame
+        .draw_solid(
+            Rectangle::from_loc_and_size((0, 0), (1, 1)),
+            &[full((1, 1))],
+            [1.0, 0.0, 0.0, 1.0],
+        )
+        .unwrap();
+    let _ = frame.finish().unwrap();
+
+    let expected = Transform::_90
+        .transform_rect_in(
+            Rectangle::<i32, Physical>::from_loc_and_size((0, 0), (1, 1)),
+            &(2, 4).into(),
+        )
+        .loc;
+    assert_eq!(
+        pixel(&buffer, expected.x as usize, expected.y as usize),
+        [255, 0, 0, 255]
+    );
+    assert_eq!(pixel(&buffer, 0, 0), [0, 0, 0, 0]);
+}
+
+#[test]
+fn render_texture_and_export() {
+    let mut renderer = SoftwareRenderer::new();
+    renderer.upscale_filter(TextureFilter::Nearest).unwrap();
+
+    // 2x1 texture, red and green in Argb8888 (little-endian BGRA)
+    let texture = renderer
+        .import_memory(
+            &[0, 0, 255, 255, 0, 255, 0, 255],
+            Fourcc::Argb8888,
+            (2, 1).into(),
+            false,
+        )
+        .unwrap();
+
+    let buffer: MemoryBuffer = renderer.create_buffer(Fourcc::Xrgb8888, (4, 2).into()).unwrap();
+    renderer.bind(buffer).unwrap();
+
+    let mut frame = renderer.render((4, 2).into(), Transform::Normal).unwrap();
+    frame
+        .render_texture_from_to(
+            &texture,
+            Rectangle::<f64, Buffer>::from_loc_and_size((0.0, 0.0), (2.0, 1.0)),
+            full((4, 2)),
+            &[full((4, 2))],
+            Transform::Normal,
+            1.0,
+        )
+        .unwrap();
+    let _ = frame.finish().unwrap();
+
+    let mapping = renderer
+        .copy_framebuffer(Rectangle::from_loc_and_size((0, 0), (4, 2)), Fourcc::Abgr8888)
+        .unwrap();
+    let data = renderer.map_texture(&mapping).unwrap();
+    assert_eq!(&data[0..4], &[255, 0, 0, 255]);
+    assert_eq!(&data[4..8], &[255, 0, 0, 255]);
+    assert_eq!(&data[8..12], &[0, 255, 0, 255]);
+    assert_eq!(&data[28..32], &[0, 255, 0, 255]);
+}
+
+#[test]
+fn damage_tracked_output() {
+    let mut renderer = SoftwareRenderer::new();
+    let buffer: MemoryBuffer = renderer.create_buffer(Fourcc::Argb8888, (8, 8).into()).unwrap();
+    renderer.bind(buffer.clone()).unwrap();
+
+    let element = SolidColorRenderElement::new(
+        Id::new(),
+        Rectangle::from_loc_and_size(Point::from((4, 4)), (4, 4)),
+        CommitCounter::default(),
+        [0.0, 1.0, 0.0, 1.0],
+        Kind::Unspecified,
+    );
+
+    let mut damage_tracker = OutputDamageTracker::new((8, 8), 1.0, Transform::Normal);
+    let res = damage_tracker
+        .render_output(&mut renderer, 0, &[element], [0.0, 0.0, 0.0, 1.0])
+        .unwrap();
+    assert!(res.damage.is_some());
+
+    assert_eq!(pixel(&buffer, 0, 0), [0, 0, 0, 255]);
+    assert_eq!(pixel(&buffer, 5, 5), [0, 255, 0, 255]);
+}
@@ -0,0 +1,103 @@
+use std::{cell::RefCell, rc::Rc};
+
+use super::pixel::Layout;
+use crate::{
+    backend::{
+        allocator::Fourcc,
+        renderer::{Texture, TextureMapping},
+    },
+    utils::{Buffer as BufferCoord, Size},
+};
+
+/// A handle to a texture of the software renderer
+///
+/// The texture contents are kept in system memory, tightly packed in the texture's format.
+#[derive(Debug, Clone)]
+pub struct SoftwareTexture(pub(super) Rc<SoftwareTextureInternal>);
+
+#[derive(Debug)]
+pub(super) struct SoftwareTextureInternal {
+    pub(super) data: RefCell<Vec<u8>>,
+    pub(super) format: Fourcc,
+    pub(super) layout: Layout,
+    pub(super) size: Size<i32, BufferCoord>,
+    pub(super) y_inverted: bool,
+}
+
+impl SoftwareTexture {
+    pub(super) fn new(
+        format: Fourcc,
+        layout: Layout,
+        size: Size<i32, BufferCoord>,
+        y_inverted: bool,
+    ) -> Self {
+        SoftwareTexture(Rc::new(SoftwareTextureInternal {
+            data: RefCell::new(vec![0; size.w as usize * size.h as usize * Layout::BPP]),
+            format,
+            layout,
+            size,
+            y_inverted,
+        }))
+    }
+
+    pub(super) fn stride(&self) -> usize {
+        self.0.size.w as usize * Layout::BPP
+    }
+
+    /// Access the raw pixel contents of this texture.
+    ///
+    /// The rows are tightly packed and stored in the format returned by [`Texture::format`].
+    pub fn with_data<T>(&self, func: impl FnOnce(&[u8]) -> T) -> T {
+        func(&self.0.data.borrow())
+    }
+}
+
+impl PartialEq for SoftwareTexture {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Texture for SoftwareTexture {
+    fn width(&self) -> u32 {
+        self.0.size.w as u32
+    }
+    fn height(&self) -> u32 {
+        self.0.size.h as u32
+    }
+    fn size(&self) -> Size<i32, BufferCoord> {
+        self.0.size
+    }
+    fn format(&self) -> Option<Fourcc> {
+        Some(self.0.format)
+    }
+}
+
+/// Texture mapping of the software renderer
+#[derive(Debug)]
+pub struct SoftwareMapping {
+    pub(super) data: Vec<u8>,
+    pub(super) format: Fourcc,
+    pub(super) size: Size<i32, BufferCoord>,
+}
+
+impl Texture for SoftwareMapping {
+    fn width(&self) -> u32 {
+        self.size.w as u32
+    }
+    fn height(&self) -> u32 {
+        self.size.h as u32
+    }
+    fn size(&self) -> Size<i32, BufferCoord> {
+        self.size
+    }
+    fn format(&self) -> Option<Fourcc> {
+        Some(self.format)
+    }
+}
+
+impl TextureMapping for SoftwareMapping {
+    fn flipped(&self) -> bool {
+        false
+    }
+}
@@ -1,3 +1,8 @@
+#[cfg(feature = "backend_drm")]
+use crate::{
+    backend::renderer::sync::SyncPoint,
+    wayland::drm_syncobj::{DrmSyncPoint, DrmSyncobjCachedState},
+};
 use crate::{
     backend::renderer::{buffer_dimensions, buffer_has_alpha, element::RenderElement, ImportAll, Renderer},
     utils::{Buffer as BufferCoord, Coordinate, Logical, Physical, Point, Rectangle, Scale, Size, Transform},
@@ -7,10 +12,12 @@ use crate::{
             with_surface_tree_upward, BufferAssignment, Damage, RectangleKind, SubsurfaceCachedState,
             SurfaceAttributes, SurfaceData, TraversalAction,
         },
-        viewporter,
+        single_pixel_buffer, viewporter,
     },
 };
 use std::sync::Arc;
+#[cfg(feature = "backend_drm")]
+use std::sync::Mutex;
 use std::{
     any::TypeId,
     cell::RefCell,
@@ -50,11 +57,24 @@ pub struct RendererSurfaceState {
 }
 
 #[derive(Debug)]
-struct InnerBuffer(WlBuffer);
+struct InnerBuffer {
+    buffer: WlBuffer,
+    #[cfg(feature = "backend_drm")]
+    acquire_point: Option<DrmSyncPoint>,
+    #[cfg(feature = "backend_drm")]
+    release_point: Option<DrmSyncPoint>,
+    #[cfg(feature = "backend_drm")]
+    sync_points: Mutex<Vec<SyncPoint>>,
+}
 
 impl Drop for InnerBuffer {
     fn drop(&mut self) {
-        self.0.release();
+        self.buffer.release();
+        #[cfg(feature = "backend_drm")]
+        if let Some(release_point) = self.release_point.take() {
+            let sync_points = std::mem::take(self.sync_points.get_mut().unwrap());
+            release_point.signal_after(sync_points);
+        }
     }
 }
 
@@ -67,28 +87,80 @@ pub struct Buffer {
 impl From<WlBuffer> for Buffer {
     fn from(buffer: WlBuffer) -> Self {
         Buffer {
-            inner: Arc::new(InnerBuffer(buffer)),
+            inner: Arc::new(InnerBuffer {
+                buffer,
+                #[cfg(feature = "backend_drm")]
+                acquire_point: None,
+                #[cfg(feature = "backend_drm")]
+                release_point: None,
+                #[cfg(feature = "backend_drm")]
+                sync_points: Mutex::new(Vec::new()),
+            }),
         }
     }
 }
 
+#[cfg(feature = "backend_drm")]
+impl Buffer {
+    /// Create a buffer with explicit synchronization points
+    ///
+    /// The release point is signaled once the buffer is released and
+    /// all sync points added through [`Buffer::add_release_sync_point`] are reached.
+    pub fn with_explicit_sync(
+        buffer: WlBuffer,
+        acquire_point: Option<DrmSyncPoint>,
+        release_point: Option<DrmSyncPoint>,
+    ) -> Self {
+        Buffer {
+            inner: Arc::new(InnerBuffer {
+                buffer,
+                acquire_point,
+                release_point,
+                sync_points: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Returns the acquire point of the buffer, if explicit synchronization is used
+    pub fn acquire_point(&self) -> Option<&DrmSyncPoint> {
+        self.inner.acquire_point.as_ref()
+    }
+
+    /// Returns the release point of the buffer, if explicit synchronization is used
+    pub fn release_point(&self) -> Option<&DrmSyncPoint> {
+        self.inner.release_point.as_ref()
+    }
+
+    /// Delay signaling the release point until the given [`SyncPoint`] is reached
+    ///
+    /// Does nothing if the buffer has no release point.
+    pub fn add_release_sync_point(&self, sync_point: &SyncPoint) {
+        if self.inner.release_point.is_none() || sync_point.is_reached() {
+            return;
+        }
+        let mut sync_points = self.inner.sync_points.lock().unwrap();
+        sync_points.retain(|sync| !sync.is_reached());
+        sync_points.push(sync_point.clone());
+    }
+}
+
 impl std::ops::Deref for Buffer {
     type Target = WlBuffer;
 
     fn deref(&self) -> &Self::Target {
-        &self.inner.0
+        &self.inner.buffer
     }
 }
 
 impl PartialEq<WlBuffer> for Buffer {
     fn eq(&self, other: &WlBuffer) -> bool {
-        self.inner.0 == *other
+        self.inner.buffer == *other
     }
 }
 
 impl PartialEq<WlBuffer> for &Buffer {
     fn eq(&self, other: &WlBuffer) -> bool {
-        self.inner.0 == *other
+        self.inner.buffer == *other
     }
 }
 
@@ -116,6 +188,19 @@ impl RendererSurfaceState {
                 self.buffer_scale = attrs.buffer_scale;
                 self.buffer_transform = attrs.buffer_transform.into();
 
+                #[cfg(feature = "backend_drm")]
+                {
+                    let mut syncobj_state = states.cached_state.current::<DrmSyncobjCachedState>();
+                    let acquire_point = syncobj_state.acquire_point.take();
+                    let release_point = syncobj_state.release_point.take();
+                    if acquire_point.is_some() || release_point.is_some() {
+                        // every commit carries its own release point, even for the same buffer
+                        self.buffer = Some(Buffer::with_explicit_sync(buffer, acquire_point, release_point));
+                    } else if !self.buffer.as_ref().map_or(false, |b| b == buffer) {
+                        self.buffer = Some(Buffer::from(buffer));
+                    }
+                }
+                #[cfg(not(feature = "backend_drm"))]
                 if !self.buffer.as_ref().map_or(false, |b| b == buffer) {
                     self.buffer = Some(Buffer::from(buffer));
                 }
@@ -296,6 +381,16 @@ impl RendererSurfaceState {
         self.surface_view
     }
 
+    /// Returns the color of the current buffer, if it is a single pixel buffer
+    ///
+    /// See [`crate::wayland::single_pixel_buffer`]
+    pub fn single_pixel_color(&self) -> Option<[f32; 4]> {
+        self.buffer
+            .as_ref()
+            .and_then(|buffer| single_pixel_buffer::get_single_pixel_buffer(buffer).ok())
+            .map(|spb| spb.rgba_f32())
+    }
+
     fn reset(&mut self) {
         self.buffer_dimensions = None;
         self.buffer = None;
@@ -447,6 +542,11 @@ where
         let buffer_damage = data.damage_since(last_commit.copied());
         if let Entry::Vacant(e) = data.textures.entry(texture_id) {
             if let Some(buffer) = data.buffer.as_ref() {
+                // single pixel buffers are drawn as a solid color, there is nothing to import
+                if single_pixel_buffer::get_single_pixel_buffer(buffer).is_ok() {
+                    return Ok(());
+                }
+
                 match renderer.import_buffer(buffer, Some(states), &buffer_damage) {
                     Some(Ok(m)) => {
                         e.insert(Box::new(m));
@@ -500,7 +600,7 @@ where
                 let mut data_ref = data.borrow_mut();
                 let data = &mut *data_ref;
                 // Now, should we be drawn ?
-                if data.textures.contains_key(&texture_id) {
+                if data.textures.contains_key(&texture_id) || data.single_pixel_color().is_some() {
                     // if yes, also process the children
                     let surface_view = data.surface_view.unwrap();
                     location += surface_view.offset.to_f64().to_physical(scale);
@@ -520,6 +620,34 @@ where
     result
 }
 
+/// Delays signaling the release points of the buffers of a surface tree until the [`SyncPoint`] is reached
+///
+/// Should be called with the [`SyncPoint`] of a rendered frame or a submitted scanout, so clients
+/// using [explicit synchronization](crate::wayland::drm_syncobj) do not reuse their buffers early.
+///
+/// Note: This does nothing, if you are not using
+/// [`crate::backend::renderer::utils::on_commit_buffer_handler`]
+/// to let smithay handle buffer management.
+#[cfg(feature = "backend_drm")]
+pub fn add_release_sync_point_surface_tree(surface: &WlSurface, sync_point: &SyncPoint) {
+    if sync_point.is_reached() {
+        return;
+    }
+    with_surface_tree_downward(
+        surface,
+        (),
+        |_, _, _| TraversalAction::DoChildren(()),
+        |_, states, _| {
+            if let Some(data) = states.data_map.get::<RendererSurfaceStateUserData>() {
+                if let Some(buffer) = data.borrow().buffer.as_ref() {
+                    buffer.add_release_sync_point(sync_point);
+                }
+            }
+        },
+        |_, _, _| true,
+    );
+}
+
 /// Draws the render elements using a given [`Renderer`] and [`Frame`](crate::backend::renderer::Frame)
 ///
 /// - `scale` needs to be equivalent to the fractional scale the rendered result should have.
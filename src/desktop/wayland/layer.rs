@@ -1,5 +1,5 @@
 use crate::{
-    backend::input::KeyState,
+    backend::input::{KeyState, TouchSlot},
     desktop::{utils::*, PopupManager},
     input::{
         keyboard::{KeyboardTarget, KeysymHandle, ModifiersState},
@@ -8,6 +8,7 @@ use crate::{
             GesturePinchEndEvent, GesturePinchUpdateEvent, GestureSwipeBeginEvent, GestureSwipeEndEvent,
             GestureSwipeUpdateEvent, MotionEvent, PointerTarget, RelativeMotionEvent,
         },
+        touch::{self, TouchTarget},
         Seat, SeatHandler,
     },
     output::{Output, WeakOutput},
@@ -33,7 +34,7 @@ use wayland_server::{
 
 use std::{
     cell::{RefCell, RefMut},
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     hash::{Hash, Hasher},
     sync::{Arc, Mutex},
     time::Duration,
@@ -514,6 +515,7 @@ pub(crate) struct LayerSurfaceInner {
     surface: WlrLayerSurface,
     namespace: String,
     focused_surface: Mutex<Option<wl_surface::WlSurface>>,
+    touch_focused_surfaces: Mutex<HashMap<TouchSlot, (wl_surface::WlSurface, Point<i32, Logical>)>>,
     userdata: UserDataMap,
 }
 
@@ -537,6 +539,7 @@ impl LayerSurface {
             surface,
             namespace,
             focused_surface: Mutex::new(None),
+            touch_focused_surfaces: Mutex::new(HashMap::new()),
             userdata: UserDataMap::new(),
         }))
     }
@@ -817,6 +820,73 @@ impl<D: SeatHandler + 'static> PointerTarget<D> for LayerSurface {
     }
 }
 
+impl<D: SeatHandler + 'static> TouchTarget<D> for LayerSurface {
+    fn down(&self, seat: &Seat<D>, data: &mut D, event: &touch::DownEvent) {
+        if let Some((surface, loc)) = self.surface_under(event.location, WindowSurfaceType::ALL) {
+            let mut new_event = *event;
+            new_event.location -= loc.to_f64();
+            self.0
+                .touch_focused_surfaces
+                .lock()
+                .unwrap()
+                .insert(event.slot, (surface.clone(), loc));
+            TouchTarget::<D>::down(&surface, seat, data, &new_event)
+        }
+    }
+    fn up(&self, seat: &Seat<D>, data: &mut D, event: &touch::UpEvent) {
+        let focus = self.0.touch_focused_surfaces.lock().unwrap().remove(&event.slot);
+        if let Some((surface, _)) = focus {
+            TouchTarget::<D>::up(&surface, seat, data, event)
+        }
+    }
+    fn motion(&self, seat: &Seat<D>, data: &mut D, event: &touch::MotionEvent) {
+        let focus = self
+            .0
+            .touch_focused_surfaces
+            .lock()
+            .unwrap()
+            .get(&event.slot)
+            .cloned();
+        if let Some((surface, loc)) = focus {
+            let mut new_event = *event;
+            new_event.location -= loc.to_f64();
+            TouchTarget::<D>::motion(&surface, seat, data, &new_event)
+        }
+    }
+    fn frame(&self, seat: &Seat<D>, data: &mut D) {
+        // frames are sent per client, so any surface of the layer will do
+        TouchTarget::<D>::frame(self.0.surface.wl_surface(), seat, data)
+    }
+    fn cancel(&self, seat: &Seat<D>, data: &mut D) {
+        self.0.touch_focused_surfaces.lock().unwrap().clear();
+        TouchTarget::<D>::cancel(self.0.surface.wl_surface(), seat, data)
+    }
+    fn shape(&self, seat: &Seat<D>, data: &mut D, event: &touch::ShapeEvent) {
+        let focus = self
+            .0
+            .touch_focused_surfaces
+            .lock()
+            .unwrap()
+            .get(&event.slot)
+            .cloned();
+        if let Some((surface, _)) = focus {
+            TouchTarget::<D>::shape(&surface, seat, data, event)
+        }
+    }
+    fn orientation(&self, seat: &Seat<D>, data: &mut D, event: &touch::OrientationEvent) {
+        let focus = self
+            .0
+            .touch_focused_surfaces
+            .lock()
+            .unwrap()
+            .get(&event.slot)
+            .cloned();
+        if let Some((surface, _)) = focus {
+            TouchTarget::<D>::orientation(&surface, seat, data, event)
+        }
+    }
+}
+
 impl<D: SeatHandler + 'static> KeyboardTarget<D> for LayerSurface {
     fn enter(&self, seat: &Seat<D>, data: &mut D, keys: Vec<KeysymHandle<'_>>, serial: Serial) {
         KeyboardTarget::<D>::enter(self.0.surface.wl_surface(), seat, data, keys, serial)
@@ -1,5 +1,5 @@
 use crate::{
-    backend::input::KeyState,
+    backend::input::{KeyState, TouchSlot},
     desktop::{space::RenderZindex, utils::*, PopupManager},
     input::{
         keyboard::{KeyboardTarget, KeysymHandle, ModifiersState},
@@ -8,6 +8,7 @@ use crate::{
             GesturePinchEndEvent, GesturePinchUpdateEvent, GestureSwipeBeginEvent, GestureSwipeEndEvent,
             GestureSwipeUpdateEvent, MotionEvent, PointerTarget, RelativeMotionEvent,
         },
+        touch::{self, TouchTarget},
         Seat, SeatHandler,
     },
     output::Output,
@@ -20,6 +21,7 @@ use crate::{
     },
 };
 use std::{
+    collections::HashMap,
     hash::{Hash, Hasher},
     sync::{
         atomic::{AtomicU8, Ordering},
@@ -41,6 +43,7 @@ pub(crate) struct WindowInner {
     bbox: Mutex<Rectangle<i32, Logical>>,
     pub(crate) z_index: AtomicU8,
     focused_surface: Mutex<Option<wl_surface::WlSurface>>,
+    touch_focused_surfaces: Mutex<HashMap<TouchSlot, (wl_surface::WlSurface, Point<i32, Logical>)>>,
     user_data: UserDataMap,
 }
 
@@ -101,6 +104,7 @@ impl Window {
             bbox: Mutex::new(Rectangle::from_loc_and_size((0, 0), (0, 0))),
             z_index: AtomicU8::new(RenderZindex::Shell as u8),
             focused_surface: Mutex::new(None),
+            touch_focused_surfaces: Mutex::new(HashMap::new()),
             user_data: UserDataMap::new(),
         }))
     }
@@ -276,6 +280,18 @@ impl Window {
         &self.0.toplevel
     }
 
+    /// Returns a modal dialog of this window blocking input to it, if any
+    ///
+    /// `windows` are the windows to search for modal children, e.g. the elements of a [`Space`](crate::desktop::Space).
+    pub fn modal_child<'a>(&self, windows: impl IntoIterator<Item = &'a Window>) -> Option<&'a Window> {
+        let surface = self.toplevel().wl_surface();
+        windows.into_iter().find(|window| {
+            window.alive()
+                && window.toplevel().parent().as_ref() == Some(surface)
+                && window.toplevel().is_modal()
+        })
+    }
+
     /// Override the z_index of this Window
     pub fn override_z_index(&self, z_index: u8) {
         self.0.z_index.store(z_index, Ordering::SeqCst);
@@ -382,6 +398,73 @@ impl<D: SeatHandler + 'static> PointerTarget<D> for Window {
     }
 }
 
+impl<D: SeatHandler + 'static> TouchTarget<D> for Window {
+    fn down(&self, seat: &Seat<D>, data: &mut D, event: &touch::DownEvent) {
+        if let Some((surface, loc)) = self.surface_under(event.location, WindowSurfaceType::ALL) {
+            let mut new_event = *event;
+            new_event.location -= loc.to_f64();
+            self.0
+                .touch_focused_surfaces
+                .lock()
+                .unwrap()
+                .insert(event.slot, (surface.clone(), loc));
+            TouchTarget::<D>::down(&surface, seat, data, &new_event)
+        }
+    }
+    fn up(&self, seat: &Seat<D>, data: &mut D, event: &touch::UpEvent) {
+        let focus = self.0.touch_focused_surfaces.lock().unwrap().remove(&event.slot);
+        if let Some((surface, _)) = focus {
+            TouchTarget::<D>::up(&surface, seat, data, event)
+        }
+    }
+    fn motion(&self, seat: &Seat<D>, data: &mut D, event: &touch::MotionEvent) {
+        let focus = self
+            .0
+            .touch_focused_surfaces
+            .lock()
+            .unwrap()
+            .get(&event.slot)
+            .cloned();
+        if let Some((surface, loc)) = focus {
+            let mut new_event = *event;
+            new_event.location -= loc.to_f64();
+            TouchTarget::<D>::motion(&surface, seat, data, &new_event)
+        }
+    }
+    fn frame(&self, seat: &Seat<D>, data: &mut D) {
+        // frames are sent per client, so any surface of the window will do
+        TouchTarget::<D>::frame(self.0.toplevel.wl_surface(), seat, data)
+    }
+    fn cancel(&self, seat: &Seat<D>, data: &mut D) {
+        self.0.touch_focused_surfaces.lock().unwrap().clear();
+        TouchTarget::<D>::cancel(self.0.toplevel.wl_surface(), seat, data)
+    }
+    fn shape(&self, seat: &Seat<D>, data: &mut D, event: &touch::ShapeEvent) {
+        let focus = self
+            .0
+            .touch_focused_surfaces
+            .lock()
+            .unwrap()
+            .get(&event.slot)
+            .cloned();
+        if let Some((surface, _)) = focus {
+            TouchTarget::<D>::shape(&surface, seat, data, event)
+        }
+    }
+    fn orientation(&self, seat: &Seat<D>, data: &mut D, event: &touch::OrientationEvent) {
+        let focus = self
+            .0
+            .touch_focused_surfaces
+            .lock()
+            .unwrap()
+            .get(&event.slot)
+            .cloned();
+        if let Some((surface, _)) = focus {
+            TouchTarget::<D>::orientation(&surface, seat, data, event)
+        }
+    }
+}
+
 impl<D: SeatHandler + 'static> KeyboardTarget<D> for Window {
     fn enter(&self, seat: &Seat<D>, data: &mut D, keys: Vec<KeysymHandle<'_>>, serial: Serial) {
         KeyboardTarget::<D>::enter(self.0.toplevel.wl_surface(), seat, data, keys, serial)
@@ -1,8 +1,8 @@
 //! Keyboard-related types for smithay's input abstraction
 
-use crate::backend::input::KeyState;
+use crate::backend::input::{Device, KeyState};
 use crate::utils::{IsAlive, Serial, SERIAL_COUNTER};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::{
     default::Default,
     fmt, io,
@@ -49,6 +49,12 @@ where
     fn modifiers(&self, seat: &Seat<D>, data: &mut D, modifiers: ModifiersState, serial: Serial);
 }
 
+// Keymap and state used for the keys of a device
+struct KeymapState {
+    keymap: xkb::Keymap,
+    state: xkb::State,
+}
+
 enum GrabStatus<D> {
     None,
     Active(Serial, Box<dyn KeyboardGrab<D>>),
@@ -62,8 +68,14 @@ pub(crate) struct KbdInternal<D: SeatHandler> {
     pub(crate) forwarded_pressed_keys: HashSet<u32>,
     pub(crate) mods_state: ModifiersState,
     context: xkb::Context,
+    // keymap and state currently in use, shared with either `seat_keymap` or
+    // the entry of the active device in `device_keymaps`
     pub(crate) keymap: xkb::Keymap,
     pub(crate) state: xkb::State,
+    seat_keymap: KeymapState,
+    device_keymaps: HashMap<String, KeymapState>,
+    // device whose keymap is in use, `None` if it is the seat keymap
+    active_device: Option<String>,
     pub(crate) repeat_rate: i32,
     pub(crate) repeat_delay: i32,
     grab: GrabStatus<D>,
@@ -80,6 +92,8 @@ impl<D: SeatHandler> fmt::Debug for KbdInternal<D> {
             .field("mods_state", &self.mods_state)
             .field("keymap", &self.keymap.get_raw_ptr())
             .field("state", &self.state.get_raw_ptr())
+            .field("device_keymaps", &self.device_keymaps.keys())
+            .field("active_device", &self.active_device)
             .field("repeat_rate", &self.repeat_rate)
             .field("repeat_delay", &self.repeat_delay)
             .finish()
@@ -108,8 +122,14 @@ impl<D: SeatHandler + 'static> KbdInternal<D> {
             forwarded_pressed_keys: HashSet::new(),
             mods_state: ModifiersState::default(),
             context,
+            seat_keymap: KeymapState {
+                keymap: keymap.clone(),
+                state: state.clone(),
+            },
             keymap,
             state,
+            device_keymaps: HashMap::new(),
+            active_device: None,
             repeat_rate,
             repeat_delay,
             grab: GrabStatus::None,
@@ -143,6 +163,61 @@ impl<D: SeatHandler + 'static> KbdInternal<D> {
         }
     }
 
+    // create a state for `keymap` matching the currently pressed keys
+    fn new_state(&self, keymap: &xkb::Keymap) -> xkb::State {
+        let mut state = xkb::State::new(keymap);
+        for key in &self.pressed_keys {
+            state.update_key((key + 8).into(), xkb::KeyDirection::Down);
+        }
+        state
+    }
+
+    fn keymap_state_mut(&mut self, device: Option<&str>) -> &mut KeymapState {
+        match device {
+            Some(id) => self.device_keymaps.get_mut(id).unwrap(),
+            None => &mut self.seat_keymap,
+        }
+    }
+
+    // switch to the keymap of the given device, or to the seat keymap for `None`
+    fn activate_keymap(&mut self, device: Option<String>) {
+        let old_locked_mods = self.state.serialize_mods(xkb::STATE_MODS_LOCKED);
+        let old_keymap = self.keymap.clone();
+        let keymap_state = self.keymap_state_mut(device.as_deref());
+        let keymap = keymap_state.keymap.clone();
+        let locked_layout = keymap_state.state.serialize_layout(xkb::STATE_LAYOUT_LOCKED);
+
+        // modifier indices are specific to a keymap, so the locked modifiers are matched by name
+        let locked_mods = (0..old_keymap.num_mods().min(32))
+            .filter(|idx| old_locked_mods & (1 << idx) != 0)
+            .map(|idx| keymap.mod_get_index(old_keymap.mod_get_name(idx)))
+            .filter(|idx| *idx < 32)
+            .fold(0, |mods, idx| mods | (1 << idx));
+
+        // The previous state of the keymap is outdated, as keys were handled by another one in the
+        // meantime. Rebuild it from the pressed keys and carry over the locked modifiers (caps lock,
+        // num lock, ...) to keep them consistent across devices. The locked layout is per device.
+        let mut state = self.new_state(&keymap);
+        let depressed_mods = state.serialize_mods(xkb::STATE_MODS_DEPRESSED);
+        let latched_mods = state.serialize_mods(xkb::STATE_MODS_LATCHED);
+        let depressed_layout = state.serialize_layout(xkb::STATE_LAYOUT_DEPRESSED);
+        let latched_layout = state.serialize_layout(xkb::STATE_LAYOUT_LATCHED);
+        state.update_mask(
+            depressed_mods,
+            latched_mods,
+            locked_mods,
+            depressed_layout,
+            latched_layout,
+            locked_layout,
+        );
+
+        self.keymap_state_mut(device.as_deref()).state = state.clone();
+        self.keymap = keymap;
+        self.state = state;
+        self.mods_state.update_with(&self.state);
+        self.active_device = device;
+    }
+
     fn with_grab<F>(&mut self, seat: &Seat<D>, f: F)
     where
         F: FnOnce(&mut KeyboardInnerHandle<'_, D>, &mut dyn KeyboardGrab<D>),
@@ -421,31 +496,147 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
 
     /// Change the [`XkbConfig`] used by the keyboard.
     pub fn set_xkb_config(&self, data: &mut D, xkb_config: XkbConfig<'_>) -> Result<(), Error> {
-        let mut internal = self.arc.internal.lock().unwrap();
+        let mut guard = self.arc.internal.lock().unwrap();
+        let internal = &mut *guard;
 
         let keymap = xkb_config.compile_keymap(&internal.context).map_err(|_| {
             debug!("Loading keymap failed");
             Error::BadKeymap
         })?;
-        let mut state = xkb::State::new(&keymap);
-        for key in &internal.pressed_keys {
-            state.update_key((key + 8).into(), xkb::KeyDirection::Down);
+        let state = internal.new_state(&keymap);
+        internal.seat_keymap = KeymapState { keymap, state };
+
+        // devices with their own keymap are not affected
+        if internal.active_device.is_none() {
+            internal.activate_keymap(None);
+            self.send_keymap(data, internal);
+        }
+
+        Ok(())
+    }
+
+    /// Use a separate [`XkbConfig`] for the keys of the given device.
+    ///
+    /// Devices without their own configuration use the one of the keyboard,
+    /// see [`KeyboardHandle::set_xkb_config`].
+    pub fn set_device_xkb_config<Dev: Device>(
+        &self,
+        data: &mut D,
+        device: &Dev,
+        xkb_config: XkbConfig<'_>,
+    ) -> Result<(), Error> {
+        let mut guard = self.arc.internal.lock().unwrap();
+        let internal = &mut *guard;
+
+        let keymap = xkb_config.compile_keymap(&internal.context).map_err(|_| {
+            debug!("Loading keymap failed");
+            Error::BadKeymap
+        })?;
+        let state = internal.new_state(&keymap);
+        let id = device.id();
+        internal
+            .device_keymaps
+            .insert(id.clone(), KeymapState { keymap, state });
+
+        if internal.active_device.as_ref() == Some(&id) {
+            internal.activate_keymap(Some(id));
+            self.send_keymap(data, internal);
+        }
+
+        Ok(())
+    }
+
+    /// Remove the [`XkbConfig`] of the given device, its keys use the configuration of the keyboard again.
+    ///
+    /// Should be called once the device is removed.
+    pub fn remove_device_xkb_config<Dev: Device>(&self, data: &mut D, device: &Dev) {
+        let mut guard = self.arc.internal.lock().unwrap();
+        let internal = &mut *guard;
+
+        let id = device.id();
+        if internal.device_keymaps.remove(&id).is_some() && internal.active_device.as_ref() == Some(&id) {
+            internal.activate_keymap(None);
+            self.send_keymap(data, internal);
         }
+    }
 
-        internal.mods_state.update_with(&state);
-        internal.state = state;
-        internal.keymap = keymap.clone();
+    /// Set the device the following key input originates from.
+    ///
+    /// When devices use their own [`XkbConfig`], this has to be called before passing their keys
+    /// to [`KeyboardHandle::input`]. If the input switches to a device with a different keymap,
+    /// the keymap is sent to clients. Locked modifiers like caps lock are kept across devices.
+    #[instrument(level = "trace", parent = &self.arc.span, skip(self, data, device), fields(device = device.id()))]
+    pub fn set_active_device<Dev: Device>(&self, data: &mut D, device: &Dev) {
+        let mut guard = self.arc.internal.lock().unwrap();
+        let internal = &mut *guard;
+
+        let id = device.id();
+        let device = internal.device_keymaps.contains_key(&id).then_some(id);
+        if device != internal.active_device {
+            debug!(device, "Switching keymap");
+            internal.activate_keymap(device);
+            self.send_keymap(data, internal);
+        }
+    }
+
+    // send the keymap in use and the resulting modifiers to clients
+    fn send_keymap(&self, data: &mut D, internal: &mut KbdInternal<D>) {
+        #[cfg(feature = "wayland_frontend")]
+        self.change_keymap(internal.keymap.clone());
 
         let mods = internal.mods_state;
         let seat = self.get_seat(data);
         if let Some((focus, _)) = internal.focus.as_mut() {
             focus.modifiers(&seat, data, mods, SERIAL_COUNTER.next_serial());
         };
+    }
 
-        #[cfg(feature = "wayland_frontend")]
-        self.change_keymap(keymap);
+    /// Names of the layouts of the current keymap
+    ///
+    /// The position of a name in the list is the index of the layout.
+    pub fn layouts(&self) -> Vec<String> {
+        let internal = self.arc.internal.lock().unwrap();
+        internal.keymap.layouts().map(String::from).collect()
+    }
 
-        Ok(())
+    /// Index of the currently effective layout
+    pub fn active_layout(&self) -> u32 {
+        self.arc
+            .internal
+            .lock()
+            .unwrap()
+            .mods_state
+            .serialized
+            .layout_effective
+    }
+
+    /// Lock the layout with the given index
+    ///
+    /// The change is sent to the focused client as a modifiers update.
+    /// Indices out of the range of [`KeyboardHandle::layouts`] are ignored.
+    #[instrument(level = "debug", parent = &self.arc.span, skip(self, data))]
+    pub fn set_layout(&self, data: &mut D, layout: u32) {
+        let mut guard = self.arc.internal.lock().unwrap();
+        let internal = &mut *guard;
+
+        if layout >= internal.keymap.num_layouts() {
+            debug!("Layout index out of range");
+            return;
+        }
+
+        let depressed = internal.state.serialize_mods(xkb::STATE_MODS_DEPRESSED);
+        let latched = internal.state.serialize_mods(xkb::STATE_MODS_LATCHED);
+        let locked = internal.state.serialize_mods(xkb::STATE_MODS_LOCKED);
+        internal
+            .state
+            .update_mask(depressed, latched, locked, 0, 0, layout);
+        internal.mods_state.update_with(&internal.state);
+
+        let mods = internal.mods_state;
+        let seat = self.get_seat(data);
+        if let Some((focus, _)) = internal.focus.as_mut() {
+            focus.modifiers(&seat, data, mods, SERIAL_COUNTER.next_serial());
+        };
     }
 
     /// Change the current grab on this keyboard to the provided grab
@@ -488,6 +679,8 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
     ///
     /// All keystrokes from the input backend should be fed _in order_ to this method of the
     /// keyboard handler. It will internally track the state of the keymap.
+    /// When using per-device keymaps, announce the device of each keystroke beforehand
+    /// through [`KeyboardHandle::set_active_device`].
     ///
     /// The `filter` argument is expected to be a closure which will peek at the generated input
     /// as interpreted by the keymap before it is forwarded to the focused client. If this closure
@@ -512,7 +705,10 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
     {
         trace!("Handling keystroke");
         let mut guard = self.arc.internal.lock().unwrap();
+        let layout = guard.mods_state.serialized.layout_effective;
         let mods_changed = guard.key_input(keycode, state);
+        let new_layout = guard.mods_state.serialized.layout_effective;
+        let layout_changed = (new_layout != layout).then_some(new_layout);
         let key_handle = KeysymHandle {
             // Offset the keycode by 8, as the evdev XKB rules reflect X's
             // broken keycode system, which starts at 8.
@@ -526,6 +722,11 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
         if let FilterResult::Intercept(val) = filter(data, &guard.mods_state, key_handle) {
             // the filter returned false, we do not forward to client
             trace!("Input was intercepted by filter");
+            drop(guard);
+            if let Some(layout) = layout_changed {
+                let seat = self.get_seat(data);
+                data.keyboard_layout_changed(&seat, layout);
+            }
             return Some(val);
         }
 
@@ -549,6 +750,11 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
         } else {
             trace!("No client currently focused");
         }
+        drop(guard);
+
+        if let Some(layout) = layout_changed {
+            data.keyboard_layout_changed(&seat, layout);
+        }
 
         None
     }
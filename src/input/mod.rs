@@ -24,6 +24,7 @@
 //! #             GesturePinchBeginEvent, GesturePinchUpdateEvent, GesturePinchEndEvent,
 //! #             GestureHoldBeginEvent, GestureHoldEndEvent},
 //! #   keyboard::{KeyboardTarget, KeysymHandle, ModifiersState},
+//! #   touch::{DownEvent, UpEvent, MotionEvent as TouchMotionEvent, ShapeEvent, OrientationEvent, TouchTarget},
 //! # };
 //! # use smithay::utils::{IsAlive, Serial};
 //!
@@ -75,11 +76,21 @@
 //! #   ) {}
 //! #   fn modifiers(&self, seat: &Seat<State>, data: &mut State, modifiers: ModifiersState, serial: Serial) {}
 //! # }
+//! # impl TouchTarget<State> for Target {
+//! #   fn down(&self, seat: &Seat<State>, data: &mut State, event: &DownEvent) {}
+//! #   fn up(&self, seat: &Seat<State>, data: &mut State, event: &UpEvent) {}
+//! #   fn motion(&self, seat: &Seat<State>, data: &mut State, event: &TouchMotionEvent) {}
+//! #   fn frame(&self, seat: &Seat<State>, data: &mut State) {}
+//! #   fn cancel(&self, seat: &Seat<State>, data: &mut State) {}
+//! #   fn shape(&self, seat: &Seat<State>, data: &mut State, event: &ShapeEvent) {}
+//! #   fn orientation(&self, seat: &Seat<State>, data: &mut State, event: &OrientationEvent) {}
+//! # }
 //!
 //! // implement the required traits
 //! impl SeatHandler for State {
 //!     type KeyboardFocus = Target;
 //!     type PointerFocus = Target;
+//!     type TouchFocus = Target;
 //!
 //!     fn seat_state(&mut self) -> &mut SeatState<Self> {
 //!         &mut self.seat_state
@@ -98,13 +109,11 @@
 //!
 //! Once the seat is initialized, you can add capabilities to it.
 //!
-//! Currently, pointer and keyboard capabilities are supported by this module.
-//! [`seat`](crate::wayland::seat) also provides an abstraction to send touch-events to client,
-//! further helpers are not provided at this point.
+//! Currently, pointer, keyboard and touch capabilities are supported by this module.
 //! [`tablet_manager`](crate::wayland::tablet_manager) also provides client interaction for drawing tablets.
 //!
 //! You can add these capabilities via methods of the [`Seat`] struct:
-//! [`Seat::add_keyboard`] and [`Seat::add_pointer`].
+//! [`Seat::add_keyboard`], [`Seat::add_pointer`] and [`Seat::add_touch`].
 //! These methods return handles that can be cloned and sent across thread, so you can keep one around
 //! in your event-handling code to forward inputs to your clients.
 //!
@@ -119,10 +128,12 @@ use tracing::{info_span, instrument};
 
 use self::keyboard::{Error as KeyboardError, KeyboardHandle, KeyboardTarget};
 use self::pointer::{CursorImageStatus, PointerHandle, PointerTarget};
+use self::touch::{TouchHandle, TouchTarget};
 use crate::utils::user_data::UserDataMap;
 
 pub mod keyboard;
 pub mod pointer;
+pub mod touch;
 
 /// Handler trait for Seats
 pub trait SeatHandler: Sized {
@@ -130,6 +141,8 @@ pub trait SeatHandler: Sized {
     type KeyboardFocus: KeyboardTarget<Self> + 'static;
     /// Type used to represent the target currently holding the pointer focus
     type PointerFocus: PointerTarget<Self> + 'static;
+    /// Type used to represent the target currently holding the touch focus
+    type TouchFocus: TouchTarget<Self> + 'static;
 
     /// [SeatState] getter
     fn seat_state(&mut self) -> &mut SeatState<Self>;
@@ -139,6 +152,13 @@ pub trait SeatHandler: Sized {
 
     /// Callback that will be notified whenever a client requests to set a custom cursor image.
     fn cursor_image(&mut self, _seat: &Seat<Self>, _image: CursorImageStatus) {}
+
+    /// Callback that will be notified whenever key input changes the active layout of the keyboard
+    /// of the seat, e.g. through xkb options like `grp:alt_shift_toggle`.
+    ///
+    /// `layout` is the index of the now effective layout in the keymap.
+    /// Changes made through [`KeyboardHandle::set_layout`] do not trigger this callback.
+    fn keyboard_layout_changed(&mut self, _seat: &Seat<Self>, _layout: u32) {}
 }
 /// Delegate type for all [Seat] globals.
 ///
@@ -187,9 +207,8 @@ impl<D: SeatHandler> Hash for Seat<D> {
 pub(crate) struct Inner<D: SeatHandler> {
     pub(crate) pointer: Option<PointerHandle<D>>,
     pub(crate) keyboard: Option<KeyboardHandle<D>>,
+    pub(crate) touch: Option<TouchHandle<D>>,
 
-    #[cfg(feature = "wayland_frontend")]
-    pub(crate) touch: Option<crate::wayland::seat::TouchHandle>,
     #[cfg(feature = "wayland_frontend")]
     pub(crate) global: Option<wayland_server::backend::GlobalId>,
     #[cfg(feature = "wayland_frontend")]
@@ -202,6 +221,7 @@ impl<D: SeatHandler> fmt::Debug for Inner<D> {
         f.debug_struct("Inner")
             .field("pointer", &self.pointer)
             .field("keyboard", &self.keyboard)
+            .field("touch", &self.touch)
             .finish()
     }
 }
@@ -270,9 +290,8 @@ impl<D: SeatHandler> SeatState<D> {
             inner: Mutex::new(Inner {
                 pointer: None,
                 keyboard: None,
-
-                #[cfg(feature = "wayland_frontend")]
                 touch: None,
+
                 #[cfg(feature = "wayland_frontend")]
                 global: None,
                 #[cfg(feature = "wayland_frontend")]
@@ -313,6 +332,7 @@ impl<D: SeatHandler + 'static> Seat<D> {
     /// #             GesturePinchBeginEvent, GesturePinchUpdateEvent, GesturePinchEndEvent,
     /// #             GestureHoldBeginEvent, GestureHoldEndEvent},
     /// #   keyboard::{KeyboardTarget, KeysymHandle, ModifiersState},
+    /// #   touch::{DownEvent, UpEvent, MotionEvent as TouchMotionEvent, ShapeEvent, OrientationEvent, TouchTarget},
     /// # };
     /// # use smithay::utils::{IsAlive, Serial};
     /// #
@@ -352,10 +372,20 @@ impl<D: SeatHandler + 'static> Seat<D> {
     /// #   ) {}
     /// #   fn modifiers(&self, seat: &Seat<State>, data: &mut State, modifiers: ModifiersState, serial: Serial) {}
     /// # }
+    /// # impl TouchTarget<State> for Target {
+    /// #   fn down(&self, seat: &Seat<State>, data: &mut State, event: &DownEvent) {}
+    /// #   fn up(&self, seat: &Seat<State>, data: &mut State, event: &UpEvent) {}
+    /// #   fn motion(&self, seat: &Seat<State>, data: &mut State, event: &TouchMotionEvent) {}
+    /// #   fn frame(&self, seat: &Seat<State>, data: &mut State) {}
+    /// #   fn cancel(&self, seat: &Seat<State>, data: &mut State) {}
+    /// #   fn shape(&self, seat: &Seat<State>, data: &mut State, event: &ShapeEvent) {}
+    /// #   fn orientation(&self, seat: &Seat<State>, data: &mut State, event: &OrientationEvent) {}
+    /// # }
     /// # struct State;
     /// # impl SeatHandler for State {
     /// #     type KeyboardFocus = Target;
     /// #     type PointerFocus = Target;
+    /// #     type TouchFocus = Target;
     /// #
     /// #     fn seat_state(&mut self) -> &mut SeatState<Self> { unimplemented!() }
     /// #     fn focus_changed(&mut self, seat: &Seat<Self>, focused: Option<&Target>) { unimplemented!() }
@@ -423,6 +453,7 @@ impl<D: SeatHandler + 'static> Seat<D> {
     /// #             GesturePinchBeginEvent, GesturePinchUpdateEvent, GesturePinchEndEvent,
     /// #             GestureHoldBeginEvent, GestureHoldEndEvent},
     /// #   keyboard::{KeyboardTarget, KeysymHandle, ModifiersState},
+    /// #   touch::{DownEvent, UpEvent, MotionEvent as TouchMotionEvent, ShapeEvent, OrientationEvent, TouchTarget},
     /// # };
     /// # use smithay::utils::{IsAlive, Serial};
     /// #
@@ -462,11 +493,21 @@ impl<D: SeatHandler + 'static> Seat<D> {
     /// #   ) {}
     /// #   fn modifiers(&self, seat: &Seat<State>, data: &mut State, modifiers: ModifiersState, serial: Serial) {}
     /// # }
+    /// # impl TouchTarget<State> for Target {
+    /// #   fn down(&self, seat: &Seat<State>, data: &mut State, event: &DownEvent) {}
+    /// #   fn up(&self, seat: &Seat<State>, data: &mut State, event: &UpEvent) {}
+    /// #   fn motion(&self, seat: &Seat<State>, data: &mut State, event: &TouchMotionEvent) {}
+    /// #   fn frame(&self, seat: &Seat<State>, data: &mut State) {}
+    /// #   fn cancel(&self, seat: &Seat<State>, data: &mut State) {}
+    /// #   fn shape(&self, seat: &Seat<State>, data: &mut State, event: &ShapeEvent) {}
+    /// #   fn orientation(&self, seat: &Seat<State>, data: &mut State, event: &OrientationEvent) {}
+    /// # }
     /// #
     /// # struct State;
     /// # impl SeatHandler for State {
     /// #     type KeyboardFocus = Target;
     /// #     type PointerFocus = Target;
+    /// #     type TouchFocus = Target;
     /// #
     /// #     fn seat_state(&mut self) -> &mut SeatState<Self> { unimplemented!() }
     /// #     fn focus_changed(&mut self, seat: &Seat<Self>, focused: Option<&Target>) { unimplemented!() }
@@ -525,6 +566,123 @@ impl<D: SeatHandler + 'static> Seat<D> {
         }
     }
 
+    /// Adds the touch capability to this seat
+    ///
+    /// You are provided a [`TouchHandle`], which allows you to send input events
+    /// to this touch device. This handle can be cloned.
+    ///
+    /// Calling this method on a seat that already has a touch capability
+    /// will overwrite it, and will be seen by the clients as if the
+    /// touchscreen was unplugged and a new one was plugged in.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use smithay::input::{Seat, SeatState, SeatHandler, pointer::CursorImageStatus};
+    /// # use smithay::backend::input::KeyState;
+    /// # use smithay::input::{
+    /// #   pointer::{PointerTarget, AxisFrame, MotionEvent, ButtonEvent, RelativeMotionEvent,
+    /// #             GestureSwipeBeginEvent, GestureSwipeUpdateEvent, GestureSwipeEndEvent,
+    /// #             GesturePinchBeginEvent, GesturePinchUpdateEvent, GesturePinchEndEvent,
+    /// #             GestureHoldBeginEvent, GestureHoldEndEvent},
+    /// #   keyboard::{KeyboardTarget, KeysymHandle, ModifiersState},
+    /// #   touch::{DownEvent, UpEvent, MotionEvent as TouchMotionEvent, ShapeEvent, OrientationEvent, TouchTarget},
+    /// # };
+    /// # use smithay::utils::{IsAlive, Serial};
+    /// #
+    /// # #[derive(Debug, Clone, PartialEq)]
+    /// # struct Target;
+    /// # impl IsAlive for Target {
+    /// #   fn alive(&self) -> bool { true }
+    /// # }
+    /// # impl PointerTarget<State> for Target {
+    /// #   fn enter(&self, seat: &Seat<State>, data: &mut State, event: &MotionEvent) {}
+    /// #   fn motion(&self, seat: &Seat<State>, data: &mut State, event: &MotionEvent) {}
+    /// #   fn relative_motion(&self, seat: &Seat<State>, data: &mut State, event: &RelativeMotionEvent) {}
+    /// #   fn button(&self, seat: &Seat<State>, data: &mut State, event: &ButtonEvent) {}
+    /// #   fn axis(&self, seat: &Seat<State>, data: &mut State, frame: AxisFrame) {}
+    /// #   fn frame(&self, seat: &Seat<State>, data: &mut State) {}
+    /// #   fn leave(&self, seat: &Seat<State>, data: &mut State, serial: Serial, time: u32) {}
+    /// #   fn gesture_swipe_begin(&self, seat: &Seat<State>, data: &mut State, event: &GestureSwipeBeginEvent) {}
+    /// #   fn gesture_swipe_update(&self, seat: &Seat<State>, data: &mut State, event: &GestureSwipeUpdateEvent) {}
+    /// #   fn gesture_swipe_end(&self, seat: &Seat<State>, data: &mut State, event: &GestureSwipeEndEvent) {}
+    /// #   fn gesture_pinch_begin(&self, seat: &Seat<State>, data: &mut State, event: &GesturePinchBeginEvent) {}
+    /// #   fn gesture_pinch_update(&self, seat: &Seat<State>, data: &mut State, event: &GesturePinchUpdateEvent) {}
+    /// #   fn gesture_pinch_end(&self, seat: &Seat<State>, data: &mut State, event: &GesturePinchEndEvent) {}
+    /// #   fn gesture_hold_begin(&self, seat: &Seat<State>, data: &mut State, event: &GestureHoldBeginEvent) {}
+    /// #   fn gesture_hold_end(&self, seat: &Seat<State>, data: &mut State, event: &GestureHoldEndEvent) {}
+    /// # }
+    /// # impl KeyboardTarget<State> for Target {
+    /// #   fn enter(&self, seat: &Seat<State>, data: &mut State, keys: Vec<KeysymHandle<'_>>, serial: Serial) {}
+    /// #   fn leave(&self, seat: &Seat<State>, data: &mut State, serial: Serial) {}
+    /// #   fn key(
+    /// #       &self,
+    /// #       seat: &Seat<State>,
+    /// #       data: &mut State,
+    /// #       key: KeysymHandle<'_>,
+    /// #       state: KeyState,
+    /// #       serial: Serial,
+    /// #       time: u32,
+    /// #   ) {}
+    /// #   fn modifiers(&self, seat: &Seat<State>, data: &mut State, modifiers: ModifiersState, serial: Serial) {}
+    /// # }
+    /// # impl TouchTarget<State> for Target {
+    /// #   fn down(&self, seat: &Seat<State>, data: &mut State, event: &DownEvent) {}
+    /// #   fn up(&self, seat: &Seat<State>, data: &mut State, event: &UpEvent) {}
+    /// #   fn motion(&self, seat: &Seat<State>, data: &mut State, event: &TouchMotionEvent) {}
+    /// #   fn frame(&self, seat: &Seat<State>, data: &mut State) {}
+    /// #   fn cancel(&self, seat: &Seat<State>, data: &mut State) {}
+    /// #   fn shape(&self, seat: &Seat<State>, data: &mut State, event: &ShapeEvent) {}
+    /// #   fn orientation(&self, seat: &Seat<State>, data: &mut State, event: &OrientationEvent) {}
+    /// # }
+    /// # struct State;
+    /// # impl SeatHandler for State {
+    /// #     type KeyboardFocus = Target;
+    /// #     type PointerFocus = Target;
+    /// #     type TouchFocus = Target;
+    /// #
+    /// #     fn seat_state(&mut self) -> &mut SeatState<Self> { unimplemented!() }
+    /// #     fn focus_changed(&mut self, seat: &Seat<Self>, focused: Option<&Target>) { unimplemented!() }
+    /// #     fn cursor_image(&mut self, seat: &Seat<Self>, image: CursorImageStatus) { unimplemented!() }
+    /// # }
+    /// # let mut seat: Seat<State> = unimplemented!();
+    /// let touch_handle = seat.add_touch();
+    /// ```
+    #[instrument(parent = &self.arc.span, skip(self))]
+    pub fn add_touch(&mut self) -> TouchHandle<D> {
+        let mut inner = self.arc.inner.lock().unwrap();
+        let touch = TouchHandle::new();
+        if inner.touch.is_some() {
+            // there is already a touch device, remove it and notify the clients
+            // of the change
+            inner.touch = None;
+            #[cfg(feature = "wayland_frontend")]
+            inner.send_all_caps();
+        }
+        inner.touch = Some(touch.clone());
+        #[cfg(feature = "wayland_frontend")]
+        inner.send_all_caps();
+        touch
+    }
+
+    /// Access the touch device of this seat if any
+    pub fn get_touch(&self) -> Option<TouchHandle<D>> {
+        self.arc.inner.lock().unwrap().touch.clone()
+    }
+
+    /// Remove the touch capability from this seat
+    ///
+    /// Clients will be appropriately notified.
+    #[instrument(parent = &self.arc.span, skip(self))]
+    pub fn remove_touch(&mut self) {
+        let mut inner = self.arc.inner.lock().unwrap();
+        if inner.touch.is_some() {
+            inner.touch = None;
+            #[cfg(feature = "wayland_frontend")]
+            inner.send_all_caps();
+        }
+    }
+
     /// Gets this seat's name
     pub fn name(&self) -> &str {
         &self.arc.name
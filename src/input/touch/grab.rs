@@ -0,0 +1,255 @@
+use std::fmt;
+
+use crate::{
+    backend::input::TouchSlot,
+    input::SeatHandler,
+    utils::Serial,
+    utils::{Logical, Point},
+};
+
+use super::{DownEvent, MotionEvent, OrientationEvent, ShapeEvent, TouchInnerHandle, UpEvent};
+
+/// A trait to implement a touch grab
+///
+/// In some context, it is necessary to temporarily change the behavior of the touch handler. This is
+/// typically known as a touch grab. A typical example would be, during a drag'n'drop operation,
+/// the underlying surfaces will no longer receive classic touch events, but rather special events.
+///
+/// This trait is the interface to intercept regular touch events and change them as needed, its
+/// interface mimics the [`TouchHandle`](super::TouchHandle) interface.
+///
+/// Any interactions with [`TouchHandle`](super::TouchHandle)
+/// should be done using [`TouchInnerHandle`], as handle is borrowed/locked before grab methods are called,
+/// so calling methods on [`TouchHandle`](super::TouchHandle) would result in a deadlock.
+///
+/// If your logic decides that the grab should end, both [`TouchInnerHandle`]
+/// and [`TouchHandle`](super::TouchHandle) have
+/// a method to change it.
+///
+/// When your grab ends (either as you requested it or if it was forcefully cancelled by the server),
+/// the struct implementing this trait will be dropped. As such you should put clean-up logic in the destructor,
+/// rather than trying to guess when the grab will end.
+pub trait TouchGrab<D: SeatHandler>: Send {
+    /// A new touch point appeared
+    ///
+    /// This method allows you attach additional behavior to a down event, possibly altering it.
+    /// You generally will want to invoke `TouchInnerHandle::down()` as part of your processing. If you
+    /// don't, the rest of the compositor will behave as if the down event never occurred.
+    ///
+    /// The provided focus is the target under the new touch point and its location
+    /// in the global compositor space.
+    fn down(
+        &mut self,
+        data: &mut D,
+        handle: &mut TouchInnerHandle<'_, D>,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &DownEvent,
+    );
+    /// A touch point disappeared
+    ///
+    /// This method allows you attach additional behavior to an up event, possibly altering it.
+    /// You generally will want to invoke `TouchInnerHandle::up()` as part of your processing. If you
+    /// don't, the rest of the compositor will behave as if the up event never occurred.
+    fn up(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &UpEvent);
+    /// A touch point has changed coordinates
+    ///
+    /// This method allows you attach additional behavior to a motion event, possibly altering it.
+    /// You generally will want to invoke `TouchInnerHandle::motion()` as part of your processing. If you
+    /// don't, the rest of the compositor will behave as if the motion event never occurred.
+    ///
+    /// The provided focus is the target currently under the touch point, which is not necessarily
+    /// the one the touch point went down on.
+    fn motion(
+        &mut self,
+        data: &mut D,
+        handle: &mut TouchInnerHandle<'_, D>,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    );
+    /// End of a touch frame
+    ///
+    /// A frame groups associated events. This terminates the frame.
+    fn frame(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>);
+    /// The touch session was cancelled
+    ///
+    /// This is usually invoked when the compositor recognizes a global gesture.
+    fn cancel(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>);
+    /// A touch point has changed its shape
+    fn shape(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &ShapeEvent);
+    /// A touch point has changed its orientation
+    fn orientation(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &OrientationEvent);
+    /// The data about the event that started the grab.
+    fn start_data(&self) -> &GrabStartData<D>;
+}
+
+/// Data about the event that started the grab.
+pub struct GrabStartData<D: SeatHandler> {
+    /// The focused target and its location, if any, at the start of the grab.
+    ///
+    /// The location coordinates are in the global compositor space.
+    pub focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+    /// The touch point that initiated the grab.
+    pub slot: TouchSlot,
+    /// The location of the down event that initiated the grab, in the global compositor space.
+    pub location: Point<f64, Logical>,
+}
+
+impl<D: SeatHandler + 'static> fmt::Debug for GrabStartData<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GrabStartData")
+            .field("focus", &self.focus.as_ref().map(|_| "..."))
+            .field("slot", &self.slot)
+            .field("location", &self.location)
+            .finish()
+    }
+}
+
+impl<D: SeatHandler + 'static> Clone for GrabStartData<D> {
+    fn clone(&self) -> Self {
+        GrabStartData {
+            focus: self.focus.clone(),
+            slot: self.slot,
+            location: self.location,
+        }
+    }
+}
+
+pub(super) enum GrabStatus<D> {
+    None,
+    Active(Serial, Box<dyn TouchGrab<D>>),
+    Borrowed,
+}
+
+// TouchGrab is a trait, so we have to impl Debug manually
+impl<D> fmt::Debug for GrabStatus<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrabStatus::None => f.debug_tuple("GrabStatus::None").finish(),
+            GrabStatus::Active(serial, _) => f.debug_tuple("GrabStatus::Active").field(&serial).finish(),
+            GrabStatus::Borrowed => f.debug_tuple("GrabStatus::Borrowed").finish(),
+        }
+    }
+}
+
+// The default grab, the behavior when no particular grab is in progress
+pub(super) struct DefaultGrab;
+
+impl<D: SeatHandler + 'static> TouchGrab<D> for DefaultGrab {
+    fn down(
+        &mut self,
+        data: &mut D,
+        handle: &mut TouchInnerHandle<'_, D>,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &DownEvent,
+    ) {
+        handle.down(data, focus.clone(), event);
+        handle.set_grab(
+            event.serial,
+            TouchDownGrab {
+                start_data: GrabStartData {
+                    focus,
+                    slot: event.slot,
+                    location: event.location,
+                },
+                touch_points: vec![event.slot],
+            },
+        );
+    }
+
+    fn up(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &UpEvent) {
+        handle.up(data, event);
+    }
+
+    fn motion(
+        &mut self,
+        data: &mut D,
+        handle: &mut TouchInnerHandle<'_, D>,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        handle.motion(data, focus, event);
+    }
+
+    fn frame(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>) {
+        handle.frame(data);
+    }
+
+    fn cancel(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>) {
+        handle.cancel(data);
+    }
+
+    fn shape(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &ShapeEvent) {
+        handle.shape(data, event);
+    }
+
+    fn orientation(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &OrientationEvent) {
+        handle.orientation(data, event);
+    }
+
+    fn start_data(&self) -> &GrabStartData<D> {
+        unreachable!()
+    }
+}
+
+// A touch down grab, basic grab started when an user touches a surface
+// to maintain it focused until the user lifts all fingers.
+//
+// Additional touch points that go down while this grab is active
+// are tracked as well, the grab is only released once all of them are up.
+struct TouchDownGrab<D: SeatHandler> {
+    start_data: GrabStartData<D>,
+    touch_points: Vec<TouchSlot>,
+}
+
+impl<D: SeatHandler + 'static> TouchGrab<D> for TouchDownGrab<D> {
+    fn down(
+        &mut self,
+        data: &mut D,
+        handle: &mut TouchInnerHandle<'_, D>,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &DownEvent,
+    ) {
+        handle.down(data, focus, event);
+        self.touch_points.push(event.slot);
+    }
+
+    fn up(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &UpEvent) {
+        handle.up(data, event);
+        self.touch_points.retain(|slot| *slot != event.slot);
+        if self.touch_points.is_empty() {
+            // no more touch points are active, release the grab
+            handle.unset_grab();
+        }
+    }
+
+    fn motion(
+        &mut self,
+        data: &mut D,
+        handle: &mut TouchInnerHandle<'_, D>,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        handle.motion(data, focus, event);
+    }
+
+    fn frame(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>) {
+        handle.frame(data);
+    }
+
+    fn cancel(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>) {
+        handle.cancel(data);
+        handle.unset_grab();
+    }
+
+    fn shape(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &ShapeEvent) {
+        handle.shape(data, event);
+    }
+
+    fn orientation(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &OrientationEvent) {
+        handle.orientation(data, event);
+    }
+
+    fn start_data(&self) -> &GrabStartData<D> {
+        &self.start_data
+    }
+}
@@ -0,0 +1,606 @@
+//! Touch-related types for smithay's input abstraction
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use tracing::{info_span, instrument};
+
+use crate::{
+    backend::input::TouchSlot,
+    input::{Seat, SeatHandler},
+    utils::Serial,
+    utils::{IsAlive, Logical, Point},
+};
+
+mod grab;
+use grab::{DefaultGrab, GrabStatus};
+pub use grab::{GrabStartData, TouchGrab};
+
+/// An handle to a touch handler
+///
+/// It can be cloned and all clones manipulate the same internal state.
+///
+/// This handle gives you access to an interface to send touch events to your
+/// clients.
+///
+/// When sending events using this handle, they will be intercepted by a touch
+/// grab if any is active. See the [`TouchGrab`] trait for details.
+pub struct TouchHandle<D: SeatHandler> {
+    pub(crate) inner: Arc<Mutex<TouchInternal<D>>>,
+    #[cfg(feature = "wayland_frontend")]
+    pub(crate) known_instances: Arc<Mutex<Vec<wayland_server::protocol::wl_touch::WlTouch>>>,
+    // clients, that already received a `wl_touch.frame` for the current frame
+    #[cfg(feature = "wayland_frontend")]
+    pub(crate) framed_clients: Arc<Mutex<Vec<wayland_server::backend::ClientId>>>,
+    pub(crate) span: tracing::Span,
+}
+
+#[cfg(not(feature = "wayland_frontend"))]
+impl<D: SeatHandler> fmt::Debug for TouchHandle<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TouchHandle").field("inner", &self.inner).finish()
+    }
+}
+
+#[cfg(feature = "wayland_frontend")]
+impl<D: SeatHandler> fmt::Debug for TouchHandle<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TouchHandle")
+            .field("inner", &self.inner)
+            .field("known_instances", &self.known_instances)
+            .field("framed_clients", &self.framed_clients)
+            .finish()
+    }
+}
+
+impl<D: SeatHandler> Clone for TouchHandle<D> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            #[cfg(feature = "wayland_frontend")]
+            known_instances: self.known_instances.clone(),
+            #[cfg(feature = "wayland_frontend")]
+            framed_clients: self.framed_clients.clone(),
+            span: self.span.clone(),
+        }
+    }
+}
+
+impl<D: SeatHandler> std::hash::Hash for TouchHandle<D> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.inner).hash(state)
+    }
+}
+
+impl<D: SeatHandler> std::cmp::PartialEq for TouchHandle<D> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl<D: SeatHandler> std::cmp::Eq for TouchHandle<D> {}
+
+/// Trait representing object that can receive touch interactions
+pub trait TouchTarget<D>: IsAlive + PartialEq + Clone + fmt::Debug + Send
+where
+    D: SeatHandler,
+{
+    /// A new touch point has appeared on the target.
+    ///
+    /// This touch point is assigned a unique ID. Future events from this touch point reference this ID.
+    /// The ID ceases to be valid after a touch up event and may be reused in the future.
+    fn down(&self, seat: &Seat<D>, data: &mut D, event: &DownEvent);
+    /// The touch point has disappeared.
+    ///
+    /// No further events will be sent for this touch point and the touch point's ID
+    /// is released and may be reused in a future touch down event.
+    fn up(&self, seat: &Seat<D>, data: &mut D, event: &UpEvent);
+    /// A touch point has changed coordinates.
+    fn motion(&self, seat: &Seat<D>, data: &mut D, event: &MotionEvent);
+    /// Indicates the end of a set of events that logically belong together.
+    fn frame(&self, seat: &Seat<D>, data: &mut D);
+    /// Touch session cancelled.
+    ///
+    /// Touch cancellation applies to all touch points currently active on this target.
+    fn cancel(&self, seat: &Seat<D>, data: &mut D);
+    /// Sent when a touch point has changed its shape.
+    ///
+    /// A touch point shape is approximated by an ellipse through the major and minor axis length.
+    fn shape(&self, seat: &Seat<D>, data: &mut D, event: &ShapeEvent);
+    /// Sent when a touch point has changed its orientation.
+    ///
+    /// The orientation describes the clockwise angle of a touch point's major axis
+    /// to the positive surface y-axis and is normalized to the -180 to +180 degree range.
+    fn orientation(&self, seat: &Seat<D>, data: &mut D, event: &OrientationEvent);
+}
+
+impl<D: SeatHandler + 'static> TouchHandle<D> {
+    pub(crate) fn new() -> TouchHandle<D> {
+        TouchHandle {
+            inner: Arc::new(Mutex::new(TouchInternal::new())),
+            #[cfg(feature = "wayland_frontend")]
+            known_instances: Arc::new(Mutex::new(Vec::new())),
+            #[cfg(feature = "wayland_frontend")]
+            framed_clients: Arc::new(Mutex::new(Vec::new())),
+            span: info_span!("input_touch"),
+        }
+    }
+
+    /// Change the current grab on this touch handler to the provided grab
+    ///
+    /// Overwrites any current grab.
+    #[instrument(level = "debug", parent = &self.span, skip(self, grab))]
+    pub fn set_grab<G: TouchGrab<D> + 'static>(&self, grab: G, serial: Serial) {
+        self.inner.lock().unwrap().set_grab(serial, grab);
+    }
+
+    /// Remove any current grab on this touch handler, resetting it to the default behavior
+    #[instrument(level = "debug", parent = &self.span, skip(self))]
+    pub fn unset_grab(&self) {
+        self.inner.lock().unwrap().unset_grab();
+    }
+
+    /// Check if this touch handler is currently grabbed with this serial
+    pub fn has_grab(&self, serial: Serial) -> bool {
+        let guard = self.inner.lock().unwrap();
+        match guard.grab {
+            GrabStatus::Active(s, _) => s == serial,
+            _ => false,
+        }
+    }
+
+    /// Check if this touch handler is currently being grabbed
+    pub fn is_grabbed(&self) -> bool {
+        let guard = self.inner.lock().unwrap();
+        !matches!(guard.grab, GrabStatus::None)
+    }
+
+    /// Returns the start data for the grab, if any.
+    pub fn grab_start_data(&self) -> Option<GrabStartData<D>> {
+        let guard = self.inner.lock().unwrap();
+        match &guard.grab {
+            GrabStatus::Active(_, g) => Some(g.start_data().clone()),
+            _ => None,
+        }
+    }
+
+    /// Notify that a new touch point appeared
+    ///
+    /// You provide the location of the touch point in the global compositor space
+    /// as part of the event, and the target on top of which the touch point went down
+    /// together with the coordinates of its origin in the global compositor space
+    /// (or `None` if the touch point is not on top of a client surface).
+    ///
+    /// The touch point stays associated with this target until it is lifted
+    /// or the touch session is cancelled.
+    #[instrument(level = "trace", parent = &self.span, skip(self, data, focus), fields(focus = ?focus.as_ref().map(|(_, loc)| ("...", loc))))]
+    pub fn down(
+        &self,
+        data: &mut D,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &DownEvent,
+    ) {
+        let seat = self.get_seat(data);
+        self.inner.lock().unwrap().with_grab(&seat, |handle, grab| {
+            grab.down(data, handle, focus, event);
+        });
+    }
+
+    /// Notify that a touch point disappeared
+    #[instrument(level = "trace", parent = &self.span, skip(self, data))]
+    pub fn up(&self, data: &mut D, event: &UpEvent) {
+        let seat = self.get_seat(data);
+        self.inner.lock().unwrap().with_grab(&seat, |handle, grab| {
+            grab.up(data, handle, event);
+        });
+    }
+
+    /// Notify that a touch point moved
+    ///
+    /// You provide the new location of the touch point in the global compositor space
+    /// as part of the event, and the target currently under the touch point together
+    /// with the coordinates of its origin in the global compositor space.
+    ///
+    /// Motion events are always delivered to the target the touch point went down on,
+    /// the provided focus is only made available to the active grab.
+    #[instrument(level = "trace", parent = &self.span, skip(self, data, focus), fields(focus = ?focus.as_ref().map(|(_, loc)| ("...", loc))))]
+    pub fn motion(
+        &self,
+        data: &mut D,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        let seat = self.get_seat(data);
+        self.inner.lock().unwrap().with_grab(&seat, |handle, grab| {
+            grab.motion(data, handle, focus, event);
+        });
+    }
+
+    /// End of a touch frame
+    ///
+    /// A frame groups associated events. This terminates the frame.
+    #[instrument(level = "trace", parent = &self.span, skip(self, data))]
+    pub fn frame(&self, data: &mut D) {
+        let seat = self.get_seat(data);
+        #[cfg(feature = "wayland_frontend")]
+        self.framed_clients.lock().unwrap().clear();
+        self.inner.lock().unwrap().with_grab(&seat, |handle, grab| {
+            grab.frame(data, handle);
+        });
+        #[cfg(feature = "wayland_frontend")]
+        self.framed_clients.lock().unwrap().clear();
+    }
+
+    /// Notify that the touch session was cancelled
+    ///
+    /// This should be sent by the compositor when the touch stream is recognized as
+    /// a global gesture. Cancellation applies to all currently active touch points.
+    #[instrument(level = "trace", parent = &self.span, skip(self, data))]
+    pub fn cancel(&self, data: &mut D) {
+        let seat = self.get_seat(data);
+        self.inner.lock().unwrap().with_grab(&seat, |handle, grab| {
+            grab.cancel(data, handle);
+        });
+    }
+
+    /// Notify that a touch point changed its shape
+    #[instrument(level = "trace", parent = &self.span, skip(self, data))]
+    pub fn shape(&self, data: &mut D, event: &ShapeEvent) {
+        let seat = self.get_seat(data);
+        self.inner.lock().unwrap().with_grab(&seat, |handle, grab| {
+            grab.shape(data, handle, event);
+        });
+    }
+
+    /// Notify that a touch point changed its orientation
+    #[instrument(level = "trace", parent = &self.span, skip(self, data))]
+    pub fn orientation(&self, data: &mut D, event: &OrientationEvent) {
+        let seat = self.get_seat(data);
+        self.inner.lock().unwrap().with_grab(&seat, |handle, grab| {
+            grab.orientation(data, handle, event);
+        });
+    }
+
+    fn get_seat(&self, data: &mut D) -> Seat<D> {
+        let seat_state = data.seat_state();
+        seat_state
+            .seats
+            .iter()
+            .find(|seat| seat.get_touch().map(|h| &h == self).unwrap_or(false))
+            .cloned()
+            .unwrap()
+    }
+}
+
+impl<D> TouchHandle<D>
+where
+    D: SeatHandler,
+    <D as SeatHandler>::TouchFocus: Clone,
+{
+    /// Retrieve the current focus of the given touch point
+    pub fn current_focus(&self, slot: TouchSlot) -> Option<<D as SeatHandler>::TouchFocus> {
+        self.inner
+            .lock()
+            .unwrap()
+            .focus
+            .get(&slot)
+            .map(|(focus, _)| focus.clone())
+    }
+}
+
+/// This inner handle is accessed from inside a touch grab logic, and directly
+/// sends event to the client
+pub struct TouchInnerHandle<'a, D: SeatHandler> {
+    inner: &'a mut TouchInternal<D>,
+    seat: &'a Seat<D>,
+}
+
+impl<'a, D: SeatHandler> fmt::Debug for TouchInnerHandle<'a, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TouchInnerHandle")
+            .field("inner", &self.inner)
+            .field("seat", &self.seat.arc.name)
+            .finish()
+    }
+}
+
+impl<'a, D: SeatHandler + 'static> TouchInnerHandle<'a, D> {
+    /// Change the current grab on this touch handler to the provided grab
+    ///
+    /// Overwrites any current grab.
+    pub fn set_grab<G: TouchGrab<D> + 'static>(&mut self, serial: Serial, grab: G) {
+        self.inner.set_grab(serial, grab);
+    }
+
+    /// Remove any current grab on this touch handler, resetting it to the default behavior
+    pub fn unset_grab(&mut self) {
+        self.inner.unset_grab();
+    }
+
+    /// Access the current focus of the given touch point
+    pub fn current_focus(
+        &self,
+        slot: TouchSlot,
+    ) -> Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)> {
+        self.inner.focus.get(&slot).cloned()
+    }
+
+    /// Notify that a new touch point appeared
+    ///
+    /// This will internally send the appropriate down event to the provided target
+    /// and associate the touch point with it.
+    pub fn down(
+        &mut self,
+        data: &mut D,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &DownEvent,
+    ) {
+        self.inner.down(data, self.seat, focus, event);
+    }
+
+    /// Notify that a touch point disappeared
+    ///
+    /// This will internally send the appropriate up event to the target
+    /// the touch point is associated with.
+    pub fn up(&mut self, data: &mut D, event: &UpEvent) {
+        self.inner.up(data, self.seat, event);
+    }
+
+    /// Notify that a touch point moved
+    ///
+    /// This will internally send the appropriate motion event to the target
+    /// the touch point is associated with.
+    pub fn motion(
+        &mut self,
+        data: &mut D,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        self.inner.motion(data, self.seat, focus, event);
+    }
+
+    /// End of a touch frame
+    ///
+    /// This will internally send the appropriate frame event to all targets
+    /// that received events since the last frame.
+    pub fn frame(&mut self, data: &mut D) {
+        self.inner.frame(data, self.seat);
+    }
+
+    /// Notify that the touch session was cancelled
+    ///
+    /// This will internally send the appropriate cancel event to all targets
+    /// with active touch points and release all of them.
+    pub fn cancel(&mut self, data: &mut D) {
+        self.inner.cancel(data, self.seat);
+    }
+
+    /// Notify that a touch point changed its shape
+    pub fn shape(&mut self, data: &mut D, event: &ShapeEvent) {
+        self.inner.shape(data, self.seat, event);
+    }
+
+    /// Notify that a touch point changed its orientation
+    pub fn orientation(&mut self, data: &mut D, event: &OrientationEvent) {
+        self.inner.orientation(data, self.seat, event);
+    }
+}
+
+pub(crate) struct TouchInternal<D: SeatHandler> {
+    pub(crate) focus: HashMap<TouchSlot, (<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+    pending_frame: Vec<<D as SeatHandler>::TouchFocus>,
+    grab: GrabStatus<D>,
+}
+
+// the grab does not implement debug, so we have to impl Debug manually
+impl<D: SeatHandler> fmt::Debug for TouchInternal<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TouchInternal")
+            .field("focus", &self.focus)
+            .field("pending_frame", &self.pending_frame)
+            .field("grab", &self.grab)
+            .finish()
+    }
+}
+
+impl<D: SeatHandler + 'static> TouchInternal<D> {
+    fn new() -> Self {
+        Self {
+            focus: HashMap::new(),
+            pending_frame: Vec::new(),
+            grab: GrabStatus::None,
+        }
+    }
+
+    fn set_grab<G: TouchGrab<D> + 'static>(&mut self, serial: Serial, grab: G) {
+        self.grab = GrabStatus::Active(serial, Box::new(grab));
+    }
+
+    fn unset_grab(&mut self) {
+        self.grab = GrabStatus::None;
+    }
+
+    fn down(
+        &mut self,
+        data: &mut D,
+        seat: &Seat<D>,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &DownEvent,
+    ) {
+        let Some((target, location)) = focus else {
+            self.focus.remove(&event.slot);
+            return;
+        };
+
+        self.focus.insert(event.slot, (target.clone(), location));
+        let event = DownEvent {
+            location: event.location - location.to_f64(),
+            ..*event
+        };
+        target.down(seat, data, &event);
+        self.frame_pending(target);
+    }
+
+    fn up(&mut self, data: &mut D, seat: &Seat<D>, event: &UpEvent) {
+        if let Some((target, _)) = self.focus.remove(&event.slot) {
+            target.up(seat, data, event);
+            self.frame_pending(target);
+        }
+    }
+
+    fn motion(
+        &mut self,
+        data: &mut D,
+        seat: &Seat<D>,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        let Some((target, location)) = self.focus.get_mut(&event.slot) else {
+            return;
+        };
+
+        // the target might have moved since the touch point went down
+        if let Some((new_target, new_location)) = focus {
+            if new_target == *target {
+                *location = new_location;
+            }
+        }
+
+        let target = target.clone();
+        let event = MotionEvent {
+            location: event.location - location.to_f64(),
+            ..*event
+        };
+        target.motion(seat, data, &event);
+        self.frame_pending(target);
+    }
+
+    fn frame(&mut self, data: &mut D, seat: &Seat<D>) {
+        for target in std::mem::take(&mut self.pending_frame) {
+            target.frame(seat, data);
+        }
+    }
+
+    fn cancel(&mut self, data: &mut D, seat: &Seat<D>) {
+        let mut targets: Vec<<D as SeatHandler>::TouchFocus> = Vec::new();
+        for (target, _) in self.focus.drain().map(|(_, focus)| focus) {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        self.pending_frame.clear();
+
+        for target in targets {
+            target.cancel(seat, data);
+        }
+    }
+
+    fn shape(&mut self, data: &mut D, seat: &Seat<D>, event: &ShapeEvent) {
+        if let Some((target, _)) = self.focus.get(&event.slot) {
+            let target = target.clone();
+            target.shape(seat, data, event);
+            self.frame_pending(target);
+        }
+    }
+
+    fn orientation(&mut self, data: &mut D, seat: &Seat<D>, event: &OrientationEvent) {
+        if let Some((target, _)) = self.focus.get(&event.slot) {
+            let target = target.clone();
+            target.orientation(seat, data, event);
+            self.frame_pending(target);
+        }
+    }
+
+    fn frame_pending(&mut self, target: <D as SeatHandler>::TouchFocus) {
+        if !self.pending_frame.contains(&target) {
+            self.pending_frame.push(target);
+        }
+    }
+
+    fn with_grab<F>(&mut self, seat: &Seat<D>, f: F)
+    where
+        F: FnOnce(&mut TouchInnerHandle<'_, D>, &mut dyn TouchGrab<D>),
+    {
+        let mut grab = std::mem::replace(&mut self.grab, GrabStatus::Borrowed);
+        match grab {
+            GrabStatus::Borrowed => panic!("Accessed a touch grab from within a touch grab access."),
+            GrabStatus::Active(_, ref mut handler) => {
+                // If this grab is associated with a target that is no longer alive, discard it
+                if let Some((ref focus, _)) = handler.start_data().focus {
+                    if !focus.alive() {
+                        self.grab = GrabStatus::None;
+                        f(&mut TouchInnerHandle { inner: self, seat }, &mut DefaultGrab);
+                        return;
+                    }
+                }
+                f(&mut TouchInnerHandle { inner: self, seat }, &mut **handler);
+            }
+            GrabStatus::None => {
+                f(&mut TouchInnerHandle { inner: self, seat }, &mut DefaultGrab);
+            }
+        }
+
+        if let GrabStatus::Borrowed = self.grab {
+            // the grab has not been ended nor replaced, put it back in place
+            self.grab = grab;
+        }
+    }
+}
+
+/// Touch down event
+#[derive(Debug, Clone, Copy)]
+pub struct DownEvent {
+    /// Slot of the touch point
+    pub slot: TouchSlot,
+    /// Location of the touch point in compositor space
+    pub location: Point<f64, Logical>,
+    /// Serial of the event
+    pub serial: Serial,
+    /// Timestamp of the event, with millisecond granularity
+    pub time: u32,
+}
+
+/// Touch up event
+#[derive(Debug, Clone, Copy)]
+pub struct UpEvent {
+    /// Slot of the touch point
+    pub slot: TouchSlot,
+    /// Serial of the event
+    pub serial: Serial,
+    /// Timestamp of the event, with millisecond granularity
+    pub time: u32,
+}
+
+/// Touch motion event
+#[derive(Debug, Clone, Copy)]
+pub struct MotionEvent {
+    /// Slot of the touch point
+    pub slot: TouchSlot,
+    /// Location of the touch point in compositor space
+    pub location: Point<f64, Logical>,
+    /// Timestamp of the event, with millisecond granularity
+    pub time: u32,
+}
+
+/// Touch shape event
+#[derive(Debug, Clone, Copy)]
+pub struct ShapeEvent {
+    /// Slot of the touch point
+    pub slot: TouchSlot,
+    /// Length of the major axis in surface-local coordinates
+    pub major: f64,
+    /// Length of the minor axis in surface-local coordinates
+    pub minor: f64,
+}
+
+/// Touch orientation event
+#[derive(Debug, Clone, Copy)]
+pub struct OrientationEvent {
+    /// Slot of the touch point
+    pub slot: TouchSlot,
+    /// Angle between the major axis and the positive y-axis in degrees
+    pub orientation: f64,
+}
@@ -148,7 +148,7 @@ pub struct PhysicalProperties {
 }
 
 /// Describes the scale advertised to clients.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Scale {
     /// Integer based scaling
     Integer(i32),
@@ -358,27 +358,41 @@ impl Output {
         new_scale: Option<Scale>,
         new_location: Option<Point<i32, Logical>>,
     ) {
-        {
+        let changed = {
             let mut inner = self.inner.0.lock().unwrap();
+            let mut changed = false;
             if let Some(mode) = new_mode {
                 if inner.modes.iter().all(|&m| m != mode) {
                     inner.modes.push(mode);
                 }
+                changed |= inner.current_mode != new_mode;
                 inner.current_mode = new_mode;
             }
             if let Some(transform) = new_transform {
+                changed |= inner.transform != transform;
                 inner.transform = transform;
             }
             if let Some(scale) = new_scale {
+                changed |= inner.scale != scale;
                 inner.scale = scale;
             }
             if let Some(new_location) = new_location {
+                changed |= inner.location != new_location;
                 inner.location = new_location;
             }
-        }
+            changed
+        };
 
         #[cfg(feature = "wayland_frontend")]
-        self.wl_change_current_state(new_mode, new_transform.map(Into::into), new_scale, new_location)
+        self.wl_change_current_state(
+            new_mode,
+            new_transform.map(Into::into),
+            new_scale,
+            new_location,
+            changed,
+        );
+        #[cfg(not(feature = "wayland_frontend"))]
+        let _ = changed;
     }
 
     /// Returns the user data of this output
@@ -0,0 +1,836 @@
+use wayland_protocols::wp::color_management::v1::server::{
+    wp_color_management_output_v1::{self, WpColorManagementOutputV1},
+    wp_color_management_surface_feedback_v1::{self, WpColorManagementSurfaceFeedbackV1},
+    wp_color_management_surface_v1::{self, WpColorManagementSurfaceV1},
+    wp_color_manager_v1::{self, WpColorManagerV1},
+    wp_image_description_creator_params_v1::{self, WpImageDescriptionCreatorParamsV1},
+    wp_image_description_info_v1::{self, WpImageDescriptionInfoV1},
+    wp_image_description_v1::{self, WpImageDescriptionV1},
+};
+use wayland_server::{
+    backend::ClientId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+
+use super::{
+    ColorManagementFeedbackUserData, ColorManagementHandler, ColorManagementOutputUserData,
+    ColorManagementState, ColorManagementSurfaceCachedState, ColorManagementSurfaceData,
+    ColorManagementSurfaceUserData, ImageDescription, ImageDescriptionContents,
+    ImageDescriptionCreatorUserData, ImageDescriptionUserData, Luminance, OutputColorData, Primaries,
+    TransferFunction,
+};
+use crate::{backend::renderer::color::ColorDescription, output::Output, wayland::compositor};
+
+const NAMED_PRIMARIES: [(wp_color_manager_v1::Primaries, Primaries); 10] = [
+    (wp_color_manager_v1::Primaries::Srgb, Primaries::SRGB),
+    (wp_color_manager_v1::Primaries::PalM, Primaries::PAL_M),
+    (wp_color_manager_v1::Primaries::Pal, Primaries::PAL),
+    (wp_color_manager_v1::Primaries::Ntsc, Primaries::NTSC),
+    (
+        wp_color_manager_v1::Primaries::GenericFilm,
+        Primaries::GENERIC_FILM,
+    ),
+    (wp_color_manager_v1::Primaries::Bt2020, Primaries::BT2020),
+    (wp_color_manager_v1::Primaries::Cie1931Xyz, Primaries::CIE1931_XYZ),
+    (wp_color_manager_v1::Primaries::DciP3, Primaries::DCI_P3),
+    (wp_color_manager_v1::Primaries::DisplayP3, Primaries::DISPLAY_P3),
+    (wp_color_manager_v1::Primaries::AdobeRgb, Primaries::ADOBE_RGB),
+];
+
+const NAMED_TRANSFER_FUNCTIONS: [(wp_color_manager_v1::TransferFunction, TransferFunction); 6] = [
+    (
+        wp_color_manager_v1::TransferFunction::Srgb,
+        TransferFunction::Srgb,
+    ),
+    (
+        wp_color_manager_v1::TransferFunction::Gamma22,
+        TransferFunction::Gamma22,
+    ),
+    (
+        wp_color_manager_v1::TransferFunction::Gamma28,
+        TransferFunction::Gamma28,
+    ),
+    (
+        wp_color_manager_v1::TransferFunction::Bt1886,
+        TransferFunction::Bt1886,
+    ),
+    (
+        wp_color_manager_v1::TransferFunction::ExtLinear,
+        TransferFunction::Linear,
+    ),
+    (
+        wp_color_manager_v1::TransferFunction::St2084Pq,
+        TransferFunction::Pq,
+    ),
+];
+
+const SUPPORTED_FEATURES: [wp_color_manager_v1::Feature; 5] = [
+    wp_color_manager_v1::Feature::Parametric,
+    wp_color_manager_v1::Feature::SetPrimaries,
+    wp_color_manager_v1::Feature::SetTfPower,
+    wp_color_manager_v1::Feature::SetLuminances,
+    wp_color_manager_v1::Feature::SetMasteringDisplayPrimaries,
+];
+
+fn primaries_from_wire(coords: [i32; 8]) -> Primaries {
+    let coord = |idx: usize| {
+        (
+            coords[idx] as f32 / 1_000_000.0,
+            coords[idx + 1] as f32 / 1_000_000.0,
+        )
+    };
+    Primaries {
+        red: coord(0),
+        green: coord(2),
+        blue: coord(4),
+        white: coord(6),
+    }
+}
+
+fn primaries_to_wire(primaries: &Primaries) -> [i32; 8] {
+    let coord = |value: f32| (value * 1_000_000.0).round() as i32;
+    [
+        coord(primaries.red.0),
+        coord(primaries.red.1),
+        coord(primaries.green.0),
+        coord(primaries.green.1),
+        coord(primaries.blue.0),
+        coord(primaries.blue.1),
+        coord(primaries.white.0),
+        coord(primaries.white.1),
+    ]
+}
+
+fn send_ready(image_description: &WpImageDescriptionV1, description: &ImageDescription) {
+    image_description.ready(description.identity());
+}
+
+fn send_information(info: &WpImageDescriptionInfoV1, contents: &ImageDescriptionContents) {
+    let color = &contents.color;
+
+    let [r_x, r_y, g_x, g_y, b_x, b_y, w_x, w_y] = primaries_to_wire(&color.primaries);
+    info.primaries(r_x, r_y, g_x, g_y, b_x, b_y, w_x, w_y);
+    if let Some((named, _)) = NAMED_PRIMARIES
+        .iter()
+        .find(|(_, primaries)| *primaries == color.primaries)
+    {
+        info.primaries_named(*named);
+    }
+
+    match NAMED_TRANSFER_FUNCTIONS
+        .iter()
+        .find(|(_, tf)| *tf == color.transfer_function)
+    {
+        Some((named, _)) => info.tf_named(*named),
+        None => {
+            let exponent = color.transfer_function.exponent().unwrap_or(1.0);
+            info.tf_power((exponent * 10_000.0).round() as u32);
+        }
+    }
+
+    info.luminances(
+        (color.luminance.min * 10_000.0).round() as u32,
+        color.luminance.max.round() as u32,
+        color.luminance.reference.round() as u32,
+    );
+
+    let [r_x, r_y, g_x, g_y, b_x, b_y, w_x, w_y] = primaries_to_wire(&contents.target_primaries);
+    info.target_primaries(r_x, r_y, g_x, g_y, b_x, b_y, w_x, w_y);
+    info.target_luminance(
+        (contents.target_luminance.0 * 10_000.0).round() as u32,
+        contents.target_luminance.1.round() as u32,
+    );
+    if let Some(max_cll) = contents.max_cll {
+        info.target_max_cll(max_cll);
+    }
+    if let Some(max_fall) = contents.max_fall {
+        info.target_max_fall(max_fall);
+    }
+
+    info.done();
+}
+
+// wp_color_manager_v1
+
+impl<D> GlobalDispatch<WpColorManagerV1, (), D> for ColorManagementState
+where
+    D: GlobalDispatch<WpColorManagerV1, ()>
+        + Dispatch<WpColorManagerV1, ()>
+        + Dispatch<WpColorManagementOutputV1, ColorManagementOutputUserData>
+        + Dispatch<WpColorManagementSurfaceV1, ColorManagementSurfaceUserData>
+        + Dispatch<WpColorManagementSurfaceFeedbackV1, ColorManagementFeedbackUserData>
+        + Dispatch<WpImageDescriptionCreatorParamsV1, ImageDescriptionCreatorUserData>
+        + Dispatch<WpImageDescriptionV1, ImageDescriptionUserData>
+        + ColorManagementHandler
+        + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<WpColorManagerV1>,
+        _: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(resource, ());
+
+        manager.supported_intent(wp_color_manager_v1::RenderIntent::Perceptual);
+        for feature in SUPPORTED_FEATURES {
+            manager.supported_feature(feature);
+        }
+        for (tf, _) in NAMED_TRANSFER_FUNCTIONS {
+            manager.supported_tf_named(tf);
+        }
+        for (primaries, _) in NAMED_PRIMARIES {
+            manager.supported_primaries_named(primaries);
+        }
+        manager.done();
+    }
+}
+
+impl<D> Dispatch<WpColorManagerV1, (), D> for ColorManagementState
+where
+    D: Dispatch<WpColorManagerV1, ()>
+        + Dispatch<WpColorManagementOutputV1, ColorManagementOutputUserData>
+        + Dispatch<WpColorManagementSurfaceV1, ColorManagementSurfaceUserData>
+        + Dispatch<WpColorManagementSurfaceFeedbackV1, ColorManagementFeedbackUserData>
+        + Dispatch<WpImageDescriptionCreatorParamsV1, ImageDescriptionCreatorUserData>
+        + Dispatch<WpImageDescriptionV1, ImageDescriptionUserData>
+        + ColorManagementHandler
+        + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _: &Client,
+        manager: &WpColorManagerV1,
+        request: wp_color_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_color_manager_v1::Request::GetOutput { id, output } => {
+                let output = Output::from_resource(&output);
+                let instance = data_init.init(
+                    id,
+                    ColorManagementOutputUserData {
+                        output: output.as_ref().map(Output::downgrade),
+                    },
+                );
+
+                if let Some(output) = output {
+                    output
+                        .user_data()
+                        .insert_if_missing_threadsafe(OutputColorData::default);
+                    let mut data = output
+                        .user_data()
+                        .get::<OutputColorData>()
+                        .unwrap()
+                        .0
+                        .lock()
+                        .unwrap();
+                    data.instances.push(instance);
+                }
+            }
+
+            wp_color_manager_v1::Request::GetSurface { id, surface } => {
+                let already_taken = compositor::with_states(&surface, |states| {
+                    states
+                        .data_map
+                        .insert_if_missing_threadsafe(ColorManagementSurfaceData::default);
+                    let data = states.data_map.get::<ColorManagementSurfaceData>().unwrap();
+
+                    let already_taken = data.is_resource_attached();
+
+                    if !already_taken {
+                        data.set_is_resource_attached(true);
+                    }
+
+                    already_taken
+                });
+
+                if already_taken {
+                    manager.post_error(
+                        wp_color_manager_v1::Error::SurfaceExists,
+                        "WlSurface already has WpColorManagementSurfaceV1 attached",
+                    )
+                } else {
+                    data_init.init(
+                        id,
+                        ColorManagementSurfaceUserData {
+                            surface: surface.downgrade(),
+                        },
+                    );
+                }
+            }
+
+            wp_color_manager_v1::Request::GetSurfaceFeedback { id, surface } => {
+                let instance = data_init.init(
+                    id,
+                    ColorManagementFeedbackUserData {
+                        surface: surface.downgrade(),
+                    },
+                );
+
+                compositor::with_states(&surface, |states| {
+                    states
+                        .data_map
+                        .insert_if_missing_threadsafe(ColorManagementSurfaceData::default);
+                    let data = states.data_map.get::<ColorManagementSurfaceData>().unwrap();
+                    data.feedback.lock().unwrap().instances.push(instance);
+                });
+            }
+
+            wp_color_manager_v1::Request::CreateIccCreator { .. } => {
+                manager.post_error(
+                    wp_color_manager_v1::Error::UnsupportedFeature,
+                    "ICC image descriptions are not supported",
+                );
+            }
+
+            wp_color_manager_v1::Request::CreateParametricCreator { obj } => {
+                data_init.init(obj, ImageDescriptionCreatorUserData::default());
+            }
+
+            wp_color_manager_v1::Request::CreateWindowsScrgb { .. } => {
+                manager.post_error(
+                    wp_color_manager_v1::Error::UnsupportedFeature,
+                    "scRGB image descriptions are not supported",
+                );
+            }
+
+            wp_color_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+// wp_color_management_output_v1
+
+impl<D> Dispatch<WpColorManagementOutputV1, ColorManagementOutputUserData, D> for ColorManagementState
+where
+    D: Dispatch<WpColorManagementOutputV1, ColorManagementOutputUserData>
+        + Dispatch<WpImageDescriptionV1, ImageDescriptionUserData>
+        + ColorManagementHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        _: &Client,
+        _: &WpColorManagementOutputV1,
+        request: wp_color_management_output_v1::Request,
+        data: &ColorManagementOutputUserData,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_color_management_output_v1::Request::GetImageDescription { image_description } => {
+                let output = data.output.as_ref().and_then(|output| output.upgrade());
+                let description =
+                    output.map(|output| state.color_management_state().output_image_description(&output));
+
+                let image_description = data_init.init(
+                    image_description,
+                    ImageDescriptionUserData {
+                        description: description.clone(),
+                        allow_information: true,
+                    },
+                );
+                match description {
+                    Some(description) => send_ready(&image_description, &description),
+                    None => image_description.failed(
+                        wp_image_description_v1::Cause::NoOutput,
+                        "The output no longer exists".into(),
+                    ),
+                }
+            }
+
+            wp_color_management_output_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        object: &WpColorManagementOutputV1,
+        data: &ColorManagementOutputUserData,
+    ) {
+        if let Some(output) = data.output.as_ref().and_then(|output| output.upgrade()) {
+            if let Some(data) = output.user_data().get::<OutputColorData>() {
+                data.0
+                    .lock()
+                    .unwrap()
+                    .instances
+                    .retain(|instance| instance != object);
+            }
+        }
+    }
+}
+
+// wp_color_management_surface_v1
+
+impl<D> Dispatch<WpColorManagementSurfaceV1, ColorManagementSurfaceUserData, D> for ColorManagementState
+where
+    D: Dispatch<WpColorManagementSurfaceV1, ColorManagementSurfaceUserData>,
+{
+    fn request(
+        _state: &mut D,
+        _: &Client,
+        resource: &WpColorManagementSurfaceV1,
+        request: wp_color_management_surface_v1::Request,
+        data: &ColorManagementSurfaceUserData,
+        _dh: &DisplayHandle,
+        _: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_color_management_surface_v1::Request::SetImageDescription {
+                image_description,
+                render_intent,
+            } => {
+                let Ok(surface) = data.surface.upgrade() else {
+                    resource.post_error(
+                        wp_color_management_surface_v1::Error::Inert,
+                        "WlSurface was destroyed",
+                    );
+                    return;
+                };
+
+                if render_intent != WEnum::Value(wp_color_manager_v1::RenderIntent::Perceptual) {
+                    resource.post_error(
+                        wp_color_management_surface_v1::Error::RenderIntent,
+                        "Unsupported rendering intent",
+                    );
+                    return;
+                }
+
+                let Some(description) = image_description
+                    .data::<ImageDescriptionUserData>()
+                    .and_then(|data| data.description.clone())
+                else {
+                    resource.post_error(
+                        wp_color_management_surface_v1::Error::ImageDescription,
+                        "Image description is not ready",
+                    );
+                    return;
+                };
+
+                compositor::with_states(&surface, |states| {
+                    states
+                        .cached_state
+                        .pending::<ColorManagementSurfaceCachedState>()
+                        .image_description = Some(description);
+                });
+            }
+
+            wp_color_management_surface_v1::Request::UnsetImageDescription => {
+                let Ok(surface) = data.surface.upgrade() else {
+                    resource.post_error(
+                        wp_color_management_surface_v1::Error::Inert,
+                        "WlSurface was destroyed",
+                    );
+                    return;
+                };
+
+                compositor::with_states(&surface, |states| {
+                    states
+                        .cached_state
+                        .pending::<ColorManagementSurfaceCachedState>()
+                        .image_description = None;
+                });
+            }
+
+            // Equivalent to unset_image_description, including double buffering semantics.
+            wp_color_management_surface_v1::Request::Destroy => {
+                let Ok(surface) = data.surface.upgrade() else {
+                    return;
+                };
+
+                compositor::with_states(&surface, |states| {
+                    states
+                        .data_map
+                        .get::<ColorManagementSurfaceData>()
+                        .unwrap()
+                        .set_is_resource_attached(false);
+
+                    states
+                        .cached_state
+                        .pending::<ColorManagementSurfaceCachedState>()
+                        .image_description = None;
+                });
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+// wp_color_management_surface_feedback_v1
+
+impl<D> Dispatch<WpColorManagementSurfaceFeedbackV1, ColorManagementFeedbackUserData, D>
+    for ColorManagementState
+where
+    D: Dispatch<WpColorManagementSurfaceFeedbackV1, ColorManagementFeedbackUserData>
+        + Dispatch<WpImageDescriptionV1, ImageDescriptionUserData>
+        + ColorManagementHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        _: &Client,
+        resource: &WpColorManagementSurfaceFeedbackV1,
+        request: wp_color_management_surface_feedback_v1::Request,
+        data: &ColorManagementFeedbackUserData,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_color_management_surface_feedback_v1::Request::GetPreferred { image_description }
+            | wp_color_management_surface_feedback_v1::Request::GetPreferredParametric {
+                image_description,
+            } => {
+                let Ok(surface) = data.surface.upgrade() else {
+                    resource.post_error(
+                        wp_color_management_surface_feedback_v1::Error::Inert,
+                        "WlSurface was destroyed",
+                    );
+                    return;
+                };
+
+                let description = state
+                    .color_management_state()
+                    .preferred_image_description(&surface);
+                let image_description = data_init.init(
+                    image_description,
+                    ImageDescriptionUserData {
+                        description: Some(description.clone()),
+                        allow_information: true,
+                    },
+                );
+                send_ready(&image_description, &description);
+            }
+
+            wp_color_management_surface_feedback_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        object: &WpColorManagementSurfaceFeedbackV1,
+        data: &ColorManagementFeedbackUserData,
+    ) {
+        if let Ok(surface) = data.surface.upgrade() {
+            compositor::with_states(&surface, |states| {
+                if let Some(data) = states.data_map.get::<ColorManagementSurfaceData>() {
+                    data.feedback
+                        .lock()
+                        .unwrap()
+                        .instances
+                        .retain(|instance| instance != object);
+                }
+            });
+        }
+    }
+}
+
+// wp_image_description_creator_params_v1
+
+#[derive(Debug, Default)]
+pub(super) struct ParametricCreator {
+    transfer_function: Option<TransferFunction>,
+    primaries: Option<Primaries>,
+    luminance: Option<Luminance>,
+    target_primaries: Option<Primaries>,
+    target_luminance: Option<(f32, f32)>,
+    max_cll: Option<u32>,
+    max_fall: Option<u32>,
+}
+
+impl ParametricCreator {
+    fn create(
+        &self,
+    ) -> Result<ImageDescriptionContents, (wp_image_description_creator_params_v1::Error, String)> {
+        let (Some(transfer_function), Some(primaries)) = (self.transfer_function, self.primaries) else {
+            return Err((
+                wp_image_description_creator_params_v1::Error::IncompleteSet,
+                "Transfer function and primaries are required".into(),
+            ));
+        };
+
+        let mut luminance = self
+            .luminance
+            .unwrap_or_else(|| Luminance::default_for(transfer_function));
+        if transfer_function == TransferFunction::Pq {
+            luminance.max = luminance.min + TransferFunction::PQ_MAX_LUMINANCE;
+        }
+        let target_luminance = self.target_luminance.unwrap_or((luminance.min, luminance.max));
+
+        if let (Some(max_cll), Some(max_fall)) = (self.max_cll, self.max_fall) {
+            if max_fall > max_cll {
+                return Err((
+                    wp_image_description_creator_params_v1::Error::InvalidLuminance,
+                    "max_fall must not exceed max_cll".into(),
+                ));
+            }
+        }
+        // required in version 1
+        for level in [self.max_cll, self.max_fall].into_iter().flatten() {
+            let level = level as f32;
+            if level <= target_luminance.0 || level > target_luminance.1 {
+                return Err((
+                    wp_image_description_creator_params_v1::Error::InvalidLuminance,
+                    "max_cll and max_fall must be within the mastering luminance range".into(),
+                ));
+            }
+        }
+
+        Ok(ImageDescriptionContents {
+            color: ColorDescription {
+                primaries,
+                transfer_function,
+                luminance,
+            },
+            target_primaries: self.target_primaries.unwrap_or(primaries),
+            target_luminance,
+            max_cll: self.max_cll,
+            max_fall: self.max_fall,
+        })
+    }
+}
+
+fn set_once<T>(resource: &WpImageDescriptionCreatorParamsV1, property: &mut Option<T>, value: T, name: &str) {
+    if property.is_some() {
+        resource.post_error(
+            wp_image_description_creator_params_v1::Error::AlreadySet,
+            format!("{} was already set", name),
+        );
+        return;
+    }
+    *property = Some(value);
+}
+
+impl<D> Dispatch<WpImageDescriptionCreatorParamsV1, ImageDescriptionCreatorUserData, D>
+    for ColorManagementState
+where
+    D: Dispatch<WpImageDescriptionCreatorParamsV1, ImageDescriptionCreatorUserData>
+        + Dispatch<WpImageDescriptionV1, ImageDescriptionUserData>
+        + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _: &Client,
+        resource: &WpImageDescriptionCreatorParamsV1,
+        request: wp_image_description_creator_params_v1::Request,
+        data: &ImageDescriptionCreatorUserData,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let mut creator = data.0.lock().unwrap();
+
+        match request {
+            wp_image_description_creator_params_v1::Request::Create { image_description } => {
+                match creator.create() {
+                    Ok(contents) => {
+                        let description = ImageDescription::new(contents);
+                        let image_description = data_init.init(
+                            image_description,
+                            ImageDescriptionUserData {
+                                description: Some(description.clone()),
+                                allow_information: false,
+                            },
+                        );
+                        send_ready(&image_description, &description);
+                    }
+                    Err((error, msg)) => resource.post_error(error, msg),
+                }
+            }
+
+            wp_image_description_creator_params_v1::Request::SetTfNamed { tf } => {
+                let Some(tf) = NAMED_TRANSFER_FUNCTIONS
+                    .iter()
+                    .find(|(named, _)| tf == WEnum::Value(*named))
+                    .map(|(_, tf)| *tf)
+                else {
+                    resource.post_error(
+                        wp_image_description_creator_params_v1::Error::InvalidTf,
+                        "Unsupported transfer function",
+                    );
+                    return;
+                };
+                set_once(resource, &mut creator.transfer_function, tf, "Transfer function");
+            }
+
+            wp_image_description_creator_params_v1::Request::SetTfPower { eexp } => {
+                let exponent = eexp as f32 / 10_000.0;
+                if !(1.0..=10.0).contains(&exponent) {
+                    resource.post_error(
+                        wp_image_description_creator_params_v1::Error::InvalidTf,
+                        "Exponent must be between 1.0 and 10.0",
+                    );
+                    return;
+                }
+                set_once(
+                    resource,
+                    &mut creator.transfer_function,
+                    TransferFunction::Power(exponent),
+                    "Transfer function",
+                );
+            }
+
+            wp_image_description_creator_params_v1::Request::SetPrimariesNamed { primaries } => {
+                let Some(primaries) = NAMED_PRIMARIES
+                    .iter()
+                    .find(|(named, _)| primaries == WEnum::Value(*named))
+                    .map(|(_, primaries)| *primaries)
+                else {
+                    resource.post_error(
+                        wp_image_description_creator_params_v1::Error::InvalidPrimariesNamed,
+                        "Unsupported primaries",
+                    );
+                    return;
+                };
+                set_once(resource, &mut creator.primaries, primaries, "Primaries");
+            }
+
+            wp_image_description_creator_params_v1::Request::SetPrimaries {
+                r_x,
+                r_y,
+                g_x,
+                g_y,
+                b_x,
+                b_y,
+                w_x,
+                w_y,
+            } => {
+                let primaries = primaries_from_wire([r_x, r_y, g_x, g_y, b_x, b_y, w_x, w_y]);
+                set_once(resource, &mut creator.primaries, primaries, "Primaries");
+            }
+
+            wp_image_description_creator_params_v1::Request::SetLuminances {
+                min_lum,
+                max_lum,
+                reference_lum,
+            } => {
+                let luminance = Luminance {
+                    min: min_lum as f32 / 10_000.0,
+                    max: max_lum as f32,
+                    reference: reference_lum as f32,
+                };
+                if luminance.max <= luminance.min || luminance.reference <= luminance.min {
+                    resource.post_error(
+                        wp_image_description_creator_params_v1::Error::InvalidLuminance,
+                        "Maximum and reference luminance must exceed the minimum luminance",
+                    );
+                    return;
+                }
+                set_once(resource, &mut creator.luminance, luminance, "Luminances");
+            }
+
+            wp_image_description_creator_params_v1::Request::SetMasteringDisplayPrimaries {
+                r_x,
+                r_y,
+                g_x,
+                g_y,
+                b_x,
+                b_y,
+                w_x,
+                w_y,
+            } => {
+                let primaries = primaries_from_wire([r_x, r_y, g_x, g_y, b_x, b_y, w_x, w_y]);
+                set_once(
+                    resource,
+                    &mut creator.target_primaries,
+                    primaries,
+                    "Mastering display primaries",
+                );
+            }
+
+            wp_image_description_creator_params_v1::Request::SetMasteringLuminance { min_lum, max_lum } => {
+                let luminance = (min_lum as f32 / 10_000.0, max_lum as f32);
+                if luminance.1 <= luminance.0 {
+                    resource.post_error(
+                        wp_image_description_creator_params_v1::Error::InvalidLuminance,
+                        "Maximum luminance must exceed the minimum luminance",
+                    );
+                    return;
+                }
+                set_once(
+                    resource,
+                    &mut creator.target_luminance,
+                    luminance,
+                    "Mastering luminance",
+                );
+            }
+
+            wp_image_description_creator_params_v1::Request::SetMaxCll { max_cll } => {
+                set_once(resource, &mut creator.max_cll, max_cll, "max_cll");
+            }
+
+            wp_image_description_creator_params_v1::Request::SetMaxFall { max_fall } => {
+                set_once(resource, &mut creator.max_fall, max_fall, "max_fall");
+            }
+
+            _ => unreachable!(),
+        }
+    }
+}
+
+// wp_image_description_v1
+
+impl<D> Dispatch<WpImageDescriptionV1, ImageDescriptionUserData, D> for ColorManagementState
+where
+    D: Dispatch<WpImageDescriptionV1, ImageDescriptionUserData> + Dispatch<WpImageDescriptionInfoV1, ()>,
+{
+    fn request(
+        _state: &mut D,
+        _: &Client,
+        resource: &WpImageDescriptionV1,
+        request: wp_image_description_v1::Request,
+        data: &ImageDescriptionUserData,
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_image_description_v1::Request::GetInformation { information } => {
+                let Some(description) = data.description.as_ref() else {
+                    resource.post_error(
+                        wp_image_description_v1::Error::NotReady,
+                        "Image description is not ready",
+                    );
+                    return;
+                };
+                if !data.allow_information {
+                    resource.post_error(
+                        wp_image_description_v1::Error::NoInformation,
+                        "get_information is not allowed for this image description",
+                    );
+                    return;
+                }
+
+                let info = data_init.init(information, ());
+                send_information(&info, description.contents());
+            }
+
+            wp_image_description_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+// wp_image_description_info_v1
+
+impl<D> Dispatch<WpImageDescriptionInfoV1, (), D> for ColorManagementState
+where
+    D: Dispatch<WpImageDescriptionInfoV1, ()>,
+{
+    fn request(
+        _state: &mut D,
+        _: &Client,
+        _: &WpImageDescriptionInfoV1,
+        _: wp_image_description_info_v1::Request,
+        _: &(),
+        _dh: &DisplayHandle,
+        _: &mut DataInit<'_, D>,
+    ) {
+    }
+}
@@ -0,0 +1,498 @@
+//! Implementation of the color management protocol
+//!
+//! This protocol allows clients to describe the color encoding of their surfaces through
+//! image descriptions and to query the preferred image description of surfaces and outputs.
+//!
+//! Only parametric image descriptions are supported. Clients create them from their
+//! primaries, transfer function and luminance range, which are exposed as a
+//! [`ColorDescription`], the format renderers supporting color transformations
+//! (e.g. `GlesRenderer::set_output_color_description`) understand.
+//!
+//! The image description of a surface is double-buffered and stored in its
+//! [`ColorManagementSurfaceCachedState`], [`surface_color_description`] returns it or the sRGB
+//! default for surfaces without one.
+//!
+//! The image descriptions of outputs are set through [`ColorManagementState::set_output_image_description`],
+//! the preferred image descriptions of surfaces through [`ColorManagementState::set_preferred_image_description`].
+//! Both default to sRGB.
+//!
+//! ### Example
+//!
+//! ```no_run
+//! # extern crate wayland_server;
+//! #
+//! use wayland_server::{protocol::wl_surface::WlSurface, DisplayHandle};
+//! use smithay::{
+//!     delegate_color_management, delegate_compositor,
+//!     wayland::compositor::{self, CompositorState, CompositorClientState, CompositorHandler},
+//!     wayland::color_management::{
+//!         surface_color_description, ColorManagementHandler, ColorManagementState,
+//!     },
+//! };
+//!
+//! pub struct State {
+//!     compositor_state: CompositorState,
+//!     color_management_state: ColorManagementState,
+//! };
+//! struct ClientState { compositor_state: CompositorClientState }
+//! impl wayland_server::backend::ClientData for ClientState {}
+//!
+//! delegate_color_management!(State);
+//! delegate_compositor!(State);
+//!
+//! impl ColorManagementHandler for State {
+//!     fn color_management_state(&mut self) -> &mut ColorManagementState {
+//!         &mut self.color_management_state
+//!     }
+//! }
+//!
+//! impl CompositorHandler for State {
+//!    fn compositor_state(&mut self) -> &mut CompositorState {
+//!        &mut self.compositor_state
+//!    }
+//!
+//!    fn client_compositor_state<'a>(&self, client: &'a wayland_server::Client) -> &'a CompositorClientState {
+//!        &client.get_data::<ClientState>().unwrap().compositor_state
+//!    }
+//!
+//!    fn commit(&mut self, surface: &WlSurface) {
+//!        compositor::with_states(&surface, |states| {
+//!            dbg!(surface_color_description(states));
+//!        });
+//!    }
+//! }
+//!
+//! let mut display = wayland_server::Display::<State>::new().unwrap();
+//!
+//! let compositor_state = CompositorState::new::<State>(&display.handle());
+//! let color_management_state = ColorManagementState::new::<State>(&display.handle());
+//!
+//! let state = State {
+//!     compositor_state,
+//!     color_management_state,
+//! };
+//! ```
+
+use std::sync::{
+    atomic::{self, AtomicBool, AtomicU32},
+    Arc, Mutex,
+};
+
+use wayland_protocols::wp::color_management::v1::server::{
+    wp_color_management_output_v1::WpColorManagementOutputV1,
+    wp_color_management_surface_feedback_v1::WpColorManagementSurfaceFeedbackV1,
+    wp_color_management_surface_v1::WpColorManagementSurfaceV1, wp_color_manager_v1::WpColorManagerV1,
+    wp_image_description_creator_params_v1::WpImageDescriptionCreatorParamsV1,
+    wp_image_description_info_v1::WpImageDescriptionInfoV1, wp_image_description_v1::WpImageDescriptionV1,
+};
+use wayland_server::{
+    backend::GlobalId, protocol::wl_surface::WlSurface, Dispatch, DisplayHandle, GlobalDispatch, Resource,
+    Weak,
+};
+
+pub use crate::backend::renderer::color::{ColorDescription, Luminance, Primaries, TransferFunction};
+use crate::output::{Output, WeakOutput};
+
+use super::compositor::{self, Cacheable, SurfaceData};
+
+mod dispatch;
+
+static NEXT_IDENTITY: AtomicU32 = AtomicU32::new(1);
+
+/// Parameters of an image description
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageDescriptionContents {
+    /// Encoding of the color values
+    pub color: ColorDescription,
+    /// Primaries of the display used for mastering the content
+    ///
+    /// Defaults to the primaries of [`ImageDescriptionContents::color`].
+    pub target_primaries: Primaries,
+    /// Minimum and maximum luminance in cd/m² of the display used for mastering the content
+    ///
+    /// Defaults to the luminance range of [`ImageDescriptionContents::color`].
+    pub target_luminance: (f32, f32),
+    /// Maximum content light level in cd/m²
+    pub max_cll: Option<u32>,
+    /// Maximum frame-average light level in cd/m²
+    pub max_fall: Option<u32>,
+}
+
+impl From<ColorDescription> for ImageDescriptionContents {
+    fn from(color: ColorDescription) -> Self {
+        ImageDescriptionContents {
+            color,
+            target_primaries: color.primaries,
+            target_luminance: (color.luminance.min, color.luminance.max),
+            max_cll: None,
+            max_fall: None,
+        }
+    }
+}
+
+/// An immutable image description
+///
+/// Cloning is cheap and clones refer to the same image description.
+#[derive(Debug, Clone)]
+pub struct ImageDescription(Arc<ImageDescriptionInner>);
+
+#[derive(Debug)]
+struct ImageDescriptionInner {
+    identity: u32,
+    contents: ImageDescriptionContents,
+}
+
+impl PartialEq for ImageDescription {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl ImageDescription {
+    /// Create a new image description
+    pub fn new(contents: impl Into<ImageDescriptionContents>) -> ImageDescription {
+        ImageDescription(Arc::new(ImageDescriptionInner {
+            identity: NEXT_IDENTITY.fetch_add(1, atomic::Ordering::Relaxed),
+            contents: contents.into(),
+        }))
+    }
+
+    /// Identity of the image description, as exposed to clients
+    pub fn identity(&self) -> u32 {
+        self.0.identity
+    }
+
+    /// Parameters of the image description
+    pub fn contents(&self) -> &ImageDescriptionContents {
+        &self.0.contents
+    }
+
+    /// Encoding of the color values
+    pub fn color_description(&self) -> &ColorDescription {
+        &self.0.contents.color
+    }
+}
+
+/// Data associated with WlSurface
+/// Represents the client pending state
+///
+/// ```no_run
+/// use smithay::wayland::compositor;
+/// use smithay::wayland::color_management::ColorManagementSurfaceCachedState;
+///
+/// # let wl_surface = todo!();
+/// compositor::with_states(&wl_surface, |states| {
+///     let current = states.cached_state.current::<ColorManagementSurfaceCachedState>();
+///     dbg!(current.image_description());
+/// });
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ColorManagementSurfaceCachedState {
+    image_description: Option<ImageDescription>,
+}
+
+impl ColorManagementSurfaceCachedState {
+    /// The image description of the surface
+    ///
+    /// `None` if the client did not set one, in which case the content should be treated as sRGB.
+    /// The rendering intent is always perceptual, the only one supported.
+    pub fn image_description(&self) -> Option<&ImageDescription> {
+        self.image_description.as_ref()
+    }
+}
+
+impl Cacheable for ColorManagementSurfaceCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        self.clone()
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        *into = self;
+    }
+}
+
+/// Returns the color description of the surface, sRGB if no image description was set
+pub fn surface_color_description(states: &SurfaceData) -> ColorDescription {
+    // Avoid inserting the cached state for surfaces never using the protocol
+    if !states.cached_state.has::<ColorManagementSurfaceCachedState>() {
+        return ColorDescription::SRGB;
+    }
+    states
+        .cached_state
+        .current::<ColorManagementSurfaceCachedState>()
+        .image_description()
+        .map(|description| *description.color_description())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Default)]
+struct ColorManagementSurfaceData {
+    is_resource_attached: AtomicBool,
+    feedback: Mutex<SurfaceFeedback>,
+}
+
+#[derive(Debug, Default)]
+struct SurfaceFeedback {
+    preferred: Option<ImageDescription>,
+    instances: Vec<WpColorManagementSurfaceFeedbackV1>,
+}
+
+impl ColorManagementSurfaceData {
+    fn set_is_resource_attached(&self, is_attached: bool) {
+        self.is_resource_attached
+            .store(is_attached, atomic::Ordering::Release)
+    }
+
+    fn is_resource_attached(&self) -> bool {
+        self.is_resource_attached.load(atomic::Ordering::Acquire)
+    }
+}
+
+#[derive(Debug, Default)]
+struct OutputColorData(Mutex<OutputColor>);
+
+#[derive(Debug, Default)]
+struct OutputColor {
+    description: Option<ImageDescription>,
+    instances: Vec<WpColorManagementOutputV1>,
+}
+
+/// User data of `WpColorManagementOutputV1` object
+#[derive(Debug)]
+pub struct ColorManagementOutputUserData {
+    output: Option<WeakOutput>,
+}
+
+/// User data of `WpColorManagementSurfaceV1` object
+#[derive(Debug)]
+pub struct ColorManagementSurfaceUserData {
+    surface: Weak<WlSurface>,
+}
+
+/// User data of `WpColorManagementSurfaceFeedbackV1` object
+#[derive(Debug)]
+pub struct ColorManagementFeedbackUserData {
+    surface: Weak<WlSurface>,
+}
+
+/// User data of `WpImageDescriptionCreatorParamsV1` object
+#[derive(Debug, Default)]
+pub struct ImageDescriptionCreatorUserData(Mutex<dispatch::ParametricCreator>);
+
+/// User data of `WpImageDescriptionV1` object
+#[derive(Debug)]
+pub struct ImageDescriptionUserData {
+    // `None` if creating the image description failed
+    description: Option<ImageDescription>,
+    allow_information: bool,
+}
+
+impl ImageDescriptionUserData {
+    /// The image description of this object, `None` if it failed to become ready
+    pub fn image_description(&self) -> Option<&ImageDescription> {
+        self.description.as_ref()
+    }
+}
+
+/// Handler trait for color management
+pub trait ColorManagementHandler {
+    /// [`ColorManagementState`] getter
+    fn color_management_state(&mut self) -> &mut ColorManagementState;
+}
+
+/// Delegate type for [WpColorManagerV1] global.
+#[derive(Debug)]
+pub struct ColorManagementState {
+    global: GlobalId,
+    default_description: ImageDescription,
+}
+
+impl ColorManagementState {
+    /// Register new [WpColorManagerV1] global
+    pub fn new<D>(display: &DisplayHandle) -> ColorManagementState
+    where
+        D: GlobalDispatch<WpColorManagerV1, ()>
+            + Dispatch<WpColorManagerV1, ()>
+            + Dispatch<WpColorManagementOutputV1, ColorManagementOutputUserData>
+            + Dispatch<WpColorManagementSurfaceV1, ColorManagementSurfaceUserData>
+            + Dispatch<WpColorManagementSurfaceFeedbackV1, ColorManagementFeedbackUserData>
+            + Dispatch<WpImageDescriptionCreatorParamsV1, ImageDescriptionCreatorUserData>
+            + Dispatch<WpImageDescriptionV1, ImageDescriptionUserData>
+            + Dispatch<WpImageDescriptionInfoV1, ()>
+            + ColorManagementHandler
+            + 'static,
+    {
+        let global = display.create_global::<D, WpColorManagerV1, _>(1, ());
+
+        ColorManagementState {
+            global,
+            default_description: ImageDescription::new(ColorDescription::SRGB),
+        }
+    }
+
+    /// Returns the WpColorManagerV1 global id
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Returns the sRGB image description used, if no other was set
+    pub fn default_image_description(&self) -> &ImageDescription {
+        &self.default_description
+    }
+
+    /// Returns the image description of an output
+    pub fn output_image_description(&self, output: &Output) -> ImageDescription {
+        output
+            .user_data()
+            .get::<OutputColorData>()
+            .and_then(|data| data.0.lock().unwrap().description.clone())
+            .unwrap_or_else(|| self.default_description.clone())
+    }
+
+    /// Sets the image description of an output, i.e. the color encoding it expects
+    ///
+    /// Notifies clients, if the description changed.
+    pub fn set_output_image_description(&mut self, output: &Output, description: ImageDescription) {
+        output
+            .user_data()
+            .insert_if_missing_threadsafe(OutputColorData::default);
+        let mut data = output
+            .user_data()
+            .get::<OutputColorData>()
+            .unwrap()
+            .0
+            .lock()
+            .unwrap();
+        if data.description.as_ref() == Some(&description) {
+            return;
+        }
+        data.description = Some(description);
+
+        data.instances.retain(|instance| instance.is_alive());
+        let mut clients = Vec::new();
+        for instance in &data.instances {
+            instance.image_description_changed();
+            if let Some(client) = instance.client() {
+                if !clients.contains(&client) {
+                    clients.push(client);
+                }
+            }
+        }
+
+        // a client might have multiple instances per wl_output, but expects only one `done`
+        for client in clients {
+            for wl_output in output.client_outputs(&client) {
+                if wl_output.version() >= 2 {
+                    wl_output.done();
+                }
+            }
+        }
+    }
+
+    /// Returns the preferred image description of a surface
+    pub fn preferred_image_description(&self, surface: &WlSurface) -> ImageDescription {
+        compositor::with_states(surface, |states| {
+            states
+                .data_map
+                .get::<ColorManagementSurfaceData>()
+                .and_then(|data| data.feedback.lock().unwrap().preferred.clone())
+        })
+        .unwrap_or_else(|| self.default_description.clone())
+    }
+
+    /// Sets the preferred image description of a surface, e.g. the one of the output it is shown on
+    ///
+    /// Notifies the client, if the description changed.
+    pub fn set_preferred_image_description(&mut self, surface: &WlSurface, description: ImageDescription) {
+        compositor::with_states(surface, |states| {
+            states
+                .data_map
+                .insert_if_missing_threadsafe(ColorManagementSurfaceData::default);
+            let mut feedback = states
+                .data_map
+                .get::<ColorManagementSurfaceData>()
+                .unwrap()
+                .feedback
+                .lock()
+                .unwrap();
+            if feedback.preferred.as_ref() == Some(&description) {
+                return;
+            }
+
+            feedback.instances.retain(|instance| instance.is_alive());
+            for instance in &feedback.instances {
+                instance.preferred_changed(description.identity());
+            }
+            feedback.preferred = Some(description);
+        })
+    }
+}
+
+/// Macro to delegate implementation of the color management protocol
+#[macro_export]
+macro_rules! delegate_color_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        type __WpColorManagerV1 =
+            $crate::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_manager_v1::WpColorManagerV1;
+        type __WpColorManagementOutputV1 =
+            $crate::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_management_output_v1::WpColorManagementOutputV1;
+        type __WpColorManagementSurfaceV1 =
+            $crate::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_management_surface_v1::WpColorManagementSurfaceV1;
+        type __WpColorManagementSurfaceFeedbackV1 =
+            $crate::reexports::wayland_protocols::wp::color_management::v1::server::wp_color_management_surface_feedback_v1::WpColorManagementSurfaceFeedbackV1;
+        type __WpImageDescriptionCreatorParamsV1 =
+            $crate::reexports::wayland_protocols::wp::color_management::v1::server::wp_image_description_creator_params_v1::WpImageDescriptionCreatorParamsV1;
+        type __WpImageDescriptionV1 =
+            $crate::reexports::wayland_protocols::wp::color_management::v1::server::wp_image_description_v1::WpImageDescriptionV1;
+        type __WpImageDescriptionInfoV1 =
+            $crate::reexports::wayland_protocols::wp::color_management::v1::server::wp_image_description_info_v1::WpImageDescriptionInfoV1;
+
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __WpColorManagerV1: ()
+            ] => $crate::wayland::color_management::ColorManagementState
+        );
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __WpColorManagerV1: ()
+            ] => $crate::wayland::color_management::ColorManagementState
+        );
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __WpColorManagementOutputV1: $crate::wayland::color_management::ColorManagementOutputUserData
+            ] => $crate::wayland::color_management::ColorManagementState
+        );
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __WpColorManagementSurfaceV1: $crate::wayland::color_management::ColorManagementSurfaceUserData
+            ] => $crate::wayland::color_management::ColorManagementState
+        );
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __WpColorManagementSurfaceFeedbackV1: $crate::wayland::color_management::ColorManagementFeedbackUserData
+            ] => $crate::wayland::color_management::ColorManagementState
+        );
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __WpImageDescriptionCreatorParamsV1: $crate::wayland::color_management::ImageDescriptionCreatorUserData
+            ] => $crate::wayland::color_management::ColorManagementState
+        );
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __WpImageDescriptionV1: $crate::wayland::color_management::ImageDescriptionUserData
+            ] => $crate::wayland::color_management::ColorManagementState
+        );
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                __WpImageDescriptionInfoV1: ()
+            ] => $crate::wayland::color_management::ColorManagementState
+        );
+    };
+}
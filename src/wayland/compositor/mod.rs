@@ -111,7 +111,13 @@ mod transaction;
 mod tree;
 
 use std::cell::RefCell;
-use std::{any::Any, sync::Mutex};
+use std::{
+    any::Any,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
 
 pub use self::cache::{Cacheable, MultiCache};
 pub use self::handlers::{RegionUserData, SubsurfaceCachedState, SubsurfaceUserData, SurfaceUserData};
@@ -486,13 +492,25 @@ where
     PrivateSurfaceData::add_post_commit_hook(surface, hook)
 }
 
+/// Identifier of a hook added to a surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HookId(usize);
+
+impl HookId {
+    fn next() -> HookId {
+        static NEXT_HOOK_ID: AtomicUsize = AtomicUsize::new(0);
+        HookId(NEXT_HOOK_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
 /// Register a destruction hook to be invoked on surface destruction
 ///
 /// It'll be invoked when the surface is destroyed (either explicitly by the client or on
 /// client disconnect).
+/// The returned [`HookId`] can be used to remove the hook again with [`remove_destruction_hook`].
 ///
 /// D generic is the compositor state, same as used in `CompositorState::new<D>()`
-pub fn add_destruction_hook<D, F>(surface: &WlSurface, hook: F)
+pub fn add_destruction_hook<D, F>(surface: &WlSurface, hook: F) -> HookId
 where
     F: Fn(&mut D, &SurfaceData) + Send + 'static,
     D: 'static,
@@ -513,6 +531,13 @@ where
     PrivateSurfaceData::add_destruction_hook(surface, hook)
 }
 
+/// Unregister a destruction hook previously added with [`add_destruction_hook`]
+///
+/// Must not be called from within a destruction hook of the same surface.
+pub fn remove_destruction_hook(surface: &WlSurface, hook_id: HookId) {
+    PrivateSurfaceData::remove_destruction_hook(surface, hook_id)
+}
+
 /// Adds a blocker for the currently queued up state changes of the given surface.
 ///
 /// Blockers will delay the pending state to be applied on the next commit until
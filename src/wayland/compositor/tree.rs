@@ -4,7 +4,7 @@ use super::{
     cache::MultiCache,
     handlers::{is_effectively_sync, SurfaceUserData},
     transaction::{Blocker, PendingTransaction, TransactionQueue},
-    BufferAssignment, CompositorHandler, SurfaceAttributes, SurfaceData,
+    BufferAssignment, CompositorHandler, HookId, SurfaceAttributes, SurfaceData,
 };
 use std::{
     any::Any,
@@ -41,7 +41,7 @@ pub struct PrivateSurfaceData {
     current_txid: Serial,
     pre_commit_hooks: Vec<Arc<Box<CommitHook>>>,
     post_commit_hooks: Vec<Arc<Box<CommitHook>>>,
-    destruction_hooks: Vec<Box<DestructionHook>>,
+    destruction_hooks: Vec<(HookId, Box<DestructionHook>)>,
 }
 
 impl fmt::Debug for PrivateSurfaceData {
@@ -158,7 +158,7 @@ impl PrivateSurfaceData {
             buffer.release();
         };
 
-        for hook in &my_data.destruction_hooks {
+        for (_, hook) in &my_data.destruction_hooks {
             hook(state, &my_data.public_data)
         }
     }
@@ -212,10 +212,18 @@ impl PrivateSurfaceData {
     pub fn add_destruction_hook(
         surface: &WlSurface,
         hook: impl Fn(&mut dyn Any, &SurfaceData) + Send + 'static,
-    ) {
+    ) -> HookId {
+        let my_data_mutex = &surface.data::<SurfaceUserData>().unwrap().inner;
+        let mut my_data = my_data_mutex.lock().unwrap();
+        let id = HookId::next();
+        my_data.destruction_hooks.push((id, Box::new(hook)));
+        id
+    }
+
+    pub fn remove_destruction_hook(surface: &WlSurface, hook_id: HookId) {
         let my_data_mutex = &surface.data::<SurfaceUserData>().unwrap().inner;
         let mut my_data = my_data_mutex.lock().unwrap();
-        my_data.destruction_hooks.push(Box::new(hook));
+        my_data.destruction_hooks.retain(|(id, _)| *id != hook_id);
     }
 
     pub fn invoke_pre_commit_hooks<D: 'static>(state: &mut D, dh: &DisplayHandle, surface: &WlSurface) {
@@ -0,0 +1,478 @@
+//! Utilities for handling the `linux-drm-syncobj-v1` protocol
+//!
+//! This protocol allows clients to use explicit synchronization for their buffers,
+//! by attaching an acquire and a release point of a DRM syncobj timeline to each commit.
+//! The compositor must not access the buffer before the acquire point is signaled and
+//! signals the release point, once it is done using the buffer.
+//!
+//! The points of the current commit are stored in [`DrmSyncobjCachedState`].
+//! If you are using [`on_commit_buffer_handler`](crate::backend::renderer::utils::on_commit_buffer_handler),
+//! the points are attached to the [`Buffer`](crate::backend::renderer::utils::Buffer) of the surface
+//! and the release point is signaled automatically once the buffer is released. Sync points of
+//! frames still reading from the buffer can be registered with
+//! [`add_release_sync_point_surface_tree`](crate::backend::renderer::utils::add_release_sync_point_surface_tree).
+//!
+//! Compositors are expected to delay commits until the acquire point is signaled
+//! by adding a [`Blocker`](crate::wayland::compositor::Blocker) through [`DrmSyncPoint::generate_blocker`].
+//!
+//! ## How to use it
+//!
+//! ### Initialization
+//!
+//! ```no_run
+//! use smithay::delegate_drm_syncobj;
+//! use smithay::wayland::drm_syncobj::{supports_syncobj_eventfd, DrmSyncobjHandler, DrmSyncobjState};
+//! # use smithay::backend::drm::DrmDeviceFd;
+//!
+//! # struct State { drm_syncobj_state: DrmSyncobjState }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! # let device: DrmDeviceFd = todo!();
+//! // the protocol can only be supported, if commits can be blocked on the acquire point
+//! if supports_syncobj_eventfd(&device) {
+//!     let drm_syncobj_state = DrmSyncobjState::new::<State>(&display.handle(), device);
+//! }
+//!
+//! impl DrmSyncobjHandler for State {
+//!     fn drm_syncobj_state(&mut self) -> &mut DrmSyncobjState {
+//!         &mut self.drm_syncobj_state
+//!     }
+//! }
+//! delegate_drm_syncobj!(State);
+//! ```
+//!
+//! ### Blocking on the acquire point
+//!
+//! ```no_run
+//! # use smithay::reexports::{calloop::LoopHandle, wayland_server::{protocol::wl_surface::WlSurface, Client, Resource}};
+//! use smithay::wayland::compositor::{self, add_blocker, CompositorClientState, CompositorHandler, CompositorState};
+//! use smithay::wayland::drm_syncobj::DrmSyncobjCachedState;
+//!
+//! # struct State { handle: LoopHandle<'static, State> }
+//! impl CompositorHandler for State {
+//! #   fn compositor_state(&mut self) -> &mut CompositorState { todo!() }
+//! #   fn client_compositor_state<'a>(&self, client: &'a Client) -> &'a CompositorClientState { todo!() }
+//! #   fn commit(&mut self, surface: &WlSurface) {}
+//!     fn new_surface(&mut self, surface: &WlSurface) {
+//!         compositor::add_pre_commit_hook::<State, _>(surface, |state, dh, surface| {
+//!             let acquire_point = compositor::with_states(surface, |states| {
+//!                 states.cached_state.pending::<DrmSyncobjCachedState>().acquire_point.clone()
+//!             });
+//!             if let Some(acquire_point) = acquire_point {
+//!                 if let Ok((blocker, source)) = acquire_point.generate_blocker() {
+//!                     let client = surface.client().unwrap();
+//!                     let dh = dh.clone();
+//!                     let res = state.handle.insert_source(source, move |_, _, state| {
+//!                         state
+//!                             .client_compositor_state(&client)
+//!                             .blocker_cleared(state, &dh);
+//!                         Ok(())
+//!                     });
+//!                     if res.is_ok() {
+//!                         add_blocker(surface, blocker);
+//!                     }
+//!                 }
+//!             }
+//!         });
+//!     }
+//! }
+//! ```
+
+use std::{os::unix::io::AsFd, sync::Mutex};
+
+use wayland_protocols::wp::linux_drm_syncobj::v1::server::{
+    wp_linux_drm_syncobj_manager_v1::{self, WpLinuxDrmSyncobjManagerV1},
+    wp_linux_drm_syncobj_surface_v1::{self, WpLinuxDrmSyncobjSurfaceV1},
+    wp_linux_drm_syncobj_timeline_v1::{self, WpLinuxDrmSyncobjTimelineV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    protocol::wl_surface::WlSurface,
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use super::{
+    compositor::{self, BufferAssignment, Cacheable, SurfaceAttributes},
+    dmabuf::get_dmabuf,
+};
+use crate::backend::drm::DrmDeviceFd;
+
+mod sync_point;
+pub use sync_point::*;
+
+/// Handler trait for linux-drm-syncobj
+pub trait DrmSyncobjHandler {
+    /// [`DrmSyncobjState`] getter
+    fn drm_syncobj_state(&mut self) -> &mut DrmSyncobjState;
+}
+
+/// Acquire and release points of a commit
+///
+/// ```no_run
+/// use smithay::wayland::compositor;
+/// use smithay::wayland::drm_syncobj::DrmSyncobjCachedState;
+///
+/// # let wl_surface = todo!();
+/// compositor::with_states(&wl_surface, |states| {
+///     let current = states.cached_state.current::<DrmSyncobjCachedState>();
+///     dbg!(&current.acquire_point);
+/// });
+/// ```
+#[derive(Debug, Default)]
+pub struct DrmSyncobjCachedState {
+    /// Timeline point, that has to be signaled before accessing the buffer
+    pub acquire_point: Option<DrmSyncPoint>,
+    /// Timeline point to signal, once the buffer is not used anymore
+    pub release_point: Option<DrmSyncPoint>,
+}
+
+impl Cacheable for DrmSyncobjCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        Self {
+            acquire_point: self.acquire_point.take(),
+            release_point: self.release_point.take(),
+        }
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        // points are only set together with a new buffer
+        if self.acquire_point.is_none() && self.release_point.is_none() {
+            return;
+        }
+
+        // the previous buffer was replaced before being used
+        if let Some(release_point) = into.release_point.take() {
+            if Some(&release_point) != self.release_point.as_ref() {
+                release_point.signal_after(Vec::new());
+            }
+        }
+        *into = self;
+    }
+}
+
+#[derive(Debug, Default)]
+struct DrmSyncobjSurfaceData {
+    resource: Mutex<Option<WpLinuxDrmSyncobjSurfaceV1>>,
+}
+
+/// User data of `WpLinuxDrmSyncobjSurfaceV1` object
+#[derive(Debug)]
+pub struct DrmSyncobjSurfaceUserData {
+    surface: WlSurface,
+}
+
+/// User data of `WpLinuxDrmSyncobjTimelineV1` object
+#[derive(Debug)]
+pub struct DrmSyncobjTimelineUserData {
+    timeline: DrmTimeline,
+}
+
+/// State of the linux-drm-syncobj global
+#[derive(Debug)]
+pub struct DrmSyncobjState {
+    global: GlobalId,
+    import_device: DrmDeviceFd,
+}
+
+impl DrmSyncobjState {
+    /// Create a new [`WpLinuxDrmSyncobjManagerV1`] global
+    ///
+    /// The `import_device` is used to import timelines of clients. It is recommended
+    /// to check for [`supports_syncobj_eventfd`] before exposing the global.
+    pub fn new<D>(display: &DisplayHandle, import_device: DrmDeviceFd) -> Self
+    where
+        D: GlobalDispatch<WpLinuxDrmSyncobjManagerV1, ()>,
+        D: Dispatch<WpLinuxDrmSyncobjManagerV1, ()>,
+        D: Dispatch<WpLinuxDrmSyncobjSurfaceV1, DrmSyncobjSurfaceUserData>,
+        D: Dispatch<WpLinuxDrmSyncobjTimelineV1, DrmSyncobjTimelineUserData>,
+        D: DrmSyncobjHandler,
+        D: 'static,
+    {
+        let global = display.create_global::<D, WpLinuxDrmSyncobjManagerV1, _>(1, ());
+
+        Self {
+            global,
+            import_device,
+        }
+    }
+
+    /// Returns the id of the [`WpLinuxDrmSyncobjManagerV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+// Validates the pending points against the attached buffer
+fn commit_hook(surface: &WlSurface) {
+    compositor::with_states(surface, |states| {
+        let Some(data) = states.data_map.get::<DrmSyncobjSurfaceData>() else {
+            return;
+        };
+        let Some(resource) = data.resource.lock().unwrap().clone() else {
+            return;
+        };
+
+        let buffer = match &states.cached_state.pending::<SurfaceAttributes>().buffer {
+            Some(BufferAssignment::NewBuffer(buffer)) => Some(buffer.clone()),
+            _ => None,
+        };
+        let cached_state = states.cached_state.pending::<DrmSyncobjCachedState>();
+
+        if let Some(buffer) = buffer {
+            if get_dmabuf(&buffer).is_err() {
+                resource.post_error(
+                    wp_linux_drm_syncobj_surface_v1::Error::UnsupportedBuffer,
+                    "Explicit sync is only supported for dmabuf buffers",
+                );
+                return;
+            }
+            let Some(acquire_point) = &cached_state.acquire_point else {
+                resource.post_error(
+                    wp_linux_drm_syncobj_surface_v1::Error::NoAcquirePoint,
+                    "No acquire point set",
+                );
+                return;
+            };
+            let Some(release_point) = &cached_state.release_point else {
+                resource.post_error(
+                    wp_linux_drm_syncobj_surface_v1::Error::NoReleasePoint,
+                    "No release point set",
+                );
+                return;
+            };
+            if acquire_point.timeline == release_point.timeline && acquire_point.point >= release_point.point
+            {
+                resource.post_error(
+                    wp_linux_drm_syncobj_surface_v1::Error::ConflictingPoints,
+                    "Release point must be greater than the acquire point on the same timeline",
+                );
+            }
+        } else if cached_state.acquire_point.is_some() || cached_state.release_point.is_some() {
+            resource.post_error(
+                wp_linux_drm_syncobj_surface_v1::Error::NoBuffer,
+                "Sync points set without a buffer",
+            );
+        }
+    });
+}
+
+impl<D> GlobalDispatch<WpLinuxDrmSyncobjManagerV1, (), D> for DrmSyncobjState
+where
+    D: GlobalDispatch<WpLinuxDrmSyncobjManagerV1, ()>,
+    D: Dispatch<WpLinuxDrmSyncobjManagerV1, ()>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        resource: New<WpLinuxDrmSyncobjManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> Dispatch<WpLinuxDrmSyncobjManagerV1, (), D> for DrmSyncobjState
+where
+    D: Dispatch<WpLinuxDrmSyncobjManagerV1, ()>,
+    D: Dispatch<WpLinuxDrmSyncobjSurfaceV1, DrmSyncobjSurfaceUserData>,
+    D: Dispatch<WpLinuxDrmSyncobjTimelineV1, DrmSyncobjTimelineUserData>,
+    D: DrmSyncobjHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        manager: &WpLinuxDrmSyncobjManagerV1,
+        request: wp_linux_drm_syncobj_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_linux_drm_syncobj_manager_v1::Request::GetSurface { id, surface } => {
+                let (already_taken, new_data) = compositor::with_states(&surface, |states| {
+                    let new_data = states
+                        .data_map
+                        .insert_if_missing_threadsafe(DrmSyncobjSurfaceData::default);
+                    let data = states.data_map.get::<DrmSyncobjSurfaceData>().unwrap();
+                    let already_taken = data.resource.lock().unwrap().is_some();
+                    (already_taken, new_data)
+                });
+
+                if already_taken {
+                    manager.post_error(
+                        wp_linux_drm_syncobj_manager_v1::Error::SurfaceExists,
+                        "WlSurface already has WpLinuxDrmSyncobjSurfaceV1 attached",
+                    );
+                    return;
+                }
+
+                if new_data {
+                    compositor::add_pre_commit_hook::<D, _>(&surface, |_state, _dh, surface| {
+                        commit_hook(surface)
+                    });
+                }
+
+                let resource = data_init.init(
+                    id,
+                    DrmSyncobjSurfaceUserData {
+                        surface: surface.clone(),
+                    },
+                );
+                compositor::with_states(&surface, |states| {
+                    let data = states.data_map.get::<DrmSyncobjSurfaceData>().unwrap();
+                    *data.resource.lock().unwrap() = Some(resource);
+                });
+            }
+            wp_linux_drm_syncobj_manager_v1::Request::ImportTimeline { id, fd } => {
+                match DrmTimeline::new(&state.drm_syncobj_state().import_device, fd.as_fd()) {
+                    Ok(timeline) => {
+                        data_init.init(id, DrmSyncobjTimelineUserData { timeline });
+                    }
+                    Err(err) => {
+                        manager.post_error(
+                            wp_linux_drm_syncobj_manager_v1::Error::InvalidTimeline,
+                            format!("Failed to import syncobj timeline: {}", err),
+                        );
+                    }
+                }
+            }
+            wp_linux_drm_syncobj_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpLinuxDrmSyncobjTimelineV1, DrmSyncobjTimelineUserData, D> for DrmSyncobjState
+where
+    D: Dispatch<WpLinuxDrmSyncobjTimelineV1, DrmSyncobjTimelineUserData>,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &WpLinuxDrmSyncobjTimelineV1,
+        request: wp_linux_drm_syncobj_timeline_v1::Request,
+        _data: &DrmSyncobjTimelineUserData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            // points already set keep a reference to the timeline
+            wp_linux_drm_syncobj_timeline_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WpLinuxDrmSyncobjSurfaceV1, DrmSyncobjSurfaceUserData, D> for DrmSyncobjState
+where
+    D: Dispatch<WpLinuxDrmSyncobjSurfaceV1, DrmSyncobjSurfaceUserData>,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &WpLinuxDrmSyncobjSurfaceV1,
+        request: wp_linux_drm_syncobj_surface_v1::Request,
+        data: &DrmSyncobjSurfaceUserData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_linux_drm_syncobj_surface_v1::Request::SetAcquirePoint {
+                timeline,
+                point_hi,
+                point_lo,
+            } => {
+                let Some(sync_point) = sync_point(resource, data, &timeline, point_hi, point_lo) else {
+                    return;
+                };
+                compositor::with_states(&data.surface, |states| {
+                    states
+                        .cached_state
+                        .pending::<DrmSyncobjCachedState>()
+                        .acquire_point = Some(sync_point);
+                });
+            }
+            wp_linux_drm_syncobj_surface_v1::Request::SetReleasePoint {
+                timeline,
+                point_hi,
+                point_lo,
+            } => {
+                let Some(sync_point) = sync_point(resource, data, &timeline, point_hi, point_lo) else {
+                    return;
+                };
+                compositor::with_states(&data.surface, |states| {
+                    states
+                        .cached_state
+                        .pending::<DrmSyncobjCachedState>()
+                        .release_point = Some(sync_point);
+                });
+            }
+            wp_linux_drm_syncobj_surface_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        _resource: &WpLinuxDrmSyncobjSurfaceV1,
+        data: &DrmSyncobjSurfaceUserData,
+    ) {
+        if !data.surface.is_alive() {
+            return;
+        }
+        compositor::with_states(&data.surface, |states| {
+            if let Some(data) = states.data_map.get::<DrmSyncobjSurfaceData>() {
+                data.resource.lock().unwrap().take();
+            }
+            // points set since the last commit are discarded
+            *states.cached_state.pending::<DrmSyncobjCachedState>() = Default::default();
+        });
+    }
+}
+
+fn sync_point(
+    resource: &WpLinuxDrmSyncobjSurfaceV1,
+    data: &DrmSyncobjSurfaceUserData,
+    timeline: &WpLinuxDrmSyncobjTimelineV1,
+    point_hi: u32,
+    point_lo: u32,
+) -> Option<DrmSyncPoint> {
+    if !data.surface.is_alive() {
+        resource.post_error(
+            wp_linux_drm_syncobj_surface_v1::Error::NoSurface,
+            "The associated wl_surface was destroyed",
+        );
+        return None;
+    }
+    let timeline = timeline.data::<DrmSyncobjTimelineUserData>()?.timeline.clone();
+    Some(DrmSyncPoint {
+        timeline,
+        point: ((point_hi as u64) << 32) | point_lo as u64,
+    })
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_drm_syncobj {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::linux_drm_syncobj::v1::server::wp_linux_drm_syncobj_manager_v1::WpLinuxDrmSyncobjManagerV1: ()
+        ] => $crate::wayland::drm_syncobj::DrmSyncobjState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::linux_drm_syncobj::v1::server::wp_linux_drm_syncobj_manager_v1::WpLinuxDrmSyncobjManagerV1: ()
+        ] => $crate::wayland::drm_syncobj::DrmSyncobjState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::linux_drm_syncobj::v1::server::wp_linux_drm_syncobj_surface_v1::WpLinuxDrmSyncobjSurfaceV1: $crate::wayland::drm_syncobj::DrmSyncobjSurfaceUserData
+        ] => $crate::wayland::drm_syncobj::DrmSyncobjState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::linux_drm_syncobj::v1::server::wp_linux_drm_syncobj_timeline_v1::WpLinuxDrmSyncobjTimelineV1: $crate::wayland::drm_syncobj::DrmSyncobjTimelineUserData
+        ] => $crate::wayland::drm_syncobj::DrmSyncobjState);
+    };
+}
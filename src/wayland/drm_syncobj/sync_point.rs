@@ -0,0 +1,433 @@
+use std::{
+    io,
+    os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use calloop::{generic::Generic, EventSource, Interest, Mode, PostAction};
+use drm::control::{syncobj, Device as ControlDevice};
+use tracing::warn;
+
+use crate::{
+    backend::{
+        drm::DrmDeviceFd,
+        renderer::sync::{Fence, SyncPoint},
+    },
+    wayland::compositor::{Blocker, BlockerState},
+};
+
+// struct drm_syncobj_eventfd { __u32 handle; __u32 flags; __u64 point; __s32 fd; __u32 pad; }
+#[repr(C)]
+struct DrmSyncobjEventfd {
+    handle: u32,
+    flags: u32,
+    point: u64,
+    fd: i32,
+    pad: u32,
+}
+
+// DRM_IOWR(0xCF, struct drm_syncobj_eventfd)
+const DRM_IOCTL_SYNCOBJ_EVENTFD: libc::c_ulong = 0xC018_64CF;
+
+fn syncobj_eventfd(
+    device: BorrowedFd<'_>,
+    handle: u32,
+    point: u64,
+    eventfd: BorrowedFd<'_>,
+) -> io::Result<()> {
+    let mut args = DrmSyncobjEventfd {
+        handle,
+        flags: 0,
+        point,
+        fd: eventfd.as_raw_fd(),
+        pad: 0,
+    };
+    loop {
+        let res = unsafe {
+            libc::ioctl(
+                device.as_raw_fd(),
+                DRM_IOCTL_SYNCOBJ_EVENTFD as _,
+                &mut args as *mut DrmSyncobjEventfd,
+            )
+        };
+        if res == 0 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        if !matches!(err.raw_os_error(), Some(libc::EINTR) | Some(libc::EAGAIN)) {
+            return Err(err);
+        }
+    }
+}
+
+// DRM_IOWR(0xC2, struct drm_syncobj_handle)
+const DRM_IOCTL_SYNCOBJ_FD_TO_HANDLE: libc::c_ulong = 0xC010_64C2;
+
+// `drm_ffi::syncobj::fd_to_handle` always creates a new handle,
+// importing a sync file however requires an existing syncobj to attach the fence to.
+fn syncobj_import_sync_file(
+    device: BorrowedFd<'_>,
+    handle: u32,
+    sync_file: BorrowedFd<'_>,
+) -> io::Result<()> {
+    let mut args = drm_ffi::drm_syncobj_handle {
+        handle,
+        flags: drm_ffi::DRM_SYNCOBJ_FD_TO_HANDLE_FLAGS_IMPORT_SYNC_FILE,
+        fd: sync_file.as_raw_fd(),
+        pad: 0,
+    };
+    loop {
+        let res = unsafe {
+            libc::ioctl(
+                device.as_raw_fd(),
+                DRM_IOCTL_SYNCOBJ_FD_TO_HANDLE as _,
+                &mut args as *mut drm_ffi::drm_syncobj_handle,
+            )
+        };
+        if res == 0 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        if !matches!(err.raw_os_error(), Some(libc::EINTR) | Some(libc::EAGAIN)) {
+            return Err(err);
+        }
+    }
+}
+
+// struct sync_merge_data { char name[32]; __s32 fd2; __s32 fence; __u32 flags; __u32 pad; }
+#[repr(C)]
+struct SyncMergeData {
+    name: [u8; 32],
+    fd2: i32,
+    fence: i32,
+    flags: u32,
+    pad: u32,
+}
+
+// _IOWR('>', 3, struct sync_merge_data)
+const SYNC_IOC_MERGE: libc::c_ulong = 0xC030_3E03;
+
+fn sync_file_merge(first: BorrowedFd<'_>, second: BorrowedFd<'_>) -> io::Result<OwnedFd> {
+    let mut args = SyncMergeData {
+        name: [0; 32],
+        fd2: second.as_raw_fd(),
+        fence: -1,
+        flags: 0,
+        pad: 0,
+    };
+    args.name[..15].copy_from_slice(b"smithay-release");
+    loop {
+        let res = unsafe {
+            libc::ioctl(
+                first.as_raw_fd(),
+                SYNC_IOC_MERGE as _,
+                &mut args as *mut SyncMergeData,
+            )
+        };
+        if res == 0 {
+            // SAFETY: on success the kernel returns a newly created sync file in `fence`
+            return Ok(unsafe { OwnedFd::from_raw_fd(args.fence) });
+        }
+        let err = io::Error::last_os_error();
+        if !matches!(err.raw_os_error(), Some(libc::EINTR) | Some(libc::EAGAIN)) {
+            return Err(err);
+        }
+    }
+}
+
+/// Checks if the kernel supports waiting on syncobj timeline points through an eventfd.
+///
+/// This is required for [`DrmSyncPoint::generate_blocker`] and was added in Linux 6.6.
+pub fn supports_syncobj_eventfd(device: &DrmDeviceFd) -> bool {
+    // Querying an invalid handle fails with `ENOENT` if the ioctl is supported,
+    // older kernels will fail the unknown ioctl with a different error.
+    match syncobj_eventfd(device.as_fd(), 0, 0, device.as_fd()) {
+        Ok(_) => unreachable!(),
+        Err(err) => err.kind() == io::ErrorKind::NotFound,
+    }
+}
+
+fn io_error(err: drm::SystemError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err)
+}
+
+#[derive(Debug)]
+struct InnerTimeline {
+    device: DrmDeviceFd,
+    syncobj: syncobj::Handle,
+}
+
+impl Drop for InnerTimeline {
+    fn drop(&mut self) {
+        if let Err(err) = self.device.destroy_syncobj(self.syncobj) {
+            warn!(?err, "Failed to destroy syncobj");
+        }
+    }
+}
+
+/// DRM syncobj timeline imported from a client
+#[derive(Debug, Clone)]
+pub struct DrmTimeline(Arc<InnerTimeline>);
+
+impl PartialEq for DrmTimeline {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl DrmTimeline {
+    /// Import a syncobj timeline from a file descriptor
+    pub fn new(device: &DrmDeviceFd, fd: BorrowedFd<'_>) -> io::Result<Self> {
+        let syncobj = device.fd_to_syncobj(fd.as_raw_fd(), false).map_err(io_error)?;
+        Ok(Self(Arc::new(InnerTimeline {
+            device: device.clone(),
+            syncobj,
+        })))
+    }
+
+    /// Query the last signaled point of the timeline
+    pub fn query_signaled_point(&self) -> io::Result<u64> {
+        let mut points = [0];
+        self.0
+            .device
+            .syncobj_timeline_query(&[self.0.syncobj], &mut points, false)
+            .map_err(io_error)?;
+        Ok(points[0])
+    }
+}
+
+/// Point on a [`DrmTimeline`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrmSyncPoint {
+    /// Timeline of the point
+    pub timeline: DrmTimeline,
+    /// Value of the point on the timeline
+    pub point: u64,
+}
+
+impl DrmSyncPoint {
+    /// Signal the point on the timeline
+    pub fn signal(&self) -> io::Result<()> {
+        self.timeline
+            .0
+            .device
+            .syncobj_timeline_signal(&[self.timeline.0.syncobj], &[self.point])
+            .map_err(io_error)?;
+        Ok(())
+    }
+
+    /// Returns whether the point was already signaled
+    pub fn is_signaled(&self) -> io::Result<bool> {
+        Ok(self.timeline.query_signaled_point()? >= self.point)
+    }
+
+    /// Blocks until the point is signaled or the timeout (in absolute `CLOCK_MONOTONIC` nanoseconds) expires.
+    pub fn wait(&self, timeout_nsec: i64) -> io::Result<()> {
+        self.timeline
+            .0
+            .device
+            .syncobj_timeline_wait(
+                &[self.timeline.0.syncobj],
+                &[self.point],
+                timeout_nsec,
+                true,
+                true,
+                false,
+            )
+            .map_err(io_error)?;
+        Ok(())
+    }
+
+    /// Export the fence of the point as a sync file.
+    ///
+    /// This fails, if no fence was submitted for the point yet.
+    pub fn export_sync_file(&self) -> io::Result<OwnedFd> {
+        let device = &self.timeline.0.device;
+        let syncobj = device.create_syncobj(false).map_err(io_error)?;
+        let res = device
+            .syncobj_timeline_transfer(self.timeline.0.syncobj, syncobj, self.point, 0)
+            .and_then(|_| drm_ffi::syncobj::handle_to_fd(device.as_raw_fd(), syncobj.into(), true))
+            .map_err(io_error)
+            // SAFETY: on success the kernel returns a newly created sync file in `fd`
+            .map(|args| unsafe { OwnedFd::from_raw_fd(args.fd) });
+        let _ = device.destroy_syncobj(syncobj);
+        res
+    }
+
+    /// Attach the fence of a sync file to the point.
+    ///
+    /// The point gets signaled once the fence is signaled.
+    pub fn import_sync_file(&self, sync_file: BorrowedFd<'_>) -> io::Result<()> {
+        let device = &self.timeline.0.device;
+        let syncobj = device.create_syncobj(false).map_err(io_error)?;
+        let res = syncobj_import_sync_file(device.as_fd(), syncobj.into(), sync_file).and_then(|_| {
+            device
+                .syncobj_timeline_transfer(syncobj, self.timeline.0.syncobj, 0, self.point)
+                .map_err(io_error)
+        });
+        let _ = device.destroy_syncobj(syncobj);
+        res
+    }
+
+    /// Create an eventfd, that becomes readable once the point is signaled
+    pub fn eventfd(&self) -> io::Result<OwnedFd> {
+        let fd = rustix::event::eventfd(
+            0,
+            rustix::event::EventfdFlags::CLOEXEC | rustix::event::EventfdFlags::NONBLOCK,
+        )?;
+        syncobj_eventfd(
+            self.timeline.0.device.as_fd(),
+            self.timeline.0.syncobj.into(),
+            self.point,
+            fd.as_fd(),
+        )?;
+        Ok(fd)
+    }
+
+    /// Generate a [`Blocker`] and an event source, that clears it once the point is signaled.
+    ///
+    /// The event source needs to be inserted into the event loop, where the callback is
+    /// expected to call [`CompositorClientState::blocker_cleared`](crate::wayland::compositor::CompositorClientState::blocker_cleared).
+    ///
+    /// Requires [`supports_syncobj_eventfd`].
+    pub fn generate_blocker(&self) -> io::Result<(DrmSyncPointBlocker, DrmSyncPointSource)> {
+        let fd = self.eventfd()?;
+        let signal = Arc::new(AtomicBool::new(false));
+        let blocker = DrmSyncPointBlocker(signal.clone());
+        let source = DrmSyncPointSource {
+            source: Generic::new(fd, Interest::READ, Mode::OneShot),
+            signal,
+        };
+        Ok((blocker, source))
+    }
+
+    /// Signal the point once all given sync points are reached.
+    ///
+    /// Does not block, the fences of pending sync points are merged and attached to the point
+    /// (see [`DrmSyncPoint::import_sync_file`]). If that fails, e.g. because a fence isn't exportable,
+    /// the point is signaled right away.
+    pub fn signal_after(self, sync_points: Vec<SyncPoint>) {
+        let pending = sync_points
+            .into_iter()
+            .filter(|sync| !sync.is_reached())
+            .collect::<Vec<_>>();
+
+        if !pending.is_empty() {
+            match self.import_sync_points(&pending) {
+                Ok(()) => return,
+                Err(err) => warn!(?err, "Failed to attach fences to syncobj release point"),
+            }
+        }
+
+        if let Err(err) = self.signal() {
+            warn!(?err, "Failed to signal syncobj release point");
+        }
+    }
+
+    fn import_sync_points(&self, sync_points: &[SyncPoint]) -> io::Result<()> {
+        let mut merged: Option<OwnedFd> = None;
+        for sync in sync_points {
+            let fd = sync
+                .export()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "fence is not exportable"))?;
+            merged = Some(match merged {
+                Some(merged) => sync_file_merge(merged.as_fd(), fd.as_fd())?,
+                None => fd,
+            });
+        }
+        match merged {
+            Some(sync_file) => self.import_sync_file(sync_file.as_fd()),
+            None => self.signal(),
+        }
+    }
+}
+
+impl Fence for DrmSyncPoint {
+    fn is_signaled(&self) -> bool {
+        DrmSyncPoint::is_signaled(self).unwrap_or(true)
+    }
+
+    fn wait(&self) {
+        if let Err(err) = DrmSyncPoint::wait(self, i64::MAX) {
+            warn!(?err, "Failed to wait for syncobj point");
+        }
+    }
+
+    fn is_exportable(&self) -> bool {
+        true
+    }
+
+    fn export(&self) -> Option<OwnedFd> {
+        self.export_sync_file().ok()
+    }
+}
+
+/// [`Blocker`] implementation for an accompaning [`DrmSyncPointSource`]
+#[derive(Debug)]
+pub struct DrmSyncPointBlocker(Arc<AtomicBool>);
+
+impl Blocker for DrmSyncPointBlocker {
+    fn state(&self) -> BlockerState {
+        if self.0.load(Ordering::SeqCst) {
+            BlockerState::Released
+        } else {
+            BlockerState::Pending
+        }
+    }
+}
+
+/// Event source monitoring a [`DrmSyncPoint`].
+///
+/// The event source is a one shot event source and will remove itself from the event loop after being triggered once.
+#[derive(Debug)]
+pub struct DrmSyncPointSource {
+    source: Generic<OwnedFd, io::Error>,
+    signal: Arc<AtomicBool>,
+}
+
+impl EventSource for DrmSyncPointSource {
+    type Event = ();
+    type Metadata = ();
+    type Ret = Result<(), io::Error>;
+
+    type Error = io::Error;
+
+    fn process_events<F>(
+        &mut self,
+        readiness: calloop::Readiness,
+        token: calloop::Token,
+        mut callback: F,
+    ) -> Result<PostAction, Self::Error>
+    where
+        F: FnMut(Self::Event, &mut Self::Metadata) -> Self::Ret,
+    {
+        self.source.process_events(readiness, token, |_, _| {
+            self.signal.store(true, Ordering::SeqCst);
+            callback((), &mut ())?;
+            Ok(PostAction::Remove)
+        })
+    }
+
+    fn register(
+        &mut self,
+        poll: &mut calloop::Poll,
+        token_factory: &mut calloop::TokenFactory,
+    ) -> calloop::Result<()> {
+        self.source.register(poll, token_factory)
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &mut calloop::Poll,
+        token_factory: &mut calloop::TokenFactory,
+    ) -> calloop::Result<()> {
+        self.source.reregister(poll, token_factory)
+    }
+
+    fn unregister(&mut self, poll: &mut calloop::Poll) -> calloop::Result<()> {
+        self.source.unregister(poll)
+    }
+}
@@ -0,0 +1,231 @@
+//! Implementation of the `ext-foreign-toplevel-list-v1` protocol
+//!
+//! See the [module-level documentation](super) for how to use it.
+
+use wayland_protocols::ext::foreign_toplevel_list::v1::server::{
+    ext_foreign_toplevel_handle_v1::{self, ExtForeignToplevelHandleV1},
+    ext_foreign_toplevel_list_v1::{self, ExtForeignToplevelListV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use super::{ForeignToplevelData, ForeignToplevelHandle};
+
+const VERSION: u32 = 1;
+
+/// State of the ext-foreign-toplevel-list global
+#[derive(Debug)]
+pub struct ForeignToplevelListState {
+    global: GlobalId,
+    display: DisplayHandle,
+    toplevels: Vec<ForeignToplevelHandle>,
+    instances: Vec<ExtForeignToplevelListV1>,
+}
+
+impl ForeignToplevelListState {
+    /// Create a new [`ExtForeignToplevelListV1`] global.
+    ///
+    /// The `filter` decides which clients are allowed to enumerate toplevels.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ExtForeignToplevelListV1, ForeignToplevelListGlobalData>,
+        D: Dispatch<ExtForeignToplevelListV1, ()>,
+        D: Dispatch<ExtForeignToplevelHandleV1, ForeignToplevelData>,
+        D: ForeignToplevelListHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = ForeignToplevelListGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ExtForeignToplevelListV1, _>(VERSION, data);
+
+        Self {
+            global,
+            display: display.clone(),
+            toplevels: Vec::new(),
+            instances: Vec::new(),
+        }
+    }
+
+    /// Returns the id of the [`ExtForeignToplevelListV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Announces a new toplevel to all clients.
+    ///
+    /// The toplevel stays advertised until [`ForeignToplevelHandle::close`] is called.
+    pub fn add_toplevel<D>(&mut self, toplevel: &ForeignToplevelHandle)
+    where
+        D: Dispatch<ExtForeignToplevelHandleV1, ForeignToplevelData>,
+        D: 'static,
+    {
+        self.toplevels.retain(|t| !t.is_closed());
+        if toplevel.is_closed() || self.toplevels.contains(toplevel) {
+            return;
+        }
+        self.toplevels.push(toplevel.clone());
+
+        for instance in &self.instances {
+            send_toplevel::<D>(&self.display, instance, toplevel);
+        }
+    }
+
+    /// Returns all currently advertised toplevels
+    pub fn toplevels(&self) -> impl Iterator<Item = &ForeignToplevelHandle> {
+        self.toplevels.iter().filter(|t| !t.is_closed())
+    }
+}
+
+/// Handler trait for ext-foreign-toplevel-list.
+pub trait ForeignToplevelListHandler {
+    /// [`ForeignToplevelListState`] getter
+    fn foreign_toplevel_list_state(&mut self) -> &mut ForeignToplevelListState;
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct ForeignToplevelListGlobalData {
+    /// Filter whether the clients can view global.
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+fn send_toplevel<D>(dh: &DisplayHandle, instance: &ExtForeignToplevelListV1, toplevel: &ForeignToplevelHandle)
+where
+    D: Dispatch<ExtForeignToplevelHandleV1, ForeignToplevelData>,
+    D: 'static,
+{
+    let Some(client) = instance.client() else {
+        return;
+    };
+    let Ok(resource) = client.create_resource::<ExtForeignToplevelHandleV1, _, D>(
+        dh,
+        instance.version(),
+        ForeignToplevelData::new(toplevel),
+    ) else {
+        return;
+    };
+    instance.toplevel(&resource);
+    toplevel.init_ext_instance(resource);
+}
+
+impl<D> GlobalDispatch<ExtForeignToplevelListV1, ForeignToplevelListGlobalData, D>
+    for ForeignToplevelListState
+where
+    D: GlobalDispatch<ExtForeignToplevelListV1, ForeignToplevelListGlobalData>,
+    D: Dispatch<ExtForeignToplevelListV1, ()>,
+    D: Dispatch<ExtForeignToplevelHandleV1, ForeignToplevelData>,
+    D: ForeignToplevelListHandler,
+    D: 'static,
+{
+    fn bind(
+        state: &mut D,
+        display: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtForeignToplevelListV1>,
+        _global_data: &ForeignToplevelListGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let instance = data_init.init(resource, ());
+
+        let list_state = state.foreign_toplevel_list_state();
+        list_state.toplevels.retain(|t| !t.is_closed());
+        for toplevel in &list_state.toplevels {
+            send_toplevel::<D>(display, &instance, toplevel);
+        }
+        list_state.instances.push(instance);
+    }
+
+    fn can_view(client: Client, global_data: &ForeignToplevelListGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ExtForeignToplevelListV1, (), D> for ForeignToplevelListState
+where
+    D: Dispatch<ExtForeignToplevelListV1, ()>,
+    D: ForeignToplevelListHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        instance: &ExtForeignToplevelListV1,
+        request: ext_foreign_toplevel_list_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_foreign_toplevel_list_v1::Request::Stop => {
+                let list_state = state.foreign_toplevel_list_state();
+                if list_state.instances.contains(instance) {
+                    list_state.instances.retain(|i| i != instance);
+                    instance.finished();
+                }
+            }
+            ext_foreign_toplevel_list_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, instance: &ExtForeignToplevelListV1, _data: &()) {
+        state
+            .foreign_toplevel_list_state()
+            .instances
+            .retain(|i| i != instance);
+    }
+}
+
+impl<D> Dispatch<ExtForeignToplevelHandleV1, ForeignToplevelData, D> for ForeignToplevelListState
+where
+    D: Dispatch<ExtForeignToplevelHandleV1, ForeignToplevelData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ExtForeignToplevelHandleV1,
+        request: ext_foreign_toplevel_handle_v1::Request,
+        _data: &ForeignToplevelData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_foreign_toplevel_handle_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        resource: &ExtForeignToplevelHandleV1,
+        data: &ForeignToplevelData,
+    ) {
+        if let Some(handle) = data.handle() {
+            handle.remove_instance(&resource.id());
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_foreign_toplevel_list {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::foreign_toplevel_list::v1::server::ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1: $crate::wayland::foreign_toplevel::list::ForeignToplevelListGlobalData
+        ] => $crate::wayland::foreign_toplevel::list::ForeignToplevelListState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::foreign_toplevel_list::v1::server::ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1: ()
+        ] => $crate::wayland::foreign_toplevel::list::ForeignToplevelListState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::foreign_toplevel_list::v1::server::ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1: $crate::wayland::foreign_toplevel::ForeignToplevelData
+        ] => $crate::wayland::foreign_toplevel::list::ForeignToplevelListState);
+    };
+}
@@ -0,0 +1,510 @@
+//! Utilities for handling foreign toplevel protocols
+//!
+//! Foreign toplevel protocols allow clients like taskbars or docks to enumerate
+//! the toplevel windows of other clients. This module implements two of them:
+//!
+//! - [`list`] implements the read-only `ext-foreign-toplevel-list-v1` protocol.
+//! - [`wlr`] implements the `wlr-foreign-toplevel-management-unstable-v1` protocol,
+//!   which additionally allows clients to activate, close, minimize, maximize or fullscreen windows.
+//!
+//! Both protocols share the same [`ForeignToplevelHandle`], which represents a single window
+//! and keeps track of its title, app_id and [`ToplevelStates`].
+//!
+//! ## How to use it
+//!
+//! ### Initialization
+//!
+//! Create the states of the protocols you want to advertise and implement their handlers:
+//!
+//! ```
+//! use smithay::{delegate_foreign_toplevel_list, delegate_foreign_toplevel_manager};
+//! use smithay::reexports::wayland_server::protocol::wl_seat::WlSeat;
+//! use smithay::wayland::foreign_toplevel::{
+//!     ForeignToplevelHandle,
+//!     list::{ForeignToplevelListHandler, ForeignToplevelListState},
+//!     wlr::{ForeignToplevelManagerHandler, ForeignToplevelManagerState},
+//! };
+//!
+//! # struct State { list: ForeignToplevelListState, manager: ForeignToplevelManagerState }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! let list_state = ForeignToplevelListState::new::<State, _>(&display.handle(), |_client| true);
+//! let manager_state = ForeignToplevelManagerState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! impl ForeignToplevelListHandler for State {
+//!     fn foreign_toplevel_list_state(&mut self) -> &mut ForeignToplevelListState {
+//!         &mut self.list
+//!     }
+//! }
+//!
+//! impl ForeignToplevelManagerHandler for State {
+//!     fn foreign_toplevel_manager_state(&mut self) -> &mut ForeignToplevelManagerState {
+//!         &mut self.manager
+//!     }
+//!
+//!     fn activate(&mut self, toplevel: ForeignToplevelHandle, seat: WlSeat) {
+//!         // focus the window associated with `toplevel`
+//!     }
+//!
+//!     fn close(&mut self, toplevel: ForeignToplevelHandle) {
+//!         // ask the window associated with `toplevel` to close
+//!     }
+//! }
+//!
+//! delegate_foreign_toplevel_list!(State);
+//! delegate_foreign_toplevel_manager!(State);
+//! ```
+//!
+//! ### Tracking windows
+//!
+//! Create a [`ForeignToplevelHandle`] for every mapped window and announce it with
+//! [`ForeignToplevelListState::add_toplevel`](list::ForeignToplevelListState::add_toplevel)
+//! and [`ForeignToplevelManagerState::add_toplevel`](wlr::ForeignToplevelManagerState::add_toplevel).
+//! The [`ForeignToplevelHandle::user_data`] can be used to associate the handle with your window type.
+//!
+//! Whenever the window changes, e.g. on every commit of a xdg toplevel, call
+//! [`ForeignToplevelHandle::update_from_toplevel`] (or
+//! `ForeignToplevelHandle::update_from_x11_surface` for xwayland windows)
+//! to forward title, app_id and state changes to clients. Output changes have to be tracked
+//! manually via [`ForeignToplevelHandle::output_enter`] and [`ForeignToplevelHandle::output_leave`].
+//!
+//! Once the window is unmapped or destroyed call [`ForeignToplevelHandle::close`].
+
+use std::sync::{Arc, Mutex, Weak};
+
+use rand::distributions::{Alphanumeric, DistString};
+use wayland_protocols::ext::foreign_toplevel_list::v1::server::ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1;
+use wayland_protocols::xdg::shell::server::xdg_toplevel;
+use wayland_protocols_wlr::foreign_toplevel::v1::server::zwlr_foreign_toplevel_handle_v1::{
+    self, ZwlrForeignToplevelHandleV1,
+};
+use wayland_server::Resource;
+
+use crate::{
+    output::Output,
+    utils::user_data::UserDataMap,
+    wayland::{
+        compositor,
+        shell::xdg::{ToplevelSurface, XdgToplevelSurfaceData},
+    },
+};
+
+pub mod list;
+pub mod wlr;
+
+bitflags::bitflags! {
+    /// States of a foreign toplevel
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct ToplevelStates: u32 {
+        /// The toplevel is maximized
+        const MAXIMIZED = 1;
+        /// The toplevel is minimized
+        const MINIMIZED = 2;
+        /// The toplevel is activated
+        const ACTIVATED = 4;
+        /// The toplevel is fullscreen
+        const FULLSCREEN = 8;
+    }
+}
+
+/// A window advertised to clients of the foreign toplevel protocols
+#[derive(Debug, Clone)]
+pub struct ForeignToplevelHandle {
+    inner: Arc<ForeignToplevelInner>,
+}
+
+#[derive(Debug)]
+struct ForeignToplevelInner {
+    identifier: String,
+    state: Mutex<HandleState>,
+    user_data: UserDataMap,
+}
+
+#[derive(Debug, Default)]
+struct HandleState {
+    title: String,
+    app_id: String,
+    states: ToplevelStates,
+    outputs: Vec<Output>,
+    parent: Option<Weak<ForeignToplevelInner>>,
+    closed: bool,
+    ext_instances: Vec<ExtForeignToplevelHandleV1>,
+    wlr_instances: Vec<ZwlrForeignToplevelHandleV1>,
+}
+
+/// User data of foreign toplevel handle objects
+#[derive(Debug)]
+pub struct ForeignToplevelData {
+    handle: Weak<ForeignToplevelInner>,
+}
+
+impl ForeignToplevelData {
+    fn new(handle: &ForeignToplevelHandle) -> Self {
+        ForeignToplevelData {
+            handle: Arc::downgrade(&handle.inner),
+        }
+    }
+
+    fn handle(&self) -> Option<ForeignToplevelHandle> {
+        self.handle.upgrade().map(|inner| ForeignToplevelHandle { inner })
+    }
+}
+
+impl PartialEq for ForeignToplevelHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl ForeignToplevelHandle {
+    /// Creates a new handle with the given title and app_id.
+    ///
+    /// The handle is not advertised to any client, until it was added to
+    /// one of the foreign toplevel states.
+    pub fn new(title: String, app_id: String) -> Self {
+        ForeignToplevelHandle {
+            inner: Arc::new(ForeignToplevelInner {
+                identifier: Alphanumeric.sample_string(&mut rand::thread_rng(), 32),
+                state: Mutex::new(HandleState {
+                    title,
+                    app_id,
+                    ..Default::default()
+                }),
+                user_data: UserDataMap::new(),
+            }),
+        }
+    }
+
+    /// Creates a new handle from the current title, app_id and state of a xdg toplevel.
+    pub fn from_toplevel(toplevel: &ToplevelSurface) -> Self {
+        let (title, app_id, states) = toplevel_info(toplevel, ToplevelStates::empty());
+        let handle = ForeignToplevelHandle::new(title, app_id);
+        handle.inner.state.lock().unwrap().states = states;
+        handle
+    }
+
+    /// Retrieve the handle of an `ext_foreign_toplevel_handle_v1` object
+    pub fn from_resource(resource: &ExtForeignToplevelHandleV1) -> Option<Self> {
+        resource.data::<ForeignToplevelData>()?.handle()
+    }
+
+    /// Retrieve the handle of a `zwlr_foreign_toplevel_handle_v1` object
+    pub fn from_wlr_resource(resource: &ZwlrForeignToplevelHandleV1) -> Option<Self> {
+        resource.data::<ForeignToplevelData>()?.handle()
+    }
+
+    /// Stable identifier of this toplevel, as advertised by `ext-foreign-toplevel-list`
+    pub fn identifier(&self) -> &str {
+        &self.inner.identifier
+    }
+
+    /// Current title of the toplevel
+    pub fn title(&self) -> String {
+        self.inner.state.lock().unwrap().title.clone()
+    }
+
+    /// Current app_id of the toplevel
+    pub fn app_id(&self) -> String {
+        self.inner.state.lock().unwrap().app_id.clone()
+    }
+
+    /// Current states of the toplevel
+    pub fn states(&self) -> ToplevelStates {
+        self.inner.state.lock().unwrap().states
+    }
+
+    /// Outputs the toplevel is currently visible on
+    pub fn outputs(&self) -> Vec<Output> {
+        self.inner.state.lock().unwrap().outputs.clone()
+    }
+
+    /// Returns if the toplevel was closed
+    pub fn is_closed(&self) -> bool {
+        self.inner.state.lock().unwrap().closed
+    }
+
+    /// Access the [`UserDataMap`] associated with this handle
+    pub fn user_data(&self) -> &UserDataMap {
+        &self.inner.user_data
+    }
+
+    /// Sets the title of the toplevel.
+    ///
+    /// Changes are applied by the next call to [`ForeignToplevelHandle::send_done`].
+    pub fn set_title(&self, title: &str) {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.closed || state.title == title {
+            return;
+        }
+        state.title = title.to_string();
+        for instance in &state.ext_instances {
+            instance.title(title.to_string());
+        }
+        for instance in &state.wlr_instances {
+            instance.title(title.to_string());
+        }
+    }
+
+    /// Sets the app_id of the toplevel.
+    ///
+    /// Changes are applied by the next call to [`ForeignToplevelHandle::send_done`].
+    pub fn set_app_id(&self, app_id: &str) {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.closed || state.app_id == app_id {
+            return;
+        }
+        state.app_id = app_id.to_string();
+        for instance in &state.ext_instances {
+            instance.app_id(app_id.to_string());
+        }
+        for instance in &state.wlr_instances {
+            instance.app_id(app_id.to_string());
+        }
+    }
+
+    /// Sets the states of the toplevel.
+    ///
+    /// Changes are applied by the next call to [`ForeignToplevelHandle::send_done`].
+    pub fn set_states(&self, states: ToplevelStates) {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.closed || state.states == states {
+            return;
+        }
+        state.states = states;
+        for instance in &state.wlr_instances {
+            instance.state(wlr_states(states, instance.version()));
+        }
+    }
+
+    /// Notifies clients, that the toplevel is now visible on the given output.
+    ///
+    /// Changes are applied by the next call to [`ForeignToplevelHandle::send_done`].
+    pub fn output_enter(&self, output: &Output) {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.closed || state.outputs.contains(output) {
+            return;
+        }
+        state.outputs.push(output.clone());
+        for instance in &state.wlr_instances {
+            if let Some(client) = instance.client() {
+                for wl_output in output.client_outputs(&client) {
+                    instance.output_enter(&wl_output);
+                }
+            }
+        }
+    }
+
+    /// Notifies clients, that the toplevel is no longer visible on the given output.
+    ///
+    /// Changes are applied by the next call to [`ForeignToplevelHandle::send_done`].
+    pub fn output_leave(&self, output: &Output) {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.closed || !state.outputs.contains(output) {
+            return;
+        }
+        state.outputs.retain(|o| o != output);
+        for instance in &state.wlr_instances {
+            if let Some(client) = instance.client() {
+                for wl_output in output.client_outputs(&client) {
+                    instance.output_leave(&wl_output);
+                }
+            }
+        }
+    }
+
+    /// Sets the parent of the toplevel, e.g. for dialogs.
+    ///
+    /// Only `wlr-foreign-toplevel-management` clients are notified about parents.
+    /// Changes are applied by the next call to [`ForeignToplevelHandle::send_done`].
+    pub fn set_parent(&self, parent: Option<&ForeignToplevelHandle>) {
+        let parent = parent.filter(|parent| *parent != self);
+        let mut state = self.inner.state.lock().unwrap();
+        let current = state.parent.as_ref().and_then(Weak::upgrade);
+        if state.closed || current.as_ref().map(Arc::as_ptr) == parent.map(|p| Arc::as_ptr(&p.inner)) {
+            return;
+        }
+        state.parent = parent.map(|p| Arc::downgrade(&p.inner));
+        for instance in &state.wlr_instances {
+            send_wlr_parent(instance, parent);
+        }
+    }
+
+    /// Notifies clients, that all changes of the toplevel were sent.
+    pub fn send_done(&self) {
+        let state = self.inner.state.lock().unwrap();
+        if state.closed {
+            return;
+        }
+        for instance in &state.ext_instances {
+            instance.done();
+        }
+        for instance in &state.wlr_instances {
+            instance.done();
+        }
+    }
+
+    /// Notifies clients, that the toplevel was closed.
+    ///
+    /// The handle becomes inert and will not be advertised to new clients.
+    pub fn close(&self) {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.closed {
+            return;
+        }
+        state.closed = true;
+        for instance in &state.ext_instances {
+            instance.closed();
+        }
+        for instance in &state.wlr_instances {
+            instance.closed();
+        }
+    }
+
+    /// Updates title, app_id and states from the given xdg toplevel and notifies clients about any changes.
+    ///
+    /// The minimized state is not part of the xdg-shell protocol and thus left untouched.
+    pub fn update_from_toplevel(&self, toplevel: &ToplevelSurface) {
+        let (title, app_id, states) = toplevel_info(toplevel, self.states() & ToplevelStates::MINIMIZED);
+        self.update(&title, &app_id, states);
+    }
+
+    /// Updates title, app_id and states from the given X11 window and notifies clients about any changes.
+    ///
+    /// The window class is used as app_id.
+    #[cfg(feature = "xwayland")]
+    pub fn update_from_x11_surface(&self, surface: &crate::xwayland::X11Surface) {
+        let mut states = ToplevelStates::empty();
+        states.set(ToplevelStates::MAXIMIZED, surface.is_maximized());
+        states.set(ToplevelStates::MINIMIZED, surface.is_minimized());
+        states.set(ToplevelStates::ACTIVATED, surface.is_activated());
+        states.set(ToplevelStates::FULLSCREEN, surface.is_fullscreen());
+        self.update(&surface.title(), &surface.class(), states);
+    }
+
+    fn update(&self, title: &str, app_id: &str, states: ToplevelStates) {
+        let changed = {
+            let state = self.inner.state.lock().unwrap();
+            state.title != title || state.app_id != app_id || state.states != states
+        };
+        if changed {
+            self.set_title(title);
+            self.set_app_id(app_id);
+            self.set_states(states);
+            self.send_done();
+        }
+    }
+
+    fn init_ext_instance(&self, instance: ExtForeignToplevelHandleV1) {
+        let mut state = self.inner.state.lock().unwrap();
+        instance.identifier(self.inner.identifier.clone());
+        instance.title(state.title.clone());
+        instance.app_id(state.app_id.clone());
+        instance.done();
+        state.ext_instances.push(instance);
+    }
+
+    fn add_wlr_instance(&self, instance: ZwlrForeignToplevelHandleV1) {
+        self.inner.state.lock().unwrap().wlr_instances.push(instance);
+    }
+
+    // sent separately from `add_wlr_instance`, so that all handles of a client
+    // exist before the parents are announced.
+    fn init_wlr_instance(&self, instance: &ZwlrForeignToplevelHandleV1) {
+        let state = self.inner.state.lock().unwrap();
+        instance.title(state.title.clone());
+        instance.app_id(state.app_id.clone());
+        if let Some(client) = instance.client() {
+            for output in &state.outputs {
+                for wl_output in output.client_outputs(&client) {
+                    instance.output_enter(&wl_output);
+                }
+            }
+        }
+        instance.state(wlr_states(state.states, instance.version()));
+        let parent = state
+            .parent
+            .as_ref()
+            .and_then(Weak::upgrade)
+            .map(|inner| ForeignToplevelHandle { inner });
+        if parent.is_some() {
+            send_wlr_parent(instance, parent.as_ref());
+        }
+        instance.done();
+    }
+
+    fn remove_instance(&self, id: &wayland_server::backend::ObjectId) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.ext_instances.retain(|i| i.id() != *id);
+        state.wlr_instances.retain(|i| i.id() != *id);
+    }
+}
+
+fn toplevel_info(toplevel: &ToplevelSurface, extra: ToplevelStates) -> (String, String, ToplevelStates) {
+    let (title, app_id) = compositor::with_states(toplevel.wl_surface(), |states| {
+        let attributes = states
+            .data_map
+            .get::<XdgToplevelSurfaceData>()
+            .unwrap()
+            .lock()
+            .unwrap();
+        (
+            attributes.title.clone().unwrap_or_default(),
+            attributes.app_id.clone().unwrap_or_default(),
+        )
+    });
+
+    let current = toplevel.current_state();
+    let mut states = extra;
+    states.set(
+        ToplevelStates::MAXIMIZED,
+        current.states.contains(xdg_toplevel::State::Maximized),
+    );
+    states.set(
+        ToplevelStates::ACTIVATED,
+        current.states.contains(xdg_toplevel::State::Activated),
+    );
+    states.set(
+        ToplevelStates::FULLSCREEN,
+        current.states.contains(xdg_toplevel::State::Fullscreen),
+    );
+
+    (title, app_id, states)
+}
+
+fn wlr_states(states: ToplevelStates, version: u32) -> Vec<u8> {
+    let mut wlr_states = Vec::new();
+    if states.contains(ToplevelStates::MAXIMIZED) {
+        wlr_states.push(zwlr_foreign_toplevel_handle_v1::State::Maximized);
+    }
+    if states.contains(ToplevelStates::MINIMIZED) {
+        wlr_states.push(zwlr_foreign_toplevel_handle_v1::State::Minimized);
+    }
+    if states.contains(ToplevelStates::ACTIVATED) {
+        wlr_states.push(zwlr_foreign_toplevel_handle_v1::State::Activated);
+    }
+    if states.contains(ToplevelStates::FULLSCREEN) && version >= 2 {
+        wlr_states.push(zwlr_foreign_toplevel_handle_v1::State::Fullscreen);
+    }
+    wlr_states
+        .into_iter()
+        .flat_map(|state| (state as u32).to_ne_bytes())
+        .collect()
+}
+
+fn send_wlr_parent(instance: &ZwlrForeignToplevelHandleV1, parent: Option<&ForeignToplevelHandle>) {
+    if instance.version() < 3 {
+        return;
+    }
+    let client = instance.client();
+    let parent_instance = parent.and_then(|parent| {
+        parent
+            .inner
+            .state
+            .lock()
+            .unwrap()
+            .wlr_instances
+            .iter()
+            .find(|p| p.client() == client)
+            .cloned()
+    });
+    instance.parent(parent_instance.as_ref());
+}
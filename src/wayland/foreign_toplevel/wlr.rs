@@ -0,0 +1,332 @@
+//! Implementation of the `wlr-foreign-toplevel-management-unstable-v1` protocol
+//!
+//! See the [module-level documentation](super) for how to use it.
+
+use wayland_protocols_wlr::foreign_toplevel::v1::server::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    protocol::{wl_output::WlOutput, wl_seat::WlSeat, wl_surface::WlSurface},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use super::{ForeignToplevelData, ForeignToplevelHandle};
+use crate::utils::{Logical, Rectangle};
+
+const VERSION: u32 = 3;
+
+/// State of the wlr-foreign-toplevel-management global
+#[derive(Debug)]
+pub struct ForeignToplevelManagerState {
+    global: GlobalId,
+    display: DisplayHandle,
+    toplevels: Vec<ForeignToplevelHandle>,
+    instances: Vec<ZwlrForeignToplevelManagerV1>,
+}
+
+impl ForeignToplevelManagerState {
+    /// Create a new [`ZwlrForeignToplevelManagerV1`] global.
+    ///
+    /// The `filter` decides which clients are allowed to enumerate and manage toplevels.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrForeignToplevelManagerV1, ForeignToplevelManagerGlobalData>,
+        D: Dispatch<ZwlrForeignToplevelManagerV1, ()>,
+        D: Dispatch<ZwlrForeignToplevelHandleV1, ForeignToplevelData>,
+        D: ForeignToplevelManagerHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = ForeignToplevelManagerGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ZwlrForeignToplevelManagerV1, _>(VERSION, data);
+
+        Self {
+            global,
+            display: display.clone(),
+            toplevels: Vec::new(),
+            instances: Vec::new(),
+        }
+    }
+
+    /// Returns the id of the [`ZwlrForeignToplevelManagerV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Announces a new toplevel to all clients.
+    ///
+    /// The toplevel stays advertised until [`ForeignToplevelHandle::close`] is called.
+    pub fn add_toplevel<D>(&mut self, toplevel: &ForeignToplevelHandle)
+    where
+        D: Dispatch<ZwlrForeignToplevelHandleV1, ForeignToplevelData>,
+        D: 'static,
+    {
+        self.toplevels.retain(|t| !t.is_closed());
+        if toplevel.is_closed() || self.toplevels.contains(toplevel) {
+            return;
+        }
+        self.toplevels.push(toplevel.clone());
+
+        for instance in &self.instances {
+            if let Some(resource) = create_toplevel::<D>(&self.display, instance, toplevel) {
+                toplevel.init_wlr_instance(&resource);
+            }
+        }
+    }
+
+    /// Returns all currently advertised toplevels
+    pub fn toplevels(&self) -> impl Iterator<Item = &ForeignToplevelHandle> {
+        self.toplevels.iter().filter(|t| !t.is_closed())
+    }
+}
+
+/// Handler trait for wlr-foreign-toplevel-management.
+///
+/// Requests are only forwarded for toplevels, that were not closed yet.
+pub trait ForeignToplevelManagerHandler {
+    /// [`ForeignToplevelManagerState`] getter
+    fn foreign_toplevel_manager_state(&mut self) -> &mut ForeignToplevelManagerState;
+
+    /// A client requested the toplevel to be activated on the given seat.
+    fn activate(&mut self, toplevel: ForeignToplevelHandle, seat: WlSeat);
+
+    /// A client requested the toplevel to be closed.
+    fn close(&mut self, toplevel: ForeignToplevelHandle);
+
+    /// A client requested the toplevel to be minimized.
+    fn set_minimized(&mut self, toplevel: ForeignToplevelHandle) {
+        let _ = toplevel;
+    }
+
+    /// A client requested the toplevel to be unminimized.
+    fn unset_minimized(&mut self, toplevel: ForeignToplevelHandle) {
+        let _ = toplevel;
+    }
+
+    /// A client requested the toplevel to be maximized.
+    fn set_maximized(&mut self, toplevel: ForeignToplevelHandle) {
+        let _ = toplevel;
+    }
+
+    /// A client requested the toplevel to be unmaximized.
+    fn unset_maximized(&mut self, toplevel: ForeignToplevelHandle) {
+        let _ = toplevel;
+    }
+
+    /// A client requested the toplevel to be fullscreened, optionally on the given output.
+    fn set_fullscreen(&mut self, toplevel: ForeignToplevelHandle, output: Option<WlOutput>) {
+        let _ = (toplevel, output);
+    }
+
+    /// A client requested the toplevel to leave fullscreen.
+    fn unset_fullscreen(&mut self, toplevel: ForeignToplevelHandle) {
+        let _ = toplevel;
+    }
+
+    /// A client set the rectangle, relative to `surface`, the toplevel is represented by, e.g.
+    /// to use as target of a minimize animation.
+    ///
+    /// An empty rectangle unsets any previously set rectangle.
+    fn set_rectangle(
+        &mut self,
+        toplevel: ForeignToplevelHandle,
+        surface: WlSurface,
+        rectangle: Rectangle<i32, Logical>,
+    ) {
+        let _ = (toplevel, surface, rectangle);
+    }
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct ForeignToplevelManagerGlobalData {
+    /// Filter whether the clients can view global.
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+fn create_toplevel<D>(
+    dh: &DisplayHandle,
+    instance: &ZwlrForeignToplevelManagerV1,
+    toplevel: &ForeignToplevelHandle,
+) -> Option<ZwlrForeignToplevelHandleV1>
+where
+    D: Dispatch<ZwlrForeignToplevelHandleV1, ForeignToplevelData>,
+    D: 'static,
+{
+    let client = instance.client()?;
+    let resource = client
+        .create_resource::<ZwlrForeignToplevelHandleV1, _, D>(
+            dh,
+            instance.version(),
+            ForeignToplevelData::new(toplevel),
+        )
+        .ok()?;
+    instance.toplevel(&resource);
+    toplevel.add_wlr_instance(resource.clone());
+    Some(resource)
+}
+
+impl<D> GlobalDispatch<ZwlrForeignToplevelManagerV1, ForeignToplevelManagerGlobalData, D>
+    for ForeignToplevelManagerState
+where
+    D: GlobalDispatch<ZwlrForeignToplevelManagerV1, ForeignToplevelManagerGlobalData>,
+    D: Dispatch<ZwlrForeignToplevelManagerV1, ()>,
+    D: Dispatch<ZwlrForeignToplevelHandleV1, ForeignToplevelData>,
+    D: ForeignToplevelManagerHandler,
+    D: 'static,
+{
+    fn bind(
+        state: &mut D,
+        display: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrForeignToplevelManagerV1>,
+        _global_data: &ForeignToplevelManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let instance = data_init.init(resource, ());
+
+        let manager_state = state.foreign_toplevel_manager_state();
+        manager_state.toplevels.retain(|t| !t.is_closed());
+        // create all handles first, so parents can be resolved
+        let resources = manager_state
+            .toplevels
+            .iter()
+            .filter_map(|toplevel| {
+                create_toplevel::<D>(display, &instance, toplevel).map(|resource| (toplevel, resource))
+            })
+            .collect::<Vec<_>>();
+        for (toplevel, resource) in resources {
+            toplevel.init_wlr_instance(&resource);
+        }
+        manager_state.instances.push(instance);
+    }
+
+    fn can_view(client: Client, global_data: &ForeignToplevelManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrForeignToplevelManagerV1, (), D> for ForeignToplevelManagerState
+where
+    D: Dispatch<ZwlrForeignToplevelManagerV1, ()>,
+    D: ForeignToplevelManagerHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        instance: &ZwlrForeignToplevelManagerV1,
+        request: zwlr_foreign_toplevel_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_foreign_toplevel_manager_v1::Request::Stop => {
+                state
+                    .foreign_toplevel_manager_state()
+                    .instances
+                    .retain(|i| i != instance);
+                instance.finished();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, instance: &ZwlrForeignToplevelManagerV1, _data: &()) {
+        state
+            .foreign_toplevel_manager_state()
+            .instances
+            .retain(|i| i != instance);
+    }
+}
+
+impl<D> Dispatch<ZwlrForeignToplevelHandleV1, ForeignToplevelData, D> for ForeignToplevelManagerState
+where
+    D: Dispatch<ZwlrForeignToplevelHandleV1, ForeignToplevelData>,
+    D: ForeignToplevelManagerHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZwlrForeignToplevelHandleV1,
+        request: zwlr_foreign_toplevel_handle_v1::Request,
+        data: &ForeignToplevelData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        if let zwlr_foreign_toplevel_handle_v1::Request::SetRectangle { width, height, .. } = &request {
+            if *width < 0 || *height < 0 {
+                resource.post_error(
+                    zwlr_foreign_toplevel_handle_v1::Error::InvalidRectangle,
+                    "width and height must be positive or zero",
+                );
+                return;
+            }
+        }
+
+        let Some(toplevel) = data.handle().filter(|toplevel| !toplevel.is_closed()) else {
+            return;
+        };
+
+        match request {
+            zwlr_foreign_toplevel_handle_v1::Request::SetMaximized => state.set_maximized(toplevel),
+            zwlr_foreign_toplevel_handle_v1::Request::UnsetMaximized => state.unset_maximized(toplevel),
+            zwlr_foreign_toplevel_handle_v1::Request::SetMinimized => state.set_minimized(toplevel),
+            zwlr_foreign_toplevel_handle_v1::Request::UnsetMinimized => state.unset_minimized(toplevel),
+            zwlr_foreign_toplevel_handle_v1::Request::Activate { seat } => state.activate(toplevel, seat),
+            zwlr_foreign_toplevel_handle_v1::Request::Close => state.close(toplevel),
+            zwlr_foreign_toplevel_handle_v1::Request::SetRectangle {
+                surface,
+                x,
+                y,
+                width,
+                height,
+            } => state.set_rectangle(
+                toplevel,
+                surface,
+                Rectangle::from_loc_and_size((x, y), (width, height)),
+            ),
+            zwlr_foreign_toplevel_handle_v1::Request::SetFullscreen { output } => {
+                state.set_fullscreen(toplevel, output)
+            }
+            zwlr_foreign_toplevel_handle_v1::Request::UnsetFullscreen => state.unset_fullscreen(toplevel),
+            zwlr_foreign_toplevel_handle_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        _state: &mut D,
+        _client: ClientId,
+        resource: &ZwlrForeignToplevelHandleV1,
+        data: &ForeignToplevelData,
+    ) {
+        if let Some(handle) = data.handle() {
+            handle.remove_instance(&resource.id());
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_foreign_toplevel_manager {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::foreign_toplevel::v1::server::zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1: $crate::wayland::foreign_toplevel::wlr::ForeignToplevelManagerGlobalData
+        ] => $crate::wayland::foreign_toplevel::wlr::ForeignToplevelManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::foreign_toplevel::v1::server::zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1: ()
+        ] => $crate::wayland::foreign_toplevel::wlr::ForeignToplevelManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::foreign_toplevel::v1::server::zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1: $crate::wayland::foreign_toplevel::ForeignToplevelData
+        ] => $crate::wayland::foreign_toplevel::wlr::ForeignToplevelManagerState);
+    };
+}
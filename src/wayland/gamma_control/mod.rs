@@ -0,0 +1,350 @@
+//! Utilities for handling the `wlr-gamma-control` protocol
+//!
+//! This protocol allows privileged clients like `gammastep` or `wlsunset` to set
+//! the gamma ramps of an output, e.g. to reduce blue light in the evening.
+//!
+//! ## How to use it
+//!
+//! ### Initialization
+//!
+//! To initialize this implementation create the [`GammaControlManagerState`] and
+//! implement the [`GammaControlHandler`], as shown in this example:
+//!
+//! ```
+//! use smithay::delegate_gamma_control;
+//! use smithay::output::Output;
+//! use smithay::wayland::gamma_control::{GammaControlHandler, GammaControlManagerState};
+//!
+//! # struct State;
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the gamma control global, only allowing privileged clients to change the gamma.
+//! let gamma_control_state = GammaControlManagerState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! // Implement the necessary trait.
+//! impl GammaControlHandler for State {
+//!     fn gamma_size(&mut self, output: &Output) -> Option<u32> {
+//!         // e.g. `DrmSurface::gamma_size` of the surface driving `output`
+//! #       None
+//!     }
+//!
+//!     fn set_gamma(&mut self, output: &Output, ramp: Option<[&[u16]; 3]>) -> bool {
+//!         // e.g. `DrmSurface::set_gamma` or `DrmSurface::reset_gamma`
+//! #       false
+//!     }
+//! }
+//! delegate_gamma_control!(State);
+//!
+//! // You're now ready to go!
+//! ```
+//!
+//! Only one client may control the gamma of an output at a time. Once the controlling
+//! client destroys its gamma control object (or disconnects), [`GammaControlHandler::set_gamma`]
+//! is called with `None` to restore the default gamma ramps.
+
+use std::{os::unix::io::OwnedFd, sync::Mutex};
+
+use tracing::{trace, warn};
+use wayland_protocols_wlr::gamma_control::v1::server::{
+    zwlr_gamma_control_manager_v1::{self, ZwlrGammaControlManagerV1},
+    zwlr_gamma_control_v1::{self, ZwlrGammaControlV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, Weak,
+};
+
+use crate::output::{Output, WeakOutput};
+
+const MANAGER_VERSION: u32 = 1;
+
+/// State of the wlr-gamma-control global
+#[derive(Debug)]
+pub struct GammaControlManagerState {
+    global: GlobalId,
+}
+
+impl GammaControlManagerState {
+    /// Create a new [`ZwlrGammaControlManagerV1`] global.
+    ///
+    /// The `filter` decides which clients are allowed to change the gamma of outputs.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>,
+        D: Dispatch<ZwlrGammaControlManagerV1, ()>,
+        D: Dispatch<ZwlrGammaControlV1, GammaControlData>,
+        D: GammaControlHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = GammaControlManagerGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ZwlrGammaControlManagerV1, _>(MANAGER_VERSION, data);
+
+        Self { global }
+    }
+
+    /// Returns the id of the [`ZwlrGammaControlManagerV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// Handler trait for wlr-gamma-control.
+pub trait GammaControlHandler {
+    /// Returns the number of entries per channel of the gamma ramps of the given output.
+    ///
+    /// Return `None`, if the gamma of the output cannot be changed.
+    fn gamma_size(&mut self, output: &Output) -> Option<u32>;
+
+    /// Sets the red, green and blue gamma ramps of the given output.
+    ///
+    /// Every ramp has exactly [`GammaControlHandler::gamma_size`] entries.
+    /// `None` requests to restore the default gamma ramps.
+    ///
+    /// Return `false`, if the ramps could not be applied. The client will be notified
+    /// and loses control over the gamma of the output.
+    fn set_gamma(&mut self, output: &Output, ramp: Option<[&[u16]; 3]>) -> bool;
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct GammaControlManagerGlobalData {
+    /// Filter whether the clients can view global.
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// User data of [`ZwlrGammaControlV1`]
+#[derive(Debug)]
+pub struct GammaControlData {
+    output: Option<WeakOutput>,
+    gamma_size: u32,
+    inner: Mutex<GammaControlInner>,
+}
+
+#[derive(Debug, Default)]
+struct GammaControlInner {
+    failed: bool,
+    applied: bool,
+}
+
+/// Tracks the gamma control object currently controlling an output
+#[derive(Debug, Default)]
+struct OutputGammaControl(Mutex<Option<Weak<ZwlrGammaControlV1>>>);
+
+impl<D> GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData, D>
+    for GammaControlManagerState
+where
+    D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>,
+    D: Dispatch<ZwlrGammaControlManagerV1, ()>,
+    D: Dispatch<ZwlrGammaControlV1, GammaControlData>,
+    D: GammaControlHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        manager: New<ZwlrGammaControlManagerV1>,
+        _global_data: &GammaControlManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+
+    fn can_view(client: Client, global_data: &GammaControlManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrGammaControlManagerV1, (), D> for GammaControlManagerState
+where
+    D: Dispatch<ZwlrGammaControlManagerV1, ()>,
+    D: Dispatch<ZwlrGammaControlV1, GammaControlData>,
+    D: GammaControlHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _manager: &ZwlrGammaControlManagerV1,
+        request: zwlr_gamma_control_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_gamma_control_manager_v1::Request::GetGammaControl { id, output } => {
+                let output = Output::from_resource(&output);
+                let gamma_size = output
+                    .as_ref()
+                    .and_then(|output| state.gamma_size(output))
+                    .unwrap_or(0);
+
+                let control = data_init.init(
+                    id,
+                    GammaControlData {
+                        output: output.as_ref().map(Output::downgrade),
+                        gamma_size,
+                        inner: Mutex::new(GammaControlInner::default()),
+                    },
+                );
+
+                let Some(output) = output.filter(|_| gamma_size > 0) else {
+                    trace!("gamma control of unsupported output requested");
+                    fail(&control, control.data::<GammaControlData>().unwrap());
+                    return;
+                };
+
+                // only one client may control the gamma of an output at a time
+                let slot = output
+                    .user_data()
+                    .get_or_insert_threadsafe(OutputGammaControl::default);
+                let mut slot = slot.0.lock().unwrap();
+                if slot.as_ref().map(|c| c.upgrade().is_ok()).unwrap_or(false) {
+                    trace!(output = output.name(), "gamma of output is already controlled");
+                    fail(&control, control.data::<GammaControlData>().unwrap());
+                    return;
+                }
+                *slot = Some(control.downgrade());
+                drop(slot);
+
+                control.gamma_size(gamma_size);
+            }
+            zwlr_gamma_control_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrGammaControlV1, GammaControlData, D> for GammaControlManagerState
+where
+    D: Dispatch<ZwlrGammaControlV1, GammaControlData>,
+    D: GammaControlHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        control: &ZwlrGammaControlV1,
+        request: zwlr_gamma_control_v1::Request,
+        data: &GammaControlData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_gamma_control_v1::Request::SetGamma { fd } => {
+                if data.inner.lock().unwrap().failed {
+                    return;
+                }
+                let Some(output) = data.output.as_ref().and_then(WeakOutput::upgrade) else {
+                    fail(control, data);
+                    return;
+                };
+
+                let size = data.gamma_size as usize;
+                let ramp = match read_ramp(fd, size) {
+                    Ok(Some(ramp)) => ramp,
+                    Ok(None) => {
+                        control.post_error(
+                            zwlr_gamma_control_v1::Error::InvalidGamma,
+                            "gamma ramp has the wrong size",
+                        );
+                        return;
+                    }
+                    Err(err) => {
+                        warn!(?err, "Failed to read gamma ramp");
+                        fail(control, data);
+                        return;
+                    }
+                };
+
+                let (red, rest) = ramp.split_at(size);
+                let (green, blue) = rest.split_at(size);
+                if state.set_gamma(&output, Some([red, green, blue])) {
+                    data.inner.lock().unwrap().applied = true;
+                } else {
+                    fail(control, data);
+                    release(state, &output, control, data);
+                }
+            }
+            zwlr_gamma_control_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, control: &ZwlrGammaControlV1, data: &GammaControlData) {
+        if let Some(output) = data.output.as_ref().and_then(WeakOutput::upgrade) {
+            release(state, &output, control, data);
+        }
+    }
+}
+
+fn fail(control: &ZwlrGammaControlV1, data: &GammaControlData) {
+    let mut inner = data.inner.lock().unwrap();
+    if !inner.failed {
+        inner.failed = true;
+        control.failed();
+    }
+}
+
+/// Gives up control over the gamma of `output`, restoring the default ramps if necessary
+fn release<D: GammaControlHandler>(
+    state: &mut D,
+    output: &Output,
+    control: &ZwlrGammaControlV1,
+    data: &GammaControlData,
+) {
+    if let Some(slot) = output.user_data().get::<OutputGammaControl>() {
+        let mut slot = slot.0.lock().unwrap();
+        if slot.as_ref().map(|c| c == control).unwrap_or(false) {
+            *slot = None;
+        }
+    }
+
+    if std::mem::take(&mut data.inner.lock().unwrap().applied) {
+        state.set_gamma(output, None);
+    }
+}
+
+/// Reads the red, green and blue ramps from the client provided file descriptor.
+///
+/// The ramps are read from the start of the file, independent of its current offset.
+/// Returns `None`, if the file does not contain `size` entries per channel.
+fn read_ramp(fd: OwnedFd, size: usize) -> std::io::Result<Option<Vec<u16>>> {
+    let mut bytes = vec![0u8; size * 3 * 2];
+    let mut read = 0;
+    while read < bytes.len() {
+        match rustix::io::pread(&fd, &mut bytes[read..], read as u64) {
+            Ok(0) => return Ok(None),
+            Ok(n) => read += n,
+            Err(rustix::io::Errno::INTR) => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(Some(
+        bytes
+            .chunks_exact(2)
+            .map(|c| u16::from_ne_bytes([c[0], c[1]]))
+            .collect(),
+    ))
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_gamma_control {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1: $crate::wayland::gamma_control::GammaControlManagerGlobalData
+        ] => $crate::wayland::gamma_control::GammaControlManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1: ()
+        ] => $crate::wayland::gamma_control::GammaControlManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_v1::ZwlrGammaControlV1: $crate::wayland::gamma_control::GammaControlData
+        ] => $crate::wayland::gamma_control::GammaControlManagerState);
+    };
+}
@@ -0,0 +1,382 @@
+//! Utilities for handling the `ext-idle-notify` protocol
+//!
+//! This protocol allows clients like screen lockers or power management daemons to be notified,
+//! when the user has been idle for a given amount of time.
+//!
+//! The [`IdleNotifierState`] tracks the activity per seat. Whenever the user interacts with a seat,
+//! the compositor is expected to call [`IdleNotifierState::notify_activity`], which resets the timers
+//! of all notifications of that seat and resumes idle notifications.
+//!
+//! Surfaces inhibiting idle through the [`idle_inhibit`](crate::wayland::idle_inhibit) protocol
+//! can be registered through [`IdleNotifierState::inhibit`]. While any visible inhibitor exists,
+//! notifications requested through `get_idle_notification` won't idle.
+//! Notifications requested through `get_input_idle_notification` ignore inhibitors.
+//!
+//! ## How to use it
+//!
+//! ```no_run
+//! use smithay::delegate_idle_notify;
+//! use smithay::input::{Seat, SeatHandler, SeatState};
+//! use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+//! use smithay::wayland::idle_inhibit::IdleInhibitHandler;
+//! use smithay::wayland::idle_notify::{IdleNotifierHandler, IdleNotifierState};
+//!
+//! # struct State { idle_notifier_state: IdleNotifierState<State>, seat_state: SeatState<State> }
+//! # impl SeatHandler for State {
+//! #     type KeyboardFocus = WlSurface;
+//! #     type PointerFocus = WlSurface;
+//! #     type TouchFocus = WlSurface;
+//! #     fn seat_state(&mut self) -> &mut SeatState<Self> { &mut self.seat_state }
+//! # }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! # let handle: smithay::reexports::calloop::LoopHandle<'static, State> = unreachable!();
+//! let idle_notifier_state = IdleNotifierState::<State>::new(&display.handle(), handle);
+//!
+//! impl IdleNotifierHandler for State {
+//!     fn idle_notifier_state(&mut self) -> &mut IdleNotifierState<Self> {
+//!         &mut self.idle_notifier_state
+//!     }
+//! }
+//! delegate_idle_notify!(State);
+//!
+//! impl IdleInhibitHandler for State {
+//!     fn inhibit(&mut self, surface: WlSurface) {
+//!         self.idle_notifier_state.inhibit(surface);
+//!     }
+//!
+//!     fn uninhibit(&mut self, surface: WlSurface) {
+//!         self.idle_notifier_state.uninhibit(&surface);
+//!     }
+//! }
+//!
+//! # let seat: Seat<State> = unreachable!();
+//! # let mut state: State = unreachable!();
+//! // on every input event of a seat
+//! state.idle_notifier_state.notify_activity(&seat);
+//!
+//! // after rendering, only surfaces visible to the user should inhibit
+//! state.idle_notifier_state.refresh_inhibitors(|surface| {
+//!     // e.g. check `surface_primary_scanout_output`
+//! #   true
+//! });
+//! ```
+
+use std::time::{Duration, Instant};
+
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    LoopHandle, RegistrationToken,
+};
+use tracing::warn;
+use wayland_protocols::ext::idle_notify::v1::server::{
+    ext_idle_notification_v1::{self, ExtIdleNotificationV1},
+    ext_idle_notifier_v1::{self, ExtIdleNotifierV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    protocol::wl_surface::WlSurface,
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use crate::input::{Seat, SeatHandler};
+
+const NOTIFIER_VERSION: u32 = 2;
+
+/// Handler trait for ext-idle-notify
+pub trait IdleNotifierHandler: SeatHandler + Sized {
+    /// [`IdleNotifierState`] getter
+    fn idle_notifier_state(&mut self) -> &mut IdleNotifierState<Self>;
+}
+
+#[derive(Debug)]
+struct IdleNotification<D: SeatHandler> {
+    resource: ExtIdleNotificationV1,
+    seat: Seat<D>,
+    timeout: Duration,
+    ignore_inhibitors: bool,
+    last_activity: Instant,
+    timer: Option<RegistrationToken>,
+    idle: bool,
+}
+
+/// State of the ext_idle_notifier_v1 global
+#[derive(Debug)]
+pub struct IdleNotifierState<D: SeatHandler> {
+    global: GlobalId,
+    loop_handle: LoopHandle<'static, D>,
+    notifications: Vec<IdleNotification<D>>,
+    // inhibiting surfaces and whether they are visible
+    inhibitors: Vec<(WlSurface, bool)>,
+    is_inhibited: bool,
+}
+
+impl<D> IdleNotifierState<D>
+where
+    D: SeatHandler + IdleNotifierHandler + 'static,
+{
+    /// Create a new [`ExtIdleNotifierV1`] global
+    ///
+    /// The `loop_handle` is used to drive the timers of the notifications.
+    pub fn new(display: &DisplayHandle, loop_handle: LoopHandle<'static, D>) -> Self
+    where
+        D: GlobalDispatch<ExtIdleNotifierV1, ()>,
+        D: Dispatch<ExtIdleNotifierV1, ()>,
+        D: Dispatch<ExtIdleNotificationV1, ()>,
+    {
+        let global = display.create_global::<D, ExtIdleNotifierV1, _>(NOTIFIER_VERSION, ());
+
+        Self {
+            global,
+            loop_handle,
+            notifications: Vec::new(),
+            inhibitors: Vec::new(),
+            is_inhibited: false,
+        }
+    }
+
+    /// Returns the id of the [`ExtIdleNotifierV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Notify about user activity on the given seat
+    ///
+    /// Resets the timers of all notifications of the seat and resumes idle ones.
+    pub fn notify_activity(&mut self, seat: &Seat<D>) {
+        let now = Instant::now();
+        for idx in 0..self.notifications.len() {
+            if self.notifications[idx].seat != *seat {
+                continue;
+            }
+            self.notifications[idx].last_activity = now;
+            self.resume(idx);
+        }
+    }
+
+    /// Returns if idle is currently inhibited by a visible inhibitor
+    pub fn is_inhibited(&self) -> bool {
+        self.is_inhibited
+    }
+
+    /// Register a surface inhibiting idle
+    ///
+    /// Usually called from [`IdleInhibitHandler::inhibit`](crate::wayland::idle_inhibit::IdleInhibitHandler::inhibit).
+    /// The surface is considered visible until the next call to [`IdleNotifierState::refresh_inhibitors`].
+    pub fn inhibit(&mut self, surface: WlSurface) {
+        if !self.inhibitors.iter().any(|(s, _)| *s == surface) {
+            self.inhibitors.push((surface, true));
+        }
+        self.update_inhibited();
+    }
+
+    /// Remove a surface inhibiting idle
+    ///
+    /// Usually called from [`IdleInhibitHandler::uninhibit`](crate::wayland::idle_inhibit::IdleInhibitHandler::uninhibit).
+    pub fn uninhibit(&mut self, surface: &WlSurface) {
+        self.inhibitors.retain(|(s, _)| s != surface);
+        self.update_inhibited();
+    }
+
+    /// Re-evaluate which inhibitors are active
+    ///
+    /// Only surfaces for which `is_visible` returns `true` inhibit idle.
+    /// Should be called, whenever the visibility of surfaces may have changed, e.g. after rendering.
+    pub fn refresh_inhibitors<F>(&mut self, mut is_visible: F)
+    where
+        F: FnMut(&WlSurface) -> bool,
+    {
+        self.inhibitors.retain(|(surface, _)| surface.is_alive());
+        for (surface, visible) in self.inhibitors.iter_mut() {
+            *visible = is_visible(surface);
+        }
+        self.update_inhibited();
+    }
+
+    fn update_inhibited(&mut self) {
+        let is_inhibited = self.inhibitors.iter().any(|(_, visible)| *visible);
+        if is_inhibited == self.is_inhibited {
+            return;
+        }
+        self.is_inhibited = is_inhibited;
+
+        // inhibition counts as activity for the affected notifications
+        let now = Instant::now();
+        for idx in 0..self.notifications.len() {
+            if self.notifications[idx].ignore_inhibitors {
+                continue;
+            }
+            self.notifications[idx].last_activity = now;
+            self.resume(idx);
+        }
+    }
+
+    fn resume(&mut self, idx: usize) {
+        let notification = &mut self.notifications[idx];
+        if notification.idle {
+            notification.idle = false;
+            notification.resource.resumed();
+        }
+        if notification.timer.is_none() {
+            let deadline = notification.last_activity + notification.timeout;
+            let resource = notification.resource.clone();
+            let timer = self
+                .loop_handle
+                .insert_source(Timer::from_deadline(deadline), move |_, _, state| {
+                    state.idle_notifier_state().timer_fired(&resource)
+                });
+            match timer {
+                Ok(token) => self.notifications[idx].timer = Some(token),
+                Err(err) => warn!(?err, "Failed to insert idle notification timer"),
+            }
+        }
+    }
+
+    fn timer_fired(&mut self, resource: &ExtIdleNotificationV1) -> TimeoutAction {
+        let is_inhibited = self.is_inhibited;
+        let Some(notification) = self.notifications.iter_mut().find(|n| n.resource == *resource) else {
+            return TimeoutAction::Drop;
+        };
+
+        // there was activity in the meantime
+        let deadline = notification.last_activity + notification.timeout;
+        if Instant::now() < deadline {
+            return TimeoutAction::ToInstant(deadline);
+        }
+
+        notification.timer = None;
+        if !is_inhibited || notification.ignore_inhibitors {
+            notification.idle = true;
+            notification.resource.idled();
+        }
+        TimeoutAction::Drop
+    }
+
+    fn new_notification(
+        &mut self,
+        resource: ExtIdleNotificationV1,
+        seat: Seat<D>,
+        timeout: Duration,
+        ignore_inhibitors: bool,
+    ) {
+        self.notifications.push(IdleNotification {
+            resource,
+            seat,
+            timeout,
+            ignore_inhibitors,
+            last_activity: Instant::now(),
+            timer: None,
+            idle: false,
+        });
+        self.resume(self.notifications.len() - 1);
+    }
+
+    fn destroy_notification(&mut self, resource: &ExtIdleNotificationV1) {
+        if let Some(pos) = self.notifications.iter().position(|n| n.resource == *resource) {
+            let notification = self.notifications.remove(pos);
+            if let Some(timer) = notification.timer {
+                self.loop_handle.remove(timer);
+            }
+        }
+    }
+}
+
+impl<D> GlobalDispatch<ExtIdleNotifierV1, (), D> for IdleNotifierState<D>
+where
+    D: GlobalDispatch<ExtIdleNotifierV1, ()>,
+    D: Dispatch<ExtIdleNotifierV1, ()>,
+    D: Dispatch<ExtIdleNotificationV1, ()>,
+    D: SeatHandler + IdleNotifierHandler + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtIdleNotifierV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> Dispatch<ExtIdleNotifierV1, (), D> for IdleNotifierState<D>
+where
+    D: Dispatch<ExtIdleNotifierV1, ()>,
+    D: Dispatch<ExtIdleNotificationV1, ()>,
+    D: SeatHandler + IdleNotifierHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _notifier: &ExtIdleNotifierV1,
+        request: ext_idle_notifier_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let (id, timeout, seat, ignore_inhibitors) = match request {
+            ext_idle_notifier_v1::Request::GetIdleNotification { id, timeout, seat } => {
+                (id, timeout, seat, false)
+            }
+            ext_idle_notifier_v1::Request::GetInputIdleNotification { id, timeout, seat } => {
+                (id, timeout, seat, true)
+            }
+            ext_idle_notifier_v1::Request::Destroy => return,
+            _ => unreachable!(),
+        };
+
+        let resource = data_init.init(id, ());
+        // notifications for inert seats never idle
+        if let Some(seat) = Seat::<D>::from_resource(&seat) {
+            state.idle_notifier_state().new_notification(
+                resource,
+                seat,
+                Duration::from_millis(timeout as u64),
+                ignore_inhibitors,
+            );
+        }
+    }
+}
+
+impl<D> Dispatch<ExtIdleNotificationV1, (), D> for IdleNotifierState<D>
+where
+    D: Dispatch<ExtIdleNotificationV1, ()>,
+    D: SeatHandler + IdleNotifierHandler + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _notification: &ExtIdleNotificationV1,
+        request: ext_idle_notification_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_idle_notification_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, notification: &ExtIdleNotificationV1, _data: &()) {
+        state.idle_notifier_state().destroy_notification(notification);
+    }
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_idle_notify {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::idle_notify::v1::server::ext_idle_notifier_v1::ExtIdleNotifierV1: ()
+        ] => $crate::wayland::idle_notify::IdleNotifierState<$ty>);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::idle_notify::v1::server::ext_idle_notifier_v1::ExtIdleNotifierV1: ()
+        ] => $crate::wayland::idle_notify::IdleNotifierState<$ty>);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::idle_notify::v1::server::ext_idle_notification_v1::ExtIdleNotificationV1: ()
+        ] => $crate::wayland::idle_notify::IdleNotifierState<$ty>);
+    };
+}
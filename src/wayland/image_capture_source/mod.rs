@@ -0,0 +1,471 @@
+//! Utilities for handling the `ext-image-capture-source` protocol
+//!
+//! This protocol lets clients create opaque image capture sources, which can then be
+//! captured using the [`image_copy_capture`](crate::wayland::image_copy_capture) protocol.
+//!
+//! Sources can be created for an [`Output`] through the [`OutputCaptureSourceState`] global
+//! and, with the `desktop` feature enabled, for a [`Window`] through the [`ToplevelCaptureSourceState`]
+//! global. Clients refer to toplevels through `ext_foreign_toplevel_handle_v1` objects, the compositor
+//! resolves those to windows in [`ToplevelCaptureSourceHandler::window_for_foreign_toplevel`].
+//!
+//! ## How to use it
+//!
+//! ```
+//! use smithay::delegate_image_capture_source;
+//! use smithay::wayland::image_capture_source::{ImageCaptureSourceHandler, OutputCaptureSourceState};
+//!
+//! # struct State;
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the global for output capture sources
+//! let output_source_state = OutputCaptureSourceState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! // Implement the necessary trait.
+//! impl ImageCaptureSourceHandler for State {}
+//! delegate_image_capture_source!(State);
+//! ```
+//!
+//! The resulting [`ImageCaptureSource`]s are handed to the compositor by the
+//! [`image_copy_capture`](crate::wayland::image_copy_capture) implementation.
+
+use std::sync::Arc;
+
+use wayland_protocols::ext::image_capture_source::v1::server::{
+    ext_image_capture_source_v1::{self, ExtImageCaptureSourceV1},
+    ext_output_image_capture_source_manager_v1::{self, ExtOutputImageCaptureSourceManagerV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+#[cfg(feature = "desktop")]
+use wayland_protocols::ext::{
+    foreign_toplevel_list::v1::server::ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1,
+    image_capture_source::v1::server::ext_foreign_toplevel_image_capture_source_manager_v1::{
+        self, ExtForeignToplevelImageCaptureSourceManagerV1,
+    },
+};
+
+#[cfg(feature = "desktop")]
+use crate::{
+    backend::renderer::{
+        element::{surface::WaylandSurfaceRenderElement, AsRenderElements},
+        ImportAll, Renderer,
+    },
+    desktop::Window,
+    utils::{Physical, Scale, Size},
+};
+use crate::{
+    output::{Output, WeakOutput},
+    utils::{user_data::UserDataMap, IsAlive},
+};
+
+const MANAGER_VERSION: u32 = 1;
+
+/// Handler trait for ext-image-capture-source
+pub trait ImageCaptureSourceHandler {
+    /// A capture source was destroyed by the client.
+    fn source_destroyed(&mut self, source: ImageCaptureSource) {
+        let _ = source;
+    }
+}
+
+/// Handler trait for toplevel capture sources
+#[cfg(feature = "desktop")]
+pub trait ToplevelCaptureSourceHandler: ImageCaptureSourceHandler {
+    /// Returns the window referred to by a foreign toplevel handle.
+    ///
+    /// Use [`ForeignToplevelHandle::from_resource`](crate::wayland::foreign_toplevel::ForeignToplevelHandle::from_resource)
+    /// to look up the handle and its associated user data.
+    ///
+    /// Returning `None` creates a source, that cannot be captured.
+    fn window_for_foreign_toplevel(&mut self, handle: &ExtForeignToplevelHandleV1) -> Option<Window>;
+}
+
+/// What an [`ImageCaptureSource`] captures
+#[derive(Debug, Clone)]
+pub enum ImageCaptureSourceKind {
+    /// The contents of an output
+    Output(WeakOutput),
+    /// The surface tree of a window, including its popups
+    #[cfg(feature = "desktop")]
+    Toplevel(Window),
+}
+
+/// An image capture source created by a client
+#[derive(Debug, Clone)]
+pub struct ImageCaptureSource {
+    inner: Arc<SourceInner>,
+}
+
+#[derive(Debug)]
+struct SourceInner {
+    kind: ImageCaptureSourceKind,
+    user_data: UserDataMap,
+}
+
+impl PartialEq for ImageCaptureSource {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for ImageCaptureSource {}
+
+impl IsAlive for ImageCaptureSource {
+    fn alive(&self) -> bool {
+        match &self.inner.kind {
+            ImageCaptureSourceKind::Output(output) => output.upgrade().is_some(),
+            #[cfg(feature = "desktop")]
+            ImageCaptureSourceKind::Toplevel(window) => window.alive(),
+        }
+    }
+}
+
+impl ImageCaptureSource {
+    fn new(kind: ImageCaptureSourceKind) -> Self {
+        ImageCaptureSource {
+            inner: Arc::new(SourceInner {
+                kind,
+                user_data: UserDataMap::new(),
+            }),
+        }
+    }
+
+    /// Returns the [`ImageCaptureSource`] of a client object, if it can be captured.
+    pub fn from_resource(resource: &ExtImageCaptureSourceV1) -> Option<ImageCaptureSource> {
+        resource
+            .data::<ImageCaptureSourceData>()
+            .and_then(|data| data.source.clone())
+    }
+
+    /// Returns what is captured by this source
+    pub fn kind(&self) -> &ImageCaptureSourceKind {
+        &self.inner.kind
+    }
+
+    /// Returns the captured output, if this is an output source and the output still exists
+    pub fn output(&self) -> Option<Output> {
+        match &self.inner.kind {
+            ImageCaptureSourceKind::Output(output) => output.upgrade(),
+            #[cfg(feature = "desktop")]
+            _ => None,
+        }
+    }
+
+    /// Returns the captured window, if this is a toplevel source
+    #[cfg(feature = "desktop")]
+    pub fn window(&self) -> Option<&Window> {
+        match &self.inner.kind {
+            ImageCaptureSourceKind::Toplevel(window) => Some(window),
+            _ => None,
+        }
+    }
+
+    /// Returns the size of the captured window at the given scale.
+    ///
+    /// The size covers the window's surface tree including its popups.
+    /// Returns `None` for output sources.
+    #[cfg(feature = "desktop")]
+    pub fn toplevel_size(&self, scale: impl Into<Scale<f64>>) -> Option<Size<i32, Physical>> {
+        let window = self.window()?;
+        Some(window.bbox_with_popups().size.to_physical_precise_ceil(scale))
+    }
+
+    /// Returns the render elements of a captured window.
+    ///
+    /// Only the window's surface tree and its popups are rendered, relative
+    /// to the origin of a capture of size [`ImageCaptureSource::toplevel_size`].
+    /// Returns an empty list for output sources.
+    #[cfg(feature = "desktop")]
+    pub fn toplevel_render_elements<R>(
+        &self,
+        renderer: &mut R,
+        scale: impl Into<Scale<f64>>,
+        alpha: f32,
+    ) -> Vec<WaylandSurfaceRenderElement<R>>
+    where
+        R: Renderer + ImportAll,
+        <R as Renderer>::TextureId: 'static,
+    {
+        let Some(window) = self.window() else {
+            return Vec::new();
+        };
+        let scale = scale.into();
+        let location = window
+            .bbox_with_popups()
+            .loc
+            .upscale(-1)
+            .to_physical_precise_round(scale);
+        window.render_elements(renderer, location, scale, alpha)
+    }
+
+    /// Returns the [`UserDataMap`] associated with this source
+    pub fn user_data(&self) -> &UserDataMap {
+        &self.inner.user_data
+    }
+}
+
+/// User data of [`ExtImageCaptureSourceV1`]
+#[derive(Debug)]
+pub struct ImageCaptureSourceData {
+    source: Option<ImageCaptureSource>,
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct ImageCaptureSourceGlobalData {
+    /// Filter whether the clients can view global.
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// State of the ext-output-image-capture-source-manager global
+#[derive(Debug)]
+pub struct OutputCaptureSourceState {
+    global: GlobalId,
+}
+
+impl OutputCaptureSourceState {
+    /// Create a new [`ExtOutputImageCaptureSourceManagerV1`] global.
+    ///
+    /// The `filter` decides which clients are allowed to create capture sources.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ExtOutputImageCaptureSourceManagerV1, ImageCaptureSourceGlobalData>,
+        D: Dispatch<ExtOutputImageCaptureSourceManagerV1, ()>,
+        D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+        D: ImageCaptureSourceHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = ImageCaptureSourceGlobalData {
+            filter: Box::new(filter),
+        };
+        let global =
+            display.create_global::<D, ExtOutputImageCaptureSourceManagerV1, _>(MANAGER_VERSION, data);
+
+        Self { global }
+    }
+
+    /// Returns the id of the [`ExtOutputImageCaptureSourceManagerV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+impl<D> GlobalDispatch<ExtOutputImageCaptureSourceManagerV1, ImageCaptureSourceGlobalData, D>
+    for OutputCaptureSourceState
+where
+    D: GlobalDispatch<ExtOutputImageCaptureSourceManagerV1, ImageCaptureSourceGlobalData>,
+    D: Dispatch<ExtOutputImageCaptureSourceManagerV1, ()>,
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+    D: ImageCaptureSourceHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        manager: New<ExtOutputImageCaptureSourceManagerV1>,
+        _global_data: &ImageCaptureSourceGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+
+    fn can_view(client: Client, global_data: &ImageCaptureSourceGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ExtOutputImageCaptureSourceManagerV1, (), D> for OutputCaptureSourceState
+where
+    D: Dispatch<ExtOutputImageCaptureSourceManagerV1, ()>,
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+    D: ImageCaptureSourceHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _manager: &ExtOutputImageCaptureSourceManagerV1,
+        request: ext_output_image_capture_source_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_output_image_capture_source_manager_v1::Request::CreateSource { source, output } => {
+                let source_data = Output::from_resource(&output).map(|output| {
+                    ImageCaptureSource::new(ImageCaptureSourceKind::Output(output.downgrade()))
+                });
+                data_init.init(source, ImageCaptureSourceData { source: source_data });
+            }
+            ext_output_image_capture_source_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// State of the ext-foreign-toplevel-image-capture-source-manager global
+#[cfg(feature = "desktop")]
+#[derive(Debug)]
+pub struct ToplevelCaptureSourceState {
+    global: GlobalId,
+}
+
+#[cfg(feature = "desktop")]
+impl ToplevelCaptureSourceState {
+    /// Create a new [`ExtForeignToplevelImageCaptureSourceManagerV1`] global.
+    ///
+    /// The `filter` decides which clients are allowed to create capture sources.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ImageCaptureSourceGlobalData>,
+        D: Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ()>,
+        D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+        D: ToplevelCaptureSourceHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = ImageCaptureSourceGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display
+            .create_global::<D, ExtForeignToplevelImageCaptureSourceManagerV1, _>(MANAGER_VERSION, data);
+
+        Self { global }
+    }
+
+    /// Returns the id of the [`ExtForeignToplevelImageCaptureSourceManagerV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+#[cfg(feature = "desktop")]
+impl<D> GlobalDispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ImageCaptureSourceGlobalData, D>
+    for ToplevelCaptureSourceState
+where
+    D: GlobalDispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ImageCaptureSourceGlobalData>,
+    D: Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ()>,
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+    D: ToplevelCaptureSourceHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        manager: New<ExtForeignToplevelImageCaptureSourceManagerV1>,
+        _global_data: &ImageCaptureSourceGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+
+    fn can_view(client: Client, global_data: &ImageCaptureSourceGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+#[cfg(feature = "desktop")]
+impl<D> Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, (), D> for ToplevelCaptureSourceState
+where
+    D: Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, ()>,
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+    D: ToplevelCaptureSourceHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _manager: &ExtForeignToplevelImageCaptureSourceManagerV1,
+        request: ext_foreign_toplevel_image_capture_source_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_foreign_toplevel_image_capture_source_manager_v1::Request::CreateSource {
+                source,
+                toplevel_handle,
+            } => {
+                let source_data = state
+                    .window_for_foreign_toplevel(&toplevel_handle)
+                    .map(|window| ImageCaptureSource::new(ImageCaptureSourceKind::Toplevel(window)));
+                data_init.init(source, ImageCaptureSourceData { source: source_data });
+            }
+            ext_foreign_toplevel_image_capture_source_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData, D> for OutputCaptureSourceState
+where
+    D: Dispatch<ExtImageCaptureSourceV1, ImageCaptureSourceData>,
+    D: ImageCaptureSourceHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _source: &ExtImageCaptureSourceV1,
+        request: ext_image_capture_source_v1::Request,
+        _data: &ImageCaptureSourceData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_image_capture_source_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        _source: &ExtImageCaptureSourceV1,
+        data: &ImageCaptureSourceData,
+    ) {
+        if let Some(source) = data.source.clone() {
+            state.source_destroyed(source);
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_image_capture_source {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_output_image_capture_source_manager_v1::ExtOutputImageCaptureSourceManagerV1: $crate::wayland::image_capture_source::ImageCaptureSourceGlobalData
+        ] => $crate::wayland::image_capture_source::OutputCaptureSourceState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_output_image_capture_source_manager_v1::ExtOutputImageCaptureSourceManagerV1: ()
+        ] => $crate::wayland::image_capture_source::OutputCaptureSourceState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_image_capture_source_v1::ExtImageCaptureSourceV1: $crate::wayland::image_capture_source::ImageCaptureSourceData
+        ] => $crate::wayland::image_capture_source::OutputCaptureSourceState);
+    };
+}
+
+/// Delegates the toplevel capture source global.
+///
+/// Has to be used in addition to [`delegate_image_capture_source`].
+#[cfg(feature = "desktop")]
+#[macro_export]
+macro_rules! delegate_toplevel_capture_source {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_foreign_toplevel_image_capture_source_manager_v1::ExtForeignToplevelImageCaptureSourceManagerV1: $crate::wayland::image_capture_source::ImageCaptureSourceGlobalData
+        ] => $crate::wayland::image_capture_source::ToplevelCaptureSourceState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_capture_source::v1::server::ext_foreign_toplevel_image_capture_source_manager_v1::ExtForeignToplevelImageCaptureSourceManagerV1: ()
+        ] => $crate::wayland::image_capture_source::ToplevelCaptureSourceState);
+    };
+}
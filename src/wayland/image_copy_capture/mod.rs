@@ -0,0 +1,1214 @@
+//! Utilities for handling the `ext-image-copy-capture` protocol
+//!
+//! This protocol allows clients to capture [`ImageCaptureSource`]s, created through the
+//! [`image_capture_source`](crate::wayland::image_capture_source) protocol, into client provided buffers.
+//! Compared to `wlr-screencopy` it supports capturing single windows, capturing the cursor separately
+//! and reports damage between consecutive frames of a capture session.
+//!
+//! ## How to use it
+//!
+//! ### Initialization
+//!
+//! To initialize this implementation create the [`ImageCopyCaptureState`] and
+//! implement the [`ImageCopyCaptureHandler`], as shown in this example:
+//!
+//! ```
+//! use smithay::{delegate_image_capture_source, delegate_image_copy_capture};
+//! use smithay::wayland::image_capture_source::{
+//!     ImageCaptureSource, ImageCaptureSourceHandler, OutputCaptureSourceState,
+//! };
+//! use smithay::wayland::image_copy_capture::{
+//!     BufferConstraints, Frame, ImageCopyCaptureHandler, ImageCopyCaptureState, Session,
+//! };
+//!
+//! # struct State { sessions: Vec<Session>, pending_frames: Vec<Frame> }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! let output_source_state = OutputCaptureSourceState::new::<State, _>(&display.handle(), |_client| true);
+//! let image_copy_capture_state = ImageCopyCaptureState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! impl ImageCaptureSourceHandler for State {}
+//! delegate_image_capture_source!(State);
+//!
+//! // Implement the necessary trait.
+//! impl ImageCopyCaptureHandler for State {
+//!     fn capture_constraints(&mut self, source: &ImageCaptureSource) -> Option<BufferConstraints> {
+//!         // Advertise the size of the source and the supported buffer formats.
+//!         BufferConstraints::for_source(source)
+//!     }
+//!
+//!     fn new_session(&mut self, session: Session) {
+//!         self.sessions.push(session);
+//!     }
+//!
+//!     fn frame(&mut self, session: &Session, frame: Frame) {
+//!         // Store the frame and fulfill it once the source was damaged.
+//!         self.pending_frames.push(frame);
+//!     }
+//!
+//!     fn session_destroyed(&mut self, session: Session) {
+//!         self.sessions.retain(|s| *s != session);
+//!     }
+//! }
+//! delegate_image_copy_capture!(State);
+//!
+//! // You're now ready to go!
+//! ```
+//!
+//! ### Fulfilling frames
+//!
+//! The compositor decides when a requested [`Frame`] is captured:
+//!
+//! - Frames should only be captured once the source was damaged. Use [`Frame::damage_elements`]
+//!   to compute the damage since the last frame of the session and keep the frame around until
+//!   it returns `true`. Damage is tracked per [`Session`] using an [`OutputDamageTracker`].
+//! - For output sources [`Frame::copy_framebuffer`] copies the currently bound framebuffer
+//!   into the client buffer, after the output was rendered.
+//! - For toplevel sources (or any other set of elements) [`Frame::render_elements`] renders
+//!   the elements directly into the client buffer. Use
+//!   [`ImageCaptureSource::toplevel_render_elements`] to render only the captured window.
+//! - [`Session::paint_cursors`] signals whether the cursor should be part of the capture.
+//! - Finally call [`Frame::submit`] to notify the client.
+//!
+//! Dropping a [`Frame`] without submitting it notifies the client about the failure.
+//!
+//! ### Cursor sessions
+//!
+//! Clients may capture the cursor image of a pointer separately. The compositor receives
+//! a [`CursorSession`] through [`ImageCopyCaptureHandler::new_cursor_session`] and should
+//! update its position and hotspot. Frames of cursor sessions are requested through the
+//! usual [`ImageCopyCaptureHandler::frame`] callback, [`Session::cursor_session`] tells them apart.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tracing::trace;
+use wayland_protocols::ext::image_copy_capture::v1::server::{
+    ext_image_copy_capture_cursor_session_v1::{self, ExtImageCopyCaptureCursorSessionV1},
+    ext_image_copy_capture_frame_v1::{self, ExtImageCopyCaptureFrameV1},
+    ext_image_copy_capture_manager_v1::{self, ExtImageCopyCaptureManagerV1},
+    ext_image_copy_capture_session_v1::{self, ExtImageCopyCaptureSessionV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    protocol::{wl_buffer::WlBuffer, wl_pointer::WlPointer, wl_shm},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+
+use crate::{
+    backend::{
+        allocator::{dmabuf::Dmabuf, Buffer as _, Format, Fourcc},
+        renderer::{
+            damage::{Error as DamageError, OutputDamageTracker},
+            element::{Element, RenderElement},
+            Bind, Blit, ExportMem, Offscreen, Renderer, Texture, TextureFilter, TextureMapping,
+        },
+    },
+    output::OutputNoMode,
+    utils::{user_data::UserDataMap, Buffer, IsAlive, Physical, Point, Rectangle, Scale, Size, Transform},
+    wayland::{
+        dmabuf::get_dmabuf,
+        image_capture_source::{ImageCaptureSource, ImageCaptureSourceKind},
+        shm::{self, shm_format_to_fourcc, BufferAccessError},
+    },
+};
+
+const MANAGER_VERSION: u32 = 1;
+
+/// State of the ext-image-copy-capture global
+#[derive(Debug)]
+pub struct ImageCopyCaptureState {
+    global: GlobalId,
+}
+
+impl ImageCopyCaptureState {
+    /// Create a new [`ExtImageCopyCaptureManagerV1`] global.
+    ///
+    /// The `filter` decides which clients are allowed to capture image sources.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ExtImageCopyCaptureManagerV1, ImageCopyCaptureGlobalData>,
+        D: Dispatch<ExtImageCopyCaptureManagerV1, ()>,
+        D: Dispatch<ExtImageCopyCaptureSessionV1, SessionData>,
+        D: Dispatch<ExtImageCopyCaptureCursorSessionV1, CursorSessionData>,
+        D: Dispatch<ExtImageCopyCaptureFrameV1, FrameData>,
+        D: ImageCopyCaptureHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = ImageCopyCaptureGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ExtImageCopyCaptureManagerV1, _>(MANAGER_VERSION, data);
+
+        Self { global }
+    }
+
+    /// Returns the id of the [`ExtImageCopyCaptureManagerV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// Dmabuf constraints of a capture session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DmabufConstraints {
+    /// Device used for allocating buffers
+    pub device: libc::dev_t,
+    /// Supported formats and modifiers
+    pub formats: Vec<Format>,
+}
+
+/// Buffer constraints advertised to clients for a capture session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferConstraints {
+    /// Size of the buffers
+    pub size: Size<i32, Buffer>,
+    /// Supported formats of shm buffers
+    pub shm: Vec<wl_shm::Format>,
+    /// Supported dmabuf formats, if dmabuf buffers are supported
+    pub dma: Option<DmabufConstraints>,
+}
+
+impl BufferConstraints {
+    /// Returns the default constraints for a source.
+    ///
+    /// Outputs are captured in their current mode, windows at scale 1.
+    /// Only shm buffers in the `Argb8888` and `Xrgb8888` formats are advertised.
+    ///
+    /// Returns `None`, if the source is not available anymore.
+    pub fn for_source(source: &ImageCaptureSource) -> Option<BufferConstraints> {
+        if !source.alive() {
+            return None;
+        }
+        let size = match source.kind() {
+            ImageCaptureSourceKind::Output(output) => output.upgrade()?.current_mode()?.size,
+            #[cfg(feature = "desktop")]
+            ImageCaptureSourceKind::Toplevel(_) => source.toplevel_size(1.)?,
+        };
+        Some(BufferConstraints {
+            size: (size.w, size.h).into(),
+            shm: vec![wl_shm::Format::Argb8888, wl_shm::Format::Xrgb8888],
+            dma: None,
+        })
+    }
+}
+
+/// Handler trait for ext-image-copy-capture
+pub trait ImageCopyCaptureHandler {
+    /// Returns the buffer constraints for capturing the given source.
+    ///
+    /// Returning `None` stops the session, because the source cannot be captured.
+    fn capture_constraints(&mut self, source: &ImageCaptureSource) -> Option<BufferConstraints>;
+
+    /// A new capture session was created.
+    ///
+    /// Keep track of the session to update its constraints, once the source changes,
+    /// and to stop it, once the source is not available anymore.
+    fn new_session(&mut self, session: Session);
+
+    /// Returns the buffer constraints for capturing the cursor image of a cursor session.
+    ///
+    /// Returning `None` stops the session. The default implementation does not support
+    /// capturing cursors.
+    fn cursor_capture_constraints(&mut self, session: &CursorSession) -> Option<BufferConstraints> {
+        let _ = session;
+        None
+    }
+
+    /// A new cursor capture session was created.
+    fn new_cursor_session(&mut self, session: CursorSession) {
+        let _ = session;
+    }
+
+    /// A client provided a buffer for a frame of a session and waits for it to be captured.
+    ///
+    /// The compositor is free to decide when the frame is captured. See [`Frame`] for details.
+    fn frame(&mut self, session: &Session, frame: Frame);
+
+    /// A capture session was destroyed by the client.
+    fn session_destroyed(&mut self, session: Session) {
+        let _ = session;
+    }
+
+    /// A cursor capture session was destroyed by the client.
+    fn cursor_session_destroyed(&mut self, session: CursorSession) {
+        let _ = session;
+    }
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct ImageCopyCaptureGlobalData {
+    /// Filter whether the clients can view global.
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// User data of [`ExtImageCopyCaptureSessionV1`]
+#[derive(Debug)]
+pub struct SessionData {
+    inner: Arc<Mutex<SessionInner>>,
+}
+
+#[derive(Debug)]
+struct SessionInner {
+    source: Option<ImageCaptureSource>,
+    cursor_session: Option<CursorSession>,
+    paint_cursors: bool,
+    constraints: Option<BufferConstraints>,
+    damage_tracker: Option<OutputDamageTracker>,
+    // offscreen buffers used for rendering into shm buffers, see `OffscreenBuffers`
+    offscreen_buffers: UserDataMap,
+    has_frame: bool,
+    announced: bool,
+    stopped: bool,
+}
+
+// Offscreen buffers of a session per renderer id
+type OffscreenBuffers<T> = RefCell<HashMap<usize, (Fourcc, Size<i32, Buffer>, T)>>;
+
+/// A capture session of an [`ImageCaptureSource`]
+#[derive(Debug, Clone)]
+pub struct Session {
+    session: ExtImageCopyCaptureSessionV1,
+    inner: Arc<Mutex<SessionInner>>,
+}
+
+impl PartialEq for Session {
+    fn eq(&self, other: &Self) -> bool {
+        self.session == other.session
+    }
+}
+
+impl Eq for Session {}
+
+impl Session {
+    /// Returns the captured source
+    ///
+    /// For sessions of a [`CursorSession`] this is the source the cursor is captured on.
+    pub fn source(&self) -> Option<ImageCaptureSource> {
+        self.inner.lock().unwrap().source.clone()
+    }
+
+    /// Returns the cursor session, if this session captures a cursor
+    pub fn cursor_session(&self) -> Option<CursorSession> {
+        self.inner.lock().unwrap().cursor_session.clone()
+    }
+
+    /// Returns whether cursors should be painted onto captured frames
+    pub fn paint_cursors(&self) -> bool {
+        self.inner.lock().unwrap().paint_cursors
+    }
+
+    /// Returns the currently advertised buffer constraints
+    pub fn constraints(&self) -> Option<BufferConstraints> {
+        self.inner.lock().unwrap().constraints.clone()
+    }
+
+    /// Returns whether the session was stopped
+    pub fn is_stopped(&self) -> bool {
+        self.inner.lock().unwrap().stopped
+    }
+
+    /// Advertises new buffer constraints to the client.
+    ///
+    /// Should be called once the size of the source changed. Frames using the
+    /// previous constraints will fail.
+    pub fn update_constraints(&self, constraints: BufferConstraints) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.stopped || inner.constraints.as_ref() == Some(&constraints) {
+            return;
+        }
+        send_constraints(&self.session, &constraints);
+        inner.constraints = Some(constraints);
+    }
+
+    /// Stops the session, e.g. because the source is not available anymore.
+    pub fn stop(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.stopped {
+            inner.stopped = true;
+            inner.damage_tracker = None;
+            self.session.stopped();
+        }
+    }
+
+    /// Returns the underlying [`ExtImageCopyCaptureSessionV1`]
+    pub fn session(&self) -> &ExtImageCopyCaptureSessionV1 {
+        &self.session
+    }
+}
+
+fn send_constraints(session: &ExtImageCopyCaptureSessionV1, constraints: &BufferConstraints) {
+    session.buffer_size(constraints.size.w as u32, constraints.size.h as u32);
+    for format in &constraints.shm {
+        session.shm_format(*format);
+    }
+    if let Some(dma) = &constraints.dma {
+        session.dmabuf_device(dma.device.to_ne_bytes().to_vec());
+        let mut codes = dma.formats.iter().map(|format| format.code).collect::<Vec<_>>();
+        codes.sort_unstable_by_key(|code| *code as u32);
+        codes.dedup();
+        for code in codes {
+            let modifiers = dma
+                .formats
+                .iter()
+                .filter(|format| format.code == code)
+                .flat_map(|format| u64::from(format.modifier).to_ne_bytes())
+                .collect::<Vec<_>>();
+            session.dmabuf_format(code as u32, modifiers);
+        }
+    }
+    session.done();
+}
+
+/// User data of [`ExtImageCopyCaptureCursorSessionV1`]
+#[derive(Debug)]
+pub struct CursorSessionData {
+    inner: Arc<Mutex<CursorSessionInner>>,
+}
+
+#[derive(Debug)]
+struct CursorSessionInner {
+    source: Option<ImageCaptureSource>,
+    pointer: WlPointer,
+    has_session: bool,
+    position: Option<Point<i32, Buffer>>,
+    hotspot: Point<i32, Buffer>,
+}
+
+/// A cursor capture session of a pointer on an [`ImageCaptureSource`]
+#[derive(Debug, Clone)]
+pub struct CursorSession {
+    session: ExtImageCopyCaptureCursorSessionV1,
+    inner: Arc<Mutex<CursorSessionInner>>,
+}
+
+impl PartialEq for CursorSession {
+    fn eq(&self, other: &Self) -> bool {
+        self.session == other.session
+    }
+}
+
+impl Eq for CursorSession {}
+
+impl CursorSession {
+    /// Returns the source the cursor is captured on
+    pub fn source(&self) -> Option<ImageCaptureSource> {
+        self.inner.lock().unwrap().source.clone()
+    }
+
+    /// Returns the pointer whose cursor is captured
+    pub fn pointer(&self) -> WlPointer {
+        self.inner.lock().unwrap().pointer.clone()
+    }
+
+    /// Updates the position of the cursor relative to the captured source.
+    ///
+    /// `None` signals, that the cursor is not within the captured area.
+    pub fn set_position(&self, position: Option<Point<i32, Buffer>>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.position == position {
+            return;
+        }
+        match (inner.position, position) {
+            (None, Some(_)) => {
+                self.session.enter();
+                // changes of the hotspot are not sent while the cursor is outside
+                self.session.hotspot(inner.hotspot.x, inner.hotspot.y);
+            }
+            (Some(_), None) => self.session.leave(),
+            _ => {}
+        }
+        if let Some(position) = position {
+            self.session.position(position.x, position.y);
+        }
+        inner.position = position;
+    }
+
+    /// Updates the hotspot of the cursor image.
+    pub fn set_hotspot(&self, hotspot: Point<i32, Buffer>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.hotspot == hotspot {
+            return;
+        }
+        inner.hotspot = hotspot;
+        if inner.position.is_some() {
+            self.session.hotspot(hotspot.x, hotspot.y);
+        }
+    }
+
+    /// Returns the underlying [`ExtImageCopyCaptureCursorSessionV1`]
+    pub fn session(&self) -> &ExtImageCopyCaptureCursorSessionV1 {
+        &self.session
+    }
+}
+
+/// User data of [`ExtImageCopyCaptureFrameV1`]
+#[derive(Debug)]
+pub struct FrameData {
+    session: Session,
+    inner: Mutex<FrameState>,
+}
+
+#[derive(Debug, Default)]
+struct FrameState {
+    buffer: Option<WlBuffer>,
+    damage: Vec<Rectangle<i32, Buffer>>,
+    captured: bool,
+}
+
+impl<D> GlobalDispatch<ExtImageCopyCaptureManagerV1, ImageCopyCaptureGlobalData, D> for ImageCopyCaptureState
+where
+    D: GlobalDispatch<ExtImageCopyCaptureManagerV1, ImageCopyCaptureGlobalData>,
+    D: Dispatch<ExtImageCopyCaptureManagerV1, ()>,
+    D: Dispatch<ExtImageCopyCaptureSessionV1, SessionData>,
+    D: Dispatch<ExtImageCopyCaptureCursorSessionV1, CursorSessionData>,
+    D: Dispatch<ExtImageCopyCaptureFrameV1, FrameData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        manager: New<ExtImageCopyCaptureManagerV1>,
+        _global_data: &ImageCopyCaptureGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ());
+    }
+
+    fn can_view(client: Client, global_data: &ImageCopyCaptureGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+fn init_session<D>(
+    state: &mut D,
+    data_init: &mut DataInit<'_, D>,
+    session: New<ExtImageCopyCaptureSessionV1>,
+    source: Option<ImageCaptureSource>,
+    cursor_session: Option<CursorSession>,
+    paint_cursors: bool,
+) where
+    D: Dispatch<ExtImageCopyCaptureSessionV1, SessionData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    let source = source.filter(|source| source.alive());
+    let constraints = match (&source, &cursor_session) {
+        (Some(_), Some(cursor_session)) => state.cursor_capture_constraints(cursor_session),
+        (Some(source), None) => state.capture_constraints(source),
+        (None, _) => None,
+    };
+    let inner = Arc::new(Mutex::new(SessionInner {
+        source,
+        cursor_session,
+        paint_cursors,
+        constraints: constraints.clone(),
+        damage_tracker: None,
+        offscreen_buffers: UserDataMap::new(),
+        has_frame: false,
+        announced: constraints.is_some(),
+        stopped: constraints.is_none(),
+    }));
+    let session = data_init.init(session, SessionData { inner: inner.clone() });
+
+    match constraints {
+        Some(constraints) => {
+            send_constraints(&session, &constraints);
+            state.new_session(Session { session, inner });
+        }
+        None => {
+            trace!("capture of unavailable source requested");
+            session.stopped();
+        }
+    }
+}
+
+impl<D> Dispatch<ExtImageCopyCaptureManagerV1, (), D> for ImageCopyCaptureState
+where
+    D: Dispatch<ExtImageCopyCaptureManagerV1, ()>,
+    D: Dispatch<ExtImageCopyCaptureSessionV1, SessionData>,
+    D: Dispatch<ExtImageCopyCaptureCursorSessionV1, CursorSessionData>,
+    D: Dispatch<ExtImageCopyCaptureFrameV1, FrameData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        manager: &ExtImageCopyCaptureManagerV1,
+        request: ext_image_copy_capture_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_image_copy_capture_manager_v1::Request::CreateSession {
+                session,
+                source,
+                options,
+            } => {
+                let options = match options {
+                    WEnum::Value(options) => options,
+                    WEnum::Unknown(_) => {
+                        manager.post_error(
+                            ext_image_copy_capture_manager_v1::Error::InvalidOption,
+                            "Unknown capture options",
+                        );
+                        return;
+                    }
+                };
+                let paint_cursors =
+                    options.contains(ext_image_copy_capture_manager_v1::Options::PaintCursors);
+                let source = ImageCaptureSource::from_resource(&source);
+                init_session(state, data_init, session, source, None, paint_cursors);
+            }
+            ext_image_copy_capture_manager_v1::Request::CreatePointerCursorSession {
+                session,
+                source,
+                pointer,
+            } => {
+                let inner = Arc::new(Mutex::new(CursorSessionInner {
+                    source: ImageCaptureSource::from_resource(&source),
+                    pointer,
+                    has_session: false,
+                    position: None,
+                    hotspot: Point::default(),
+                }));
+                let session = data_init.init(session, CursorSessionData { inner: inner.clone() });
+                state.new_cursor_session(CursorSession { session, inner });
+            }
+            ext_image_copy_capture_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ExtImageCopyCaptureCursorSessionV1, CursorSessionData, D> for ImageCopyCaptureState
+where
+    D: Dispatch<ExtImageCopyCaptureCursorSessionV1, CursorSessionData>,
+    D: Dispatch<ExtImageCopyCaptureSessionV1, SessionData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        cursor_session: &ExtImageCopyCaptureCursorSessionV1,
+        request: ext_image_copy_capture_cursor_session_v1::Request,
+        data: &CursorSessionData,
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_image_copy_capture_cursor_session_v1::Request::GetCaptureSession { session } => {
+                let source = {
+                    let mut inner = data.inner.lock().unwrap();
+                    if inner.has_session {
+                        cursor_session.post_error(
+                            ext_image_copy_capture_cursor_session_v1::Error::DuplicateSession,
+                            "get_capture_session was already sent",
+                        );
+                        return;
+                    }
+                    inner.has_session = true;
+                    inner.source.clone()
+                };
+                let cursor_session = CursorSession {
+                    session: cursor_session.clone(),
+                    inner: data.inner.clone(),
+                };
+                init_session(state, data_init, session, source, Some(cursor_session), false);
+            }
+            ext_image_copy_capture_cursor_session_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        cursor_session: &ExtImageCopyCaptureCursorSessionV1,
+        data: &CursorSessionData,
+    ) {
+        state.cursor_session_destroyed(CursorSession {
+            session: cursor_session.clone(),
+            inner: data.inner.clone(),
+        });
+    }
+}
+
+impl<D> Dispatch<ExtImageCopyCaptureSessionV1, SessionData, D> for ImageCopyCaptureState
+where
+    D: Dispatch<ExtImageCopyCaptureSessionV1, SessionData>,
+    D: Dispatch<ExtImageCopyCaptureFrameV1, FrameData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        session: &ExtImageCopyCaptureSessionV1,
+        request: ext_image_copy_capture_session_v1::Request,
+        data: &SessionData,
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_image_copy_capture_session_v1::Request::CreateFrame { frame } => {
+                {
+                    let mut inner = data.inner.lock().unwrap();
+                    if inner.has_frame {
+                        session.post_error(
+                            ext_image_copy_capture_session_v1::Error::DuplicateFrame,
+                            "The previous frame was not destroyed",
+                        );
+                        return;
+                    }
+                    inner.has_frame = true;
+                }
+                data_init.init(
+                    frame,
+                    FrameData {
+                        session: Session {
+                            session: session.clone(),
+                            inner: data.inner.clone(),
+                        },
+                        inner: Mutex::new(FrameState::default()),
+                    },
+                );
+            }
+            ext_image_copy_capture_session_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        session: &ExtImageCopyCaptureSessionV1,
+        data: &SessionData,
+    ) {
+        let announced = {
+            let mut inner = data.inner.lock().unwrap();
+            inner.stopped = true;
+            inner.damage_tracker = None;
+            inner.announced
+        };
+        // sessions stopped during creation were never handed to the compositor
+        if announced {
+            state.session_destroyed(Session {
+                session: session.clone(),
+                inner: data.inner.clone(),
+            });
+        }
+    }
+}
+
+impl<D> Dispatch<ExtImageCopyCaptureFrameV1, FrameData, D> for ImageCopyCaptureState
+where
+    D: Dispatch<ExtImageCopyCaptureFrameV1, FrameData>,
+    D: ImageCopyCaptureHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        frame: &ExtImageCopyCaptureFrameV1,
+        request: ext_image_copy_capture_frame_v1::Request,
+        data: &FrameData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let mut inner = data.inner.lock().unwrap();
+        if !matches!(request, ext_image_copy_capture_frame_v1::Request::Destroy) && inner.captured {
+            frame.post_error(
+                ext_image_copy_capture_frame_v1::Error::AlreadyCaptured,
+                "The frame was already captured",
+            );
+            return;
+        }
+
+        match request {
+            ext_image_copy_capture_frame_v1::Request::AttachBuffer { buffer } => {
+                inner.buffer = Some(buffer);
+            }
+            ext_image_copy_capture_frame_v1::Request::DamageBuffer { x, y, width, height } => {
+                if x < 0 || y < 0 || width <= 0 || height <= 0 {
+                    frame.post_error(
+                        ext_image_copy_capture_frame_v1::Error::InvalidBufferDamage,
+                        "Invalid buffer damage",
+                    );
+                    return;
+                }
+                inner
+                    .damage
+                    .push(Rectangle::from_loc_and_size((x, y), (width, height)));
+            }
+            ext_image_copy_capture_frame_v1::Request::Capture => {
+                let Some(buffer) = inner.buffer.clone() else {
+                    frame.post_error(
+                        ext_image_copy_capture_frame_v1::Error::NoBuffer,
+                        "Capture sent without attach_buffer",
+                    );
+                    return;
+                };
+                inner.captured = true;
+                let buffer_damage = std::mem::take(&mut inner.damage);
+                drop(inner);
+
+                let session = data.session.clone();
+                let (stopped, constraints) = {
+                    let session = session.inner.lock().unwrap();
+                    (session.stopped, session.constraints.clone())
+                };
+                if stopped {
+                    frame.failed(ext_image_copy_capture_frame_v1::FailureReason::Stopped);
+                    return;
+                }
+                let Some(buffer) = constraints
+                    .as_ref()
+                    .and_then(|constraints| CaptureBuffer::validate(&buffer, constraints))
+                else {
+                    frame.failed(ext_image_copy_capture_frame_v1::FailureReason::BufferConstraints);
+                    return;
+                };
+
+                state.frame(
+                    &session,
+                    Frame {
+                        frame: frame.clone(),
+                        session: session.clone(),
+                        buffer,
+                        buffer_damage,
+                        submitted: false,
+                    },
+                );
+            }
+            ext_image_copy_capture_frame_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(_state: &mut D, _client: ClientId, _frame: &ExtImageCopyCaptureFrameV1, data: &FrameData) {
+        data.session.inner.lock().unwrap().has_frame = false;
+    }
+}
+
+/// Buffer provided by a client to capture a frame into
+#[derive(Debug, Clone)]
+pub enum CaptureBuffer {
+    /// A shm buffer
+    Shm {
+        /// The client buffer
+        buffer: WlBuffer,
+        /// The format of the buffer
+        format: wl_shm::Format,
+    },
+    /// A dmabuf buffer
+    Dmabuf {
+        /// The client buffer
+        buffer: WlBuffer,
+        /// The dmabuf backing the buffer
+        dmabuf: Dmabuf,
+    },
+}
+
+impl CaptureBuffer {
+    fn validate(buffer: &WlBuffer, constraints: &BufferConstraints) -> Option<CaptureBuffer> {
+        let size = constraints.size;
+
+        if let Ok(dmabuf) = get_dmabuf(buffer) {
+            let dma = constraints.dma.as_ref()?;
+            if !dma.formats.contains(&dmabuf.format()) || dmabuf.size() != size {
+                return None;
+            }
+            return Some(CaptureBuffer::Dmabuf {
+                buffer: buffer.clone(),
+                dmabuf,
+            });
+        }
+
+        shm::with_buffer_contents(buffer, |_, _, data| {
+            let bytes_per_pixel = shm::wl_bytes_per_pixel(WEnum::Value(data.format));
+            (constraints.shm.contains(&data.format)
+                && bytes_per_pixel > 0
+                && data.width == size.w
+                && data.height == size.h
+                && data.stride >= size.w * bytes_per_pixel)
+                .then(|| CaptureBuffer::Shm {
+                    buffer: buffer.clone(),
+                    format: data.format,
+                })
+        })
+        .ok()
+        .flatten()
+    }
+
+    /// Returns the underlying [`WlBuffer`]
+    pub fn wl_buffer(&self) -> &WlBuffer {
+        match self {
+            CaptureBuffer::Shm { buffer, .. } => buffer,
+            CaptureBuffer::Dmabuf { buffer, .. } => buffer,
+        }
+    }
+}
+
+/// Errors thrown by [`Frame::copy_framebuffer`] and [`Frame::render_elements`]
+#[derive(Debug, thiserror::Error)]
+pub enum CaptureError<E: std::error::Error> {
+    /// The renderer failed to capture the frame
+    #[error(transparent)]
+    Rendering(E),
+    /// The captured output has no mode set
+    #[error(transparent)]
+    OutputNoMode(OutputNoMode),
+    /// The client buffer could not be accessed
+    #[error(transparent)]
+    BufferAccess(BufferAccessError),
+    /// The format of the client buffer is not supported
+    #[error("Unsupported buffer format: {0:?}")]
+    UnsupportedFormat(wl_shm::Format),
+    /// The copied data does not match the size of the client buffer
+    #[error("The copied data does not match the size of the client buffer")]
+    UnexpectedSize,
+}
+
+/// A pending frame of a capture [`Session`]
+///
+/// Dropping this object without calling [`Frame::submit`] will notify the client,
+/// that the capture failed.
+#[derive(Debug)]
+pub struct Frame {
+    frame: ExtImageCopyCaptureFrameV1,
+    session: Session,
+    buffer: CaptureBuffer,
+    buffer_damage: Vec<Rectangle<i32, Buffer>>,
+    submitted: bool,
+}
+
+impl Frame {
+    /// Returns the session of this frame
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Returns the buffer provided by the client
+    pub fn buffer(&self) -> &CaptureBuffer {
+        &self.buffer
+    }
+
+    /// Returns the regions of the buffer, that the client reported as damaged
+    ///
+    /// These regions have to be redrawn, even if the source was not damaged.
+    pub fn buffer_damage(&self) -> &[Rectangle<i32, Buffer>] {
+        &self.buffer_damage
+    }
+
+    /// Returns the underlying [`ExtImageCopyCaptureFrameV1`]
+    pub fn frame(&self) -> &ExtImageCopyCaptureFrameV1 {
+        &self.frame
+    }
+
+    /// Reports damage to the client.
+    ///
+    /// The damage is given in buffer coordinates and is clipped to the buffer size.
+    pub fn damage(&mut self, damage: impl IntoIterator<Item = Rectangle<i32, Buffer>>) {
+        let size = self.buffer_size();
+        let bounds = Rectangle::from_loc_and_size((0, 0), size);
+        for rect in damage {
+            let Some(rect) = rect.intersection(bounds) else {
+                continue;
+            };
+            self.frame
+                .damage(rect.loc.x, rect.loc.y, rect.size.w, rect.size.h);
+        }
+    }
+
+    /// Computes and reports the damage of the session based on the given elements.
+    ///
+    /// The damage is tracked per session using an [`OutputDamageTracker`], relative to the
+    /// last call of this function for a frame of the same session. For output sources the
+    /// elements have to be provided the same way as for rendering the output, for toplevel
+    /// sources the elements returned by [`ImageCaptureSource::toplevel_render_elements`]
+    /// at the given `scale` should be used. The damage reported by the client through
+    /// [`Frame::buffer_damage`] is included as well.
+    ///
+    /// Returns whether the frame was damaged. A frame should only be captured once this returns `true`.
+    pub fn damage_elements<E: Element>(
+        &mut self,
+        elements: &[E],
+        scale: impl Into<Scale<f64>>,
+    ) -> Result<bool, OutputNoMode> {
+        let size = self.buffer_size();
+        let transform = self.transform();
+        let damage = {
+            let mut session = self.session.inner.lock().unwrap();
+            let source = session.source.clone();
+            let tracker =
+                session
+                    .damage_tracker
+                    .get_or_insert_with(|| match source.and_then(|s| s.output()) {
+                        Some(output) => OutputDamageTracker::from_output(&output),
+                        None => OutputDamageTracker::new((size.w, size.h), scale, Transform::Normal),
+                    });
+            tracker.damage_output(1, elements)?.0
+        };
+
+        let damage = damage
+            .into_iter()
+            .flatten()
+            .map(|rect| {
+                to_buffer_rect(transform.transform_rect_in(rect, &transform.transform_size(physical(size))))
+            })
+            .chain(self.buffer_damage.iter().copied())
+            .collect::<Vec<_>>();
+
+        let damaged = !damage.is_empty();
+        self.damage(damage);
+        Ok(damaged)
+    }
+
+    /// Copies the currently bound framebuffer into the client buffer.
+    ///
+    /// This is meant for output sources, after the output was rendered. Shm buffers
+    /// are filled using [`ExportMem::copy_framebuffer`], dmabuf buffers using [`Blit::blit_to`].
+    pub fn copy_framebuffer<R>(&mut self, renderer: &mut R) -> Result<(), CaptureError<R::Error>>
+    where
+        R: ExportMem + Blit<Dmabuf>,
+    {
+        let size = self.buffer_size();
+        let region = Rectangle::from_loc_and_size((0, 0), size);
+        match &self.buffer {
+            CaptureBuffer::Dmabuf { dmabuf, .. } => {
+                let rect = Rectangle::from_loc_and_size((0, 0), (size.w, size.h));
+                renderer
+                    .blit_to(dmabuf.clone(), rect, rect, TextureFilter::Nearest)
+                    .map_err(CaptureError::Rendering)
+            }
+            CaptureBuffer::Shm { buffer, format } => copy_to_shm(renderer, region, buffer, *format),
+        }
+    }
+
+    /// Renders the given elements into the client buffer.
+    ///
+    /// Dmabuf buffers are bound and rendered into directly, shm buffers are rendered into an
+    /// offscreen buffer created through [`Offscreen`] and read back using [`ExportMem`].
+    /// The offscreen buffer is kept by the session and reused, as long as the size and format
+    /// of the client buffers do not change.
+    /// The elements are expected in front-to-back order and in the coordinate space of the
+    /// source, e.g. as returned by [`ImageCaptureSource::toplevel_render_elements`].
+    pub fn render_elements<R, E, T>(
+        &mut self,
+        renderer: &mut R,
+        elements: &[E],
+        scale: impl Into<Scale<f64>>,
+        clear_color: [f32; 4],
+    ) -> Result<(), CaptureError<R::Error>>
+    where
+        R: Renderer + Bind<Dmabuf> + Offscreen<T> + ExportMem,
+        <R as Renderer>::TextureId: Texture,
+        E: RenderElement<R>,
+        T: Clone + 'static,
+    {
+        let size = self.buffer_size();
+        let transform = self.transform();
+        let mut damage_tracker = OutputDamageTracker::new(physical(size), scale, transform);
+        let render_result = match &self.buffer {
+            CaptureBuffer::Dmabuf { dmabuf, .. } => {
+                damage_tracker.render_output_with(renderer, dmabuf.clone(), 0, elements, clear_color)
+            }
+            CaptureBuffer::Shm { buffer, format } => {
+                let format = *format;
+                let fourcc = shm_format_to_fourcc(format).ok_or(CaptureError::UnsupportedFormat(format))?;
+                let target = self.offscreen_buffer(renderer, fourcc, size)?;
+                let buffer = buffer.clone();
+                let result = damage_tracker.render_output_with(renderer, target, 0, elements, clear_color);
+                if result.is_ok() {
+                    copy_to_shm(
+                        renderer,
+                        Rectangle::from_loc_and_size((0, 0), size),
+                        &buffer,
+                        format,
+                    )?;
+                }
+                result
+            }
+        };
+        match render_result {
+            Ok(_) => Ok(()),
+            Err(DamageError::Rendering(err)) => Err(CaptureError::Rendering(err)),
+            Err(DamageError::OutputNoMode(err)) => Err(CaptureError::OutputNoMode(err)),
+        }
+    }
+
+    /// Notifies the client, that the frame was captured.
+    ///
+    /// `timestamp` is the presentation time of the captured content in the monotonic clock.
+    pub fn submit(mut self, timestamp: impl Into<Duration>) {
+        let timestamp = timestamp.into();
+        let secs = timestamp.as_secs();
+        self.frame.transform(self.transform().into());
+        self.frame.presentation_time(
+            (secs >> 32) as u32,
+            (secs & 0xffff_ffff) as u32,
+            timestamp.subsec_nanos(),
+        );
+        self.frame.ready();
+        self.submitted = true;
+    }
+
+    /// Notifies the client, that the capture failed.
+    pub fn fail(mut self, reason: ext_image_copy_capture_frame_v1::FailureReason) {
+        self.frame.failed(reason);
+        self.submitted = true;
+    }
+
+    fn offscreen_buffer<R, T>(
+        &self,
+        renderer: &mut R,
+        fourcc: Fourcc,
+        size: Size<i32, Buffer>,
+    ) -> Result<T, CaptureError<R::Error>>
+    where
+        R: Offscreen<T>,
+        T: Clone + 'static,
+    {
+        let session = self.session.inner.lock().unwrap();
+        session
+            .offscreen_buffers
+            .insert_if_missing(OffscreenBuffers::<T>::default);
+        // the map is thread local, so it might not be accessible
+        let Some(buffers) = session.offscreen_buffers.get::<OffscreenBuffers<T>>() else {
+            return renderer
+                .create_buffer(fourcc, size)
+                .map_err(CaptureError::Rendering);
+        };
+
+        let mut buffers = buffers.borrow_mut();
+        match buffers.get(&renderer.id()) {
+            Some((cached_fourcc, cached_size, buffer))
+                if *cached_fourcc == fourcc && *cached_size == size =>
+            {
+                Ok(buffer.clone())
+            }
+            _ => {
+                let buffer = renderer
+                    .create_buffer(fourcc, size)
+                    .map_err(CaptureError::Rendering)?;
+                buffers.insert(renderer.id(), (fourcc, size, buffer.clone()));
+                Ok(buffer)
+            }
+        }
+    }
+
+    fn buffer_size(&self) -> Size<i32, Buffer> {
+        self.session
+            .constraints()
+            .map(|constraints| constraints.size)
+            .unwrap_or_default()
+    }
+
+    /// Returns the transform of the captured content within the buffer.
+    ///
+    /// For output sources this is the transform of the output, toplevels are never transformed.
+    pub fn transform(&self) -> Transform {
+        self.session
+            .source()
+            .and_then(|source| source.output())
+            .map(|output| output.current_transform())
+            .unwrap_or(Transform::Normal)
+    }
+}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        if !self.submitted {
+            self.frame
+                .failed(ext_image_copy_capture_frame_v1::FailureReason::Unknown);
+        }
+    }
+}
+
+fn physical(size: Size<i32, Buffer>) -> Size<i32, Physical> {
+    (size.w, size.h).into()
+}
+
+fn to_buffer_rect(rect: Rectangle<i32, Physical>) -> Rectangle<i32, Buffer> {
+    Rectangle::from_loc_and_size((rect.loc.x, rect.loc.y), (rect.size.w, rect.size.h))
+}
+
+/// Copies a region of the currently bound framebuffer into a shm buffer
+fn copy_to_shm<R: ExportMem>(
+    renderer: &mut R,
+    region: Rectangle<i32, Buffer>,
+    buffer: &WlBuffer,
+    format: wl_shm::Format,
+) -> Result<(), CaptureError<R::Error>> {
+    let fourcc = shm_format_to_fourcc(format).ok_or(CaptureError::UnsupportedFormat(format))?;
+    let bytes_per_pixel = match shm::wl_bytes_per_pixel(WEnum::Value(format)) {
+        0 => return Err(CaptureError::UnsupportedFormat(format)),
+        bpp => bpp as usize,
+    };
+    let mapping = renderer
+        .copy_framebuffer(region, fourcc)
+        .map_err(CaptureError::Rendering)?;
+    let flipped = mapping.flipped();
+    let data = renderer.map_texture(&mapping).map_err(CaptureError::Rendering)?;
+
+    let width = region.size.w as usize * bytes_per_pixel;
+    let height = region.size.h as usize;
+    if data.len() < width * height {
+        return Err(CaptureError::UnexpectedSize);
+    }
+
+    shm::with_buffer_contents_mut(buffer, |ptr, len, buffer_data| {
+        let offset = buffer_data.offset as usize;
+        let stride = buffer_data.stride as usize;
+        if offset + stride * height > len {
+            return Err(CaptureError::UnexpectedSize);
+        }
+        for row in 0..height {
+            let src_row = if flipped { height - 1 - row } else { row };
+            let src = &data[src_row * width..(src_row + 1) * width];
+            // SAFETY: the destination range was checked to be inside the pool
+            unsafe {
+                std::ptr::copy_nonoverlapping(src.as_ptr(), ptr.add(offset + row * stride), width);
+            }
+        }
+        Ok(())
+    })
+    .map_err(CaptureError::BufferAccess)?
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_image_copy_capture {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_manager_v1::ExtImageCopyCaptureManagerV1: $crate::wayland::image_copy_capture::ImageCopyCaptureGlobalData
+        ] => $crate::wayland::image_copy_capture::ImageCopyCaptureState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_manager_v1::ExtImageCopyCaptureManagerV1: ()
+        ] => $crate::wayland::image_copy_capture::ImageCopyCaptureState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_session_v1::ExtImageCopyCaptureSessionV1: $crate::wayland::image_copy_capture::SessionData
+        ] => $crate::wayland::image_copy_capture::ImageCopyCaptureState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_cursor_session_v1::ExtImageCopyCaptureCursorSessionV1: $crate::wayland::image_copy_capture::CursorSessionData
+        ] => $crate::wayland::image_copy_capture::ImageCopyCaptureState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::ext::image_copy_capture::v1::server::ext_image_copy_capture_frame_v1::ExtImageCopyCaptureFrameV1: $crate::wayland::image_copy_capture::FrameData
+        ] => $crate::wayland::image_copy_capture::ImageCopyCaptureState);
+    };
+}
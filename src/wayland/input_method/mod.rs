@@ -39,6 +39,7 @@
 //! impl SeatHandler for State {
 //!     type KeyboardFocus = WlSurface;
 //!     type PointerFocus = WlSurface;
+//!     type TouchFocus = WlSurface;
 //!     fn seat_state(&mut self) -> &mut SeatState<Self> {
 //!         &mut self.seat_state
 //!     }
@@ -45,33 +45,51 @@
 //! are not, for example).
 //!
 
+pub mod alpha_modifier;
 pub mod buffer;
+pub mod color_management;
 pub mod compositor;
 pub mod content_type;
 pub mod cursor_shape;
 pub mod dmabuf;
 #[cfg(feature = "backend_drm")]
 pub mod drm_lease;
+#[cfg(feature = "backend_drm")]
+pub mod drm_syncobj;
+pub mod foreign_toplevel;
 pub mod fractional_scale;
+pub mod gamma_control;
 pub mod idle_inhibit;
+pub mod idle_notify;
+pub mod image_capture_source;
+pub mod image_copy_capture;
 pub mod input_method;
 pub mod keyboard_shortcuts_inhibit;
 pub mod output;
+pub mod output_management;
 pub mod pointer_constraints;
 pub mod pointer_gestures;
 pub mod presentation;
 pub mod relative_pointer;
+pub mod screencopy;
 pub mod seat;
 pub mod security_context;
 pub mod selection;
 pub mod session_lock;
 pub mod shell;
 pub mod shm;
+pub mod single_pixel_buffer;
 pub mod socket;
 pub mod tablet_manager;
+pub mod tearing_control;
 pub mod text_input;
 pub mod viewporter;
 pub mod virtual_keyboard;
+pub mod virtual_pointer;
 pub mod xdg_activation;
+pub mod xdg_foreign;
+pub mod xdg_toplevel_icon;
 #[cfg(feature = "xwayland")]
 pub mod xwayland_keyboard_grab;
+#[cfg(feature = "xwayland")]
+pub mod xwayland_shell;
@@ -188,6 +188,7 @@ impl Output {
         new_transform: Option<Transform>,
         new_scale: Option<Scale>,
         new_location: Option<Point<i32, Logical>>,
+        changed: bool,
     ) {
         let inner = self.inner.0.lock().unwrap();
         // XdgOutput has to be updated before WlOutput
@@ -217,6 +218,12 @@ impl Output {
                 output.done();
             }
         }
+        drop(inner);
+
+        // every update invalidates pending output configurations, so only notify on actual changes
+        if changed {
+            crate::wayland::output_management::output_state_changed(self);
+        }
     }
 
     /// Check is given [`wl_output`](WlOutput) instance is managed by this [`Output`].
@@ -0,0 +1,966 @@
+//! Utilities for handling the `wlr-output-management` protocol
+//!
+//! This protocol allows privileged clients like `kanshi` or `wlr-randr` to inspect
+//! the connected outputs of the compositor and to change their configuration.
+//!
+//! ## How to use it
+//!
+//! ### Initialization
+//!
+//! To initialize this implementation create the [`OutputManagementState`] and
+//! implement the [`OutputManagementHandler`], as shown in this example:
+//!
+//! ```
+//! use smithay::delegate_output_management;
+//! use smithay::output::Output;
+//! use smithay::wayland::output_management::{
+//!     OutputConfiguration, OutputManagementHandler, OutputManagementState,
+//! };
+//!
+//! # struct State { output_management_state: OutputManagementState }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the output management global, only allowing privileged clients to change outputs.
+//! let mut output_management_state =
+//!     OutputManagementState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! // Implement the necessary trait.
+//! impl OutputManagementHandler for State {
+//!     fn test_configuration(&mut self, config: Vec<(Output, OutputConfiguration)>) -> bool {
+//!         // Check whether the configuration could be applied
+//!         true
+//!     }
+//!
+//!     fn apply_configuration(&mut self, config: Vec<(Output, OutputConfiguration)>) -> bool {
+//!         // Apply the configuration using `Output::change_current_state`
+//!         // and `OutputManagementState::set_head_enabled`
+//!         true
+//!     }
+//! }
+//! delegate_output_management!(State);
+//!
+//! // You're now ready to go!
+//! ```
+//!
+//! ### Advertising outputs
+//!
+//! Every output, that should be configurable by clients, has to be added using
+//! [`OutputManagementState::add_head`], including outputs that are currently disabled.
+//! Whether the output is enabled is tracked through [`OutputManagementState::set_head_enabled`].
+//!
+//! Changes to the mode, position, transform and scale of an output made through
+//! [`Output::change_current_state`] are automatically sent to clients.
+//! Modes added or removed otherwise can be announced using [`OutputManagementState::update_head`].
+
+use std::sync::{Arc, Mutex, Weak};
+
+use tracing::trace;
+use wayland_protocols_wlr::output_management::v1::server::{
+    zwlr_output_configuration_head_v1::{self, ZwlrOutputConfigurationHeadV1},
+    zwlr_output_configuration_v1::{self, ZwlrOutputConfigurationV1},
+    zwlr_output_head_v1::{self, ZwlrOutputHeadV1},
+    zwlr_output_manager_v1::{self, ZwlrOutputManagerV1},
+    zwlr_output_mode_v1::{self, ZwlrOutputModeV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+
+use crate::{
+    output::{Mode, Output, WeakOutput},
+    utils::{Logical, Physical, Point, Size, Transform},
+};
+
+const MANAGER_VERSION: u32 = 4;
+
+/// Requested mode of an output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModeConfiguration {
+    /// One of the advertised modes of the output
+    Mode(Mode),
+    /// A custom mode
+    Custom {
+        /// Size of the mode
+        size: Size<i32, Physical>,
+        /// Refresh rate in mHz, if requested
+        refresh: Option<i32>,
+    },
+}
+
+/// Requested configuration of an output
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputConfiguration {
+    /// The output should be disabled
+    Disabled,
+    /// The output should be enabled
+    ///
+    /// Properties, that are `None`, should be left unchanged.
+    Enabled {
+        /// Mode of the output
+        mode: Option<ModeConfiguration>,
+        /// Position of the output in the global compositor space
+        position: Option<Point<i32, Logical>>,
+        /// Transform of the output
+        transform: Option<Transform>,
+        /// Scale of the output
+        scale: Option<f64>,
+        /// Whether adaptive sync should be enabled
+        adaptive_sync: Option<bool>,
+    },
+}
+
+/// Handler trait for wlr-output-management
+pub trait OutputManagementHandler {
+    /// A client wants to know, whether a configuration could be applied.
+    ///
+    /// The configuration contains every head known to the [`OutputManagementState`].
+    /// Return `true`, if the configuration would be accepted by [`OutputManagementHandler::apply_configuration`].
+    fn test_configuration(&mut self, config: Vec<(Output, OutputConfiguration)>) -> bool;
+
+    /// A client requested to apply a configuration.
+    ///
+    /// The configuration contains every head known to the [`OutputManagementState`].
+    /// Return `true`, if the configuration was applied successfully, otherwise no changes should be made.
+    fn apply_configuration(&mut self, config: Vec<(Output, OutputConfiguration)>) -> bool;
+}
+
+#[derive(Debug)]
+struct Head {
+    output: Output,
+    enabled: bool,
+    adaptive_sync: Option<bool>,
+}
+
+#[derive(Debug)]
+struct HeadInstance {
+    output: WeakOutput,
+    head: ZwlrOutputHeadV1,
+    modes: Vec<(Mode, ZwlrOutputModeV1)>,
+}
+
+#[derive(Debug)]
+struct ManagerInstance {
+    manager: ZwlrOutputManagerV1,
+    heads: Vec<HeadInstance>,
+}
+
+type CreateHead = fn(&DisplayHandle, &ZwlrOutputManagerV1, &Output) -> Option<ZwlrOutputHeadV1>;
+type CreateMode = fn(&DisplayHandle, &ZwlrOutputHeadV1, &Output, Mode) -> Option<ZwlrOutputModeV1>;
+
+#[derive(Debug)]
+struct ManagementInner {
+    display: DisplayHandle,
+    serial: u32,
+    heads: Vec<Head>,
+    managers: Vec<ManagerInstance>,
+    create_head: CreateHead,
+    create_mode: CreateMode,
+}
+
+/// Links an [`Output`] to the [`OutputManagementState`] it is a head of
+struct OutputManagementUserData(Mutex<Weak<Mutex<ManagementInner>>>);
+
+/// State of the wlr-output-management global
+#[derive(Debug)]
+pub struct OutputManagementState {
+    inner: Arc<Mutex<ManagementInner>>,
+    global: GlobalId,
+}
+
+impl OutputManagementState {
+    /// Create a new [`ZwlrOutputManagerV1`] global.
+    ///
+    /// The `filter` decides which clients are allowed to configure outputs.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrOutputManagerV1, OutputManagementGlobalData>,
+        D: Dispatch<ZwlrOutputManagerV1, OutputManagerData>,
+        D: Dispatch<ZwlrOutputHeadV1, OutputHeadData>,
+        D: Dispatch<ZwlrOutputModeV1, OutputModeData>,
+        D: Dispatch<ZwlrOutputConfigurationV1, OutputConfigurationData>,
+        D: Dispatch<ZwlrOutputConfigurationHeadV1, OutputConfigurationHeadData>,
+        D: OutputManagementHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let inner = Arc::new(Mutex::new(ManagementInner {
+            display: display.clone(),
+            serial: 0,
+            heads: Vec::new(),
+            managers: Vec::new(),
+            create_head: create_head::<D>,
+            create_mode: create_mode::<D>,
+        }));
+        let data = OutputManagementGlobalData {
+            inner: inner.clone(),
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ZwlrOutputManagerV1, _>(MANAGER_VERSION, data);
+
+        Self { inner, global }
+    }
+
+    /// Returns the id of the [`ZwlrOutputManagerV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Advertises an output to clients.
+    ///
+    /// `enabled` signals, whether the output is currently in use.
+    pub fn add_head(&mut self, output: &Output, enabled: bool) {
+        output
+            .user_data()
+            .insert_if_missing_threadsafe(|| OutputManagementUserData(Mutex::new(Weak::new())));
+        *output
+            .user_data()
+            .get::<OutputManagementUserData>()
+            .unwrap()
+            .0
+            .lock()
+            .unwrap() = Arc::downgrade(&self.inner);
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.heads.iter().any(|head| head.output == *output) {
+            return;
+        }
+        inner.heads.push(Head {
+            output: output.clone(),
+            enabled,
+            adaptive_sync: None,
+        });
+        inner.serial += 1;
+
+        let inner = &mut *inner;
+        let head = inner.heads.last().unwrap();
+        for manager in &mut inner.managers {
+            manager.add_head(&inner.display, inner.create_head, inner.create_mode, head);
+            manager.manager.done(inner.serial);
+        }
+    }
+
+    /// Stops advertising an output to clients, e.g. once it was disconnected.
+    pub fn remove_head(&mut self, output: &Output) {
+        let mut inner = self.inner.lock().unwrap();
+        let len = inner.heads.len();
+        inner.heads.retain(|head| head.output != *output);
+        if inner.heads.len() == len {
+            return;
+        }
+        inner.serial += 1;
+
+        let serial = inner.serial;
+        for manager in &mut inner.managers {
+            manager.heads.retain(|instance| {
+                if instance.output != *output {
+                    return true;
+                }
+                for (_, mode) in &instance.modes {
+                    mode.finished();
+                }
+                instance.head.finished();
+                false
+            });
+            manager.manager.done(serial);
+        }
+    }
+
+    /// Updates whether an output is in use.
+    pub fn set_head_enabled(&mut self, output: &Output, enabled: bool) {
+        let changed = self.with_head(output, |head| {
+            std::mem::replace(&mut head.enabled, enabled) != enabled
+        });
+        if changed == Some(true) {
+            self.update_head(output);
+        }
+    }
+
+    /// Updates the advertised adaptive sync state of an output.
+    ///
+    /// `None` signals, that adaptive sync is not supported by the output.
+    pub fn set_head_adaptive_sync(&mut self, output: &Output, adaptive_sync: Option<bool>) {
+        let changed = self.with_head(output, |head| {
+            std::mem::replace(&mut head.adaptive_sync, adaptive_sync) != adaptive_sync
+        });
+        if changed == Some(true) {
+            self.update_head(output);
+        }
+    }
+
+    /// Re-sends the current state of an output to all clients.
+    ///
+    /// This is done automatically, when [`Output::change_current_state`] is called.
+    pub fn update_head(&self, output: &Output) {
+        update_head(&self.inner, output);
+    }
+
+    fn with_head<T>(&mut self, output: &Output, f: impl FnOnce(&mut Head) -> T) -> Option<T> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.heads.iter_mut().find(|head| head.output == *output).map(f)
+    }
+}
+
+/// Re-sends the state of an output, that might be a head of an [`OutputManagementState`]
+pub(crate) fn output_state_changed(output: &Output) {
+    let Some(inner) = output
+        .user_data()
+        .get::<OutputManagementUserData>()
+        .and_then(|data| data.0.lock().unwrap().upgrade())
+    else {
+        return;
+    };
+    update_head(&inner, output);
+}
+
+fn update_head(inner: &Mutex<ManagementInner>, output: &Output) {
+    let mut inner = inner.lock().unwrap();
+    let inner = &mut *inner;
+    let Some(head) = inner.heads.iter().find(|head| head.output == *output) else {
+        return;
+    };
+    inner.serial += 1;
+    trace!(
+        output = output.name(),
+        serial = inner.serial,
+        "Output management head changed"
+    );
+
+    for manager in &mut inner.managers {
+        if let Some(instance) = manager
+            .heads
+            .iter_mut()
+            .find(|instance| instance.output == *output)
+        {
+            instance.update(&inner.display, inner.create_mode, head);
+        }
+        manager.manager.done(inner.serial);
+    }
+}
+
+impl ManagerInstance {
+    fn add_head(
+        &mut self,
+        display: &DisplayHandle,
+        create_head: CreateHead,
+        create_mode: CreateMode,
+        head: &Head,
+    ) {
+        let Some(resource) = create_head(display, &self.manager, &head.output) else {
+            return;
+        };
+        self.manager.head(&resource);
+
+        let physical = head.output.physical_properties();
+        resource.name(head.output.name());
+        resource.description(head.output.description());
+        if physical.size.w > 0 && physical.size.h > 0 {
+            resource.physical_size(physical.size.w, physical.size.h);
+        }
+        if resource.version() >= 2 {
+            resource.make(physical.make);
+            resource.model(physical.model);
+        }
+
+        let mut instance = HeadInstance {
+            output: head.output.downgrade(),
+            head: resource,
+            modes: Vec::new(),
+        };
+        instance.update(display, create_mode, head);
+        self.heads.push(instance);
+    }
+}
+
+impl HeadInstance {
+    fn update(&mut self, display: &DisplayHandle, create_mode: CreateMode, head: &Head) {
+        let output = &head.output;
+        let modes = output.modes();
+
+        self.modes.retain(|(mode, resource)| {
+            let retain = modes.contains(mode);
+            if !retain {
+                resource.finished();
+            }
+            retain
+        });
+        let preferred = output.preferred_mode();
+        for mode in modes {
+            if self.modes.iter().any(|(m, _)| *m == mode) {
+                continue;
+            }
+            let Some(resource) = create_mode(display, &self.head, output, mode) else {
+                continue;
+            };
+            self.head.mode(&resource);
+            resource.size(mode.size.w, mode.size.h);
+            if mode.refresh > 0 {
+                resource.refresh(mode.refresh);
+            }
+            if preferred == Some(mode) {
+                resource.preferred();
+            }
+            self.modes.push((mode, resource));
+        }
+
+        self.head.enabled(head.enabled as i32);
+        if head.enabled {
+            if let Some(current) = output.current_mode() {
+                if let Some((_, resource)) = self.modes.iter().find(|(mode, _)| *mode == current) {
+                    self.head.current_mode(resource);
+                }
+            }
+            let location = output.current_location();
+            self.head.position(location.x, location.y);
+            self.head.transform(output.current_transform().into());
+            self.head.scale(output.current_scale().fractional_scale());
+        }
+        if let Some(adaptive_sync) = head.adaptive_sync {
+            if self.head.version() >= 4 {
+                self.head.adaptive_sync(if adaptive_sync {
+                    zwlr_output_head_v1::AdaptiveSyncState::Enabled
+                } else {
+                    zwlr_output_head_v1::AdaptiveSyncState::Disabled
+                });
+            }
+        }
+    }
+}
+
+fn create_head<D>(
+    display: &DisplayHandle,
+    manager: &ZwlrOutputManagerV1,
+    output: &Output,
+) -> Option<ZwlrOutputHeadV1>
+where
+    D: Dispatch<ZwlrOutputHeadV1, OutputHeadData>,
+    D: 'static,
+{
+    let client = manager.client()?;
+    client
+        .create_resource::<ZwlrOutputHeadV1, _, D>(
+            display,
+            manager.version(),
+            OutputHeadData {
+                output: output.downgrade(),
+            },
+        )
+        .ok()
+}
+
+fn create_mode<D>(
+    display: &DisplayHandle,
+    head: &ZwlrOutputHeadV1,
+    output: &Output,
+    mode: Mode,
+) -> Option<ZwlrOutputModeV1>
+where
+    D: Dispatch<ZwlrOutputModeV1, OutputModeData>,
+    D: 'static,
+{
+    let client = head.client()?;
+    client
+        .create_resource::<ZwlrOutputModeV1, _, D>(
+            display,
+            head.version().min(3),
+            OutputModeData {
+                output: output.downgrade(),
+                mode,
+            },
+        )
+        .ok()
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct OutputManagementGlobalData {
+    inner: Arc<Mutex<ManagementInner>>,
+    /// Filter whether the clients can view global.
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// User data of [`ZwlrOutputManagerV1`]
+#[derive(Debug)]
+pub struct OutputManagerData {
+    inner: Arc<Mutex<ManagementInner>>,
+}
+
+/// User data of [`ZwlrOutputHeadV1`]
+#[derive(Debug)]
+pub struct OutputHeadData {
+    output: WeakOutput,
+}
+
+/// User data of [`ZwlrOutputModeV1`]
+#[derive(Debug)]
+pub struct OutputModeData {
+    output: WeakOutput,
+    mode: Mode,
+}
+
+/// User data of [`ZwlrOutputConfigurationV1`]
+#[derive(Debug)]
+pub struct OutputConfigurationData {
+    inner: Arc<Mutex<ManagementInner>>,
+    serial: u32,
+    state: Mutex<ConfigurationState>,
+}
+
+#[derive(Debug, Default)]
+struct ConfigurationState {
+    heads: Vec<(WeakOutput, Option<HeadConfigurationState>)>,
+    used: bool,
+}
+
+#[derive(Debug)]
+enum HeadConfigurationState {
+    Disabled,
+    Enabled(Arc<Mutex<HeadConfiguration>>),
+}
+
+/// User data of [`ZwlrOutputConfigurationHeadV1`]
+#[derive(Debug)]
+pub struct OutputConfigurationHeadData {
+    config: Arc<Mutex<HeadConfiguration>>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct HeadConfiguration {
+    output: Option<WeakOutput>,
+    mode: Option<ModeConfiguration>,
+    position: Option<Point<i32, Logical>>,
+    transform: Option<Transform>,
+    scale: Option<f64>,
+    adaptive_sync: Option<bool>,
+}
+
+impl<D> GlobalDispatch<ZwlrOutputManagerV1, OutputManagementGlobalData, D> for OutputManagementState
+where
+    D: GlobalDispatch<ZwlrOutputManagerV1, OutputManagementGlobalData>,
+    D: Dispatch<ZwlrOutputManagerV1, OutputManagerData>,
+    D: Dispatch<ZwlrOutputHeadV1, OutputHeadData>,
+    D: Dispatch<ZwlrOutputModeV1, OutputModeData>,
+    D: OutputManagementHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        manager: New<ZwlrOutputManagerV1>,
+        global_data: &OutputManagementGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(
+            manager,
+            OutputManagerData {
+                inner: global_data.inner.clone(),
+            },
+        );
+
+        let mut inner = global_data.inner.lock().unwrap();
+        let inner = &mut *inner;
+        let mut instance = ManagerInstance {
+            manager,
+            heads: Vec::new(),
+        };
+        for head in &inner.heads {
+            instance.add_head(&inner.display, inner.create_head, inner.create_mode, head);
+        }
+        instance.manager.done(inner.serial);
+        inner.managers.push(instance);
+    }
+
+    fn can_view(client: Client, global_data: &OutputManagementGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputManagerV1, OutputManagerData, D> for OutputManagementState
+where
+    D: Dispatch<ZwlrOutputManagerV1, OutputManagerData>,
+    D: Dispatch<ZwlrOutputConfigurationV1, OutputConfigurationData>,
+    D: OutputManagementHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        manager: &ZwlrOutputManagerV1,
+        request: zwlr_output_manager_v1::Request,
+        data: &OutputManagerData,
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_manager_v1::Request::CreateConfiguration { id, serial } => {
+                let heads = data
+                    .inner
+                    .lock()
+                    .unwrap()
+                    .heads
+                    .iter()
+                    .map(|head| (head.output.downgrade(), None))
+                    .collect();
+                data_init.init(
+                    id,
+                    OutputConfigurationData {
+                        inner: data.inner.clone(),
+                        serial,
+                        state: Mutex::new(ConfigurationState { heads, used: false }),
+                    },
+                );
+            }
+            zwlr_output_manager_v1::Request::Stop => {
+                data.inner
+                    .lock()
+                    .unwrap()
+                    .managers
+                    .retain(|instance| instance.manager != *manager);
+                manager.finished();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(_state: &mut D, _client: ClientId, manager: &ZwlrOutputManagerV1, data: &OutputManagerData) {
+        data.inner
+            .lock()
+            .unwrap()
+            .managers
+            .retain(|instance| instance.manager != *manager);
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputHeadV1, OutputHeadData, D> for OutputManagementState
+where
+    D: Dispatch<ZwlrOutputHeadV1, OutputHeadData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _head: &ZwlrOutputHeadV1,
+        request: zwlr_output_head_v1::Request,
+        _data: &OutputHeadData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_head_v1::Request::Release => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(_state: &mut D, _client: ClientId, head: &ZwlrOutputHeadV1, data: &OutputHeadData) {
+        let Some(output) = data.output.upgrade() else {
+            return;
+        };
+        let Some(inner) = output
+            .user_data()
+            .get::<OutputManagementUserData>()
+            .and_then(|data| data.0.lock().unwrap().upgrade())
+        else {
+            return;
+        };
+        for manager in &mut inner.lock().unwrap().managers {
+            manager.heads.retain(|instance| instance.head != *head);
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputModeV1, OutputModeData, D> for OutputManagementState
+where
+    D: Dispatch<ZwlrOutputModeV1, OutputModeData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _mode: &ZwlrOutputModeV1,
+        request: zwlr_output_mode_v1::Request,
+        _data: &OutputModeData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_mode_v1::Request::Release => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(_state: &mut D, _client: ClientId, mode: &ZwlrOutputModeV1, data: &OutputModeData) {
+        let Some(output) = data.output.upgrade() else {
+            return;
+        };
+        let Some(inner) = output
+            .user_data()
+            .get::<OutputManagementUserData>()
+            .and_then(|data| data.0.lock().unwrap().upgrade())
+        else {
+            return;
+        };
+        for manager in &mut inner.lock().unwrap().managers {
+            for head in &mut manager.heads {
+                head.modes.retain(|(_, resource)| resource != mode);
+            }
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputConfigurationV1, OutputConfigurationData, D> for OutputManagementState
+where
+    D: Dispatch<ZwlrOutputConfigurationV1, OutputConfigurationData>,
+    D: Dispatch<ZwlrOutputConfigurationHeadV1, OutputConfigurationHeadData>,
+    D: OutputManagementHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        configuration: &ZwlrOutputConfigurationV1,
+        request: zwlr_output_configuration_v1::Request,
+        data: &OutputConfigurationData,
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let mut config_state = data.state.lock().unwrap();
+        if config_state.used && !matches!(request, zwlr_output_configuration_v1::Request::Destroy) {
+            configuration.post_error(
+                zwlr_output_configuration_v1::Error::AlreadyUsed,
+                "The configuration was already applied or tested",
+            );
+            return;
+        }
+
+        match request {
+            zwlr_output_configuration_v1::Request::EnableHead { id, head } => {
+                let head_config = Arc::new(Mutex::new(HeadConfiguration::default()));
+                data_init.init(
+                    id,
+                    OutputConfigurationHeadData {
+                        config: head_config.clone(),
+                    },
+                );
+                let Some(output) = head.data::<OutputHeadData>().map(|data| data.output.clone()) else {
+                    return;
+                };
+                head_config.lock().unwrap().output = Some(output.clone());
+                configure_head(configuration, &mut config_state, output, Some(head_config));
+            }
+            zwlr_output_configuration_v1::Request::DisableHead { head } => {
+                let Some(output) = head.data::<OutputHeadData>().map(|data| data.output.clone()) else {
+                    return;
+                };
+                configure_head(configuration, &mut config_state, output, None);
+            }
+            zwlr_output_configuration_v1::Request::Apply | zwlr_output_configuration_v1::Request::Test => {
+                config_state.used = true;
+                let apply = matches!(request, zwlr_output_configuration_v1::Request::Apply);
+
+                let current_serial = data.inner.lock().unwrap().serial;
+                if data.serial != current_serial {
+                    trace!("Outdated output configuration, cancelling");
+                    configuration.cancelled();
+                    return;
+                }
+
+                let mut config = Vec::with_capacity(config_state.heads.len());
+                for (output, head_config) in &config_state.heads {
+                    let Some(output) = output.upgrade() else {
+                        continue;
+                    };
+                    let Some(head_config) = head_config else {
+                        // every head has to be either enabled or disabled by the client
+                        configuration.post_error(
+                            zwlr_output_configuration_v1::Error::UnconfiguredHead,
+                            "Not all heads were configured",
+                        );
+                        return;
+                    };
+                    let head_config = match head_config {
+                        HeadConfigurationState::Disabled => OutputConfiguration::Disabled,
+                        HeadConfigurationState::Enabled(head_config) => {
+                            let head_config = head_config.lock().unwrap();
+                            OutputConfiguration::Enabled {
+                                mode: head_config.mode,
+                                position: head_config.position,
+                                transform: head_config.transform,
+                                scale: head_config.scale,
+                                adaptive_sync: head_config.adaptive_sync,
+                            }
+                        }
+                    };
+                    config.push((output, head_config));
+                }
+                drop(config_state);
+
+                let result = if apply {
+                    state.apply_configuration(config)
+                } else {
+                    state.test_configuration(config)
+                };
+                if result {
+                    configuration.succeeded();
+                } else {
+                    configuration.failed();
+                }
+            }
+            zwlr_output_configuration_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn configure_head(
+    configuration: &ZwlrOutputConfigurationV1,
+    config_state: &mut ConfigurationState,
+    output: WeakOutput,
+    head_config: Option<Arc<Mutex<HeadConfiguration>>>,
+) {
+    let Some(entry) = config_state.heads.iter_mut().find(|(o, _)| *o == output) else {
+        // the head was added after the configuration was created
+        return;
+    };
+    if entry.1.is_some() {
+        configuration.post_error(
+            zwlr_output_configuration_v1::Error::AlreadyConfiguredHead,
+            "The head was already configured",
+        );
+        return;
+    }
+    entry.1 = Some(match head_config {
+        Some(head_config) => HeadConfigurationState::Enabled(head_config),
+        None => HeadConfigurationState::Disabled,
+    });
+}
+
+impl<D> Dispatch<ZwlrOutputConfigurationHeadV1, OutputConfigurationHeadData, D> for OutputManagementState
+where
+    D: Dispatch<ZwlrOutputConfigurationHeadV1, OutputConfigurationHeadData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        config_head: &ZwlrOutputConfigurationHeadV1,
+        request: zwlr_output_configuration_head_v1::Request,
+        data: &OutputConfigurationHeadData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let mut config = data.config.lock().unwrap();
+        let already_set = match request {
+            zwlr_output_configuration_head_v1::Request::SetMode { mode } => {
+                let Some(mode_data) = mode.data::<OutputModeData>() else {
+                    return;
+                };
+                if config.output.as_ref() != Some(&mode_data.output) {
+                    config_head.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidMode,
+                        "The mode does not belong to the head",
+                    );
+                    return;
+                }
+                config
+                    .mode
+                    .replace(ModeConfiguration::Mode(mode_data.mode))
+                    .is_some()
+            }
+            zwlr_output_configuration_head_v1::Request::SetCustomMode {
+                width,
+                height,
+                refresh,
+            } => {
+                if width <= 0 || height <= 0 || refresh < 0 {
+                    config_head.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidCustomMode,
+                        "Invalid custom mode",
+                    );
+                    return;
+                }
+                config
+                    .mode
+                    .replace(ModeConfiguration::Custom {
+                        size: (width, height).into(),
+                        refresh: (refresh > 0).then_some(refresh),
+                    })
+                    .is_some()
+            }
+            zwlr_output_configuration_head_v1::Request::SetPosition { x, y } => {
+                config.position.replace((x, y).into()).is_some()
+            }
+            zwlr_output_configuration_head_v1::Request::SetTransform { transform } => {
+                let WEnum::Value(transform) = transform else {
+                    config_head.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidTransform,
+                        "Invalid transform",
+                    );
+                    return;
+                };
+                config.transform.replace(transform.into()).is_some()
+            }
+            zwlr_output_configuration_head_v1::Request::SetScale { scale } => {
+                if scale <= 0. {
+                    config_head.post_error(
+                        zwlr_output_configuration_head_v1::Error::InvalidScale,
+                        "Scale has to be positive",
+                    );
+                    return;
+                }
+                config.scale.replace(scale).is_some()
+            }
+            zwlr_output_configuration_head_v1::Request::SetAdaptiveSync { state } => {
+                let enabled = match state {
+                    WEnum::Value(zwlr_output_head_v1::AdaptiveSyncState::Enabled) => true,
+                    WEnum::Value(zwlr_output_head_v1::AdaptiveSyncState::Disabled) => false,
+                    _ => {
+                        config_head.post_error(
+                            zwlr_output_configuration_head_v1::Error::InvalidAdaptiveSyncState,
+                            "Invalid adaptive sync state",
+                        );
+                        return;
+                    }
+                };
+                config.adaptive_sync.replace(enabled).is_some()
+            }
+            _ => unreachable!(),
+        };
+
+        if already_set {
+            config_head.post_error(
+                zwlr_output_configuration_head_v1::Error::AlreadySet,
+                "The property was already set",
+            );
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_output_management {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_manager_v1::ZwlrOutputManagerV1: $crate::wayland::output_management::OutputManagementGlobalData
+        ] => $crate::wayland::output_management::OutputManagementState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_manager_v1::ZwlrOutputManagerV1: $crate::wayland::output_management::OutputManagerData
+        ] => $crate::wayland::output_management::OutputManagementState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_head_v1::ZwlrOutputHeadV1: $crate::wayland::output_management::OutputHeadData
+        ] => $crate::wayland::output_management::OutputManagementState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_mode_v1::ZwlrOutputModeV1: $crate::wayland::output_management::OutputModeData
+        ] => $crate::wayland::output_management::OutputManagementState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_configuration_v1::ZwlrOutputConfigurationV1: $crate::wayland::output_management::OutputConfigurationData
+        ] => $crate::wayland::output_management::OutputManagementState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::output_management::v1::server::zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1: $crate::wayland::output_management::OutputConfigurationHeadData
+        ] => $crate::wayland::output_management::OutputManagementState);
+    };
+}
@@ -0,0 +1,682 @@
+//! Utilities for handling the `wlr-screencopy` protocol
+//!
+//! This protocol allows privileged clients like `grim` or `wf-recorder` to capture
+//! the contents of an output, or a region of an output, into a client provided buffer.
+//!
+//! ## How to use it
+//!
+//! ### Initialization
+//!
+//! To initialize this implementation create the [`ScreencopyManagerState`] and
+//! implement the [`ScreencopyHandler`], as shown in this example:
+//!
+//! ```
+//! use smithay::delegate_screencopy;
+//! use smithay::wayland::screencopy::{Screencopy, ScreencopyHandler, ScreencopyManagerState};
+//!
+//! # struct State { pending_screencopies: Vec<Screencopy> }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the screencopy global, only allowing privileged clients to capture the screen.
+//! let screencopy_state = ScreencopyManagerState::new::<State, _>(&display.handle(), |_client| true);
+//!
+//! // Implement the necessary trait.
+//! impl ScreencopyHandler for State {
+//!     fn frame(&mut self, frame: Screencopy) {
+//!         // Store the frame and fulfill it during the next repaint of `frame.output()`.
+//!         self.pending_screencopies.push(frame);
+//!     }
+//! }
+//! delegate_screencopy!(State);
+//!
+//! // You're now ready to go!
+//! ```
+//!
+//! ### Fulfilling frames
+//!
+//! The compositor decides when a requested [`Screencopy`] is copied. Usually this is done
+//! after the output was rendered, while the framebuffer is still bound to the renderer:
+//!
+//! - If [`Screencopy::with_damage`] is set, the client is only interested in a new frame
+//!   once the output was damaged. Use [`Screencopy::damage_elements`] (or [`Screencopy::damage`])
+//!   to report the damage and keep the frame around until there is some.
+//! - [`Screencopy::overlay_cursor`] signals whether the cursor should be part of the capture.
+//! - [`Screencopy::copy_framebuffer`] copies the captured region of the currently bound framebuffer
+//!   into the client buffer, using [`ExportMem`] for shm and [`Blit`] for dmabuf buffers.
+//! - Finally call [`Screencopy::submit`] to notify the client.
+//!
+//! Dropping a [`Screencopy`] without submitting it notifies the client about the failure.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tracing::{trace, warn};
+use wayland_protocols_wlr::screencopy::v1::server::{
+    zwlr_screencopy_frame_v1::{self, ZwlrScreencopyFrameV1},
+    zwlr_screencopy_manager_v1::{self, ZwlrScreencopyManagerV1},
+};
+use wayland_server::{
+    backend::GlobalId,
+    protocol::{wl_buffer::WlBuffer, wl_shm},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use crate::{
+    backend::{
+        allocator::{dmabuf::Dmabuf, format::get_bpp, Buffer as _, Fourcc},
+        renderer::{
+            damage::OutputDamageTracker, element::Element, Blit, ExportMem, TextureFilter, TextureMapping,
+        },
+    },
+    output::{Output, OutputNoMode, WeakOutput},
+    utils::{Buffer, Logical, Physical, Rectangle, Size, Transform},
+    wayland::{
+        dmabuf::get_dmabuf,
+        shm::{self, shm_format_to_fourcc, BufferAccessError},
+    },
+};
+
+const MANAGER_VERSION: u32 = 3;
+
+/// State of the wlr-screencopy global
+#[derive(Debug)]
+pub struct ScreencopyManagerState {
+    global: GlobalId,
+}
+
+impl ScreencopyManagerState {
+    /// Create a new [`ZwlrScreencopyManagerV1`] global.
+    ///
+    /// The `filter` decides which clients are allowed to capture the screen.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrScreencopyManagerV1, ScreencopyManagerGlobalData>,
+        D: Dispatch<ZwlrScreencopyManagerV1, ScreencopyManagerData>,
+        D: Dispatch<ZwlrScreencopyFrameV1, ScreencopyFrameData>,
+        D: ScreencopyHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = ScreencopyManagerGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ZwlrScreencopyManagerV1, _>(MANAGER_VERSION, data);
+
+        Self { global }
+    }
+
+    /// Returns the id of the [`ZwlrScreencopyManagerV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// Buffer constraints advertised to clients for capturing an output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferConstraints {
+    /// Format of shm buffers
+    pub shm: wl_shm::Format,
+    /// Format of dmabuf buffers, if dmabuf buffers are supported
+    pub dmabuf: Option<Fourcc>,
+}
+
+impl Default for BufferConstraints {
+    fn default() -> Self {
+        BufferConstraints {
+            shm: wl_shm::Format::Argb8888,
+            dmabuf: None,
+        }
+    }
+}
+
+/// Handler trait for wlr-screencopy.
+pub trait ScreencopyHandler {
+    /// Returns the buffer constraints for capturing the given output.
+    ///
+    /// The default implementation only advertises shm buffers in the `Argb8888` format.
+    fn buffer_constraints(&mut self, output: &Output) -> BufferConstraints {
+        let _ = output;
+        BufferConstraints::default()
+    }
+
+    /// A client provided a buffer for a capture and waits for it to be copied.
+    ///
+    /// The compositor is free to decide when the frame is copied. See [`Screencopy`] for details.
+    fn frame(&mut self, frame: Screencopy);
+}
+
+#[allow(missing_debug_implementations)]
+#[doc(hidden)]
+pub struct ScreencopyManagerGlobalData {
+    /// Filter whether the clients can view global.
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// Per-client damage trackers of captured outputs
+type DamageTrackers = Arc<Mutex<Vec<(WeakOutput, OutputDamageTracker)>>>;
+
+/// User data of [`ZwlrScreencopyManagerV1`]
+#[derive(Debug, Default)]
+pub struct ScreencopyManagerData {
+    damage_trackers: DamageTrackers,
+}
+
+/// User data of [`ZwlrScreencopyFrameV1`]
+#[derive(Debug)]
+pub struct ScreencopyFrameData {
+    inner: Mutex<FrameState>,
+}
+
+#[derive(Debug)]
+struct FrameState {
+    capture: Option<Capture>,
+    used: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Capture {
+    output: Output,
+    region: Rectangle<i32, Buffer>,
+    overlay_cursor: bool,
+    constraints: BufferConstraints,
+    damage_trackers: DamageTrackers,
+}
+
+impl<D> GlobalDispatch<ZwlrScreencopyManagerV1, ScreencopyManagerGlobalData, D> for ScreencopyManagerState
+where
+    D: GlobalDispatch<ZwlrScreencopyManagerV1, ScreencopyManagerGlobalData>,
+    D: Dispatch<ZwlrScreencopyManagerV1, ScreencopyManagerData>,
+    D: Dispatch<ZwlrScreencopyFrameV1, ScreencopyFrameData>,
+    D: ScreencopyHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        manager: New<ZwlrScreencopyManagerV1>,
+        _global_data: &ScreencopyManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(manager, ScreencopyManagerData::default());
+    }
+
+    fn can_view(client: Client, global_data: &ScreencopyManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrScreencopyManagerV1, ScreencopyManagerData, D> for ScreencopyManagerState
+where
+    D: Dispatch<ZwlrScreencopyManagerV1, ScreencopyManagerData>,
+    D: Dispatch<ZwlrScreencopyFrameV1, ScreencopyFrameData>,
+    D: ScreencopyHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _manager: &ZwlrScreencopyManagerV1,
+        request: zwlr_screencopy_manager_v1::Request,
+        data: &ScreencopyManagerData,
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let (frame, overlay_cursor, output, region) = match request {
+            zwlr_screencopy_manager_v1::Request::CaptureOutput {
+                frame,
+                overlay_cursor,
+                output,
+            } => (frame, overlay_cursor, output, None),
+            zwlr_screencopy_manager_v1::Request::CaptureOutputRegion {
+                frame,
+                overlay_cursor,
+                output,
+                x,
+                y,
+                width,
+                height,
+            } => (
+                frame,
+                overlay_cursor,
+                output,
+                Some(Rectangle::<i32, Logical>::from_loc_and_size(
+                    (x, y),
+                    (width, height),
+                )),
+            ),
+            zwlr_screencopy_manager_v1::Request::Destroy => return,
+            _ => unreachable!(),
+        };
+
+        let capture = Output::from_resource(&output).and_then(|output| {
+            let mode = output.current_mode()?;
+            let region = match region {
+                Some(region) => output_region(
+                    region,
+                    output.current_scale().fractional_scale(),
+                    output.current_transform(),
+                    mode.size,
+                )?,
+                None => Rectangle::from_loc_and_size((0, 0), (mode.size.w, mode.size.h)),
+            };
+            let constraints = state.buffer_constraints(&output);
+            Some(Capture {
+                output,
+                region,
+                overlay_cursor: overlay_cursor != 0,
+                constraints,
+                damage_trackers: data.damage_trackers.clone(),
+            })
+        });
+
+        let frame = data_init.init(
+            frame,
+            ScreencopyFrameData {
+                inner: Mutex::new(FrameState {
+                    capture: capture.clone(),
+                    used: false,
+                }),
+            },
+        );
+
+        let Some(capture) = capture else {
+            trace!("screencopy of unavailable output requested");
+            frame.failed();
+            return;
+        };
+
+        let Some(bytes_per_pixel) = shm_bytes_per_pixel(capture.constraints.shm) else {
+            warn!(format = ?capture.constraints.shm, "unsupported shm format for screencopy");
+            frame.failed();
+            return;
+        };
+
+        let size = capture.region.size;
+        frame.buffer(
+            capture.constraints.shm,
+            size.w as u32,
+            size.h as u32,
+            (size.w as usize * bytes_per_pixel) as u32,
+        );
+        if frame.version() >= 3 {
+            if let Some(format) = capture.constraints.dmabuf {
+                frame.linux_dmabuf(format as u32, size.w as u32, size.h as u32);
+            }
+            frame.buffer_done();
+        }
+    }
+}
+
+/// Returns the size of a pixel of the given shm format in bytes, if known
+fn shm_bytes_per_pixel(format: wl_shm::Format) -> Option<usize> {
+    shm_format_to_fourcc(format)
+        .and_then(get_bpp)
+        .filter(|bpp| bpp % 8 == 0)
+        .map(|bpp| bpp / 8)
+}
+
+/// Converts a logical region of an output into the coordinate space of the output's framebuffer
+fn output_region(
+    region: Rectangle<i32, Logical>,
+    scale: f64,
+    transform: Transform,
+    mode_size: Size<i32, Physical>,
+) -> Option<Rectangle<i32, Buffer>> {
+    let frame_size = transform.transform_size(mode_size);
+    let region = region
+        .to_physical_precise_round::<f64, i32>(scale)
+        .intersection(Rectangle::from_loc_and_size((0, 0), frame_size))?;
+    Some(to_buffer_rect(transform.transform_rect_in(region, &frame_size)))
+}
+
+fn to_buffer_rect(rect: Rectangle<i32, Physical>) -> Rectangle<i32, Buffer> {
+    Rectangle::from_loc_and_size((rect.loc.x, rect.loc.y), (rect.size.w, rect.size.h))
+}
+
+impl<D> Dispatch<ZwlrScreencopyFrameV1, ScreencopyFrameData, D> for ScreencopyManagerState
+where
+    D: Dispatch<ZwlrScreencopyFrameV1, ScreencopyFrameData>,
+    D: ScreencopyHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        frame: &ZwlrScreencopyFrameV1,
+        request: zwlr_screencopy_frame_v1::Request,
+        data: &ScreencopyFrameData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let (buffer, with_damage) = match request {
+            zwlr_screencopy_frame_v1::Request::Copy { buffer } => (buffer, false),
+            zwlr_screencopy_frame_v1::Request::CopyWithDamage { buffer } => (buffer, true),
+            zwlr_screencopy_frame_v1::Request::Destroy => return,
+            _ => unreachable!(),
+        };
+
+        let capture = {
+            let mut inner = data.inner.lock().unwrap();
+            if inner.used {
+                frame.post_error(
+                    zwlr_screencopy_frame_v1::Error::AlreadyUsed,
+                    "The frame was already used to copy",
+                );
+                return;
+            }
+            inner.used = true;
+            inner.capture.clone()
+        };
+
+        let Some(capture) = capture else {
+            frame.failed();
+            return;
+        };
+
+        let Some(buffer) = ScreencopyBuffer::validate(&buffer, &capture) else {
+            frame.post_error(
+                zwlr_screencopy_frame_v1::Error::InvalidBuffer,
+                "The buffer does not match the advertised constraints",
+            );
+            return;
+        };
+
+        state.frame(Screencopy {
+            frame: frame.clone(),
+            capture,
+            buffer,
+            with_damage,
+            submitted: false,
+        });
+    }
+}
+
+/// Buffer provided by a client to copy a capture into
+#[derive(Debug, Clone)]
+pub enum ScreencopyBuffer {
+    /// A shm buffer
+    Shm(WlBuffer),
+    /// A dmabuf buffer
+    Dmabuf {
+        /// The client buffer
+        buffer: WlBuffer,
+        /// The dmabuf backing the buffer
+        dmabuf: Dmabuf,
+    },
+}
+
+impl ScreencopyBuffer {
+    fn validate(buffer: &WlBuffer, capture: &Capture) -> Option<ScreencopyBuffer> {
+        let size = capture.region.size;
+
+        if let Ok(dmabuf) = get_dmabuf(buffer) {
+            let format = capture.constraints.dmabuf?;
+            if dmabuf.format().code != format || dmabuf.size() != size {
+                return None;
+            }
+            return Some(ScreencopyBuffer::Dmabuf {
+                buffer: buffer.clone(),
+                dmabuf,
+            });
+        }
+
+        let bytes_per_pixel = shm_bytes_per_pixel(capture.constraints.shm)?;
+        let valid = shm::with_buffer_contents(buffer, |_, _, data| {
+            data.format == capture.constraints.shm
+                && data.width == size.w
+                && data.height == size.h
+                && data.stride as usize == size.w as usize * bytes_per_pixel
+        })
+        .ok()?;
+        valid.then(|| ScreencopyBuffer::Shm(buffer.clone()))
+    }
+
+    /// Returns the underlying [`WlBuffer`]
+    pub fn wl_buffer(&self) -> &WlBuffer {
+        match self {
+            ScreencopyBuffer::Shm(buffer) => buffer,
+            ScreencopyBuffer::Dmabuf { buffer, .. } => buffer,
+        }
+    }
+}
+
+/// Errors thrown by [`Screencopy::copy_framebuffer`]
+#[derive(Debug, thiserror::Error)]
+pub enum ScreencopyError<E: std::error::Error> {
+    /// The renderer failed to copy the framebuffer
+    #[error(transparent)]
+    Rendering(E),
+    /// The client buffer could not be accessed
+    #[error(transparent)]
+    BufferAccess(BufferAccessError),
+    /// The format of the client buffer is not supported
+    #[error("Unsupported buffer format: {0:?}")]
+    UnsupportedFormat(wl_shm::Format),
+    /// The copied data does not match the size of the client buffer
+    #[error("The copied data does not match the size of the client buffer")]
+    UnexpectedSize,
+}
+
+/// A pending screencopy request
+///
+/// Dropping this object without calling [`Screencopy::submit`] will notify the client,
+/// that the capture failed.
+#[derive(Debug)]
+pub struct Screencopy {
+    frame: ZwlrScreencopyFrameV1,
+    capture: Capture,
+    buffer: ScreencopyBuffer,
+    with_damage: bool,
+    submitted: bool,
+}
+
+impl Screencopy {
+    /// Returns the captured output
+    pub fn output(&self) -> &Output {
+        &self.capture.output
+    }
+
+    /// Returns the captured region in the coordinate space of the output's framebuffer
+    pub fn region(&self) -> Rectangle<i32, Buffer> {
+        self.capture.region
+    }
+
+    /// Returns whether the cursor should be included in the capture
+    pub fn overlay_cursor(&self) -> bool {
+        self.capture.overlay_cursor
+    }
+
+    /// Returns whether the client requested damage
+    ///
+    /// In this case the copy should be delayed until the captured region was damaged.
+    pub fn with_damage(&self) -> bool {
+        self.with_damage
+    }
+
+    /// Returns the buffer provided by the client
+    pub fn buffer(&self) -> &ScreencopyBuffer {
+        &self.buffer
+    }
+
+    /// Returns the underlying [`ZwlrScreencopyFrameV1`]
+    pub fn frame(&self) -> &ZwlrScreencopyFrameV1 {
+        &self.frame
+    }
+
+    /// Reports damage to the client.
+    ///
+    /// The damage is given in the coordinate space of the output's framebuffer and
+    /// is clipped to the captured region. Does nothing, unless [`Screencopy::with_damage`] is set.
+    pub fn damage(&mut self, damage: impl IntoIterator<Item = Rectangle<i32, Buffer>>) {
+        if !self.with_damage {
+            return;
+        }
+
+        let region = self.capture.region;
+        for rect in damage {
+            let Some(rect) = rect.intersection(region) else {
+                continue;
+            };
+            self.frame.damage(
+                (rect.loc.x - region.loc.x) as u32,
+                (rect.loc.y - region.loc.y) as u32,
+                rect.size.w as u32,
+                rect.size.h as u32,
+            );
+        }
+    }
+
+    /// Computes and reports the damage of the captured region based on the given elements.
+    ///
+    /// The damage is tracked per client and output using an [`OutputDamageTracker`], relative to
+    /// the last call of this function for a frame of the same client and output.
+    /// The elements have to be provided the same way as for rendering the output.
+    ///
+    /// Returns whether the captured region was damaged. A frame requested with damage
+    /// should only be copied once this returns `true`.
+    pub fn damage_elements<E: Element>(&mut self, elements: &[E]) -> Result<bool, OutputNoMode> {
+        let output = self.capture.output.clone();
+        let damage = {
+            let mut trackers = self.capture.damage_trackers.lock().unwrap();
+            trackers.retain(|(output, _)| output.upgrade().is_some());
+            let tracker = match trackers.iter().position(|(o, _)| *o == output) {
+                Some(idx) => &mut trackers[idx].1,
+                None => {
+                    trackers.push((output.downgrade(), OutputDamageTracker::from_output(&output)));
+                    &mut trackers.last_mut().unwrap().1
+                }
+            };
+            tracker.damage_output(1, elements)?.0
+        };
+
+        let mode = output.current_mode().ok_or(OutputNoMode)?;
+        let transform = output.current_transform();
+        let frame_size = transform.transform_size(mode.size);
+        let region = self.capture.region;
+        let damage = damage
+            .into_iter()
+            .flatten()
+            .map(|rect| to_buffer_rect(transform.transform_rect_in(rect, &frame_size)))
+            .filter(|rect| rect.overlaps(region))
+            .collect::<Vec<_>>();
+
+        let damaged = !damage.is_empty();
+        self.damage(damage);
+        Ok(damaged)
+    }
+
+    /// Copies the captured region of the currently bound framebuffer into the client buffer.
+    ///
+    /// Shm buffers are filled using [`ExportMem::copy_framebuffer`], dmabuf buffers
+    /// using [`Blit::blit_to`].
+    pub fn copy_framebuffer<R>(&mut self, renderer: &mut R) -> Result<(), ScreencopyError<R::Error>>
+    where
+        R: ExportMem + Blit<Dmabuf>,
+    {
+        let region = self.capture.region;
+        match &self.buffer {
+            ScreencopyBuffer::Dmabuf { dmabuf, .. } => {
+                let src = Rectangle::from_loc_and_size(
+                    (region.loc.x, region.loc.y),
+                    (region.size.w, region.size.h),
+                );
+                let dst = Rectangle::from_loc_and_size((0, 0), src.size);
+                renderer
+                    .blit_to(dmabuf.clone(), src, dst, TextureFilter::Nearest)
+                    .map_err(ScreencopyError::Rendering)
+            }
+            ScreencopyBuffer::Shm(buffer) => {
+                let format = self.capture.constraints.shm;
+                let fourcc =
+                    shm_format_to_fourcc(format).ok_or(ScreencopyError::UnsupportedFormat(format))?;
+                let bytes_per_pixel =
+                    shm_bytes_per_pixel(format).ok_or(ScreencopyError::UnsupportedFormat(format))?;
+                let mapping = renderer
+                    .copy_framebuffer(region, fourcc)
+                    .map_err(ScreencopyError::Rendering)?;
+                let flipped = mapping.flipped();
+                let data = renderer
+                    .map_texture(&mapping)
+                    .map_err(ScreencopyError::Rendering)?;
+
+                let width = region.size.w as usize * bytes_per_pixel;
+                let height = region.size.h as usize;
+                if data.len() < width * height {
+                    return Err(ScreencopyError::UnexpectedSize);
+                }
+
+                shm::with_buffer_contents_mut(buffer, |ptr, len, buffer_data| {
+                    let offset = buffer_data.offset as usize;
+                    let stride = buffer_data.stride as usize;
+                    if offset + stride * height > len {
+                        return Err(ScreencopyError::UnexpectedSize);
+                    }
+                    for row in 0..height {
+                        let src_row = if flipped { height - 1 - row } else { row };
+                        let src = &data[src_row * width..(src_row + 1) * width];
+                        // SAFETY: the destination range was checked to be inside the pool
+                        unsafe {
+                            std::ptr::copy_nonoverlapping(
+                                src.as_ptr(),
+                                ptr.add(offset + row * stride),
+                                width,
+                            );
+                        }
+                    }
+                    Ok(())
+                })
+                .map_err(ScreencopyError::BufferAccess)?
+            }
+        }
+    }
+
+    /// Notifies the client, that the frame was copied.
+    ///
+    /// `timestamp` is the presentation time of the captured content in the monotonic clock.
+    /// `flags` describe the layout of the copied content, e.g. [`Flags::YInvert`](zwlr_screencopy_frame_v1::Flags::YInvert)
+    /// if the content is stored upside down in the buffer.
+    pub fn submit(mut self, timestamp: impl Into<Duration>, flags: zwlr_screencopy_frame_v1::Flags) {
+        let timestamp = timestamp.into();
+        let secs = timestamp.as_secs();
+        self.frame.flags(flags);
+        self.frame.ready(
+            (secs >> 32) as u32,
+            (secs & 0xffff_ffff) as u32,
+            timestamp.subsec_nanos(),
+        );
+        self.submitted = true;
+    }
+
+    /// Notifies the client, that the capture failed.
+    pub fn failed(self) {
+        // the frame is failed on drop
+    }
+}
+
+impl Drop for Screencopy {
+    fn drop(&mut self) {
+        if !self.submitted {
+            self.frame.failed();
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_screencopy {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1: $crate::wayland::screencopy::ScreencopyManagerGlobalData
+        ] => $crate::wayland::screencopy::ScreencopyManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1: $crate::wayland::screencopy::ScreencopyManagerData
+        ] => $crate::wayland::screencopy::ScreencopyManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_frame_v1::ZwlrScreencopyFrameV1: $crate::wayland::screencopy::ScreencopyFrameData
+        ] => $crate::wayland::screencopy::ScreencopyManagerState);
+    };
+}
@@ -1,195 +1,125 @@
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
-};
+use std::fmt;
 
 use wayland_server::{
     backend::ClientId,
-    protocol::wl_touch::{self, WlTouch},
+    protocol::{
+        wl_surface::WlSurface,
+        wl_touch::{self, WlTouch},
+    },
     Dispatch, DisplayHandle, Resource,
 };
 
 use super::{SeatHandler, SeatState};
-use crate::backend::input::TouchSlot;
-use crate::utils::Serial;
-use crate::utils::{Logical, Point};
-use crate::wayland::seat::wl_surface::WlSurface;
-
-/// An handle to a touch handler.
-///
-/// It can be cloned and all clones manipulate the same internal state.
-///
-/// This handle gives you access to an interface to send touch events to your
-/// clients.
-#[derive(Debug, Clone)]
-pub struct TouchHandle {
-    inner: Arc<Mutex<TouchInternal>>,
-}
-
-impl TouchHandle {
-    pub(crate) fn new() -> Self {
-        Self {
-            inner: Default::default(),
-        }
-    }
+use crate::input::{
+    touch::{DownEvent, MotionEvent, OrientationEvent, ShapeEvent, TouchHandle, TouchTarget, UpEvent},
+    Seat,
+};
 
-    /// Register a new touch handle to this handler
-    ///
-    /// This should be done first, before anything else is done with this touch handle.
+impl<D: SeatHandler> TouchHandle<D> {
     pub(crate) fn new_touch(&self, touch: WlTouch) {
-        self.inner.lock().unwrap().known_handles.push(touch);
-    }
-
-    /// Notify clients about new touch points.
-    ///
-    /// The `position` parameter is in surface-local coordinates.
-    pub fn down(
-        &mut self,
-        serial: Serial,
-        time: u32,
-        surface: &WlSurface,
-        position: Point<f64, Logical>,
-        slot: TouchSlot,
-    ) {
-        self.inner
-            .lock()
-            .unwrap()
-            .down(serial, time, surface, position, slot);
-    }
-
-    /// Notify clients about touch point removal.
-    pub fn up(&self, serial: Serial, time: u32, slot: TouchSlot) {
-        self.inner.lock().unwrap().up(serial, time, slot);
+        let mut guard = self.known_instances.lock().unwrap();
+        guard.push(touch);
     }
-
-    /// Notify clients about touch motion.
-    pub fn motion(&self, time: u32, slot: TouchSlot, location: Point<f64, Logical>) {
-        self.inner.lock().unwrap().motion(time, slot, location);
-    }
-
-    /// Notify clients about touch shape changes.
-    pub fn shape(&self, slot: TouchSlot, major: f64, minor: f64) {
-        self.inner.lock().unwrap().shape(slot, major, minor);
-    }
-
-    /// Notify clients about touch shape orientation.
-    pub fn orientation(&self, slot: TouchSlot, orientation: f64) {
-        self.inner.lock().unwrap().orientation(slot, orientation);
-    }
-
-    /// Notify clients about touch cancellation.
-    ///
-    /// This should be sent by the compositor when the touch stream is recognized as
-    /// a global gesture. Cancellation applies to all currently active touch slots.
-    pub fn cancel(&self) {
-        self.inner.lock().unwrap().cancel();
-    }
-}
-
-/// Touch-slot focused Wayland client state.
-#[derive(Default, Debug)]
-struct TouchFocus {
-    surface_offset: Point<f64, Logical>,
-    handles: Vec<WlTouch>,
 }
 
-#[derive(Default, Debug)]
-struct TouchInternal {
-    known_handles: Vec<WlTouch>,
-    focus: HashMap<TouchSlot, TouchFocus>,
-}
-
-impl TouchInternal {
-    fn down(
-        &mut self,
-        serial: Serial,
-        time: u32,
-        surface: &WlSurface,
-        position: Point<f64, Logical>,
-        slot: TouchSlot,
-    ) {
-        // Update focused client state.
-        let focus = self.focus.entry(slot).or_default();
-        focus.handles.clear();
-
-        // Select all WlTouch instances associated to the active WlSurface.
-        for handle in &self.known_handles {
-            if handle.id().same_client_as(&surface.id()) {
-                focus.handles.push(handle.clone());
+fn for_each_focused_touch<D: SeatHandler + 'static>(
+    seat: &Seat<D>,
+    surface: &WlSurface,
+    mut f: impl FnMut(WlTouch),
+) {
+    if let Some(touch) = seat.get_touch() {
+        let inner = touch.known_instances.lock().unwrap();
+        for touch in &*inner {
+            if touch.id().same_client_as(&surface.id()) {
+                f(touch.clone())
             }
         }
-
-        self.with_focused_handles(slot, |handle| {
-            handle.down(serial.into(), time, surface, slot.into(), position.x, position.y)
-        });
     }
+}
 
-    fn up(&mut self, serial: Serial, time: u32, slot: TouchSlot) {
-        self.with_focused_handles(slot, |handle| handle.up(serial.into(), time, slot.into()));
-
-        // Clear this slot's associated WlTouch handles.
-        if let Some(focus) = self.focus.get_mut(&slot) {
-            focus.handles.clear();
+impl<D> TouchTarget<D> for WlSurface
+where
+    D: SeatHandler + 'static,
+{
+    fn down(&self, seat: &Seat<D>, _data: &mut D, event: &DownEvent) {
+        for_each_focused_touch(seat, self, |touch| {
+            touch.down(
+                event.serial.into(),
+                event.time,
+                self,
+                event.slot.into(),
+                event.location.x,
+                event.location.y,
+            );
+        })
+    }
+
+    fn up(&self, seat: &Seat<D>, _data: &mut D, event: &UpEvent) {
+        for_each_focused_touch(seat, self, |touch| {
+            touch.up(event.serial.into(), event.time, event.slot.into());
+        })
+    }
+
+    fn motion(&self, seat: &Seat<D>, _data: &mut D, event: &MotionEvent) {
+        for_each_focused_touch(seat, self, |touch| {
+            touch.motion(event.time, event.slot.into(), event.location.x, event.location.y);
+        })
+    }
+
+    fn frame(&self, seat: &Seat<D>, _data: &mut D) {
+        // multiple surfaces of the same client might have been touched during the frame
+        if let (Some(touch), Some(client)) = (seat.get_touch(), self.client()) {
+            let mut framed_clients = touch.framed_clients.lock().unwrap();
+            if framed_clients.contains(&client.id()) {
+                return;
+            }
+            framed_clients.push(client.id());
         }
-    }
 
-    fn motion(&self, time: u32, slot: TouchSlot, location: Point<f64, Logical>) {
-        let focus = match self.focus.get(&slot) {
-            Some(slot) => slot,
-            None => return,
-        };
-
-        let (x, y) = (location - focus.surface_offset).into();
-        self.with_focused_handles(slot, |handle| handle.motion(time, slot.into(), x, y));
+        for_each_focused_touch(seat, self, |touch| {
+            touch.frame();
+        })
     }
 
-    fn shape(&self, slot: TouchSlot, major: f64, minor: f64) {
-        self.with_focused_handles(slot, |handle| {
-            if handle.version() >= 6 {
-                handle.shape(slot.into(), major, minor);
-            }
-        });
+    fn cancel(&self, seat: &Seat<D>, _data: &mut D) {
+        for_each_focused_touch(seat, self, |touch| {
+            touch.cancel();
+        })
     }
 
-    fn orientation(&self, slot: TouchSlot, orientation: f64) {
-        self.with_focused_handles(slot, |handle| {
-            if handle.version() >= 6 {
-                handle.orientation(slot.into(), orientation);
+    fn shape(&self, seat: &Seat<D>, _data: &mut D, event: &ShapeEvent) {
+        for_each_focused_touch(seat, self, |touch| {
+            if touch.version() >= 6 {
+                touch.shape(event.slot.into(), event.major, event.minor);
             }
-        });
-    }
-
-    fn cancel(&mut self) {
-        for handle in &self.known_handles {
-            handle.cancel();
-        }
-
-        self.focus.clear();
+        })
     }
 
-    #[inline]
-    fn with_focused_handles<F>(&self, slot: TouchSlot, mut f: F)
-    where
-        F: FnMut(&WlTouch),
-    {
-        if let Some(focus) = self.focus.get(&slot) {
-            for handle in &focus.handles {
-                f(handle);
-                handle.frame();
+    fn orientation(&self, seat: &Seat<D>, _data: &mut D, event: &OrientationEvent) {
+        for_each_focused_touch(seat, self, |touch| {
+            if touch.version() >= 6 {
+                touch.orientation(event.slot.into(), event.orientation);
             }
-        }
+        })
     }
 }
 
-/// User data for keyboard
-#[derive(Debug)]
-pub struct TouchUserData {
-    pub(crate) handle: Option<TouchHandle>,
+/// User data for touch
+pub struct TouchUserData<D: SeatHandler> {
+    pub(crate) handle: Option<TouchHandle<D>>,
+}
+
+impl<D: SeatHandler> fmt::Debug for TouchUserData<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TouchUserData")
+            .field("handle", &self.handle)
+            .finish()
+    }
 }
 
-impl<D> Dispatch<WlTouch, TouchUserData, D> for SeatState<D>
+impl<D> Dispatch<WlTouch, TouchUserData<D>, D> for SeatState<D>
 where
-    D: Dispatch<WlTouch, TouchUserData>,
+    D: Dispatch<WlTouch, TouchUserData<D>>,
     D: SeatHandler,
     D: 'static,
 {
@@ -198,19 +128,18 @@ where
         _client: &wayland_server::Client,
         _resource: &WlTouch,
         _request: wl_touch::Request,
-        _data: &TouchUserData,
+        _data: &TouchUserData<D>,
         _dhandle: &DisplayHandle,
         _data_init: &mut wayland_server::DataInit<'_, D>,
     ) {
     }
 
-    fn destroyed(_state: &mut D, _client_id: ClientId, touch: &WlTouch, data: &TouchUserData) {
+    fn destroyed(_state: &mut D, _client_id: ClientId, touch: &WlTouch, data: &TouchUserData<D>) {
         if let Some(ref handle) = data.handle {
             handle
-                .inner
+                .known_instances
                 .lock()
                 .unwrap()
-                .known_handles
                 .retain(|k| k.id() != touch.id())
         }
     }
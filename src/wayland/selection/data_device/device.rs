@@ -43,6 +43,7 @@ where
     D: SeatHandler,
     <D as SeatHandler>::PointerFocus: WaylandFocus,
     <D as SeatHandler>::KeyboardFocus: WaylandFocus,
+    <D as SeatHandler>::TouchFocus: WaylandFocus,
     D: 'static,
 {
     fn request(
@@ -67,30 +68,53 @@ where
                 serial,
             } => {
                 let serial = Serial::from(serial);
-                if let Some(pointer) = seat.get_pointer() {
-                    if pointer.has_grab(serial) {
-                        if let Some(ref icon) = icon {
-                            if compositor::give_role(icon, DND_ICON_ROLE).is_err() {
-                                resource.post_error(
-                                    wl_data_device::Error::Role,
-                                    "Given surface already has an other role",
-                                );
-                                return;
-                            }
-                        }
-                        // The StartDrag is in response to a pointer implicit grab, all is good
-                        handler.started(source.clone(), icon.clone(), seat.clone());
-                        let start_data = pointer.grab_start_data().unwrap();
-                        pointer.set_grab(
-                            handler,
-                            dnd_grab::DnDGrab::new(dh, start_data, source, origin, seat, icon),
-                            serial,
-                            Focus::Clear,
+                let pointer = seat.get_pointer().filter(|pointer| pointer.has_grab(serial));
+                let touch = seat.get_touch().filter(|touch| touch.has_grab(serial));
+                if pointer.is_none() && touch.is_none() {
+                    debug!(serial = ?serial, client = ?client, "denying drag from client without implicit grab");
+                    return;
+                }
+
+                if let Some(ref icon) = icon {
+                    if compositor::give_role(icon, DND_ICON_ROLE).is_err() {
+                        resource.post_error(
+                            wl_data_device::Error::Role,
+                            "Given surface already has an other role",
                         );
                         return;
                     }
                 }
-                debug!(serial = ?serial, client = ?client, "denying drag from client without implicit grab");
+                // The StartDrag is in response to an implicit grab, all is good
+                handler.started(source.clone(), icon.clone(), seat.clone());
+                if let Some(pointer) = pointer {
+                    let start_data = pointer.grab_start_data().unwrap();
+                    pointer.set_grab(
+                        handler,
+                        dnd_grab::DnDGrab::new(
+                            dh,
+                            dnd_grab::DnDStartData::Pointer(start_data),
+                            source,
+                            origin,
+                            seat,
+                            icon,
+                        ),
+                        serial,
+                        Focus::Clear,
+                    );
+                } else if let Some(touch) = touch {
+                    let start_data = touch.grab_start_data().unwrap();
+                    touch.set_grab(
+                        dnd_grab::DnDGrab::new(
+                            dh,
+                            dnd_grab::DnDStartData::Touch(start_data),
+                            source,
+                            origin,
+                            seat,
+                            icon,
+                        ),
+                        serial,
+                    );
+                }
             }
             wl_data_device::Request::SetSelection { source, .. } => {
                 let seat_data = match seat.get_keyboard() {
@@ -23,17 +23,23 @@ use crate::{
             GestureSwipeUpdateEvent, GrabStartData as PointerGrabStartData, MotionEvent, PointerGrab,
             PointerInnerHandle, RelativeMotionEvent,
         },
+        touch::{self, GrabStartData as TouchGrabStartData, TouchGrab, TouchInnerHandle},
         Seat, SeatHandler,
     },
-    utils::{IsAlive, Logical, Point},
+    utils::{IsAlive, Logical, Point, Serial, SERIAL_COUNTER},
     wayland::{seat::WaylandFocus, selection::seat_data::SeatData},
 };
 
 use super::{with_source_metadata, ClientDndGrabHandler, DataDeviceHandler};
 
+pub(crate) enum DnDStartData<D: SeatHandler> {
+    Pointer(PointerGrabStartData<D>),
+    Touch(TouchGrabStartData<D>),
+}
+
 pub(crate) struct DnDGrab<D: SeatHandler> {
     dh: DisplayHandle,
-    start_data: PointerGrabStartData<D>,
+    start_data: DnDStartData<D>,
     data_source: Option<wl_data_source::WlDataSource>,
     current_focus: Option<WlSurface>,
     pending_offers: Vec<wl_data_offer::WlDataOffer>,
@@ -46,7 +52,7 @@ pub(crate) struct DnDGrab<D: SeatHandler> {
 impl<D: SeatHandler> DnDGrab<D> {
     pub(crate) fn new(
         dh: &DisplayHandle,
-        start_data: PointerGrabStartData<D>,
+        start_data: DnDStartData<D>,
         source: Option<wl_data_source::WlDataSource>,
         origin: WlSurface,
         seat: Seat<D>,
@@ -66,30 +72,26 @@ impl<D: SeatHandler> DnDGrab<D> {
     }
 }
 
-impl<D> PointerGrab<D> for DnDGrab<D>
+impl<D> DnDGrab<D>
 where
     D: DataDeviceHandler,
     D: SeatHandler,
-    <D as SeatHandler>::PointerFocus: WaylandFocus,
     D: 'static,
 {
-    fn motion(
+    fn update_focus(
         &mut self,
-        data: &mut D,
-        handle: &mut PointerInnerHandle<'_, D>,
-        focus: Option<(<D as SeatHandler>::PointerFocus, Point<i32, Logical>)>,
-        event: &MotionEvent,
+        focus: Option<(WlSurface, Point<i32, Logical>)>,
+        location: Point<f64, Logical>,
+        serial: Serial,
+        time: u32,
     ) {
-        // While the grab is active, no client has pointer focus
-        handle.motion(data, None, event);
-
         let seat_data = self
             .seat
             .user_data()
             .get::<RefCell<SeatData<D::SelectionUserData>>>()
             .unwrap()
             .borrow_mut();
-        if focus.as_ref().and_then(|(s, _)| s.wl_surface()) != self.current_focus.clone() {
+        if focus.as_ref().map(|(s, _)| s) != self.current_focus.as_ref() {
             // focus changed, we need to make a leave if appropriate
             if let Some(surface) = self.current_focus.take() {
                 // only leave if there is a data source or we are on the original client
@@ -107,16 +109,13 @@ where
                 }
             }
         }
-        if let Some((surface, surface_location)) = focus
-            .as_ref()
-            .and_then(|(h, loc)| h.wl_surface().map(|s| (s, loc)))
-        {
+        if let Some((surface, surface_location)) = focus {
             // early return if the surface is no longer valid
             let client = match self.dh.get_client(surface.id()) {
                 Ok(c) => c,
                 Err(_) => return,
             };
-            let (x, y) = (event.location - surface_location.to_f64()).into();
+            let (x, y) = (location - surface_location.to_f64()).into();
             if self.current_focus.is_none() {
                 // We entered a new surface, send the data offer if appropriate
                 if let Some(ref source) = self.data_source {
@@ -155,7 +154,7 @@ where
                             offer.source_actions(meta.dnd_action);
                         })
                         .unwrap();
-                        device.enter(event.serial.into(), &surface, x, y, Some(&offer));
+                        device.enter(serial.into(), &surface, x, y, Some(&offer));
                         self.pending_offers.push(offer);
                     }
                     self.offer_data = Some(offer_data);
@@ -164,7 +163,7 @@ where
                     if self.origin.id().same_client_as(&surface.id()) {
                         for device in seat_data.known_data_devices() {
                             if device.id().same_client_as(&surface.id()) {
-                                device.enter(event.serial.into(), &surface, x, y, None);
+                                device.enter(serial.into(), &surface, x, y, None);
                             }
                         }
                     }
@@ -175,7 +174,7 @@ where
                 if self.data_source.is_some() || self.origin.id().same_client_as(&surface.id()) {
                     for device in seat_data.known_data_devices() {
                         if device.id().same_client_as(&surface.id()) {
-                            device.motion(event.time, x, y);
+                            device.motion(time, x, y);
                         }
                     }
                 }
@@ -183,6 +182,86 @@ where
         }
     }
 
+    // Ends the drag'n'drop operation, either by dropping on the currently
+    // focused surface or, if `cancelled` is set, by abandoning it.
+    fn drop(&mut self, data: &mut D, cancelled: bool) {
+        let seat_data = self
+            .seat
+            .user_data()
+            .get::<RefCell<SeatData<D::SelectionUserData>>>()
+            .unwrap()
+            .borrow_mut();
+        let validated = match self.offer_data {
+            Some(ref data) if !cancelled => {
+                let data = data.lock().unwrap();
+                data.accepted && (!data.chosen_action.is_empty())
+            }
+            _ => false,
+        };
+        if let Some(ref surface) = self.current_focus {
+            if self.data_source.is_some() || self.origin.id().same_client_as(&surface.id()) {
+                for device in seat_data.known_data_devices() {
+                    if device.id().same_client_as(&surface.id()) && validated {
+                        device.drop();
+                    }
+                }
+            }
+        }
+        if let Some(ref offer_data) = self.offer_data {
+            let mut data = offer_data.lock().unwrap();
+            if validated {
+                data.dropped = true;
+            } else {
+                data.active = false;
+            }
+        }
+        if let Some(ref source) = self.data_source {
+            if source.version() >= 3 && !cancelled {
+                source.dnd_drop_performed();
+            }
+            if !validated {
+                source.cancelled();
+            }
+        }
+
+        ClientDndGrabHandler::dropped(data, self.seat.clone());
+        self.icon = None;
+        // in all cases abandon the drop
+        if let Some(ref surface) = self.current_focus {
+            for device in seat_data.known_data_devices() {
+                if device.id().same_client_as(&surface.id()) {
+                    device.leave();
+                }
+            }
+        }
+    }
+}
+
+impl<D> PointerGrab<D> for DnDGrab<D>
+where
+    D: DataDeviceHandler,
+    D: SeatHandler,
+    <D as SeatHandler>::PointerFocus: WaylandFocus,
+    D: 'static,
+{
+    fn motion(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        focus: Option<(<D as SeatHandler>::PointerFocus, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        // While the grab is active, no client has pointer focus
+        handle.motion(data, None, event);
+
+        self.update_focus(
+            focus.and_then(|(h, loc)| h.wl_surface().map(|s| (s, loc))),
+            event.location,
+            event.serial,
+            event.time,
+        );
+    }
+
     fn relative_motion(
         &mut self,
         data: &mut D,
@@ -196,55 +275,7 @@ where
     fn button(&mut self, data: &mut D, handle: &mut PointerInnerHandle<'_, D>, event: &ButtonEvent) {
         if handle.current_pressed().is_empty() {
             // the user dropped, proceed to the drop
-            let seat_data = self
-                .seat
-                .user_data()
-                .get::<RefCell<SeatData<D::SelectionUserData>>>()
-                .unwrap()
-                .borrow_mut();
-            let validated = if let Some(ref data) = self.offer_data {
-                let data = data.lock().unwrap();
-                data.accepted && (!data.chosen_action.is_empty())
-            } else {
-                false
-            };
-            if let Some(ref surface) = self.current_focus {
-                if self.data_source.is_some() || self.origin.id().same_client_as(&surface.id()) {
-                    for device in seat_data.known_data_devices() {
-                        if device.id().same_client_as(&surface.id()) && validated {
-                            device.drop();
-                        }
-                    }
-                }
-            }
-            if let Some(ref offer_data) = self.offer_data {
-                let mut data = offer_data.lock().unwrap();
-                if validated {
-                    data.dropped = true;
-                } else {
-                    data.active = false;
-                }
-            }
-            if let Some(ref source) = self.data_source {
-                if source.version() >= 3 {
-                    source.dnd_drop_performed();
-                }
-                if !validated {
-                    source.cancelled();
-                }
-            }
-
-            ClientDndGrabHandler::dropped(data, self.seat.clone());
-            self.icon = None;
-            // in all cases abandon the drop
-            // no more buttons are pressed, release the grab
-            if let Some(ref surface) = self.current_focus {
-                for device in seat_data.known_data_devices() {
-                    if device.id().same_client_as(&surface.id()) {
-                        device.leave();
-                    }
-                }
-            }
+            self.drop(data, false);
             handle.unset_grab(data, event.serial, event.time, true);
         }
     }
@@ -331,7 +362,91 @@ where
     }
 
     fn start_data(&self) -> &PointerGrabStartData<D> {
-        &self.start_data
+        match &self.start_data {
+            DnDStartData::Pointer(start_data) => start_data,
+            DnDStartData::Touch(_) => unreachable!(),
+        }
+    }
+}
+
+impl<D> TouchGrab<D> for DnDGrab<D>
+where
+    D: DataDeviceHandler,
+    D: SeatHandler,
+    <D as SeatHandler>::TouchFocus: WaylandFocus,
+    D: 'static,
+{
+    fn down(
+        &mut self,
+        data: &mut D,
+        handle: &mut TouchInnerHandle<'_, D>,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &touch::DownEvent,
+    ) {
+        // additional touch points are not part of the drag'n'drop operation
+        handle.down(data, focus, event);
+    }
+
+    fn up(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &touch::UpEvent) {
+        handle.up(data, event);
+        if event.slot != <Self as TouchGrab<D>>::start_data(self).slot {
+            return;
+        }
+
+        // the user dropped, proceed to the drop
+        self.drop(data, false);
+        handle.unset_grab();
+    }
+
+    fn motion(
+        &mut self,
+        data: &mut D,
+        handle: &mut TouchInnerHandle<'_, D>,
+        focus: Option<(<D as SeatHandler>::TouchFocus, Point<i32, Logical>)>,
+        event: &touch::MotionEvent,
+    ) {
+        if event.slot != <Self as TouchGrab<D>>::start_data(self).slot {
+            handle.motion(data, focus, event);
+            return;
+        }
+
+        // While the grab is active, the dragging touch point is not forwarded to clients
+        self.update_focus(
+            focus.and_then(|(h, loc)| h.wl_surface().map(|s| (s, loc))),
+            event.location,
+            SERIAL_COUNTER.next_serial(),
+            event.time,
+        );
+    }
+
+    fn frame(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>) {
+        handle.frame(data);
+    }
+
+    fn cancel(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>) {
+        self.drop(data, true);
+        handle.cancel(data);
+        handle.unset_grab();
+    }
+
+    fn shape(&mut self, data: &mut D, handle: &mut TouchInnerHandle<'_, D>, event: &touch::ShapeEvent) {
+        handle.shape(data, event);
+    }
+
+    fn orientation(
+        &mut self,
+        data: &mut D,
+        handle: &mut TouchInnerHandle<'_, D>,
+        event: &touch::OrientationEvent,
+    ) {
+        handle.orientation(data, event);
+    }
+
+    fn start_data(&self) -> &TouchGrabStartData<D> {
+        match &self.start_data {
+            DnDStartData::Touch(start_data) => start_data,
+            DnDStartData::Pointer(_) => unreachable!(),
+        }
     }
 }
 
@@ -14,12 +14,16 @@
 //! - The [`wlr_layer`](wlr_layer/index.html) module provides handlers for the `wlr_layer_shell`
 //!   protocol, which is for windows rendering above/below normal XDG windows
 //! - The [`kde`](kde/index.html) module provides handlers for KDE-specific protocols
+//!
+//! Additionally the [`ping`](ping/index.html) module provides a tracker to detect unresponsive clients
+//! across these shells.
 
 use crate::{utils::Serial, wayland::compositor};
 use thiserror::Error;
 use wayland_server::protocol::wl_surface::WlSurface;
 
 pub mod kde;
+pub mod ping;
 pub mod wlr_layer;
 pub mod xdg;
 
@@ -0,0 +1,297 @@
+//! Detection of unresponsive clients
+//!
+//! The [`PingTracker`] pings clients through the means of their shell protocol and reports
+//! clients, which fail to answer in time, through [`PingHandler::client_unresponsive`].
+//! Once such a client answers again, [`PingHandler::client_responsive`] is called,
+//! or [`PingHandler::client_gone`], if it is destroyed without answering.
+//!
+//! Clients are pinged on demand, typically whenever one of their windows gets focused
+//! or receives input. This way a hung client is noticed, once the user interacts with it.
+//!
+//! Supported are xdg-shell clients and, with the `xwayland` feature, X11 windows supporting
+//! the `_NET_WM_PING` protocol.
+//!
+//! ## How to use it
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use smithay::wayland::shell::ping::{PingHandler, PingTarget, PingTracker};
+//!
+//! struct State {
+//!     ping_tracker: PingTracker<State>,
+//!     // ...
+//! }
+//!
+//! impl PingHandler for State {
+//!     fn ping_tracker(&mut self) -> &mut PingTracker<Self> {
+//!         &mut self.ping_tracker
+//!     }
+//!
+//!     fn client_unresponsive(&mut self, target: PingTarget) {
+//!         // show an "application not responding" dialog
+//!     }
+//!
+//!     fn client_responsive(&mut self, target: PingTarget) {
+//!         // dismiss the dialog again
+//!     }
+//!
+//!     fn client_gone(&mut self, target: PingTarget) {
+//!         // dismiss the dialog as well
+//!     }
+//! }
+//!
+//! # let handle: smithay::reexports::calloop::LoopHandle<'static, State> = unreachable!();
+//! # let toplevel: smithay::wayland::shell::xdg::ToplevelSurface = unreachable!();
+//! let ping_tracker = PingTracker::new(handle, Duration::from_secs(5));
+//! # let mut state = State { ping_tracker };
+//!
+//! // e.g. whenever a toplevel gets focused
+//! state.ping_tracker.ping(toplevel.client());
+//! ```
+
+use std::time::Duration;
+
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    LoopHandle, RegistrationToken,
+};
+use tracing::warn;
+
+use crate::utils::{user_data::UserDataMap, SERIAL_COUNTER};
+
+use super::{xdg::ShellClient, PingError};
+
+#[cfg(feature = "xwayland")]
+use crate::xwayland::X11Surface;
+
+/// Handler trait for the [`PingTracker`]
+///
+/// Needs to be implemented by the data of the event loop.
+pub trait PingHandler: Sized + 'static {
+    /// [`PingTracker`] getter
+    fn ping_tracker(&mut self) -> &mut PingTracker<Self>;
+
+    /// The client failed to answer a ping in time
+    fn client_unresponsive(&mut self, target: PingTarget);
+
+    /// A previously unresponsive client answered its ping
+    fn client_responsive(&mut self, target: PingTarget);
+
+    /// A previously unresponsive client was destroyed without answering its ping
+    fn client_gone(&mut self, target: PingTarget);
+}
+
+/// A pingable client
+#[derive(Debug, Clone, PartialEq)]
+pub enum PingTarget {
+    /// A xdg-shell client
+    Xdg(ShellClient),
+    /// A X11 window
+    #[cfg(feature = "xwayland")]
+    X11(X11Surface),
+}
+
+impl PingTarget {
+    /// Is the client still alive?
+    pub fn alive(&self) -> bool {
+        match self {
+            PingTarget::Xdg(client) => client.alive(),
+            #[cfg(feature = "xwayland")]
+            PingTarget::X11(window) => window.alive(),
+        }
+    }
+}
+
+impl From<ShellClient> for PingTarget {
+    fn from(client: ShellClient) -> Self {
+        PingTarget::Xdg(client)
+    }
+}
+
+#[cfg(feature = "xwayland")]
+impl From<X11Surface> for PingTarget {
+    fn from(window: X11Surface) -> Self {
+        PingTarget::X11(window)
+    }
+}
+
+// Type-erased access to the tracker of a target, used to notify about pongs
+pub(crate) struct PongNotifier(Box<dyn Fn(PingTarget)>);
+
+/// Notifies the [`PingTracker`] of a pinged target about a pong
+pub(crate) fn notify_pong(user_data: &UserDataMap, target: PingTarget) {
+    if let Some(notifier) = user_data.get::<PongNotifier>() {
+        (notifier.0)(target);
+    }
+}
+
+#[derive(Debug)]
+struct PendingPing {
+    target: PingTarget,
+    // keeps firing after the timeout to notice unresponsive targets being destroyed
+    timer: RegistrationToken,
+    unresponsive: bool,
+}
+
+/// Tracker for pings sent to clients
+#[derive(Debug)]
+pub struct PingTracker<D: PingHandler> {
+    handle: LoopHandle<'static, D>,
+    timeout: Duration,
+    pending: Vec<PendingPing>,
+}
+
+impl<D: PingHandler> PingTracker<D> {
+    /// Create a new tracker, considering clients unresponsive after `timeout`
+    pub fn new(handle: LoopHandle<'static, D>, timeout: Duration) -> Self {
+        PingTracker {
+            handle,
+            timeout,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the timeout after which clients are considered unresponsive
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Set the timeout after which clients are considered unresponsive
+    ///
+    /// Only affects pings sent afterwards.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Returns if the target failed to answer a ping in time and did not answer since
+    pub fn is_unresponsive(&self, target: &PingTarget) -> bool {
+        self.pending
+            .iter()
+            .any(|pending| pending.target == *target && pending.unresponsive)
+    }
+
+    /// Returns all targets currently considered unresponsive
+    pub fn unresponsive(&self) -> impl Iterator<Item = &PingTarget> {
+        self.pending
+            .iter()
+            .filter(|pending| pending.unresponsive)
+            .map(|pending| &pending.target)
+    }
+
+    /// Ping a client
+    ///
+    /// Should be called when the client becomes relevant to the user, e.g. when one of its windows
+    /// got focused or interacted with. Does nothing, if the client is already being pinged
+    /// or is a X11 window not supporting `_NET_WM_PING`.
+    pub fn ping(&mut self, target: impl Into<PingTarget>) {
+        let target = target.into();
+
+        if self.pending.iter().any(|pending| pending.target == target) {
+            return;
+        }
+
+        let handle = self.handle.clone();
+        let notifier = move || {
+            PongNotifier(Box::new(move |target| {
+                handle.insert_idle(move |state| Self::pong(state, target));
+            }))
+        };
+
+        match &target {
+            PingTarget::Xdg(client) => {
+                let _ = client.with_data(|data| data.insert_if_missing(notifier));
+                match client.send_ping(SERIAL_COUNTER.next_serial()) {
+                    // somebody else is pinging, we can still wait for the pong
+                    Ok(_) | Err(PingError::PingAlreadyPending(_)) => {}
+                    Err(PingError::DeadSurface) => return,
+                }
+            }
+            #[cfg(feature = "xwayland")]
+            PingTarget::X11(window) => {
+                if !window.supports_ping() {
+                    return;
+                }
+                window.user_data().insert_if_missing(notifier);
+                if let Err(err) = window.send_ping() {
+                    warn!(?err, "Failed to ping X11 window");
+                    return;
+                }
+            }
+        }
+
+        let timer_target = target.clone();
+        let timer = self
+            .handle
+            .insert_source(Timer::from_duration(self.timeout), move |_, _, state| {
+                Self::timed_out(state, &timer_target)
+            });
+        let timer = match timer {
+            Ok(token) => token,
+            Err(err) => {
+                warn!(?err, "Failed to insert ping timer");
+                return;
+            }
+        };
+
+        self.pending.push(PendingPing {
+            target,
+            timer,
+            unresponsive: false,
+        });
+    }
+
+    fn timed_out(state: &mut D, target: &PingTarget) -> TimeoutAction {
+        let timeout = state.ping_tracker().timeout;
+        Self::remove_dead(state, target);
+
+        let tracker = state.ping_tracker();
+        let Some(pending) = tracker
+            .pending
+            .iter_mut()
+            .find(|pending| pending.target == *target)
+        else {
+            return TimeoutAction::Drop;
+        };
+
+        if !std::mem::replace(&mut pending.unresponsive, true) {
+            state.client_unresponsive(target.clone());
+        }
+        // check again later, if the target is still alive
+        TimeoutAction::ToDuration(timeout)
+    }
+
+    // `current` is the target whose timer is currently firing and thus cannot be removed
+    fn remove_dead(state: &mut D, current: &PingTarget) {
+        let tracker = state.ping_tracker();
+        let (dead, alive) = std::mem::take(&mut tracker.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|pending| !pending.target.alive());
+        tracker.pending = alive;
+
+        for pending in dead {
+            if pending.target != *current {
+                state.ping_tracker().handle.remove(pending.timer);
+            }
+            if pending.unresponsive {
+                state.client_gone(pending.target);
+            }
+        }
+    }
+
+    fn pong(state: &mut D, target: PingTarget) {
+        let tracker = state.ping_tracker();
+        let Some(pos) = tracker
+            .pending
+            .iter()
+            .position(|pending| pending.target == target)
+        else {
+            return;
+        };
+
+        let pending = tracker.pending.remove(pos);
+        tracker.handle.remove(pending.timer);
+        if pending.unresponsive {
+            state.client_responsive(pending.target);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! XDG Dialog Windows
+//!
+//! This interface allows clients to mark their toplevels as modal dialogs of their parent.
+//!
+//! Modal dialogs typically need to be closed, before the user can interact with the parent
+//! again. The compositor may use this hint to e.g. dim the parent or to not focus it.
+//! The current state is available through [`ToplevelSurface::is_modal`].
+//!
+//! ```no_run
+//! # extern crate wayland_server;
+//! #
+//! use smithay::{delegate_xdg_dialog, delegate_xdg_shell};
+//! use smithay::wayland::shell::xdg::{ToplevelSurface, XdgShellHandler};
+//! # use smithay::utils::Serial;
+//! # use smithay::wayland::shell::xdg::{XdgShellState, PopupSurface, PositionerState};
+//! # use smithay::reexports::wayland_server::protocol::wl_seat;
+//! use smithay::wayland::shell::xdg::dialog::{XdgDialogState, XdgDialogHandler};
+//!
+//! # struct State { dialog_state: XdgDialogState }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//!
+//! // Create a dialog state
+//! let dialog_state = XdgDialogState::new::<State>(
+//!     &display.handle(),
+//! );
+//!
+//! // store that state inside your compositor state
+//! // ...
+//!
+//! // implement the necessary traits
+//! impl XdgShellHandler for State {
+//!     # fn xdg_shell_state(&mut self) -> &mut XdgShellState { unimplemented!() }
+//!     # fn new_toplevel(&mut self, surface: ToplevelSurface) { unimplemented!() }
+//!     # fn new_popup(
+//!     #     &mut self,
+//!     #     surface: PopupSurface,
+//!     #     positioner: PositionerState,
+//!     # ) { unimplemented!() }
+//!     # fn grab(
+//!     #     &mut self,
+//!     #     surface: PopupSurface,
+//!     #     seat: wl_seat::WlSeat,
+//!     #     serial: Serial,
+//!     # ) { unimplemented!() }
+//!     # fn reposition_request(
+//!     #     &mut self,
+//!     #     surface: PopupSurface,
+//!     #     positioner: PositionerState,
+//!     #     token: u32,
+//!     # ) { unimplemented!() }
+//!     // ...
+//! }
+//! impl XdgDialogHandler for State {
+//!     fn modal_changed(&mut self, toplevel: ToplevelSurface, is_modal: bool) {
+//!         // dim the parent of the toplevel
+//!     }
+//! }
+//! delegate_xdg_shell!(State);
+//! delegate_xdg_dialog!(State);
+//! ```
+
+use wayland_protocols::xdg::dialog::v1::server::{
+    xdg_dialog_v1::{self, XdgDialogV1},
+    xdg_wm_dialog_v1::{self, XdgWmDialogV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use super::{ToplevelSurface, XdgShellHandler, XdgShellSurfaceUserData, XdgToplevelSurfaceData};
+use crate::wayland::compositor;
+
+/// Delegate type for handling xdg dialog events.
+#[derive(Debug)]
+pub struct XdgDialogState {
+    global: GlobalId,
+}
+
+impl XdgDialogState {
+    /// Creates a new delegate type for handling xdg dialog events.
+    pub fn new<D>(display: &DisplayHandle) -> XdgDialogState
+    where
+        D: GlobalDispatch<XdgWmDialogV1, ()> + Dispatch<XdgWmDialogV1, ()> + 'static,
+    {
+        let global = display.create_global::<D, XdgWmDialogV1, _>(1, ());
+
+        XdgDialogState { global }
+    }
+
+    /// Returns the xdg-wm-dialog global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// Handler trait for xdg dialog events.
+pub trait XdgDialogHandler {
+    /// Notification the toplevel became or stopped being a modal dialog.
+    fn modal_changed(&mut self, toplevel: ToplevelSurface, is_modal: bool);
+}
+
+/// Macro to delegate implementation of the xdg dialog to [`XdgDialogState`].
+///
+/// You must also implement [`XdgDialogHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_xdg_dialog {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::dialog::v1::server::xdg_wm_dialog_v1::XdgWmDialogV1: ()
+        ] => $crate::wayland::shell::xdg::dialog::XdgDialogState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::dialog::v1::server::xdg_wm_dialog_v1::XdgWmDialogV1: ()
+        ] => $crate::wayland::shell::xdg::dialog::XdgDialogState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::dialog::v1::server::xdg_dialog_v1::XdgDialogV1: $crate::wayland::shell::xdg::ToplevelSurface
+        ] => $crate::wayland::shell::xdg::dialog::XdgDialogState);
+    };
+}
+
+// Updates the modal flag and returns whether it changed
+fn set_modal(toplevel: &ToplevelSurface, modal: bool) -> bool {
+    compositor::with_states(toplevel.wl_surface(), |states| {
+        let mut attributes = states
+            .data_map
+            .get::<XdgToplevelSurfaceData>()
+            .unwrap()
+            .lock()
+            .unwrap();
+        std::mem::replace(&mut attributes.modal, modal) != modal
+    })
+}
+
+// xdg_wm_dialog_v1
+
+impl<D> GlobalDispatch<XdgWmDialogV1, (), D> for XdgDialogState
+where
+    D: GlobalDispatch<XdgWmDialogV1, ()>
+        + Dispatch<XdgWmDialogV1, ()>
+        + Dispatch<XdgDialogV1, ToplevelSurface>
+        + XdgShellHandler
+        + XdgDialogHandler
+        + 'static,
+{
+    fn bind(
+        _: &mut D,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<XdgWmDialogV1>,
+        _: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> Dispatch<XdgWmDialogV1, (), D> for XdgDialogState
+where
+    D: Dispatch<XdgWmDialogV1, ()>
+        + Dispatch<XdgDialogV1, ToplevelSurface>
+        + XdgShellHandler
+        + XdgDialogHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        _: &Client,
+        resource: &XdgWmDialogV1,
+        request: xdg_wm_dialog_v1::Request,
+        _: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            xdg_wm_dialog_v1::Request::GetXdgDialog { id, toplevel } => {
+                let data = toplevel.data::<XdgShellSurfaceUserData>().unwrap();
+
+                let mut dialog_guard = data.dialog.lock().unwrap();
+
+                if dialog_guard.is_some() {
+                    resource.post_error(
+                        xdg_wm_dialog_v1::Error::AlreadyUsed,
+                        "toplevel dialog is already constructed",
+                    );
+                    return;
+                }
+
+                let toplevel = state.xdg_shell_state().get_toplevel(&toplevel).unwrap();
+                let dialog = data_init.init(id, toplevel);
+
+                *dialog_guard = Some(dialog);
+            }
+
+            xdg_wm_dialog_v1::Request::Destroy => {}
+
+            _ => unreachable!(),
+        }
+    }
+}
+
+// xdg_dialog_v1
+
+impl<D> Dispatch<XdgDialogV1, ToplevelSurface, D> for XdgDialogState
+where
+    D: Dispatch<XdgDialogV1, ToplevelSurface> + XdgDialogHandler,
+{
+    fn request(
+        state: &mut D,
+        _: &Client,
+        _: &XdgDialogV1,
+        request: xdg_dialog_v1::Request,
+        data: &ToplevelSurface,
+        _dh: &DisplayHandle,
+        _: &mut DataInit<'_, D>,
+    ) {
+        // The dialog becomes inert, once the toplevel is destroyed
+        if !data.alive() {
+            return;
+        }
+
+        match request {
+            xdg_dialog_v1::Request::SetModal => {
+                if set_modal(data, true) {
+                    state.modal_changed(data.clone(), true);
+                }
+            }
+
+            xdg_dialog_v1::Request::UnsetModal => {
+                if set_modal(data, false) {
+                    state.modal_changed(data.clone(), false);
+                }
+            }
+
+            xdg_dialog_v1::Request::Destroy => {
+                if let Some(data) = data.xdg_toplevel().data::<XdgShellSurfaceUserData>() {
+                    data.dialog.lock().unwrap().take();
+                }
+            }
+
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, _: &XdgDialogV1, data: &ToplevelSurface) {
+        // Destroying the dialog unapplies its effects
+        if data.alive() && set_modal(data, false) {
+            state.modal_changed(data.clone(), false);
+        }
+    }
+}
@@ -68,8 +68,10 @@ where
             xdg_positioner::Request::SetConstraintAdjustment {
                 constraint_adjustment,
             } => {
-                let constraint_adjustment =
-                    xdg_positioner::ConstraintAdjustment::from_bits_truncate(constraint_adjustment);
+                let constraint_adjustment = match constraint_adjustment {
+                    WEnum::Value(constraint_adjustment) => constraint_adjustment,
+                    WEnum::Unknown(bits) => xdg_positioner::ConstraintAdjustment::from_bits_truncate(bits),
+                };
                 state.constraint_adjustment = constraint_adjustment;
             }
             xdg_positioner::Request::SetOffset { x, y } => {
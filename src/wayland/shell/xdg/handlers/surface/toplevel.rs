@@ -48,6 +48,9 @@ where
 
                 // Parent is not double buffered, we can set it directly
                 set_parent(toplevel, parent_surface);
+
+                let handle = make_toplevel_handle(toplevel);
+                XdgShellHandler::parent_changed(state, handle);
             }
             xdg_toplevel::Request::SetTitle { title } => {
                 // Title is not double buffered, we can set it directly
@@ -128,6 +131,7 @@ where
     ) {
         data.alive_tracker.destroy_notify();
         data.decoration.lock().unwrap().take();
+        data.dialog.lock().unwrap().take();
 
         if let Some(index) = state
             .xdg_shell_state()
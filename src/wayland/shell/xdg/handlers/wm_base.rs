@@ -4,7 +4,7 @@ use indexmap::IndexSet;
 
 use crate::{
     utils::{alive_tracker::AliveTracker, IsAlive, Serial},
-    wayland::shell::xdg::XdgShellState,
+    wayland::shell::{ping, xdg::XdgShellState},
 };
 
 use wayland_protocols::xdg::shell::server::{
@@ -85,6 +85,7 @@ where
                     let mut guard = data.client_data.lock().unwrap();
                     if guard.pending_ping == Some(serial) {
                         guard.pending_ping = None;
+                        ping::notify_pong(&guard.data, ShellClient::new(wm_base).into());
                         true
                     } else {
                         false
@@ -119,6 +119,7 @@ use wayland_server::{
 use super::PingError;
 
 pub mod decoration;
+pub mod dialog;
 
 // handlers for the xdg_shell protocol
 pub(super) mod handlers;
@@ -332,7 +333,11 @@ xdg_role!(
         ///
         /// For D-Bus activatable applications, the app ID is used as the D-Bus
         /// service name.
-        pub app_id: Option<String>
+        pub app_id: Option<String>,
+        /// Whether this toplevel is a modal dialog of its parent
+        ///
+        /// Set through the `xdg_dialog_v1` protocol, see [`dialog`].
+        pub modal: bool
     }
 );
 
@@ -885,9 +890,16 @@ pub trait XdgShellHandler {
     fn new_popup(&mut self, surface: PopupSurface, positioner: PositionerState);
 
     /// The client requested the start of an interactive move for this surface
+    ///
+    /// The serial identifies the implicit grab the move was started from, use
+    /// [`PointerHandle::has_grab`](crate::input::pointer::PointerHandle::has_grab) or
+    /// [`TouchHandle::has_grab`](crate::input::touch::TouchHandle::has_grab) to find the
+    /// matching input device.
     fn move_request(&mut self, surface: ToplevelSurface, seat: wl_seat::WlSeat, serial: Serial) {}
 
     /// The client requested the start of an interactive resize for this surface
+    ///
+    /// See [`XdgShellHandler::move_request`] on how to handle the provided serial.
     fn resize_request(
         &mut self,
         surface: ToplevelSurface,
@@ -956,6 +968,11 @@ pub trait XdgShellHandler {
     ///     The token itself is opaque, and has no other special meaning.
     fn reposition_request(&mut self, surface: PopupSurface, positioner: PositionerState, token: u32);
 
+    /// The parent of a toplevel surface has changed.
+    ///
+    /// This is also invoked, if the parent was set by another protocol like `xdg-foreign`.
+    fn parent_changed(&mut self, surface: ToplevelSurface) {}
+
     /// A toplevel surface was destroyed.
     fn toplevel_destroyed(&mut self, surface: ToplevelSurface) {}
 
@@ -1067,7 +1084,7 @@ pub(crate) struct ShellClientData {
 ///
 /// You can use this handle to access a storage for any
 /// client-specific data you wish to associate with it.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ShellClient {
     kind: xdg_wm_base::XdgWmBase,
 }
@@ -1427,6 +1444,19 @@ impl ToplevelSurface {
         handlers::get_parent(&self.shell_surface)
     }
 
+    /// Returns whether this toplevel is a modal dialog of its parent.
+    pub fn is_modal(&self) -> bool {
+        compositor::with_states(&self.wl_surface, |states| {
+            states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .modal
+        })
+    }
+
     /// Sets the parent of this toplevel surface and returns whether the parent was successfully set.
     ///
     /// The parent must be another toplevel equivalent surface.
@@ -1439,8 +1469,7 @@ impl ToplevelSurface {
             }
         }
 
-        // Unset the parent
-        handlers::set_parent(&self.shell_surface, None);
+        handlers::set_parent(&self.shell_surface, parent.cloned());
 
         true
     }
@@ -0,0 +1,201 @@
+//! Utilities for handling the `wp-single-pixel-buffer` protocol
+//!
+//! Single pixel buffers are 1x1 buffers of a fixed color. Clients typically use them
+//! together with `wp-viewporter` to draw solid backgrounds or letterboxing without
+//! having to allocate and fill larger buffers.
+//!
+//! Buffers created through this global are recognized by [`buffer_type`](crate::backend::renderer::buffer_type)
+//! as [`BufferType::SinglePixel`](crate::backend::renderer::BufferType::SinglePixel).
+//! [`WaylandSurfaceRenderElement`](crate::backend::renderer::element::surface::WaylandSurfaceRenderElement)
+//! draws them as a solid color, without importing a texture.
+//!
+//! ## How to use it
+//!
+//! ### Initialization
+//!
+//! ```
+//! use smithay::wayland::single_pixel_buffer::SinglePixelBufferState;
+//! use smithay::delegate_single_pixel_buffer;
+//! # use smithay::wayland::buffer::BufferHandler;
+//! # use smithay::reexports::wayland_server::protocol::wl_buffer::WlBuffer;
+//!
+//! # struct State;
+//! # impl BufferHandler for State {
+//! #     fn buffer_destroyed(&mut self, _buffer: &WlBuffer) {}
+//! # }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! let single_pixel_buffer_state = SinglePixelBufferState::new::<State>(&display.handle());
+//!
+//! // BufferHandler is required to be notified about destroyed buffers
+//! delegate_single_pixel_buffer!(State);
+//! ```
+
+use wayland_protocols::wp::single_pixel_buffer::v1::server::wp_single_pixel_buffer_manager_v1::{
+    self, WpSinglePixelBufferManagerV1,
+};
+use wayland_server::{
+    backend::GlobalId,
+    protocol::wl_buffer::{self, WlBuffer},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use crate::utils::UnmanagedResource;
+
+use super::buffer::BufferHandler;
+
+/// State of the wp_single_pixel_buffer_manager_v1 global
+#[derive(Debug)]
+pub struct SinglePixelBufferState {
+    global: GlobalId,
+}
+
+impl SinglePixelBufferState {
+    /// Create a new [`WpSinglePixelBufferManagerV1`] global
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<WpSinglePixelBufferManagerV1, ()>,
+        D: Dispatch<WpSinglePixelBufferManagerV1, ()>,
+        D: Dispatch<WlBuffer, SinglePixelBufferUserData>,
+        D: BufferHandler,
+        D: 'static,
+    {
+        let global = display.create_global::<D, WpSinglePixelBufferManagerV1, _>(1, ());
+
+        Self { global }
+    }
+
+    /// Returns the id of the [`WpSinglePixelBufferManagerV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// User data of a single pixel `wl_buffer`
+///
+/// The color channels are premultiplied by alpha and span the full
+/// range of an `u32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SinglePixelBufferUserData {
+    /// Value of the red channel
+    pub r: u32,
+    /// Value of the green channel
+    pub g: u32,
+    /// Value of the blue channel
+    pub b: u32,
+    /// Value of the alpha channel
+    pub a: u32,
+}
+
+impl SinglePixelBufferUserData {
+    /// Returns whether the buffer is fully opaque
+    pub fn has_alpha(&self) -> bool {
+        self.a != u32::MAX
+    }
+
+    /// Returns the color as premultiplied rgba floats, as used by
+    /// [`Frame::draw_solid`](crate::backend::renderer::Frame::draw_solid)
+    pub fn rgba_f32(&self) -> [f32; 4] {
+        let max = u32::MAX as f64;
+        [
+            (self.r as f64 / max) as f32,
+            (self.g as f64 / max) as f32,
+            (self.b as f64 / max) as f32,
+            (self.a as f64 / max) as f32,
+        ]
+    }
+}
+
+/// Gets the single pixel data of a buffer
+///
+/// If the buffer was not created through the [`SinglePixelBufferState`] global,
+/// this function will return an [`UnmanagedResource`].
+pub fn get_single_pixel_buffer(buffer: &WlBuffer) -> Result<&SinglePixelBufferUserData, UnmanagedResource> {
+    buffer
+        .data::<SinglePixelBufferUserData>()
+        .ok_or(UnmanagedResource)
+}
+
+impl<D> GlobalDispatch<WpSinglePixelBufferManagerV1, (), D> for SinglePixelBufferState
+where
+    D: GlobalDispatch<WpSinglePixelBufferManagerV1, ()>,
+    D: Dispatch<WpSinglePixelBufferManagerV1, ()>,
+    D: Dispatch<WlBuffer, SinglePixelBufferUserData>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        resource: New<WpSinglePixelBufferManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<D> Dispatch<WpSinglePixelBufferManagerV1, (), D> for SinglePixelBufferState
+where
+    D: Dispatch<WpSinglePixelBufferManagerV1, ()>,
+    D: Dispatch<WlBuffer, SinglePixelBufferUserData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _manager: &WpSinglePixelBufferManagerV1,
+        request: wp_single_pixel_buffer_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wp_single_pixel_buffer_manager_v1::Request::CreateU32RgbaBuffer { id, r, g, b, a } => {
+                data_init.init(id, SinglePixelBufferUserData { r, g, b, a });
+            }
+            wp_single_pixel_buffer_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<WlBuffer, SinglePixelBufferUserData, D> for SinglePixelBufferState
+where
+    D: Dispatch<WlBuffer, SinglePixelBufferUserData>,
+    D: BufferHandler,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        buffer: &WlBuffer,
+        request: wl_buffer::Request,
+        _data: &SinglePixelBufferUserData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            wl_buffer::Request::Destroy => {
+                state.buffer_destroyed(buffer);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_single_pixel_buffer {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::single_pixel_buffer::v1::server::wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1: ()
+        ] => $crate::wayland::single_pixel_buffer::SinglePixelBufferState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::single_pixel_buffer::v1::server::wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1: ()
+        ] => $crate::wayland::single_pixel_buffer::SinglePixelBufferState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_server::protocol::wl_buffer::WlBuffer: $crate::wayland::single_pixel_buffer::SinglePixelBufferUserData
+        ] => $crate::wayland::single_pixel_buffer::SinglePixelBufferState);
+    };
+}
@@ -1,11 +1,12 @@
 //! Utilities for graphics tablet support
 //!
-//! This module provides helpers to handle graphics tablets.
+//! This module provides helpers to handle graphics tablets, their tools and pads.
 //!
 //! ```
 //! use smithay::{delegate_seat, delegate_tablet_manager};
 //! use smithay::input::{Seat, SeatState, SeatHandler, pointer::CursorImageStatus};
 //! use smithay::wayland::tablet_manager::{TabletManagerState, TabletDescriptor};
+//! use smithay::backend::input::{TabletPadDescriptor, TabletPadGroupDescriptor};
 //! use smithay::reexports::wayland_server::{Display, protocol::wl_surface::WlSurface};
 //!
 //! # struct State { seat_state: SeatState<Self> };
@@ -25,7 +26,7 @@
 //!
 //! use smithay::wayland::tablet_manager::TabletSeatTrait;
 //!
-//! seat
+//! let tablet = seat
 //!    .tablet_seat()                     // Get TabletSeat asosiated with this seat
 //!    .add_tablet::<State>(              // Add a new tablet to a seat
 //!      &display_handle,
@@ -36,10 +37,30 @@
 //!      }
 //!    );
 //!
+//! // Pads are added the same way, attaching them to a tablet makes them
+//! // follow the surface the tools of that tablet are focused on
+//! let pad = seat.tablet_seat().add_pad::<State>(
+//!     &display_handle,
+//!     &TabletPadDescriptor {
+//!         name: "Test Pad".into(),
+//!         usb_id: None,
+//!         syspath: None,
+//!         buttons: 4,
+//!         groups: vec![TabletPadGroupDescriptor {
+//!             buttons: vec![0, 1, 2, 3],
+//!             rings: vec![0],
+//!             strips: Vec::new(),
+//!             modes: 1,
+//!         }],
+//!     },
+//! );
+//! tablet.add_pad(&pad);
+//!
 //! // implement the required traits
 //! impl SeatHandler for State {
 //!     type KeyboardFocus = WlSurface;
 //!     type PointerFocus = WlSurface;
+//!     type TouchFocus = WlSurface;
 //!     fn seat_state(&mut self) -> &mut SeatState<Self> {
 //!         &mut self.seat_state
 //!     }
@@ -81,6 +102,10 @@
 use crate::input::{Seat, SeatHandler};
 use wayland_protocols::wp::tablet::zv2::server::{
     zwp_tablet_manager_v2::{self, ZwpTabletManagerV2},
+    zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2,
+    zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2,
+    zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2,
+    zwp_tablet_pad_v2::ZwpTabletPadV2,
     zwp_tablet_seat_v2::ZwpTabletSeatV2,
     zwp_tablet_tool_v2::ZwpTabletToolV2,
     zwp_tablet_v2::ZwpTabletV2,
@@ -90,10 +115,12 @@ use wayland_server::{backend::GlobalId, Client, DataInit, Dispatch, DisplayHandl
 const MANAGER_VERSION: u32 = 1;
 
 mod tablet;
+mod tablet_pad;
 mod tablet_seat;
 pub(crate) mod tablet_tool;
 
 pub use tablet::{TabletDescriptor, TabletHandle, TabletUserData};
+pub use tablet_pad::{TabletPadHandle, TabletPadUserData};
 pub use tablet_seat::{TabletSeatHandle, TabletSeatUserData};
 pub use tablet_tool::{TabletToolHandle, TabletToolUserData};
 
@@ -125,6 +152,10 @@ impl TabletManagerState {
         D: Dispatch<ZwpTabletManagerV2, ()>,
         D: Dispatch<ZwpTabletSeatV2, TabletSeatUserData>,
         D: Dispatch<ZwpTabletToolV2, TabletToolUserData>,
+        D: Dispatch<ZwpTabletPadV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadGroupV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadRingV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadStripV2, TabletPadUserData>,
         D: 'static,
     {
         let global = display.create_global::<D, ZwpTabletManagerV2, _>(MANAGER_VERSION, ());
@@ -163,6 +194,10 @@ where
     D: Dispatch<ZwpTabletSeatV2, TabletSeatUserData>,
     D: Dispatch<ZwpTabletV2, TabletUserData>,
     D: Dispatch<ZwpTabletToolV2, TabletToolUserData>,
+    D: Dispatch<ZwpTabletPadV2, TabletPadUserData>,
+    D: Dispatch<ZwpTabletPadGroupV2, TabletPadUserData>,
+    D: Dispatch<ZwpTabletPadRingV2, TabletPadUserData>,
+    D: Dispatch<ZwpTabletPadStripV2, TabletPadUserData>,
     D: SeatHandler + 'static,
 {
     fn request(
@@ -219,5 +254,17 @@ macro_rules! delegate_tablet_manager {
         $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
             $crate::reexports::wayland_protocols::wp::tablet::zv2::server::zwp_tablet_v2::ZwpTabletV2: $crate::wayland::tablet_manager::TabletUserData
         ] => $crate::wayland::tablet_manager::TabletManagerState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::tablet::zv2::server::zwp_tablet_pad_v2::ZwpTabletPadV2: $crate::wayland::tablet_manager::TabletPadUserData
+        ] => $crate::wayland::tablet_manager::TabletManagerState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::tablet::zv2::server::zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2: $crate::wayland::tablet_manager::TabletPadUserData
+        ] => $crate::wayland::tablet_manager::TabletManagerState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::tablet::zv2::server::zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2: $crate::wayland::tablet_manager::TabletPadUserData
+        ] => $crate::wayland::tablet_manager::TabletManagerState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::tablet::zv2::server::zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2: $crate::wayland::tablet_manager::TabletPadUserData
+        ] => $crate::wayland::tablet_manager::TabletManagerState);
     };
 }
@@ -1,6 +1,6 @@
 use std::{
     path::PathBuf,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, Weak},
 };
 
 use wayland_protocols::wp::tablet::zv2::server::{
@@ -12,7 +12,9 @@ use wayland_server::{
 };
 
 use crate::backend::input::Device;
+use crate::utils::Serial;
 
+use super::tablet_pad::{TabletPad, TabletPadHandle};
 use super::TabletManagerState;
 
 /// Description of graphics tablet device
@@ -39,6 +41,7 @@ impl<D: Device> From<&D> for TabletDescriptor {
 #[derive(Debug, Default)]
 struct Tablet {
     instances: Vec<ZwpTabletV2>,
+    pads: Vec<Weak<Mutex<TabletPad>>>,
 }
 
 /// Handle to a tablet device
@@ -96,6 +99,41 @@ impl TabletHandle {
             cb(instance);
         }
     }
+
+    /// Attach a pad to this tablet
+    ///
+    /// Attached pads follow the surface the tools of this tablet come into proximity of.
+    /// The tablet does not keep the pad alive, removing the pad from the seat also detaches it.
+    pub fn add_pad(&self, pad: &TabletPadHandle) {
+        let mut inner = self.inner.lock().unwrap();
+        let weak = Arc::downgrade(&pad.inner);
+        if !inner.pads.iter().any(|p| p.ptr_eq(&weak)) {
+            inner.pads.push(weak);
+        }
+    }
+
+    /// Detach a pad from this tablet
+    pub fn remove_pad(&self, pad: &TabletPadHandle) {
+        let weak = Arc::downgrade(&pad.inner);
+        self.inner.lock().unwrap().pads.retain(|p| !p.ptr_eq(&weak));
+    }
+
+    pub(super) fn focus_pads(&self, focus: &WlSurface, serial: Serial, time: u32) {
+        let pads = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.pads.retain(|p| p.strong_count() > 0);
+            inner
+                .pads
+                .iter()
+                .filter_map(|p| p.upgrade())
+                .map(|inner| TabletPadHandle { inner })
+                .collect::<Vec<_>>()
+        };
+
+        for pad in pads {
+            pad.set_focus(Some(focus), self, serial, time);
+        }
+    }
 }
 
 /// User data of ZwpTabletV2 object
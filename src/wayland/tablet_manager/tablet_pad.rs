@@ -0,0 +1,421 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use wayland_protocols::wp::tablet::zv2::server::{
+    zwp_tablet_pad_group_v2::{self, ZwpTabletPadGroupV2},
+    zwp_tablet_pad_ring_v2::{self, ZwpTabletPadRingV2},
+    zwp_tablet_pad_strip_v2::{self, ZwpTabletPadStripV2},
+    zwp_tablet_pad_v2::{self, ZwpTabletPadV2},
+    zwp_tablet_seat_v2::ZwpTabletSeatV2,
+};
+use wayland_server::{
+    backend::{ClientId, ObjectId},
+    protocol::wl_surface::WlSurface,
+    Client, DataInit, Dispatch, DisplayHandle, Resource,
+};
+
+use crate::backend::input::{ButtonState, TabletPadAxisSource, TabletPadDescriptor};
+use crate::utils::Serial;
+
+use super::tablet::TabletHandle;
+use super::TabletManagerState;
+
+#[derive(Debug)]
+struct TabletPadInstance {
+    pad: ZwpTabletPadV2,
+    groups: Vec<(u32, ZwpTabletPadGroupV2)>,
+    rings: Vec<(u32, ZwpTabletPadRingV2)>,
+    strips: Vec<(u32, ZwpTabletPadStripV2)>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct TabletPad {
+    instances: Vec<TabletPadInstance>,
+    focus: Option<WlSurface>,
+    modes: Vec<u32>,
+}
+
+impl TabletPad {
+    fn focused_instance(&self) -> Option<&TabletPadInstance> {
+        let focus = self.focus.as_ref()?;
+        self.instances
+            .iter()
+            .find(|i| i.pad.id().same_client_as(&focus.id()))
+    }
+
+    fn enter(&mut self, focus: &WlSurface, tablet: &TabletHandle, serial: Serial, time: u32) {
+        self.focus = Some(focus.clone());
+
+        if let Some(instance) = self.focused_instance() {
+            tablet.with_focused_tablet(focus, |wl_tablet| {
+                instance.pad.enter(serial.into(), wl_tablet, focus);
+                // the current mode of every group has to be announced after enter (required by protocol)
+                for (index, group) in instance.groups.iter() {
+                    let mode = self.modes.get(*index as usize).copied().unwrap_or(0);
+                    group.mode_switch(time, serial.into(), mode);
+                }
+            });
+        }
+    }
+
+    fn leave(&mut self, serial: Serial) {
+        if let Some(instance) = self.focused_instance() {
+            if let Some(ref focus) = self.focus {
+                instance.pad.leave(serial.into(), focus);
+            }
+        }
+
+        self.focus = None;
+    }
+
+    fn set_focus(&mut self, focus: Option<&WlSurface>, tablet: &TabletHandle, serial: Serial, time: u32) {
+        if self.focus.as_ref() == focus {
+            return;
+        }
+
+        self.leave(serial);
+        if let Some(focus) = focus {
+            self.enter(focus, tablet, serial, time);
+        }
+    }
+
+    fn button(&self, button: u32, state: ButtonState, time: u32) {
+        if let Some(instance) = self.focused_instance() {
+            instance.pad.button(time, button, state.into());
+        }
+    }
+
+    fn ring(&self, ring: u32, angle: Option<f64>, source: TabletPadAxisSource, time: u32) {
+        if let Some((_, wl_ring)) = self
+            .focused_instance()
+            .and_then(|i| i.rings.iter().find(|(number, _)| *number == ring))
+        {
+            if source == TabletPadAxisSource::Finger {
+                wl_ring.source(zwp_tablet_pad_ring_v2::Source::Finger);
+            }
+            match angle {
+                Some(angle) => wl_ring.angle(angle),
+                None => wl_ring.stop(),
+            }
+            wl_ring.frame(time);
+        }
+    }
+
+    fn strip(&self, strip: u32, position: Option<f64>, source: TabletPadAxisSource, time: u32) {
+        if let Some((_, wl_strip)) = self
+            .focused_instance()
+            .and_then(|i| i.strips.iter().find(|(number, _)| *number == strip))
+        {
+            if source == TabletPadAxisSource::Finger {
+                wl_strip.source(zwp_tablet_pad_strip_v2::Source::Finger);
+            }
+            match position {
+                Some(position) => wl_strip.position((position * 65535.0).round() as u32),
+                None => wl_strip.stop(),
+            }
+            wl_strip.frame(time);
+        }
+    }
+
+    fn mode_switch(&mut self, group: u32, mode: u32, serial: Serial, time: u32) {
+        let index = group as usize;
+        if self.modes.len() <= index {
+            self.modes.resize(index + 1, 0);
+        }
+        if self.modes[index] == mode {
+            return;
+        }
+        self.modes[index] = mode;
+
+        if let Some((_, wl_group)) = self
+            .focused_instance()
+            .and_then(|i| i.groups.iter().find(|(number, _)| *number == group))
+        {
+            wl_group.mode_switch(time, serial.into(), mode);
+        }
+    }
+}
+
+impl Drop for TabletPad {
+    fn drop(&mut self) {
+        for instance in self.instances.iter() {
+            // This event is sent when the pad is removed from the system and will send no further events.
+            instance.pad.removed();
+        }
+    }
+}
+
+/// Handle to a tablet pad device
+///
+/// TabletPad represents the buttons, rings and strips usually physically present on a tablet device.
+///
+/// Pads have no notion of position, the surface they are focused on is decided by the compositor.
+/// A pad attached to a tablet via [`TabletHandle::add_pad`] follows the surface the tools of that
+/// tablet come into proximity of, alternatively its focus can be set explicitly with
+/// [`TabletPadHandle::set_focus`].
+#[derive(Debug, Default, Clone)]
+pub struct TabletPadHandle {
+    pub(crate) inner: Arc<Mutex<TabletPad>>,
+}
+
+impl TabletPadHandle {
+    pub(super) fn new_instance<D>(
+        &mut self,
+        client: &Client,
+        dh: &DisplayHandle,
+        seat: &ZwpTabletSeatV2,
+        pad: &TabletPadDescriptor,
+    ) where
+        D: Dispatch<ZwpTabletPadV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadGroupV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadRingV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadStripV2, TabletPadUserData>,
+        D: 'static,
+    {
+        let user_data = || TabletPadUserData {
+            pad: Arc::downgrade(&self.inner),
+        };
+
+        let wl_pad = client
+            .create_resource::<ZwpTabletPadV2, _, D>(dh, seat.version(), user_data())
+            .unwrap();
+
+        seat.pad_added(&wl_pad);
+
+        let mut instance = TabletPadInstance {
+            pad: wl_pad.clone(),
+            groups: Vec::new(),
+            rings: Vec::new(),
+            strips: Vec::new(),
+        };
+
+        for (index, group) in pad.groups.iter().enumerate() {
+            let wl_group = client
+                .create_resource::<ZwpTabletPadGroupV2, _, D>(dh, wl_pad.version(), user_data())
+                .unwrap();
+            wl_pad.group(&wl_group);
+
+            wl_group.buttons(group.buttons.iter().flat_map(|b| b.to_ne_bytes()).collect());
+
+            for ring in group.rings.iter() {
+                let wl_ring = client
+                    .create_resource::<ZwpTabletPadRingV2, _, D>(dh, wl_pad.version(), user_data())
+                    .unwrap();
+                wl_group.ring(&wl_ring);
+                instance.rings.push((*ring, wl_ring));
+            }
+
+            for strip in group.strips.iter() {
+                let wl_strip = client
+                    .create_resource::<ZwpTabletPadStripV2, _, D>(dh, wl_pad.version(), user_data())
+                    .unwrap();
+                wl_group.strip(&wl_strip);
+                instance.strips.push((*strip, wl_strip));
+            }
+
+            if group.modes > 1 {
+                wl_group.modes(group.modes);
+            }
+
+            wl_group.done();
+            instance.groups.push((index as u32, wl_group));
+        }
+
+        if let Some(syspath) = pad.syspath.as_ref().and_then(|p| p.to_str()) {
+            wl_pad.path(syspath.to_owned());
+        }
+
+        if pad.buttons > 0 {
+            wl_pad.buttons(pad.buttons);
+        }
+
+        wl_pad.done();
+        self.inner.lock().unwrap().instances.push(instance);
+    }
+
+    /// Notify that this pad is focused on a certain surface, or on none.
+    ///
+    /// The tablet is the one this pad is physically attached to.
+    ///
+    /// This will internally take care of notifying the appropriate client objects
+    /// of enter/leave events, and of the current mode of every group of the pad.
+    pub fn set_focus(&self, focus: Option<&WlSurface>, tablet: &TabletHandle, serial: Serial, time: u32) {
+        self.inner.lock().unwrap().set_focus(focus, tablet, serial, time);
+    }
+
+    /// Button on the pad was pressed or released
+    pub fn button(&self, button: u32, state: ButtonState, time: u32) {
+        self.inner.lock().unwrap().button(button, state, time);
+    }
+
+    /// Notify that a ring of the pad changed its angle
+    ///
+    /// The angle is provided in degrees clockwise from the logical north of the ring,
+    /// `None` signals that the interaction with the ring has stopped.
+    pub fn ring(&self, ring: u32, angle: Option<f64>, source: TabletPadAxisSource, time: u32) {
+        self.inner.lock().unwrap().ring(ring, angle, source, time);
+    }
+
+    /// Notify that a strip of the pad changed its position
+    ///
+    /// The position is normalized to the range [0, 1], `None` signals that the
+    /// interaction with the strip has stopped.
+    pub fn strip(&self, strip: u32, position: Option<f64>, source: TabletPadAxisSource, time: u32) {
+        self.inner.lock().unwrap().strip(strip, position, source, time);
+    }
+
+    /// Notify that the mode of a pad group changed
+    ///
+    /// It is safe to call this for every pad event with the mode the event reports,
+    /// clients are only notified when the mode actually changes.
+    pub fn mode_switch(&self, group: u32, mode: u32, serial: Serial, time: u32) {
+        self.inner.lock().unwrap().mode_switch(group, mode, serial, time);
+    }
+}
+
+impl From<ButtonState> for zwp_tablet_pad_v2::ButtonState {
+    fn from(from: ButtonState) -> zwp_tablet_pad_v2::ButtonState {
+        match from {
+            ButtonState::Pressed => zwp_tablet_pad_v2::ButtonState::Pressed,
+            ButtonState::Released => zwp_tablet_pad_v2::ButtonState::Released,
+        }
+    }
+}
+
+/// User data of ZwpTabletPadV2 object and its groups, rings and strips
+#[derive(Debug)]
+pub struct TabletPadUserData {
+    // weak, so removing the pad from the seat drops it even while the client holds its objects
+    pad: Weak<Mutex<TabletPad>>,
+}
+
+impl TabletPadUserData {
+    fn remove_resource(&self, id: &ObjectId) {
+        let Some(pad) = self.pad.upgrade() else {
+            return;
+        };
+        let mut inner = pad.lock().unwrap();
+        inner.instances.retain(|i| i.pad.id() != *id);
+        for instance in inner.instances.iter_mut() {
+            instance.groups.retain(|(_, g)| g.id() != *id);
+            instance.rings.retain(|(_, r)| r.id() != *id);
+            instance.strips.retain(|(_, s)| s.id() != *id);
+        }
+    }
+}
+
+impl<D> Dispatch<ZwpTabletPadV2, TabletPadUserData, D> for TabletManagerState
+where
+    D: Dispatch<ZwpTabletPadV2, TabletPadUserData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _pad: &ZwpTabletPadV2,
+        request: zwp_tablet_pad_v2::Request,
+        _data: &TabletPadUserData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwp_tablet_pad_v2::Request::SetFeedback { .. } => {
+                // Feedback strings are not used
+            }
+            zwp_tablet_pad_v2::Request::Destroy => {
+                // Nothing to do
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(_state: &mut D, _client: ClientId, pad: &ZwpTabletPadV2, data: &TabletPadUserData) {
+        data.remove_resource(&pad.id());
+    }
+}
+
+impl<D> Dispatch<ZwpTabletPadGroupV2, TabletPadUserData, D> for TabletManagerState
+where
+    D: Dispatch<ZwpTabletPadGroupV2, TabletPadUserData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _group: &ZwpTabletPadGroupV2,
+        request: zwp_tablet_pad_group_v2::Request,
+        _data: &TabletPadUserData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwp_tablet_pad_group_v2::Request::Destroy => {
+                // Nothing to do
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(_state: &mut D, _client: ClientId, group: &ZwpTabletPadGroupV2, data: &TabletPadUserData) {
+        data.remove_resource(&group.id());
+    }
+}
+
+impl<D> Dispatch<ZwpTabletPadRingV2, TabletPadUserData, D> for TabletManagerState
+where
+    D: Dispatch<ZwpTabletPadRingV2, TabletPadUserData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _ring: &ZwpTabletPadRingV2,
+        request: zwp_tablet_pad_ring_v2::Request,
+        _data: &TabletPadUserData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwp_tablet_pad_ring_v2::Request::SetFeedback { .. } => {
+                // Feedback strings are not used
+            }
+            zwp_tablet_pad_ring_v2::Request::Destroy => {
+                // Nothing to do
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(_state: &mut D, _client: ClientId, ring: &ZwpTabletPadRingV2, data: &TabletPadUserData) {
+        data.remove_resource(&ring.id());
+    }
+}
+
+impl<D> Dispatch<ZwpTabletPadStripV2, TabletPadUserData, D> for TabletManagerState
+where
+    D: Dispatch<ZwpTabletPadStripV2, TabletPadUserData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _strip: &ZwpTabletPadStripV2,
+        request: zwp_tablet_pad_strip_v2::Request,
+        _data: &TabletPadUserData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwp_tablet_pad_strip_v2::Request::SetFeedback { .. } => {
+                // Feedback strings are not used
+            }
+            zwp_tablet_pad_strip_v2::Request::Destroy => {
+                // Nothing to do
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(_state: &mut D, _client: ClientId, strip: &ZwpTabletPadStripV2, data: &TabletPadUserData) {
+        data.remove_resource(&strip.id());
+    }
+}
@@ -53,6 +53,9 @@ impl TabletTool {
             });
         }
 
+        // pads attached to the tablet follow the tool
+        tablet.focus_pads(&focus, serial, time);
+
         self.focus = Some(focus.clone());
     }
 
@@ -0,0 +1,541 @@
+//! Utilities for handling the `wlr-virtual-pointer-unstable-v1` protocol
+//!
+//! This protocol allows clients to emulate a physical pointer, e.g. for remote desktop
+//! servers or on-screen input methods. Requests of virtual pointers are translated into
+//! calls on the [`PointerHandle`] of the chosen seat, so they are processed like events of
+//! real input devices, including active grabs.
+//!
+//! Virtual pointers can inject arbitrary input, so the global should not be exposed to
+//! untrusted clients. To restrict access, the global is created with a filter, which can
+//! for example deny clients connected through a
+//! [security context](crate::wayland::security_context).
+//!
+//! ## How to use it
+//!
+//! ```
+//! use smithay::{delegate_seat, delegate_virtual_pointer};
+//! use smithay::input::{Seat, SeatState, SeatHandler, pointer::CursorImageStatus};
+//! use smithay::wayland::security_context::SecurityContext;
+//! use smithay::wayland::virtual_pointer::{VirtualPointerHandler, VirtualPointerManagerState};
+//! use smithay::reexports::wayland_server::{Display, protocol::wl_surface::WlSurface};
+//! use smithay::output::Output;
+//! use smithay::utils::{Logical, Point, Rectangle};
+//!
+//! # struct State { seat_state: SeatState<Self> };
+//! # struct ClientState { security_context: Option<SecurityContext> }
+//! # impl smithay::reexports::wayland_server::backend::ClientData for ClientState {}
+//!
+//! delegate_seat!(State);
+//! delegate_virtual_pointer!(State);
+//!
+//! # let mut display = Display::<State>::new().unwrap();
+//! # let display_handle = display.handle();
+//!
+//! impl SeatHandler for State {
+//!     type KeyboardFocus = WlSurface;
+//!     type PointerFocus = WlSurface;
+//!     type TouchFocus = WlSurface;
+//!     fn seat_state(&mut self) -> &mut SeatState<Self> {
+//!         &mut self.seat_state
+//!     }
+//!     fn focus_changed(&mut self, seat: &Seat<Self>, focused: Option<&WlSurface>) { unimplemented!() }
+//!     fn cursor_image(&mut self, seat: &Seat<Self>, image: CursorImageStatus) { unimplemented!() }
+//! }
+//!
+//! impl VirtualPointerHandler for State {
+//!     fn pointer_focus_at(
+//!         &mut self,
+//!         seat: &Seat<Self>,
+//!         location: Point<f64, Logical>,
+//!     ) -> Option<(WlSurface, Point<i32, Logical>)> {
+//!         // find the surface under the pointer
+//! #       None
+//!     }
+//!
+//!     fn absolute_motion_region(
+//!         &mut self,
+//!         seat: &Seat<Self>,
+//!         output: Option<&Output>,
+//!     ) -> Option<Rectangle<i32, Logical>> {
+//!         // the geometry of `output` or the bounding box of all outputs
+//! #       None
+//!     }
+//! }
+//!
+//! // Create the global, hidden from sandboxed clients
+//! VirtualPointerManagerState::new::<State, _>(&display_handle, |client| {
+//!     client
+//!         .get_data::<ClientState>()
+//!         .map_or(true, |data| data.security_context.is_none())
+//! });
+//! ```
+
+use std::{fmt, sync::Mutex};
+
+use wayland_protocols_wlr::virtual_pointer::v1::server::{
+    zwlr_virtual_pointer_manager_v1::{self, ZwlrVirtualPointerManagerV1},
+    zwlr_virtual_pointer_v1::{self, ZwlrVirtualPointerV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId},
+    protocol::{
+        wl_output::WlOutput,
+        wl_pointer::{self, AxisSource as WlAxisSource},
+        wl_seat::WlSeat,
+    },
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource, WEnum,
+};
+
+use crate::{
+    backend::input::{Axis, AxisSource, ButtonState},
+    input::{
+        pointer::{AxisFrame, ButtonEvent, MotionEvent, PointerHandle, RelativeMotionEvent},
+        Seat, SeatHandler,
+    },
+    output::Output,
+    utils::{Logical, Point, Rectangle, SERIAL_COUNTER},
+};
+
+const MANAGER_VERSION: u32 = 2;
+
+/// Handler trait for virtual pointers
+pub trait VirtualPointerHandler: SeatHandler + Sized + 'static {
+    /// Returns the focus of the pointer at the given location in compositor space,
+    /// together with the location of the focus' origin.
+    fn pointer_focus_at(
+        &mut self,
+        seat: &Seat<Self>,
+        location: Point<f64, Logical>,
+    ) -> Option<(<Self as SeatHandler>::PointerFocus, Point<i32, Logical>)>;
+
+    /// Returns the seat used by virtual pointers created without specifying one
+    ///
+    /// Defaults to the first seat having a pointer.
+    fn default_seat(&mut self) -> Option<Seat<Self>> {
+        self.seat_state()
+            .seats
+            .iter()
+            .find(|seat| seat.get_pointer().is_some())
+            .cloned()
+    }
+
+    /// Returns the region absolute motion of a virtual pointer is mapped to
+    ///
+    /// `output` is the output requested by the client, if any. This should be the geometry
+    /// of `output` in compositor space or, if no output was requested, the bounding box of
+    /// all outputs available to `seat`. Absolute motion is ignored, if this returns `None`.
+    fn absolute_motion_region(
+        &mut self,
+        seat: &Seat<Self>,
+        output: Option<&Output>,
+    ) -> Option<Rectangle<i32, Logical>>;
+
+    /// Constrain the new location of the pointer after relative motion
+    ///
+    /// Can be used to keep the pointer within the bounds of the outputs.
+    /// Defaults to not constraining the location.
+    fn constrain_pointer_location(
+        &mut self,
+        seat: &Seat<Self>,
+        location: Point<f64, Logical>,
+    ) -> Point<f64, Logical> {
+        let _ = seat;
+        location
+    }
+}
+
+/// State of the wlr virtual pointer protocol
+#[derive(Debug)]
+pub struct VirtualPointerManagerState {
+    global: GlobalId,
+}
+
+/// Data associated with a VirtualPointerManager global.
+#[allow(missing_debug_implementations)]
+pub struct VirtualPointerManagerGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+impl VirtualPointerManagerState {
+    /// Initialize a virtual pointer manager global.
+    ///
+    /// The filter determines, which clients can see the global.
+    pub fn new<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrVirtualPointerManagerV1, VirtualPointerManagerGlobalData>,
+        D: Dispatch<ZwlrVirtualPointerManagerV1, ()>,
+        D: Dispatch<ZwlrVirtualPointerV1, VirtualPointerUserData<D>>,
+        D: VirtualPointerHandler,
+        D: 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let data = VirtualPointerManagerGlobalData {
+            filter: Box::new(filter),
+        };
+        let global = display.create_global::<D, ZwlrVirtualPointerManagerV1, _>(MANAGER_VERSION, data);
+
+        Self { global }
+    }
+
+    /// Get the id of ZwlrVirtualPointerManagerV1 global
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// User data of ZwlrVirtualPointerV1 object
+pub struct VirtualPointerUserData<D: SeatHandler> {
+    seat: Option<Seat<D>>,
+    output: Option<Output>,
+    state: Mutex<VirtualPointerState>,
+}
+
+#[derive(Debug, Default)]
+struct VirtualPointerState {
+    axis_frame: Option<AxisFrame>,
+    pressed_buttons: Vec<u32>,
+    last_time: u32,
+}
+
+impl<D: SeatHandler> fmt::Debug for VirtualPointerUserData<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VirtualPointerUserData")
+            .field("seat", &self.seat.as_ref().map(|seat| seat.arc.clone()))
+            .field("output", &self.output)
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+impl<D: SeatHandler + 'static> VirtualPointerUserData<D> {
+    /// Returns the seat of the virtual pointer, if any
+    pub fn seat(&self) -> Option<&Seat<D>> {
+        self.seat.as_ref()
+    }
+
+    /// Returns the output absolute motion is mapped to, if any
+    pub fn output(&self) -> Option<&Output> {
+        self.output.as_ref()
+    }
+
+    fn pointer(&self) -> Option<(Seat<D>, PointerHandle<D>)> {
+        let seat = self.seat.clone()?;
+        let pointer = seat.get_pointer()?;
+        Some((seat, pointer))
+    }
+}
+
+fn axis_frame(state: &mut VirtualPointerState, time: u32) -> &mut AxisFrame {
+    state.last_time = time;
+    state.axis_frame.get_or_insert_with(|| AxisFrame::new(time))
+}
+
+fn convert_axis(pointer: &ZwlrVirtualPointerV1, axis: WEnum<wl_pointer::Axis>) -> Option<Axis> {
+    match axis {
+        WEnum::Value(wl_pointer::Axis::VerticalScroll) => Some(Axis::Vertical),
+        WEnum::Value(wl_pointer::Axis::HorizontalScroll) => Some(Axis::Horizontal),
+        _ => {
+            pointer.post_error(zwlr_virtual_pointer_v1::Error::InvalidAxis, "Invalid axis.");
+            None
+        }
+    }
+}
+
+fn pointer_motion<D: VirtualPointerHandler>(
+    state: &mut D,
+    seat: &Seat<D>,
+    pointer: &PointerHandle<D>,
+    time: u32,
+    location: Point<f64, Logical>,
+) {
+    let delta = location - pointer.current_location();
+    let focus = state.pointer_focus_at(seat, location);
+    pointer.motion(
+        state,
+        focus.clone(),
+        &MotionEvent {
+            location,
+            serial: SERIAL_COUNTER.next_serial(),
+            time,
+        },
+    );
+    pointer.relative_motion(
+        state,
+        focus,
+        &RelativeMotionEvent {
+            delta,
+            delta_unaccel: delta,
+            utime: time as u64 * 1000,
+        },
+    );
+}
+
+impl<D> GlobalDispatch<ZwlrVirtualPointerManagerV1, VirtualPointerManagerGlobalData, D>
+    for VirtualPointerManagerState
+where
+    D: GlobalDispatch<ZwlrVirtualPointerManagerV1, VirtualPointerManagerGlobalData>,
+    D: Dispatch<ZwlrVirtualPointerManagerV1, ()>,
+    D: Dispatch<ZwlrVirtualPointerV1, VirtualPointerUserData<D>>,
+    D: VirtualPointerHandler,
+    D: 'static,
+{
+    fn bind(
+        _: &mut D,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<ZwlrVirtualPointerManagerV1>,
+        _: &VirtualPointerManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &VirtualPointerManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+fn create_virtual_pointer<D>(
+    state: &mut D,
+    seat: Option<WlSeat>,
+    output: Option<WlOutput>,
+    id: New<ZwlrVirtualPointerV1>,
+    data_init: &mut DataInit<'_, D>,
+) where
+    D: Dispatch<ZwlrVirtualPointerV1, VirtualPointerUserData<D>>,
+    D: VirtualPointerHandler,
+    D: 'static,
+{
+    let seat = match seat {
+        Some(seat) => Seat::<D>::from_resource(&seat),
+        None => state.default_seat(),
+    };
+    let output = output.as_ref().and_then(Output::from_resource);
+
+    data_init.init(
+        id,
+        VirtualPointerUserData {
+            seat,
+            output,
+            state: Mutex::new(VirtualPointerState::default()),
+        },
+    );
+}
+
+impl<D> Dispatch<ZwlrVirtualPointerManagerV1, (), D> for VirtualPointerManagerState
+where
+    D: Dispatch<ZwlrVirtualPointerManagerV1, ()>,
+    D: Dispatch<ZwlrVirtualPointerV1, VirtualPointerUserData<D>>,
+    D: VirtualPointerHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrVirtualPointerManagerV1,
+        request: zwlr_virtual_pointer_manager_v1::Request,
+        _data: &(),
+        _handle: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_virtual_pointer_manager_v1::Request::CreateVirtualPointer { seat, id } => {
+                create_virtual_pointer(state, seat, None, id, data_init);
+            }
+            zwlr_virtual_pointer_manager_v1::Request::CreateVirtualPointerWithOutput { seat, output, id } => {
+                create_virtual_pointer(state, seat, output, id, data_init);
+            }
+            zwlr_virtual_pointer_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrVirtualPointerV1, VirtualPointerUserData<D>, D> for VirtualPointerManagerState
+where
+    D: Dispatch<ZwlrVirtualPointerV1, VirtualPointerUserData<D>>,
+    D: VirtualPointerHandler,
+    D: 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        virtual_pointer: &ZwlrVirtualPointerV1,
+        request: zwlr_virtual_pointer_v1::Request,
+        data: &VirtualPointerUserData<D>,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let Some((seat, pointer)) = data.pointer() else {
+            return;
+        };
+
+        match request {
+            zwlr_virtual_pointer_v1::Request::Motion { time, dx, dy } => {
+                let location = pointer.current_location() + Point::from((dx, dy));
+                let location = state.constrain_pointer_location(&seat, location);
+                data.state.lock().unwrap().last_time = time;
+                pointer_motion(state, &seat, &pointer, time, location);
+            }
+            zwlr_virtual_pointer_v1::Request::MotionAbsolute {
+                time,
+                x,
+                y,
+                x_extent,
+                y_extent,
+            } => {
+                if x_extent == 0 || y_extent == 0 {
+                    return;
+                }
+                let Some(region) = state.absolute_motion_region(&seat, data.output.as_ref()) else {
+                    return;
+                };
+                // the far edge of the extents maps to the last pixel inside the region
+                let region = region.to_f64();
+                let location = region.loc
+                    + Point::from((
+                        x.min(x_extent) as f64 / x_extent as f64 * (region.size.w - 1.0).max(0.0),
+                        y.min(y_extent) as f64 / y_extent as f64 * (region.size.h - 1.0).max(0.0),
+                    ));
+                data.state.lock().unwrap().last_time = time;
+                pointer_motion(state, &seat, &pointer, time, location);
+            }
+            zwlr_virtual_pointer_v1::Request::Button {
+                time,
+                button,
+                state: button_state,
+            } => {
+                let button_state = match button_state {
+                    WEnum::Value(wl_pointer::ButtonState::Pressed) => ButtonState::Pressed,
+                    WEnum::Value(wl_pointer::ButtonState::Released) => ButtonState::Released,
+                    _ => return,
+                };
+
+                let mut pointer_state = data.state.lock().unwrap();
+                pointer_state.last_time = time;
+                match button_state {
+                    ButtonState::Pressed => pointer_state.pressed_buttons.push(button),
+                    ButtonState::Released => pointer_state.pressed_buttons.retain(|b| *b != button),
+                }
+                std::mem::drop(pointer_state);
+
+                pointer.button(
+                    state,
+                    &ButtonEvent {
+                        serial: SERIAL_COUNTER.next_serial(),
+                        time,
+                        button,
+                        state: button_state,
+                    },
+                );
+            }
+            zwlr_virtual_pointer_v1::Request::Axis { time, axis, value } => {
+                let Some(axis) = convert_axis(virtual_pointer, axis) else {
+                    return;
+                };
+                let mut pointer_state = data.state.lock().unwrap();
+                let frame = axis_frame(&mut pointer_state, time);
+                *frame = frame.value(axis, value);
+            }
+            zwlr_virtual_pointer_v1::Request::AxisSource { axis_source } => {
+                let source = match axis_source {
+                    WEnum::Value(WlAxisSource::Wheel) => AxisSource::Wheel,
+                    WEnum::Value(WlAxisSource::Finger) => AxisSource::Finger,
+                    WEnum::Value(WlAxisSource::Continuous) => AxisSource::Continuous,
+                    WEnum::Value(WlAxisSource::WheelTilt) => AxisSource::WheelTilt,
+                    _ => {
+                        virtual_pointer.post_error(
+                            zwlr_virtual_pointer_v1::Error::InvalidAxisSource,
+                            "Invalid axis source.",
+                        );
+                        return;
+                    }
+                };
+                let mut pointer_state = data.state.lock().unwrap();
+                let time = pointer_state.last_time;
+                let frame = axis_frame(&mut pointer_state, time);
+                *frame = frame.source(source);
+            }
+            zwlr_virtual_pointer_v1::Request::AxisStop { time, axis } => {
+                let Some(axis) = convert_axis(virtual_pointer, axis) else {
+                    return;
+                };
+                let mut pointer_state = data.state.lock().unwrap();
+                let frame = axis_frame(&mut pointer_state, time);
+                *frame = frame.stop(axis);
+            }
+            zwlr_virtual_pointer_v1::Request::AxisDiscrete {
+                time,
+                axis,
+                value,
+                discrete,
+            } => {
+                let Some(axis) = convert_axis(virtual_pointer, axis) else {
+                    return;
+                };
+                let mut pointer_state = data.state.lock().unwrap();
+                let frame = axis_frame(&mut pointer_state, time);
+                *frame = frame.value(axis, value).discrete(axis, discrete);
+            }
+            zwlr_virtual_pointer_v1::Request::Frame => {
+                let axis_frame = data.state.lock().unwrap().axis_frame.take();
+                if let Some(axis_frame) = axis_frame {
+                    pointer.axis(state, axis_frame);
+                }
+                pointer.frame(state);
+            }
+            zwlr_virtual_pointer_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut D,
+        _client: ClientId,
+        _resource: &ZwlrVirtualPointerV1,
+        data: &VirtualPointerUserData<D>,
+    ) {
+        // Release buttons still held by the virtual pointer
+        let Some((_, pointer)) = data.pointer() else {
+            return;
+        };
+        let mut pointer_state = data.state.lock().unwrap();
+        let pressed_buttons = std::mem::take(&mut pointer_state.pressed_buttons);
+        let time = pointer_state.last_time;
+        std::mem::drop(pointer_state);
+        if pressed_buttons.is_empty() {
+            return;
+        }
+
+        for button in pressed_buttons {
+            pointer.button(
+                state,
+                &ButtonEvent {
+                    serial: SERIAL_COUNTER.next_serial(),
+                    time,
+                    button,
+                    state: ButtonState::Released,
+                },
+            );
+        }
+        pointer.frame(state);
+    }
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_virtual_pointer {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::virtual_pointer::v1::server::zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1: $crate::wayland::virtual_pointer::VirtualPointerManagerGlobalData
+        ] => $crate::wayland::virtual_pointer::VirtualPointerManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::virtual_pointer::v1::server::zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1: ()
+        ] => $crate::wayland::virtual_pointer::VirtualPointerManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::virtual_pointer::v1::server::zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1: $crate::wayland::virtual_pointer::VirtualPointerUserData<Self>
+        ] => $crate::wayland::virtual_pointer::VirtualPointerManagerState);
+    };
+}
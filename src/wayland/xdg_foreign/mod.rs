@@ -0,0 +1,634 @@
+//! Utilities for handling the `xdg-foreign` protocol
+//!
+//! This protocol allows a client to export one of its toplevels as an opaque handle,
+//! which another client can import to make one of its own toplevels a child of the exported one.
+//! This is e.g. used by `xdg-desktop-portal`, to parent dialogs like file choosers to
+//! the window of the application requesting them.
+//!
+//! Both `zxdg_exporter_v1`/`zxdg_importer_v1` and `zxdg_exporter_v2`/`zxdg_importer_v2` are supported.
+//!
+//! Imported parents are applied through [`ToplevelSurface::set_parent`] and announced via
+//! [`XdgShellHandler::parent_changed`], so [`ToplevelSurface::parent`] covers
+//! parents of other clients as well. Once either side is destroyed the parent relationship is removed again.
+//!
+//! ## How to use it
+//!
+//! ```
+//! use smithay::delegate_xdg_foreign;
+//! use smithay::wayland::xdg_foreign::{XdgForeignHandler, XdgForeignState};
+//! # use smithay::wayland::shell::xdg::{XdgShellHandler, XdgShellState, ToplevelSurface, PopupSurface, PositionerState};
+//! # use smithay::reexports::wayland_server::protocol::wl_seat;
+//! # use smithay::utils::Serial;
+//!
+//! # struct State { xdg_shell_state: XdgShellState, xdg_foreign_state: XdgForeignState }
+//! # impl XdgShellHandler for State {
+//! #     fn xdg_shell_state(&mut self) -> &mut XdgShellState { &mut self.xdg_shell_state }
+//! #     fn new_toplevel(&mut self, _: ToplevelSurface) {}
+//! #     fn new_popup(&mut self, _: PopupSurface, _: PositionerState) {}
+//! #     fn grab(&mut self, _: PopupSurface, _: wl_seat::WlSeat, _: Serial) {}
+//! #     fn reposition_request(&mut self, _: PopupSurface, _: PositionerState, _: u32) {}
+//! # }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! let xdg_foreign_state = XdgForeignState::new::<State>(&display.handle());
+//!
+//! impl XdgForeignHandler for State {
+//!     fn xdg_foreign_state(&mut self) -> &mut XdgForeignState {
+//!         &mut self.xdg_foreign_state
+//!     }
+//! }
+//! delegate_xdg_foreign!(State);
+//! ```
+
+use std::collections::HashMap;
+
+use rand::distributions::{Alphanumeric, DistString};
+use tracing::trace;
+use wayland_protocols::xdg::foreign::{
+    zv1::server::{
+        zxdg_exported_v1::{self, ZxdgExportedV1},
+        zxdg_exporter_v1::{self, ZxdgExporterV1},
+        zxdg_imported_v1::{self, ZxdgImportedV1},
+        zxdg_importer_v1::{self, ZxdgImporterV1},
+    },
+    zv2::server::{
+        zxdg_exported_v2::{self, ZxdgExportedV2},
+        zxdg_exporter_v2::{self, ZxdgExporterV2},
+        zxdg_imported_v2::{self, ZxdgImportedV2},
+        zxdg_importer_v2::{self, ZxdgImporterV2},
+    },
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId, ObjectId},
+    protocol::wl_surface::WlSurface,
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use crate::wayland::{
+    compositor::{self, HookId},
+    shell::{
+        is_toplevel_equivalent,
+        xdg::{ToplevelSurface, XdgShellHandler},
+    },
+};
+
+/// Handler trait for xdg-foreign
+pub trait XdgForeignHandler: XdgShellHandler {
+    /// [`XdgForeignState`] getter
+    fn xdg_foreign_state(&mut self) -> &mut XdgForeignState;
+}
+
+/// Opaque handle of an exported toplevel
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct XdgForeignHandle(String);
+
+impl XdgForeignHandle {
+    fn new() -> Self {
+        XdgForeignHandle(Alphanumeric.sample_string(&mut rand::thread_rng(), 32))
+    }
+
+    /// Returns the handle as sent to clients
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Imported {
+    V1(ZxdgImportedV1),
+    V2(ZxdgImportedV2),
+}
+
+impl Imported {
+    fn id(&self) -> ObjectId {
+        match self {
+            Imported::V1(imported) => imported.id(),
+            Imported::V2(imported) => imported.id(),
+        }
+    }
+
+    fn destroyed(&self) {
+        match self {
+            Imported::V1(imported) => imported.destroyed(),
+            Imported::V2(imported) => imported.destroyed(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ExportedState {
+    surface: WlSurface,
+    exported: ObjectId,
+    destruction_hook: HookId,
+    imports: Vec<ImportedState>,
+}
+
+#[derive(Debug)]
+struct ImportedState {
+    imported: Imported,
+    children: Vec<WlSurface>,
+}
+
+/// State of the xdg-foreign globals
+#[derive(Debug)]
+pub struct XdgForeignState {
+    exported: HashMap<XdgForeignHandle, ExportedState>,
+    exporter_v1: GlobalId,
+    importer_v1: GlobalId,
+    exporter_v2: GlobalId,
+    importer_v2: GlobalId,
+}
+
+impl XdgForeignState {
+    /// Creates the xdg-foreign exporter and importer globals, for version 1 and 2 of the protocol.
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<ZxdgExporterV1, ()>
+            + GlobalDispatch<ZxdgImporterV1, ()>
+            + GlobalDispatch<ZxdgExporterV2, ()>
+            + GlobalDispatch<ZxdgImporterV2, ()>
+            + Dispatch<ZxdgExporterV1, ()>
+            + Dispatch<ZxdgImporterV1, ()>
+            + Dispatch<ZxdgExporterV2, ()>
+            + Dispatch<ZxdgImporterV2, ()>
+            + Dispatch<ZxdgExportedV1, XdgExportedUserData>
+            + Dispatch<ZxdgImportedV1, XdgImportedUserData>
+            + Dispatch<ZxdgExportedV2, XdgExportedUserData>
+            + Dispatch<ZxdgImportedV2, XdgImportedUserData>
+            + XdgForeignHandler
+            + 'static,
+    {
+        XdgForeignState {
+            exported: HashMap::new(),
+            exporter_v1: display.create_global::<D, ZxdgExporterV1, _>(1, ()),
+            importer_v1: display.create_global::<D, ZxdgImporterV1, _>(1, ()),
+            exporter_v2: display.create_global::<D, ZxdgExporterV2, _>(1, ()),
+            importer_v2: display.create_global::<D, ZxdgImporterV2, _>(1, ()),
+        }
+    }
+
+    /// Returns the ids of the `zxdg_exporter_v1` and `zxdg_importer_v1` globals
+    pub fn v1_globals(&self) -> (GlobalId, GlobalId) {
+        (self.exporter_v1.clone(), self.importer_v1.clone())
+    }
+
+    /// Returns the ids of the `zxdg_exporter_v2` and `zxdg_importer_v2` globals
+    pub fn v2_globals(&self) -> (GlobalId, GlobalId) {
+        (self.exporter_v2.clone(), self.importer_v2.clone())
+    }
+
+    /// Returns the surface exported under the given handle, if any
+    pub fn exported_surface(&self, handle: &str) -> Option<WlSurface> {
+        self.exported
+            .get(&XdgForeignHandle(handle.to_string()))
+            .map(|exported| exported.surface.clone())
+    }
+}
+
+/// User data of exported objects
+#[derive(Debug)]
+pub struct XdgExportedUserData {
+    handle: XdgForeignHandle,
+}
+
+/// User data of imported objects
+#[derive(Debug)]
+pub struct XdgImportedUserData {
+    handle: XdgForeignHandle,
+}
+
+fn export<D: XdgForeignHandler + 'static>(
+    state: &mut D,
+    handle: &XdgForeignHandle,
+    surface: &WlSurface,
+    exported: ObjectId,
+) {
+    // revoke the export, once the surface is gone
+    let hook_handle = handle.clone();
+    let destruction_hook = compositor::add_destruction_hook(surface, move |state: &mut D, _| {
+        revoke(state, &hook_handle);
+    });
+
+    state.xdg_foreign_state().exported.insert(
+        handle.clone(),
+        ExportedState {
+            surface: surface.clone(),
+            exported,
+            destruction_hook,
+            imports: Vec::new(),
+        },
+    );
+}
+
+/// Removes an export, notifying all importers and removing any parent relationships
+fn revoke<D: XdgForeignHandler>(state: &mut D, handle: &XdgForeignHandle) {
+    let Some(exported) = state.xdg_foreign_state().exported.remove(handle) else {
+        return;
+    };
+    trace!(handle = handle.as_str(), "revoking exported toplevel");
+    for import in exported.imports {
+        unset_parents(state, &exported.surface, import.children);
+        import.imported.destroyed();
+    }
+}
+
+fn unset_parents<D: XdgForeignHandler>(state: &mut D, parent: &WlSurface, children: Vec<WlSurface>) {
+    for child in children {
+        let Some(toplevel) = toplevel_for_surface(state, &child) else {
+            continue;
+        };
+        if toplevel.parent().as_ref() == Some(parent) {
+            toplevel.set_parent(None);
+            XdgShellHandler::parent_changed(state, toplevel);
+        }
+    }
+}
+
+fn toplevel_for_surface<D: XdgShellHandler>(state: &mut D, surface: &WlSurface) -> Option<ToplevelSurface> {
+    state
+        .xdg_shell_state()
+        .toplevel_surfaces()
+        .iter()
+        .find(|toplevel| toplevel.wl_surface() == surface)
+        .cloned()
+}
+
+fn import<D: XdgForeignHandler>(state: &mut D, handle: &XdgForeignHandle, imported: Imported) {
+    match state.xdg_foreign_state().exported.get_mut(handle) {
+        Some(exported) => exported.imports.push(ImportedState {
+            imported,
+            children: Vec::new(),
+        }),
+        // unknown handles are immediately invalid
+        None => imported.destroyed(),
+    }
+}
+
+/// Handles `set_parent_of`, returns `false` if the surface is not a toplevel
+fn set_parent_of<D: XdgForeignHandler>(
+    state: &mut D,
+    handle: &XdgForeignHandle,
+    imported: &Imported,
+    surface: WlSurface,
+) -> bool {
+    if !is_toplevel_equivalent(&surface) {
+        return false;
+    }
+
+    let foreign_state = state.xdg_foreign_state();
+    let Some(exported) = foreign_state.exported.get_mut(handle) else {
+        // the export was already revoked
+        return true;
+    };
+    let parent = exported.surface.clone();
+    let Some(import) = exported.imports.iter_mut().find(|i| i.imported == *imported) else {
+        return true;
+    };
+    import
+        .children
+        .retain(|child| child.is_alive() && *child != surface);
+    import.children.push(surface.clone());
+
+    if let Some(toplevel) = toplevel_for_surface(state, &surface) {
+        if toplevel.set_parent(Some(&parent)) {
+            XdgShellHandler::parent_changed(state, toplevel);
+        }
+    }
+    true
+}
+
+fn exported_destroyed<D: XdgForeignHandler>(state: &mut D, handle: &XdgForeignHandle, exported: ObjectId) {
+    let Some(current) = state.xdg_foreign_state().exported.get(handle) else {
+        return;
+    };
+    if current.exported == exported {
+        // the surface is still alive, otherwise the export would have been revoked already
+        compositor::remove_destruction_hook(&current.surface, current.destruction_hook);
+        revoke(state, handle);
+    }
+}
+
+fn imported_destroyed<D: XdgForeignHandler>(state: &mut D, handle: &XdgForeignHandle, imported: ObjectId) {
+    let Some(exported) = state.xdg_foreign_state().exported.get_mut(handle) else {
+        return;
+    };
+    let Some(pos) = exported.imports.iter().position(|i| i.imported.id() == imported) else {
+        return;
+    };
+    let import = exported.imports.remove(pos);
+    let parent = exported.surface.clone();
+    unset_parents(state, &parent, import.children);
+}
+
+macro_rules! impl_global {
+    ($($iface:ty),*) => {
+        $(
+            impl<D> GlobalDispatch<$iface, (), D> for XdgForeignState
+            where
+                D: GlobalDispatch<$iface, ()> + Dispatch<$iface, ()> + 'static,
+            {
+                fn bind(
+                    _state: &mut D,
+                    _display: &DisplayHandle,
+                    _client: &Client,
+                    resource: New<$iface>,
+                    _global_data: &(),
+                    data_init: &mut DataInit<'_, D>,
+                ) {
+                    data_init.init(resource, ());
+                }
+            }
+        )*
+    };
+}
+
+impl_global!(ZxdgExporterV1, ZxdgImporterV1, ZxdgExporterV2, ZxdgImporterV2);
+
+impl<D> Dispatch<ZxdgExporterV1, (), D> for XdgForeignState
+where
+    D: Dispatch<ZxdgExporterV1, ()> + Dispatch<ZxdgExportedV1, XdgExportedUserData>,
+    D: XdgForeignHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZxdgExporterV1,
+        request: zxdg_exporter_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_exporter_v1::Request::Export { id, surface } => {
+                let handle = XdgForeignHandle::new();
+                let exported = data_init.init(
+                    id,
+                    XdgExportedUserData {
+                        handle: handle.clone(),
+                    },
+                );
+                export(state, &handle, &surface, exported.id());
+                exported.handle(handle.0);
+            }
+            zxdg_exporter_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZxdgExporterV2, (), D> for XdgForeignState
+where
+    D: Dispatch<ZxdgExporterV2, ()> + Dispatch<ZxdgExportedV2, XdgExportedUserData>,
+    D: XdgForeignHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZxdgExporterV2,
+        request: zxdg_exporter_v2::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_exporter_v2::Request::ExportToplevel { id, surface } => {
+                if !is_toplevel_equivalent(&surface) {
+                    resource.post_error(
+                        zxdg_exporter_v2::Error::InvalidSurface,
+                        "surface must be an xdg_toplevel",
+                    );
+                    return;
+                }
+                let handle = XdgForeignHandle::new();
+                let exported = data_init.init(
+                    id,
+                    XdgExportedUserData {
+                        handle: handle.clone(),
+                    },
+                );
+                export(state, &handle, &surface, exported.id());
+                exported.handle(handle.0);
+            }
+            zxdg_exporter_v2::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZxdgImporterV1, (), D> for XdgForeignState
+where
+    D: Dispatch<ZxdgImporterV1, ()> + Dispatch<ZxdgImportedV1, XdgImportedUserData>,
+    D: XdgForeignHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZxdgImporterV1,
+        request: zxdg_importer_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_importer_v1::Request::Import { id, handle } => {
+                let handle = XdgForeignHandle(handle);
+                let imported = data_init.init(
+                    id,
+                    XdgImportedUserData {
+                        handle: handle.clone(),
+                    },
+                );
+                import(state, &handle, Imported::V1(imported));
+            }
+            zxdg_importer_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZxdgImporterV2, (), D> for XdgForeignState
+where
+    D: Dispatch<ZxdgImporterV2, ()> + Dispatch<ZxdgImportedV2, XdgImportedUserData>,
+    D: XdgForeignHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZxdgImporterV2,
+        request: zxdg_importer_v2::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_importer_v2::Request::ImportToplevel { id, handle } => {
+                let handle = XdgForeignHandle(handle);
+                let imported = data_init.init(
+                    id,
+                    XdgImportedUserData {
+                        handle: handle.clone(),
+                    },
+                );
+                import(state, &handle, Imported::V2(imported));
+            }
+            zxdg_importer_v2::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZxdgExportedV1, XdgExportedUserData, D> for XdgForeignState
+where
+    D: Dispatch<ZxdgExportedV1, XdgExportedUserData>,
+    D: XdgForeignHandler + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ZxdgExportedV1,
+        request: zxdg_exported_v1::Request,
+        _data: &XdgExportedUserData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_exported_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZxdgExportedV1, data: &XdgExportedUserData) {
+        exported_destroyed(state, &data.handle, resource.id());
+    }
+}
+
+impl<D> Dispatch<ZxdgExportedV2, XdgExportedUserData, D> for XdgForeignState
+where
+    D: Dispatch<ZxdgExportedV2, XdgExportedUserData>,
+    D: XdgForeignHandler + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ZxdgExportedV2,
+        request: zxdg_exported_v2::Request,
+        _data: &XdgExportedUserData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_exported_v2::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZxdgExportedV2, data: &XdgExportedUserData) {
+        exported_destroyed(state, &data.handle, resource.id());
+    }
+}
+
+impl<D> Dispatch<ZxdgImportedV1, XdgImportedUserData, D> for XdgForeignState
+where
+    D: Dispatch<ZxdgImportedV1, XdgImportedUserData>,
+    D: XdgForeignHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZxdgImportedV1,
+        request: zxdg_imported_v1::Request,
+        data: &XdgImportedUserData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_imported_v1::Request::SetParentOf { surface } => {
+                // v1 has no error for this case, so we just ignore non-toplevel surfaces
+                set_parent_of(state, &data.handle, &Imported::V1(resource.clone()), surface);
+            }
+            zxdg_imported_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZxdgImportedV1, data: &XdgImportedUserData) {
+        imported_destroyed(state, &data.handle, resource.id());
+    }
+}
+
+impl<D> Dispatch<ZxdgImportedV2, XdgImportedUserData, D> for XdgForeignState
+where
+    D: Dispatch<ZxdgImportedV2, XdgImportedUserData>,
+    D: XdgForeignHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZxdgImportedV2,
+        request: zxdg_imported_v2::Request,
+        data: &XdgImportedUserData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zxdg_imported_v2::Request::SetParentOf { surface } => {
+                if !set_parent_of(state, &data.handle, &Imported::V2(resource.clone()), surface) {
+                    resource.post_error(
+                        zxdg_imported_v2::Error::InvalidSurface,
+                        "surface must be an xdg_toplevel",
+                    );
+                }
+            }
+            zxdg_imported_v2::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZxdgImportedV2, data: &XdgImportedUserData) {
+        imported_destroyed(state, &data.handle, resource.id());
+    }
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_xdg_foreign {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::foreign::zv1::server::zxdg_exporter_v1::ZxdgExporterV1: ()
+        ] => $crate::wayland::xdg_foreign::XdgForeignState);
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::foreign::zv1::server::zxdg_importer_v1::ZxdgImporterV1: ()
+        ] => $crate::wayland::xdg_foreign::XdgForeignState);
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::foreign::zv2::server::zxdg_exporter_v2::ZxdgExporterV2: ()
+        ] => $crate::wayland::xdg_foreign::XdgForeignState);
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::foreign::zv2::server::zxdg_importer_v2::ZxdgImporterV2: ()
+        ] => $crate::wayland::xdg_foreign::XdgForeignState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::foreign::zv1::server::zxdg_exporter_v1::ZxdgExporterV1: ()
+        ] => $crate::wayland::xdg_foreign::XdgForeignState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::foreign::zv1::server::zxdg_importer_v1::ZxdgImporterV1: ()
+        ] => $crate::wayland::xdg_foreign::XdgForeignState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::foreign::zv2::server::zxdg_exporter_v2::ZxdgExporterV2: ()
+        ] => $crate::wayland::xdg_foreign::XdgForeignState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::foreign::zv2::server::zxdg_importer_v2::ZxdgImporterV2: ()
+        ] => $crate::wayland::xdg_foreign::XdgForeignState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::foreign::zv1::server::zxdg_exported_v1::ZxdgExportedV1: $crate::wayland::xdg_foreign::XdgExportedUserData
+        ] => $crate::wayland::xdg_foreign::XdgForeignState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::foreign::zv1::server::zxdg_imported_v1::ZxdgImportedV1: $crate::wayland::xdg_foreign::XdgImportedUserData
+        ] => $crate::wayland::xdg_foreign::XdgForeignState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::foreign::zv2::server::zxdg_exported_v2::ZxdgExportedV2: $crate::wayland::xdg_foreign::XdgExportedUserData
+        ] => $crate::wayland::xdg_foreign::XdgForeignState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::foreign::zv2::server::zxdg_imported_v2::ZxdgImportedV2: $crate::wayland::xdg_foreign::XdgImportedUserData
+        ] => $crate::wayland::xdg_foreign::XdgForeignState);
+    };
+}
@@ -0,0 +1,426 @@
+//! Utilities for handling the `xdg-toplevel-icon-v1` protocol
+//!
+//! This protocol allows clients to set icons for their toplevel windows, either by
+//! naming an icon of the current icon theme or by providing pixel data in shm buffers.
+//!
+//! The icon is double-buffered state of the toplevel's `wl_surface`, available as the
+//! [`ToplevelIconCachedState`] once committed. [`XdgToplevelIconHandler::icon_changed`]
+//! notifies about newly committed icons.
+//!
+//! Pixel data is copied out of the client buffers, once they are added to an icon.
+//! [`ToplevelIcon::best_render_buffer`] turns the buffer best matching a given size
+//! into a [`MemoryRenderBuffer`] ready to be drawn.
+//!
+//! ## How to use it
+//!
+//! ```no_run
+//! use smithay::delegate_xdg_toplevel_icon;
+//! use smithay::wayland::compositor::with_states;
+//! use smithay::wayland::shell::xdg::{ToplevelSurface, XdgShellHandler};
+//! use smithay::wayland::xdg_toplevel_icon::{
+//!     ToplevelIconCachedState, XdgToplevelIconHandler, XdgToplevelIconManager,
+//! };
+//!
+//! # struct State;
+//! # impl XdgShellHandler for State {
+//! #     fn xdg_shell_state(&mut self) -> &mut smithay::wayland::shell::xdg::XdgShellState { unimplemented!() }
+//! #     fn new_toplevel(&mut self, surface: ToplevelSurface) { unimplemented!() }
+//! #     fn new_popup(&mut self, surface: smithay::wayland::shell::xdg::PopupSurface, positioner: smithay::wayland::shell::xdg::PositionerState) { unimplemented!() }
+//! #     fn grab(&mut self, surface: smithay::wayland::shell::xdg::PopupSurface, seat: smithay::reexports::wayland_server::protocol::wl_seat::WlSeat, serial: smithay::utils::Serial) { unimplemented!() }
+//! #     fn reposition_request(&mut self, surface: smithay::wayland::shell::xdg::PopupSurface, positioner: smithay::wayland::shell::xdg::PositionerState, token: u32) { unimplemented!() }
+//! # }
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the global, announcing 32x32 and 64x64 as preferred icon sizes
+//! let icon_manager = XdgToplevelIconManager::new::<State>(&display.handle(), vec![32, 64]);
+//!
+//! impl XdgToplevelIconHandler for State {
+//!     fn icon_changed(&mut self, toplevel: ToplevelSurface) {
+//!         let icon = with_states(toplevel.wl_surface(), |states| {
+//!             states.cached_state.current::<ToplevelIconCachedState>().icon.clone()
+//!         });
+//!         // update the task switcher entry of the toplevel
+//!         let buffer = icon.and_then(|icon| icon.best_render_buffer(32, 1));
+//!     }
+//! }
+//!
+//! delegate_xdg_toplevel_icon!(State);
+//! ```
+
+use std::{fmt, sync::Arc, sync::Mutex};
+
+use wayland_protocols::xdg::{
+    shell::server::xdg_toplevel::XdgToplevel,
+    toplevel_icon::v1::server::{
+        xdg_toplevel_icon_manager_v1::{self, XdgToplevelIconManagerV1},
+        xdg_toplevel_icon_v1::{self, XdgToplevelIconV1},
+    },
+};
+use wayland_server::{
+    backend::GlobalId, protocol::wl_buffer::WlBuffer, Client, DataInit, Dispatch, DisplayHandle,
+    GlobalDispatch, New, Resource,
+};
+
+use crate::{
+    backend::{
+        allocator::{format::get_bpp, Fourcc},
+        renderer::element::memory::MemoryRenderBuffer,
+    },
+    utils::{Buffer, Size, Transform},
+    wayland::{
+        compositor::{self, Cacheable},
+        shell::xdg::{ToplevelSurface, XdgShellHandler, XdgShellSurfaceUserData},
+        shm,
+    },
+};
+
+/// Handler trait for the xdg-toplevel-icon protocol
+#[allow(unused_variables)]
+pub trait XdgToplevelIconHandler: XdgShellHandler {
+    /// A new icon of the toplevel was committed
+    ///
+    /// The icon is available as the [`ToplevelIconCachedState`] of the toplevel's surface
+    /// and is `None`, if the icon was reset to the default icon.
+    fn icon_changed(&mut self, toplevel: ToplevelSurface) {}
+}
+
+/// Icon of a toplevel window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToplevelIcon {
+    /// Name of the icon in the current icon theme
+    pub name: Option<String>,
+    /// Pixel data of the icon in different sizes and scales
+    pub buffers: Vec<ToplevelIconBuffer>,
+}
+
+impl ToplevelIcon {
+    /// Returns the buffer best matching the given size and scale
+    ///
+    /// This is the buffer with the smallest logical size (its size divided by its scale)
+    /// of at least `size`, preferring buffers with a scale close to `scale`,
+    /// or the largest buffer, if none is large enough.
+    pub fn best_buffer(&self, size: i32, scale: i32) -> Option<&ToplevelIconBuffer> {
+        let edge = |buffer: &ToplevelIconBuffer| buffer.size.w.max(buffer.size.h) / buffer.scale.max(1);
+        let scale_diff = |buffer: &ToplevelIconBuffer| (buffer.scale - scale).abs();
+        self.buffers
+            .iter()
+            .filter(|buffer| edge(buffer) >= size)
+            .min_by_key(|buffer| (edge(buffer), scale_diff(buffer)))
+            .or_else(|| {
+                self.buffers
+                    .iter()
+                    .max_by_key(|buffer| (edge(buffer), -scale_diff(buffer)))
+            })
+    }
+
+    /// Returns the buffer best matching the given size and scale as a [`MemoryRenderBuffer`]
+    ///
+    /// See [`ToplevelIcon::best_buffer`].
+    pub fn best_render_buffer(&self, size: i32, scale: i32) -> Option<MemoryRenderBuffer> {
+        self.best_buffer(size, scale)
+            .map(ToplevelIconBuffer::to_memory_render_buffer)
+    }
+}
+
+/// Pixel data of a [`ToplevelIcon`]
+#[derive(Clone, PartialEq)]
+pub struct ToplevelIconBuffer {
+    /// Size of the buffer in pixels
+    pub size: Size<i32, Buffer>,
+    /// Scale of the buffer
+    pub scale: i32,
+    /// Format of the pixel data
+    pub format: Fourcc,
+    /// Pixel data without any padding between rows
+    pub data: Arc<[u8]>,
+}
+
+impl fmt::Debug for ToplevelIconBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToplevelIconBuffer")
+            .field("size", &self.size)
+            .field("scale", &self.scale)
+            .field("format", &self.format)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ToplevelIconBuffer {
+    /// Copy the contents of a shm buffer
+    ///
+    /// Returns `None`, if the buffer is not a shm buffer or its format is unknown.
+    pub fn from_shm_buffer(buffer: &WlBuffer, scale: i32) -> Option<Self> {
+        shm::with_buffer_contents(buffer, |ptr, len, data| {
+            let format = shm::shm_format_to_fourcc(data.format)?;
+            let bytes_per_pixel = get_bpp(format)? / 8;
+            let row_len = data.width as usize * bytes_per_pixel;
+            let end = data.offset as usize + data.stride as usize * data.height as usize;
+            if bytes_per_pixel == 0 || end > len || (data.stride as usize) < row_len {
+                return None;
+            }
+
+            let pool = unsafe { std::slice::from_raw_parts(ptr, len) };
+            let mut pixels = Vec::with_capacity(row_len * data.height as usize);
+            for row in 0..data.height as usize {
+                let start = data.offset as usize + row * data.stride as usize;
+                pixels.extend_from_slice(&pool[start..start + row_len]);
+            }
+
+            Some(ToplevelIconBuffer {
+                size: (data.width, data.height).into(),
+                scale,
+                format,
+                data: pixels.into(),
+            })
+        })
+        .ok()
+        .flatten()
+    }
+
+    /// Create a [`MemoryRenderBuffer`] from the pixel data
+    pub fn to_memory_render_buffer(&self) -> MemoryRenderBuffer {
+        MemoryRenderBuffer::from_memory(
+            &self.data,
+            self.format,
+            self.size,
+            self.scale,
+            Transform::Normal,
+            None,
+        )
+    }
+}
+
+/// Committed icon of a toplevel
+#[derive(Debug, Default, Clone)]
+pub struct ToplevelIconCachedState {
+    /// The icon of the toplevel, `None` means the default icon should be used
+    pub icon: Option<ToplevelIcon>,
+    changed: bool,
+}
+
+impl Cacheable for ToplevelIconCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        Self {
+            icon: self.icon.clone(),
+            changed: std::mem::take(&mut self.changed),
+        }
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        into.changed |= self.changed;
+        into.icon = self.icon;
+    }
+}
+
+/// State of the xdg_toplevel_icon_manager_v1 global
+#[derive(Debug)]
+pub struct XdgToplevelIconManager {
+    global: GlobalId,
+}
+
+/// Data associated with the xdg_toplevel_icon_manager_v1 global
+#[derive(Debug)]
+pub struct XdgToplevelIconGlobalData {
+    preferred_sizes: Vec<i32>,
+}
+
+impl XdgToplevelIconManager {
+    /// Create a new [`XdgToplevelIconManagerV1`] global
+    ///
+    /// `preferred_sizes` are the icon sizes in surface-local coordinates announced to
+    /// clients, which are able to render their icons at any size.
+    pub fn new<D>(display: &DisplayHandle, preferred_sizes: Vec<i32>) -> Self
+    where
+        D: GlobalDispatch<XdgToplevelIconManagerV1, XdgToplevelIconGlobalData>,
+        D: Dispatch<XdgToplevelIconManagerV1, ()>,
+        D: Dispatch<XdgToplevelIconV1, XdgToplevelIconUserData>,
+        D: XdgToplevelIconHandler,
+        D: 'static,
+    {
+        let global = display.create_global::<D, XdgToplevelIconManagerV1, _>(
+            1,
+            XdgToplevelIconGlobalData { preferred_sizes },
+        );
+
+        Self { global }
+    }
+
+    /// Returns the id of the [`XdgToplevelIconManagerV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// User data of xdg_toplevel_icon_v1
+#[derive(Debug, Default)]
+pub struct XdgToplevelIconUserData(Mutex<IconData>);
+
+#[derive(Debug, Default)]
+struct IconData {
+    icon: ToplevelIcon,
+    immutable: bool,
+}
+
+// Marks a surface, whose icon changes are reported to the handler
+struct IconCommitHook;
+
+impl<D> GlobalDispatch<XdgToplevelIconManagerV1, XdgToplevelIconGlobalData, D> for XdgToplevelIconManager
+where
+    D: GlobalDispatch<XdgToplevelIconManagerV1, XdgToplevelIconGlobalData>,
+    D: Dispatch<XdgToplevelIconManagerV1, ()>,
+    D: Dispatch<XdgToplevelIconV1, XdgToplevelIconUserData>,
+    D: XdgToplevelIconHandler,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        resource: New<XdgToplevelIconManagerV1>,
+        global_data: &XdgToplevelIconGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(resource, ());
+        for size in &global_data.preferred_sizes {
+            manager.icon_size(*size);
+        }
+        manager.done();
+    }
+}
+
+impl<D> Dispatch<XdgToplevelIconManagerV1, (), D> for XdgToplevelIconManager
+where
+    D: Dispatch<XdgToplevelIconManagerV1, ()>,
+    D: Dispatch<XdgToplevelIconV1, XdgToplevelIconUserData>,
+    D: XdgToplevelIconHandler,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _manager: &XdgToplevelIconManagerV1,
+        request: xdg_toplevel_icon_manager_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            xdg_toplevel_icon_manager_v1::Request::CreateIcon { id } => {
+                data_init.init(id, XdgToplevelIconUserData::default());
+            }
+            xdg_toplevel_icon_manager_v1::Request::SetIcon { toplevel, icon } => {
+                let Some(data) = toplevel.data::<XdgShellSurfaceUserData>() else {
+                    return;
+                };
+
+                let icon = icon.and_then(|icon| {
+                    let mut data = icon.data::<XdgToplevelIconUserData>()?.0.lock().unwrap();
+                    data.immutable = true;
+                    Some(data.icon.clone())
+                });
+                // An empty icon resets the icon just like a null icon
+                let icon = icon.filter(|icon| icon.name.is_some() || !icon.buffers.is_empty());
+
+                let needs_hook = compositor::with_states(&data.wl_surface, |states| {
+                    let mut pending = states.cached_state.pending::<ToplevelIconCachedState>();
+                    pending.icon = icon;
+                    pending.changed = true;
+
+                    states.data_map.insert_if_missing_threadsafe(|| IconCommitHook)
+                });
+
+                if needs_hook {
+                    let hook_toplevel = toplevel.clone();
+                    compositor::add_post_commit_hook::<D, _>(
+                        &data.wl_surface,
+                        move |state, _dh, _surface| icon_commit_hook(state, &hook_toplevel),
+                    );
+                }
+            }
+            xdg_toplevel_icon_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn icon_commit_hook<D: XdgToplevelIconHandler>(state: &mut D, toplevel: &XdgToplevel) {
+    let Some(surface) = state.xdg_shell_state().get_toplevel(toplevel) else {
+        return;
+    };
+
+    let changed = compositor::with_states(surface.wl_surface(), |states| {
+        std::mem::take(&mut states.cached_state.current::<ToplevelIconCachedState>().changed)
+    });
+    if changed {
+        state.icon_changed(surface);
+    }
+}
+
+impl<D> Dispatch<XdgToplevelIconV1, XdgToplevelIconUserData, D> for XdgToplevelIconManager
+where
+    D: Dispatch<XdgToplevelIconV1, XdgToplevelIconUserData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &XdgToplevelIconV1,
+        request: xdg_toplevel_icon_v1::Request,
+        data: &XdgToplevelIconUserData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let mut data = data.0.lock().unwrap();
+        if data.immutable && !matches!(request, xdg_toplevel_icon_v1::Request::Destroy) {
+            resource.post_error(
+                xdg_toplevel_icon_v1::Error::Immutable,
+                "Icon was already assigned to a toplevel.",
+            );
+            return;
+        }
+
+        match request {
+            xdg_toplevel_icon_v1::Request::SetName { icon_name } => {
+                data.icon.name = Some(icon_name);
+            }
+            xdg_toplevel_icon_v1::Request::AddBuffer { buffer, scale } => {
+                let icon_buffer = ToplevelIconBuffer::from_shm_buffer(&buffer, scale).filter(|icon_buffer| {
+                    icon_buffer.format == Fourcc::Argb8888
+                        && icon_buffer.size.w == icon_buffer.size.h
+                        && scale > 0
+                });
+                let Some(icon_buffer) = icon_buffer else {
+                    resource.post_error(
+                        xdg_toplevel_icon_v1::Error::InvalidBuffer,
+                        "Buffer must be a square argb8888 wl_shm buffer.",
+                    );
+                    return;
+                };
+
+                data.icon.buffers.retain(|existing| {
+                    existing.size != icon_buffer.size || existing.scale != icon_buffer.scale
+                });
+                data.icon.buffers.push(icon_buffer);
+            }
+            xdg_toplevel_icon_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_xdg_toplevel_icon {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::toplevel_icon::v1::server::xdg_toplevel_icon_manager_v1::XdgToplevelIconManagerV1: $crate::wayland::xdg_toplevel_icon::XdgToplevelIconGlobalData
+        ] => $crate::wayland::xdg_toplevel_icon::XdgToplevelIconManager);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::toplevel_icon::v1::server::xdg_toplevel_icon_manager_v1::XdgToplevelIconManagerV1: ()
+        ] => $crate::wayland::xdg_toplevel_icon::XdgToplevelIconManager);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xdg::toplevel_icon::v1::server::xdg_toplevel_icon_v1::XdgToplevelIconV1: $crate::wayland::xdg_toplevel_icon::XdgToplevelIconUserData
+        ] => $crate::wayland::xdg_toplevel_icon::XdgToplevelIconManager);
+    };
+}
@@ -0,0 +1,234 @@
+//! Utilities for handling the `xwayland-shell-v1` protocol
+//!
+//! This protocol is used by Xwayland to associate its `wl_surface`s with X11 windows,
+//! replacing the racy `WL_SURFACE_ID` client message.
+//!
+//! The global is only advertised to the Xwayland client. Serials committed through it are
+//! forwarded to the [`X11Wm`](crate::xwayland::X11Wm) of the client, which pairs them with
+//! the `WL_SURFACE_SERIAL` client messages it receives. Older Xwayland versions not binding
+//! the global keep being handled through `WL_SURFACE_ID`, so
+//! [`X11Wm::commit_hook`](crate::xwayland::X11Wm::commit_hook) is still required.
+//!
+//! ## How to use it
+//!
+//! ```no_run
+//! use smithay::delegate_xwayland_shell;
+//! use smithay::wayland::xwayland_shell::XWaylandShellState;
+//!
+//! # struct State;
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! // Create the global before spawning Xwayland
+//! let xwayland_shell_state = XWaylandShellState::new::<State>(&display.handle());
+//!
+//! delegate_xwayland_shell!(State);
+//! ```
+
+use wayland_protocols::xwayland::shell::v1::server::{
+    xwayland_shell_v1::{self, XwaylandShellV1},
+    xwayland_surface_v1::{self, XwaylandSurfaceV1},
+};
+use wayland_server::{
+    backend::GlobalId, protocol::wl_surface::WlSurface, Client, DataInit, Dispatch, DisplayHandle,
+    GlobalDispatch, New, Resource,
+};
+
+use crate::{
+    wayland::compositor::{self, Cacheable},
+    xwayland::{xwm::X11_SURFACE_ROLE, X11Wm, XWaylandClientData},
+};
+
+/// State of the xwayland_shell_v1 global
+#[derive(Debug)]
+pub struct XWaylandShellState {
+    global: GlobalId,
+}
+
+impl XWaylandShellState {
+    /// Create a new [`XwaylandShellV1`] global
+    ///
+    /// The global is only visible to clients spawned through [`XWayland`](crate::xwayland::XWayland).
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<XwaylandShellV1, ()>,
+        D: Dispatch<XwaylandShellV1, ()>,
+        D: Dispatch<XwaylandSurfaceV1, XWaylandSurfaceUserData>,
+        D: 'static,
+    {
+        let global = display.create_global::<D, XwaylandShellV1, _>(1, ());
+
+        Self { global }
+    }
+
+    /// Returns the id of the [`XwaylandShellV1`] global.
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+}
+
+/// Serial associating the surface with an X11 window
+///
+/// Set once, with the first commit after `set_serial`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct XWaylandShellCachedState {
+    /// The serial of the `WL_SURFACE_SERIAL` client message matching this surface
+    pub serial: Option<u64>,
+}
+
+impl Cacheable for XWaylandShellCachedState {
+    fn commit(&mut self, _dh: &DisplayHandle) -> Self {
+        Self {
+            serial: self.serial.take(),
+        }
+    }
+
+    fn merge_into(self, into: &mut Self, _dh: &DisplayHandle) {
+        if self.serial.is_some() {
+            *into = self;
+        }
+    }
+}
+
+// Marks a surface, whose serial was committed
+struct SerialCommitted;
+
+/// User data of `XwaylandSurfaceV1` object
+#[derive(Debug)]
+pub struct XWaylandSurfaceUserData {
+    wl_surface: WlSurface,
+}
+
+impl XWaylandSurfaceUserData {
+    /// Returns the [`WlSurface`] of this xwayland surface
+    pub fn wl_surface(&self) -> &WlSurface {
+        &self.wl_surface
+    }
+}
+
+fn serial_commit_hook(surface: &WlSurface) {
+    let serial = compositor::with_states(surface, |states| {
+        let serial = states.cached_state.current::<XWaylandShellCachedState>().serial;
+        serial.filter(|_| states.data_map.insert_if_missing_threadsafe(|| SerialCommitted))
+    });
+
+    if let Some(serial) = serial {
+        X11Wm::associate_serial(surface, serial);
+    }
+}
+
+impl<D> GlobalDispatch<XwaylandShellV1, (), D> for XWaylandShellState
+where
+    D: GlobalDispatch<XwaylandShellV1, ()>,
+    D: Dispatch<XwaylandShellV1, ()>,
+    D: 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _display: &DisplayHandle,
+        _client: &Client,
+        resource: New<XwaylandShellV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, _global_data: &()) -> bool {
+        client.get_data::<XWaylandClientData>().is_some()
+    }
+}
+
+impl<D> Dispatch<XwaylandShellV1, (), D> for XWaylandShellState
+where
+    D: Dispatch<XwaylandShellV1, ()>,
+    D: Dispatch<XwaylandSurfaceV1, XWaylandSurfaceUserData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        shell: &XwaylandShellV1,
+        request: xwayland_shell_v1::Request,
+        _data: &(),
+        _display: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            xwayland_shell_v1::Request::GetXwaylandSurface { id, surface } => {
+                // Surfaces get the same role as the ones associated through `WL_SURFACE_ID`
+                if compositor::give_role(&surface, X11_SURFACE_ROLE).is_err() {
+                    shell.post_error(xwayland_shell_v1::Error::Role, "Surface already has a role.");
+                    return;
+                }
+
+                compositor::add_post_commit_hook::<D, _>(&surface, |_state, _dh, surface| {
+                    serial_commit_hook(surface)
+                });
+
+                data_init.init(id, XWaylandSurfaceUserData { wl_surface: surface });
+            }
+            xwayland_shell_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<XwaylandSurfaceV1, XWaylandSurfaceUserData, D> for XWaylandShellState
+where
+    D: Dispatch<XwaylandSurfaceV1, XWaylandSurfaceUserData>,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        resource: &XwaylandSurfaceV1,
+        request: xwayland_surface_v1::Request,
+        data: &XWaylandSurfaceUserData,
+        _display: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            xwayland_surface_v1::Request::SetSerial { serial_lo, serial_hi } => {
+                let serial = ((serial_hi as u64) << 32) | serial_lo as u64;
+                if serial == 0 {
+                    resource.post_error(
+                        xwayland_surface_v1::Error::InvalidSerial,
+                        "Serial must be non-zero.",
+                    );
+                    return;
+                }
+
+                compositor::with_states(&data.wl_surface, |states| {
+                    if states.data_map.get::<SerialCommitted>().is_some() {
+                        resource.post_error(
+                            xwayland_surface_v1::Error::AlreadyAssociated,
+                            "Surface is already associated with an X11 window.",
+                        );
+                        return;
+                    }
+
+                    states.cached_state.pending::<XWaylandShellCachedState>().serial = Some(serial);
+                });
+            }
+            // Existing associations are unaffected
+            xwayland_surface_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[macro_export]
+macro_rules! delegate_xwayland_shell {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xwayland::shell::v1::server::xwayland_shell_v1::XwaylandShellV1: ()
+        ] => $crate::wayland::xwayland_shell::XWaylandShellState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xwayland::shell::v1::server::xwayland_shell_v1::XwaylandShellV1: ()
+        ] => $crate::wayland::xwayland_shell::XWaylandShellState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::xwayland::shell::v1::server::xwayland_surface_v1::XwaylandSurfaceV1: $crate::wayland::xwayland_shell::XWaylandSurfaceUserData
+        ] => $crate::wayland::xwayland_shell::XWaylandShellState);
+    };
+}
@@ -69,6 +69,7 @@ use crate::{
     wayland::{
         compositor::{get_role, give_role},
         selection::SelectionTarget,
+        shell::ping,
     },
 };
 use calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction, RegistrationToken};
@@ -125,6 +126,7 @@ mod atoms {
         AtomsCookie {
             // wayland-stuff
             WL_SURFACE_ID,
+            WL_SURFACE_SERIAL,
 
             // private
             _SMITHAY_CLOSE_CONNECTION,
@@ -156,6 +158,7 @@ mod atoms {
             _NET_WM_STATE_MODAL,
             _MOTIF_WM_HINTS,
             _NET_STARTUP_ID,
+            _NET_WM_ICON,
 
             // server -> client
             WM_S0,
@@ -308,6 +311,12 @@ pub trait XwmHandler {
     fn unfullscreen_request(&mut self, xwm: XwmId, window: X11Surface) {
         let _ = (xwm, window);
     }
+    /// Window became or stopped being a modal dialog.
+    ///
+    /// Corresponds to the `_NET_WM_STATE_MODAL` state, see [`X11Surface::is_modal`].
+    fn modal_changed(&mut self, xwm: XwmId, window: X11Surface, is_modal: bool) {
+        let _ = (xwm, window, is_modal);
+    }
     /// Window requests to be minimized.
     fn minimize_request(&mut self, xwm: XwmId, window: X11Surface) {
         let _ = (xwm, window);
@@ -362,6 +371,9 @@ pub struct X11Wm {
 
     wl_client: Client,
     unpaired_surfaces: HashMap<u32, X11Window>,
+    // xwayland-shell serials, which are only known on one side yet
+    unpaired_serials: HashMap<u64, X11Window>,
+    unpaired_serial_surfaces: HashMap<u64, WlSurface>,
     sequences_to_ignore: BinaryHeap<Reverse<u16>>,
 
     // selections
@@ -621,6 +633,42 @@ impl<D: XwmHandler> X11Injector<D> {
     }
 }
 
+// Type-erased access to the xwm of a client, used by the xwayland-shell protocol,
+// which is not generic over the event loop data
+struct XwmSerialInjector(Box<dyn Fn(&WlSurface, u64)>);
+
+impl<D: XwmHandler> X11Injector<D> {
+    fn associate_serial(&self, wl_surface: &WlSurface, serial: u64) {
+        let xwm_id = self.xwm;
+        let wl_surface = wl_surface.clone();
+
+        self.handle.insert_idle(move |data| {
+            if !wl_surface.is_alive() {
+                return;
+            }
+
+            let xwm = data.xwm_state(xwm_id);
+            match xwm.unpaired_serials.remove(&serial) {
+                Some(window) => {
+                    if let Some(surface) = xwm
+                        .windows
+                        .iter()
+                        .find(|x| x.window_id() == window || x.mapped_window_id() == Some(window))
+                    {
+                        let surface = surface.clone();
+                        X11Wm::new_surface(data, xwm_id, surface, wl_surface);
+                    }
+                }
+                None => {
+                    xwm.unpaired_serial_surfaces
+                        .retain(|_, surface| surface.is_alive());
+                    xwm.unpaired_serial_surfaces.insert(serial, wl_surface);
+                }
+            }
+        });
+    }
+}
+
 /// Edge values for resizing
 ///
 // These values are used to indicate which edge of a surface is being dragged in a resize operation.
@@ -809,11 +857,17 @@ impl X11Wm {
             xwm: id,
             handle: handle.clone(),
         };
-        client
-            .get_data::<XWaylandClientData>()
-            .unwrap()
-            .user_data()
-            .insert_if_missing(move || injector);
+        let serial_injector = X11Injector {
+            xwm: id,
+            handle: handle.clone(),
+        };
+        let user_data = client.get_data::<XWaylandClientData>().unwrap().user_data();
+        user_data.insert_if_missing(move || injector);
+        user_data.insert_if_missing(move || {
+            XwmSerialInjector(Box::new(move |wl_surface, serial| {
+                serial_injector.associate_serial(wl_surface, serial)
+            }))
+        });
 
         let _xfixes_data = conn
             .query_extension(x11rb::protocol::xfixes::X11_EXTENSION_NAME.as_bytes())?
@@ -840,6 +894,8 @@ impl X11Wm {
             clipboard,
             primary,
             unpaired_surfaces: Default::default(),
+            unpaired_serials: Default::default(),
+            unpaired_serial_surfaces: Default::default(),
             sequences_to_ignore: Default::default(),
             windows: Vec::new(),
             client_list: Vec::new(),
@@ -1016,6 +1072,9 @@ impl X11Wm {
 
     /// This function has to be called on [`CompositorHandler::commit`](crate::wayland::compositor::CompositorHandler::commit) to correctly
     /// update the internal state of Xwayland WMs.
+    ///
+    /// Surfaces associated through the [`xwayland-shell`](crate::wayland::xwayland_shell) protocol
+    /// don't rely on this, but it is still required for Xwayland versions not supporting it.
     pub fn commit_hook<D: XwmHandler + 'static>(surface: &WlSurface) {
         if let Some(client) = surface.client() {
             if let Some(x11) = client
@@ -1029,13 +1088,28 @@ impl X11Wm {
         }
     }
 
+    // Forwards a serial committed through xwayland-shell to the xwm of the client
+    pub(crate) fn associate_serial(wl_surface: &WlSurface, serial: u64) {
+        if let Some(client) = wl_surface.client() {
+            if let Some(injector) = client
+                .get_data::<XWaylandClientData>()
+                .and_then(|data| data.user_data().get::<XwmSerialInjector>())
+            {
+                (injector.0)(wl_surface, serial);
+            }
+        }
+    }
+
     fn new_surface<D: XwmHandler>(state: &mut D, xwm_id: XwmId, surface: X11Surface, wl_surface: WlSurface) {
         info!(
             window_id = surface.window_id(),
             surface = ?wl_surface,
             "Matched X11 surface to wayland surface",
         );
-        if give_role(&wl_surface, X11_SURFACE_ROLE).is_err() {
+        // surfaces of the xwayland-shell protocol already got their role
+        if get_role(&wl_surface) != Some(X11_SURFACE_ROLE)
+            && give_role(&wl_surface, X11_SURFACE_ROLE).is_err()
+        {
             // It makes no sense to post a protocol error here since that would only kill Xwayland
             error!(surface = ?wl_surface, "Surface already has a role?!");
             return;
@@ -1609,6 +1683,9 @@ fn handle_event<D: XwmHandler + 'static>(
                 }
             }
 
+            // the window will never be paired with a surface
+            xwm.unpaired_serials.retain(|_, window| *window != n.window);
+
             if let Some(pos) = xwm.windows.iter().position(|x| x.window_id() == n.window) {
                 let surface = xwm.windows.remove(pos);
                 surface.state.lock().unwrap().alive = false;
@@ -2134,6 +2211,48 @@ fn handle_event<D: XwmHandler + 'static>(
                         }
                     }
                 }
+                x if x == xwm.atoms.WL_SURFACE_SERIAL => {
+                    let data = msg.data.as_data32();
+                    let serial = ((data[1] as u64) << 32) | data[0] as u64;
+                    info!(
+                        window = ?msg.window,
+                        serial,
+                        "got X11 window surface serial",
+                    );
+                    if let Some(surface) = xwm
+                        .windows
+                        .iter()
+                        .find(|x| x.window_id() == msg.window || x.mapped_window_id() == Some(msg.window))
+                    {
+                        // Like with WL_SURFACE_ID the serial may be committed on the wayland
+                        // socket before or after we receive this message.
+                        match xwm
+                            .unpaired_serial_surfaces
+                            .remove(&serial)
+                            .filter(|wl_surface| wl_surface.is_alive())
+                        {
+                            Some(wl_surface) => {
+                                let surface = surface.clone();
+                                drop(_guard);
+                                X11Wm::new_surface(state, xwm_id, surface, wl_surface);
+                            }
+                            None => {
+                                xwm.unpaired_serials.insert(serial, msg.window);
+                            }
+                        }
+                    }
+                }
+                x if x == xwm.atoms.WM_PROTOCOLS && msg.data.as_data32()[0] == xwm.atoms._NET_WM_PING => {
+                    // pongs are sent to the root window, referring to the pinged window
+                    let window = msg.data.as_data32()[2];
+                    if let Some(surface) = xwm.windows.iter().find(|x| x.window_id() == window) {
+                        let was_pending =
+                            std::mem::replace(&mut surface.state.lock().unwrap().pending_ping, false);
+                        if was_pending {
+                            ping::notify_pong(surface.user_data(), surface.clone().into());
+                        }
+                    }
+                }
                 x if x == xwm.atoms.WM_CHANGE_STATE => {
                     if let Some(surface) = xwm.windows.iter().find(|x| x.window_id() == msg.window).cloned() {
                         drop(_guard);
@@ -2187,6 +2306,18 @@ fn handle_event<D: XwmHandler + 'static>(
                                     _ => {}
                                 }
                             }
+                            actions if actions.contains(&xwm.atoms._NET_WM_STATE_MODAL) => {
+                                let modal = match data[0] {
+                                    0 => false,
+                                    1 => true,
+                                    2 => !surface.is_modal(),
+                                    _ => surface.is_modal(),
+                                };
+                                if modal != surface.is_modal() {
+                                    surface.set_modal(modal)?;
+                                    state.modal_changed(xwm_id, surface, modal);
+                                }
+                            }
                             actions if actions.contains(&xwm.atoms._NET_WM_STATE_FULLSCREEN) => {
                                 match data[0] {
                                     0 => state.unfullscreen_request(xwm_id, surface),
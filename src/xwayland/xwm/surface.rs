@@ -1,5 +1,5 @@
 use crate::{
-    backend::{input::KeyState, renderer::element::Id},
+    backend::{allocator::Fourcc, input::KeyState, renderer::element::Id},
     input::{
         keyboard::{KeyboardTarget, KeysymHandle, ModifiersState},
         pointer::{
@@ -7,9 +7,11 @@ use crate::{
             GesturePinchEndEvent, GesturePinchUpdateEvent, GestureSwipeBeginEvent, GestureSwipeEndEvent,
             GestureSwipeUpdateEvent, MotionEvent, PointerTarget, RelativeMotionEvent,
         },
+        touch::{self, TouchTarget},
         Seat, SeatHandler,
     },
     utils::{user_data::UserDataMap, IsAlive, Logical, Rectangle, Serial, Size},
+    wayland::xdg_toplevel_icon::{ToplevelIcon, ToplevelIconBuffer},
 };
 use encoding_rs::WINDOWS_1252;
 use std::{
@@ -54,11 +56,13 @@ pub(crate) struct SharedSurfaceState {
     pub(crate) wl_surface: Option<WlSurface>,
     pub(super) mapped_onto: Option<X11Window>,
     pub(super) geometry: Rectangle<i32, Logical>,
+    pub(super) pending_ping: bool,
 
     title: String,
     class: String,
     instance: String,
     startup_id: Option<String>,
+    icon: Option<ToplevelIcon>,
     protocols: Protocols,
     hints: Option<WmHints>,
     normal_hints: Option<WmSizeHints>,
@@ -74,6 +78,7 @@ pub(super) type Protocols = Vec<WMProtocol>;
 pub(super) enum WMProtocol {
     TakeFocus,
     DeleteWindow,
+    Ping,
 }
 
 /// https://x.org/releases/X11R7.6/doc/xorg-docs/specs/ICCCM/icccm.html#input_focus
@@ -149,10 +154,12 @@ impl X11Surface {
                 wl_surface: None,
                 mapped_onto: None,
                 geometry,
+                pending_ping: false,
                 title: String::from(""),
                 class: String::from(""),
                 instance: String::from(""),
                 startup_id: None,
+                icon: None,
                 protocols: Vec::new(),
                 hints: None,
                 normal_hints: None,
@@ -303,6 +310,19 @@ impl X11Surface {
         self.state.lock().unwrap().startup_id.clone()
     }
 
+    /// Returns the icon of the underlying X11 window set through `_NET_WM_ICON`
+    pub fn icon(&self) -> Option<ToplevelIcon> {
+        self.state.lock().unwrap().icon.clone()
+    }
+
+    /// Returns if the window is a modal dialog of the window it is transient for.
+    ///
+    /// Corresponds to the `_NET_WM_STATE_MODAL` state of the underlying X11 window.
+    pub fn is_modal(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.net_state.contains(&self.atoms._NET_WM_STATE_MODAL)
+    }
+
     /// Returns if the window is considered to be a popup.
     ///
     /// Corresponds to the internal `_NET_WM_STATE_MODAL` state of the underlying X11 window.
@@ -483,6 +503,14 @@ impl X11Surface {
             })
     }
 
+    pub(super) fn set_modal(&self, modal: bool) -> Result<(), ConnectionError> {
+        if modal {
+            self.change_net_state(&[self.atoms._NET_WM_STATE_MODAL], &[])
+        } else {
+            self.change_net_state(&[], &[self.atoms._NET_WM_STATE_MODAL])
+        }
+    }
+
     fn change_net_state(&self, added: &[Atom], removed: &[Atom]) -> Result<(), ConnectionError> {
         let mut state = self.state.lock().unwrap();
 
@@ -542,6 +570,8 @@ impl X11Surface {
             Some(atom) if atom == self.atoms._NET_WM_WINDOW_TYPE => self.update_net_window_type(),
             Some(atom) if atom == self.atoms._MOTIF_WM_HINTS => self.update_motif_hints(),
             Some(atom) if atom == self.atoms._NET_STARTUP_ID => self.update_startup_id(),
+            Some(atom) if atom == self.atoms._NET_WM_ICON => self.update_icon(),
+            Some(atom) if atom == self.atoms._NET_WM_STATE => self.update_modal(),
             Some(_) => Ok(()), // unknown
             None => {
                 self.update_title()?;
@@ -554,6 +584,8 @@ impl X11Surface {
                 self.update_net_window_type()?;
                 self.update_motif_hints()?;
                 self.update_startup_id()?;
+                self.update_icon()?;
+                self.update_modal()?;
                 Ok(())
             }
         }
@@ -636,6 +668,62 @@ impl X11Surface {
         Ok(())
     }
 
+    fn update_icon(&self) -> Result<(), ConnectionError> {
+        let conn = self.conn.upgrade().ok_or(ConnectionError::UnknownError)?;
+        let values = match conn
+            .get_property(
+                false,
+                self.window,
+                self.atoms._NET_WM_ICON,
+                AtomEnum::CARDINAL,
+                0,
+                u32::MAX,
+            )?
+            .reply_unchecked()
+        {
+            Ok(Some(reply)) => reply.value32().map(|vals| vals.collect::<Vec<_>>()),
+            Ok(None) | Err(ConnectionError::ParseError(_)) => None,
+            Err(err) => return Err(err),
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.icon = values
+            .map(|values| parse_net_wm_icon(&values))
+            .filter(|icon| !icon.buffers.is_empty());
+        Ok(())
+    }
+
+    // Clients may set _NET_WM_STATE_MODAL before mapping their window,
+    // all other states are managed by the WM.
+    fn update_modal(&self) -> Result<(), ConnectionError> {
+        let conn = self.conn.upgrade().ok_or(ConnectionError::UnknownError)?;
+        let modal = match conn
+            .get_property(
+                false,
+                self.window,
+                self.atoms._NET_WM_STATE,
+                AtomEnum::ATOM,
+                0,
+                2048,
+            )?
+            .reply_unchecked()
+        {
+            Ok(Some(reply)) => reply.value32().map_or(false, |mut atoms| {
+                atoms.any(|atom| atom == self.atoms._NET_WM_STATE_MODAL)
+            }),
+            Ok(None) | Err(ConnectionError::ParseError(_)) => false,
+            Err(err) => return Err(err),
+        };
+
+        let mut state = self.state.lock().unwrap();
+        if modal {
+            state.net_state.insert(self.atoms._NET_WM_STATE_MODAL);
+        } else {
+            state.net_state.remove(&self.atoms._NET_WM_STATE_MODAL);
+        }
+        Ok(())
+    }
+
     fn update_protocols(&self) -> Result<(), ConnectionError> {
         let conn = self.conn.upgrade().ok_or(ConnectionError::UnknownError)?;
         let Some(protocols) = (match conn
@@ -662,6 +750,7 @@ impl X11Surface {
             .filter_map(|atom| match atom {
                 x if x == self.atoms.WM_TAKE_FOCUS => Some(WMProtocol::TakeFocus),
                 x if x == self.atoms.WM_DELETE_WINDOW => Some(WMProtocol::DeleteWindow),
+                x if x == self.atoms._NET_WM_PING => Some(WMProtocol::Ping),
                 _ => None,
             })
             .collect::<Vec<_>>();
@@ -777,6 +866,37 @@ impl X11Surface {
         }
         conn.flush()
     }
+
+    /// Returns if the window supports the `_NET_WM_PING` protocol
+    pub fn supports_ping(&self) -> bool {
+        self.state.lock().unwrap().protocols.contains(&WMProtocol::Ping)
+    }
+
+    /// Returns if a ping sent to this window is still unanswered
+    pub fn pending_ping(&self) -> bool {
+        self.state.lock().unwrap().pending_ping
+    }
+
+    /// Send a `_NET_WM_PING` request to this window.
+    ///
+    /// The reply is tracked by the [`X11Wm`](super::X11Wm) and clears [`X11Surface::pending_ping`].
+    /// Does nothing if the window does not support the protocol, see [`X11Surface::supports_ping`].
+    pub fn send_ping(&self) -> Result<(), ConnectionError> {
+        let conn = self.conn.upgrade().ok_or(ConnectionError::UnknownError)?;
+        let mut state = self.state.lock().unwrap();
+        if !state.protocols.contains(&WMProtocol::Ping) {
+            return Ok(());
+        }
+        let event = ClientMessageEvent::new(
+            32,
+            self.window,
+            self.atoms.WM_PROTOCOLS,
+            [self.atoms._NET_WM_PING, x11rb::CURRENT_TIME, self.window, 0, 0],
+        );
+        conn.send_event(false, self.window, EventMask::NO_EVENT, event)?;
+        state.pending_ping = true;
+        conn.flush()
+    }
 }
 
 /// Trait for objects, that represent an x11 window in some shape or form
@@ -996,3 +1116,79 @@ impl<D: SeatHandler + 'static> PointerTarget<D> for X11Surface {
         }
     }
 }
+
+impl<D: SeatHandler + 'static> TouchTarget<D> for X11Surface {
+    fn down(&self, seat: &Seat<D>, data: &mut D, event: &touch::DownEvent) {
+        if let Some(surface) = self.state.lock().unwrap().wl_surface.as_ref() {
+            TouchTarget::down(surface, seat, data, event);
+        }
+    }
+
+    fn up(&self, seat: &Seat<D>, data: &mut D, event: &touch::UpEvent) {
+        if let Some(surface) = self.state.lock().unwrap().wl_surface.as_ref() {
+            TouchTarget::up(surface, seat, data, event);
+        }
+    }
+
+    fn motion(&self, seat: &Seat<D>, data: &mut D, event: &touch::MotionEvent) {
+        if let Some(surface) = self.state.lock().unwrap().wl_surface.as_ref() {
+            TouchTarget::motion(surface, seat, data, event);
+        }
+    }
+
+    fn frame(&self, seat: &Seat<D>, data: &mut D) {
+        if let Some(surface) = self.state.lock().unwrap().wl_surface.as_ref() {
+            TouchTarget::frame(surface, seat, data);
+        }
+    }
+
+    fn cancel(&self, seat: &Seat<D>, data: &mut D) {
+        if let Some(surface) = self.state.lock().unwrap().wl_surface.as_ref() {
+            TouchTarget::cancel(surface, seat, data);
+        }
+    }
+
+    fn shape(&self, seat: &Seat<D>, data: &mut D, event: &touch::ShapeEvent) {
+        if let Some(surface) = self.state.lock().unwrap().wl_surface.as_ref() {
+            TouchTarget::shape(surface, seat, data, event);
+        }
+    }
+
+    fn orientation(&self, seat: &Seat<D>, data: &mut D, event: &touch::OrientationEvent) {
+        if let Some(surface) = self.state.lock().unwrap().wl_surface.as_ref() {
+            TouchTarget::orientation(surface, seat, data, event);
+        }
+    }
+}
+
+// _NET_WM_ICON is an array of icons, each given by its width, height and
+// width * height non-premultiplied ARGB pixels.
+fn parse_net_wm_icon(mut values: &[u32]) -> ToplevelIcon {
+    let mut icon = ToplevelIcon::default();
+    while let [width, height, rest @ ..] = values {
+        let Some(len) = width.checked_mul(*height).map(|len| len as usize) else {
+            break;
+        };
+        if len == 0 || rest.len() < len {
+            break;
+        }
+
+        let data = rest[..len]
+            .iter()
+            .flat_map(|pixel| {
+                let [b, g, r, a] = pixel.to_le_bytes();
+                let premultiply = |c: u8| ((c as u32 * a as u32 + 127) / 255) as u8;
+                [premultiply(b), premultiply(g), premultiply(r), a]
+            })
+            .collect::<Vec<_>>();
+        icon.buffers.push(ToplevelIconBuffer {
+            size: (*width as i32, *height as i32).into(),
+            scale: 1,
+            format: Fourcc::Argb8888,
+            data: data.into(),
+        });
+
+        values = &rest[len..];
+    }
+    icon
+}